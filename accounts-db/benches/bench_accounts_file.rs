@@ -5,10 +5,18 @@ use {
         account_storage::meta::StorableAccountsWithHashesAndWriteVersions,
         accounts_hash::AccountHash,
         append_vec::{self, AppendVec},
-        tiered_storage::hot::HotStorageWriter,
+        tiered_storage::{
+            file::TieredReadableFile,
+            hot::{HotStorageReader, HotStorageWriter},
+            index::IndexOffset,
+            meta::TieredAccountMeta,
+        },
     },
     solana_sdk::{
-        account::Account, clock::Slot, hash::Hash, pubkey::Pubkey,
+        account::{Account, ReadableAccount},
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
         rent_collector::RENT_EXEMPT_RENT_EPOCH,
     },
 };
@@ -89,5 +97,82 @@ fn bench_write_accounts_file(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench_write_accounts_file);
+fn bench_read_accounts_file(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_accounts_file");
+
+    // most accounts on mnb are 165-200 bytes, so use that here too
+    let space = 200;
+    let lamports_per_account = 2_282_880; // the rent-exempt amount for 200 bytes of data
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    for accounts_count in ACCOUNTS_COUNTS {
+        group.throughput(Throughput::Elements(accounts_count as u64));
+
+        let accounts: Vec<_> = std::iter::repeat_with(|| {
+            (
+                Pubkey::new_unique(),
+                Account::new_rent_epoch(
+                    lamports_per_account,
+                    space,
+                    &Pubkey::new_unique(),
+                    RENT_EXEMPT_RENT_EPOCH,
+                ),
+            )
+        })
+        .take(accounts_count)
+        .collect();
+        let accounts_refs: Vec<_> = accounts.iter().collect();
+        let accounts_data = (Slot::MAX, accounts_refs.as_slice());
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &accounts_data,
+                vec![AccountHash(Hash::default()); accounts_count],
+                vec![0; accounts_count],
+            );
+
+        let path = temp_dir
+            .path()
+            .join(format!("hot_storage_{accounts_count}"));
+        HotStorageWriter::new(&path)
+            .unwrap()
+            .write_accounts(&storable_accounts, 0)
+            .unwrap();
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+        // the public path, which assembles a StoredAccountMeta per account
+        group.bench_function(
+            BenchmarkId::new("hot_storage_accounts", accounts_count),
+            |b| {
+                b.iter(|| {
+                    let lamports: u64 = hot_storage
+                        .accounts(IndexOffset(0))
+                        .unwrap()
+                        .iter()
+                        .map(|account| account.lamports())
+                        .sum();
+                    assert_eq!(lamports, accounts_count as u64 * lamports_per_account);
+                });
+            },
+        );
+
+        // the raw path, which visits the parts of each account directly
+        group.bench_function(
+            BenchmarkId::new("hot_storage_scan_accounts_raw", accounts_count),
+            |b| {
+                b.iter(|| {
+                    let mut lamports = 0;
+                    hot_storage
+                        .scan_accounts_raw(IndexOffset(0), |_, _, meta, _| {
+                            lamports += meta.lamports();
+                            Ok(())
+                        })
+                        .unwrap();
+                    assert_eq!(lamports, accounts_count as u64 * lamports_per_account);
+                });
+            },
+        );
+    }
+}
+
+criterion_group!(benches, bench_write_accounts_file, bench_read_accounts_file);
 criterion_main!(benches);
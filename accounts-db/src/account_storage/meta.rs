@@ -1,5 +1,6 @@
 use {
     crate::{
+        accounts_file::tiered_offset,
         accounts_hash::AccountHash,
         append_vec::AppendVecStoredAccountMeta,
         storable_accounts::StorableAccounts,
@@ -18,7 +19,7 @@ pub struct StoredAccountInfo {
 }
 
 lazy_static! {
-    static ref DEFAULT_ACCOUNT_HASH: AccountHash = AccountHash(Hash::default());
+    pub(crate) static ref DEFAULT_ACCOUNT_HASH: AccountHash = AccountHash(Hash::default());
 }
 
 /// Goal is to eliminate copies and data reshaping given various code paths that store accounts.
@@ -128,22 +129,31 @@ impl<'storage> StoredAccountMeta<'storage> {
     pub fn hash(&self) -> &'storage AccountHash {
         match self {
             Self::AppendVec(av) => av.hash(),
-            // tiered-storage has deprecated the use of AccountHash
-            Self::Hot(_) => &DEFAULT_ACCOUNT_HASH,
+            Self::Hot(hot) => hot.hash(),
+        }
+    }
+
+    /// Returns whether the account content matches its persisted checksum,
+    /// or None if the account does not persist one, which is always the
+    /// case for an AppendVec.
+    pub fn verify_checksum(&self) -> Option<bool> {
+        match self {
+            Self::AppendVec(_) => None,
+            Self::Hot(hot) => hot.verify_checksum(),
         }
     }
 
     pub fn stored_size(&self) -> usize {
         match self {
             Self::AppendVec(av) => av.stored_size(),
-            Self::Hot(_) => unimplemented!(),
+            Self::Hot(hot) => hot.stored_size(),
         }
     }
 
     pub fn offset(&self) -> usize {
         match self {
             Self::AppendVec(av) => av.offset(),
-            Self::Hot(hot) => hot.index().0 as usize,
+            Self::Hot(hot) => tiered_offset(hot.index()),
         }
     }
 
@@ -191,8 +201,7 @@ impl<'storage> StoredAccountMeta<'storage> {
     pub(crate) fn sanitize(&self) -> bool {
         match self {
             Self::AppendVec(av) => av.sanitize(),
-            // Hot account currently doesn't have the concept of sanitization.
-            Self::Hot(_) => unimplemented!(),
+            Self::Hot(hot) => hot.sanitize(),
         }
     }
 }
@@ -32,7 +32,8 @@ use {
         },
         accounts_cache::{AccountsCache, CachedAccount, SlotCache},
         accounts_file::{
-            AccountsFile, AccountsFileError, MatchAccountOwnerError, ALIGN_BOUNDARY_OFFSET,
+            AccountsFile, AccountsFileError, AccountsFileIter, AccountsFileProvider,
+            MatchAccountOwnerError, ALIGN_BOUNDARY_OFFSET,
         },
         accounts_hash::{
             AccountHash, AccountsDeltaHash, AccountsHash, AccountsHashKind, AccountsHasher,
@@ -498,6 +499,7 @@ pub const ACCOUNTS_DB_CONFIG_FOR_TESTING: AccountsDbConfig = AccountsDbConfig {
     skip_initial_hash_calc: false,
     exhaustively_verify_refcounts: false,
     create_ancient_storage: CreateAncientStorage::Pack,
+    accounts_file_provider: AccountsFileProvider::AlwaysAppendVec,
     test_partitioned_epoch_rewards: TestPartitionedEpochRewards::CompareResults,
     test_skip_rewrites_but_include_in_bank_hash: false,
 };
@@ -511,6 +513,7 @@ pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig
     skip_initial_hash_calc: false,
     exhaustively_verify_refcounts: false,
     create_ancient_storage: CreateAncientStorage::Pack,
+    accounts_file_provider: AccountsFileProvider::AlwaysAppendVec,
     test_partitioned_epoch_rewards: TestPartitionedEpochRewards::None,
     test_skip_rewrites_but_include_in_bank_hash: false,
 };
@@ -557,6 +560,8 @@ pub struct AccountsDbConfig {
     pub exhaustively_verify_refcounts: bool,
     /// how to create ancient storages
     pub create_ancient_storage: CreateAncientStorage,
+    /// how to choose the kind of accounts file for new storages
+    pub accounts_file_provider: AccountsFileProvider,
     pub test_partitioned_epoch_rewards: TestPartitionedEpochRewards,
 }
 
@@ -1076,8 +1081,11 @@ impl AccountStorageEntry {
             //          **and**
             //  the append_vec has previously been completely full
             //
-            self.accounts.reset();
-            status = AccountStorageStatus::Available;
+            // a storage that cannot be reset stays full, so that new
+            //  storages are created for the following stores instead
+            if self.accounts.reset().is_ok() {
+                status = AccountStorageStatus::Available;
+            }
         }
 
         *count_and_status = (count, status);
@@ -1166,8 +1174,12 @@ impl AccountStorageEntry {
             //
             // otherwise, the storage may be in flight with a store()
             //   call
-            self.accounts.reset();
-            status = AccountStorageStatus::Available;
+            //
+            // a storage that cannot be reset stays full, so that new
+            //  storages are created for the following stores instead
+            if self.accounts.reset().is_ok() {
+                status = AccountStorageStatus::Available;
+            }
         }
 
         // Some code path is removing accounts too many; this may result in an
@@ -1286,6 +1298,9 @@ pub struct AccountsDb {
     /// from AccountsDbConfig
     create_ancient_storage: CreateAncientStorage,
 
+    /// from AccountsDbConfig
+    accounts_file_provider: AccountsFileProvider,
+
     /// true if this client should skip rewrites but still include those rewrites in the bank hash as if rewrites had occurred.
     pub test_skip_rewrites_but_include_in_bank_hash: bool,
 
@@ -2323,6 +2338,7 @@ impl AccountsDb {
 
         AccountsDb {
             create_ancient_storage: CreateAncientStorage::Pack,
+            accounts_file_provider: AccountsFileProvider::default(),
             verify_accounts_hash_in_bg: VerifyAccountsHashInBackground::default(),
             active_stats: ActiveStats::default(),
             skip_initial_hash_calc: false,
@@ -2438,6 +2454,11 @@ impl AccountsDb {
             .map(|config| config.create_ancient_storage)
             .unwrap_or(CreateAncientStorage::Append);
 
+        let accounts_file_provider = accounts_db_config
+            .as_ref()
+            .map(|config| config.accounts_file_provider)
+            .unwrap_or_default();
+
         let test_partitioned_epoch_rewards = accounts_db_config
             .as_ref()
             .map(|config| config.test_partitioned_epoch_rewards)
@@ -2461,6 +2482,7 @@ impl AccountsDb {
             shrink_ratio,
             accounts_update_notifier,
             create_ancient_storage,
+            accounts_file_provider,
             write_cache_limit_bytes: accounts_db_config
                 .as_ref()
                 .and_then(|x| x.write_cache_limit_bytes),
@@ -2514,7 +2536,14 @@ impl AccountsDb {
     }
 
     fn new_storage_entry(&self, slot: Slot, path: &Path, size: u64) -> AccountStorageEntry {
-        AccountStorageEntry::new(path, slot, self.next_id(), size)
+        let id = self.next_id();
+        let accounts = self.accounts_file_provider.create(
+            slot,
+            self.accounts_index.max_root_inclusive(),
+            path.join(AccountsFile::file_name(slot, id)),
+            size as usize,
+        );
+        AccountStorageEntry::new_existing(slot, id, accounts, 0)
     }
 
     pub fn expected_cluster_type(&self) -> ClusterType {
@@ -5513,6 +5542,22 @@ impl AccountsDb {
         self.storage.insert(slot, store)
     }
 
+    /// Replaces the storage of `slot`, whose write failed without storing
+    /// any account, with a new storage of the same capacity.
+    fn replace_failed_store(
+        &self,
+        slot: Slot,
+        failed_store: &AccountStorageEntry,
+    ) -> Arc<AccountStorageEntry> {
+        let removed_store = self.storage.remove(&slot, false);
+        assert_eq!(
+            removed_store.map(|store| store.append_vec_id()),
+            Some(failed_store.append_vec_id()),
+            "only the storage of slot {slot} can be replaced",
+        );
+        self.create_and_insert_store(slot, failed_store.capacity(), "replace failed store")
+    }
+
     pub fn enable_bank_drop_callback(&self) {
         self.is_bank_drop_callback_enabled
             .store(true, Ordering::Release);
@@ -5961,16 +6006,53 @@ impl AccountsDb {
     ) -> Vec<AccountInfo> {
         let mut infos: Vec<AccountInfo> = Vec::with_capacity(accounts_and_meta_to_store.len());
         let mut total_append_accounts_us = 0;
+        // the storage that replaces `storage` once its write failed
+        let mut replacement_storage: Option<Arc<AccountStorageEntry>> = None;
         while infos.len() < accounts_and_meta_to_store.len() {
+            let storage = replacement_storage.as_deref().unwrap_or(storage);
             let mut append_accounts = Measure::start("append_accounts");
             let rvs = storage
                 .accounts
                 .append_accounts(accounts_and_meta_to_store, infos.len());
             append_accounts.stop();
             total_append_accounts_us += append_accounts.as_us();
+            let rvs = match rvs {
+                Ok(rvs) => rvs,
+                Err(err) => {
+                    // Only a TieredStorage fails to write, and as it is written
+                    // only once, it can never be retried.  The failed write
+                    // stored no account, so the accounts go to a new storage
+                    // instead, which is not replaced again if it fails too.
+                    assert!(
+                        replacement_storage.is_none(),
+                        "failed to write accounts to the replacement storage {} of slot {slot}: \
+                         {err}",
+                        storage.append_vec_id(),
+                    );
+                    warn!(
+                        "write_accounts_to_storage, replacing storage {} of slot {slot}: {err}",
+                        storage.append_vec_id(),
+                    );
+                    replacement_storage = Some(self.replace_failed_store(slot, storage));
+                    continue;
+                }
+            };
             if rvs.is_none() {
                 storage.set_status(AccountStorageStatus::Full);
 
+                // A TieredStorage has no room left after its first write, and
+                // the slot cannot have another storage for the rest of the
+                // accounts, so retrying would never end.
+                if let AccountsFile::TieredStorage(_) = storage.accounts {
+                    panic!(
+                        "write_accounts_to_storage, no room for {} of {} accounts in the tiered \
+                         storage {} of slot {slot}",
+                        accounts_and_meta_to_store.len() - infos.len(),
+                        accounts_and_meta_to_store.len(),
+                        storage.append_vec_id(),
+                    );
+                }
+
                 // See if an account overflows the append vecs in the slot.
                 let account = accounts_and_meta_to_store.account(infos.len());
                 let data_len = account
@@ -6356,7 +6438,7 @@ impl AccountsDb {
             StorableAccountsWithHashesAndWriteVersions::<'_, '_, _, _, &AccountHash>::new(
                 &to_store,
             );
-        storage.accounts.append_accounts(&storable, 0);
+        storage.accounts.append_accounts(&storable, 0).unwrap();
 
         Arc::new(storage)
     }
@@ -8640,6 +8722,23 @@ impl AccountsDb {
         })
     }
 
+    /// Panics if the specified account iterator stopped before scanning all
+    /// the accounts in the storage, as generating the index from a partially
+    /// scanned storage would silently drop accounts from the index.
+    fn check_account_iter_error(
+        accounts: &mut AccountsFileIter,
+        slot: Slot,
+        storage: &AccountStorageEntry,
+    ) {
+        if let Some(err) = accounts.take_error() {
+            panic!(
+                "failed to scan all accounts in storage {} for slot {slot} after {} accounts: {err}",
+                storage.get_path().display(),
+                accounts.scanned(),
+            );
+        }
+    }
+
     fn generate_index_for_slot(
         &self,
         storage: &Arc<AccountStorageEntry>,
@@ -8650,9 +8749,10 @@ impl AccountsDb {
     ) -> SlotIndexGenerationInfo {
         let mut accounts = storage.accounts.account_iter();
         if accounts.next().is_none() {
+            Self::check_account_iter_error(&mut accounts, slot, storage);
             return SlotIndexGenerationInfo::default();
         }
-        let accounts = storage.accounts.account_iter();
+        let mut accounts = storage.accounts.account_iter();
 
         let secondary = !self.account_indexes.is_empty();
 
@@ -8662,7 +8762,7 @@ impl AccountsDb {
         let mut amount_to_top_off_rent = 0;
         let mut stored_size_alive = 0;
 
-        let items = accounts.map(|stored_account| {
+        let items = accounts.by_ref().map(|stored_account| {
             stored_size_alive += stored_account.stored_size();
             let pubkey = stored_account.pubkey();
             if secondary {
@@ -8697,6 +8797,7 @@ impl AccountsDb {
         let (dirty_pubkeys, insert_time_us, mut generate_index_results) = self
             .accounts_index
             .insert_new_if_missing_into_primary_index(slot, storage.approx_stored_count(), items);
+        Self::check_account_iter_error(&mut accounts, slot, storage);
 
         if let Some(duplicates_this_slot) = std::mem::take(&mut generate_index_results.duplicates) {
             // there were duplicate pubkeys in this same slot
@@ -9505,12 +9606,16 @@ pub mod tests {
         crate::{
             account_info::StoredSize,
             account_storage::meta::{AccountMeta, StoredMeta},
+            accounts_file::AccountsFileKind,
             accounts_hash::MERKLE_FANOUT,
             accounts_index::{tests::*, AccountSecondaryIndexesIncludeExclude},
             ancient_append_vecs,
             append_vec::{test_utils::TempFile, AppendVecStoredAccountMeta},
             cache_hash_data::CacheHashDataFile,
             inline_spl_token,
+            tiered_storage::{
+                generation::WriteOptions, test_utils::write_hot_storage, TieredStorage,
+            },
         },
         assert_matches::assert_matches,
         itertools::Itertools,
@@ -9659,7 +9764,7 @@ pub mod tests {
                 );
 
             // construct append vec with account to generate an index from
-            append_vec.accounts.append_accounts(&append, 0);
+            append_vec.accounts.append_accounts(&append, 0).unwrap();
             // append vecs set this at load
             append_vec
                 .approx_store_count
@@ -10171,7 +10276,8 @@ pub mod tests {
                     );
                 copied_storage
                     .accounts
-                    .append_accounts(&storable_accounts, 0);
+                    .append_accounts(&storable_accounts, 0)
+                    .unwrap();
                 copied_storage
             })
             .collect::<Vec<_>>();
@@ -10217,7 +10323,8 @@ pub mod tests {
                     );
                 copied_storage
                     .accounts
-                    .append_accounts(&storable_accounts, 0);
+                    .append_accounts(&storable_accounts, 0)
+                    .unwrap();
                 copied_storage
             })
             .collect::<Vec<_>>();
@@ -10679,6 +10786,7 @@ pub mod tests {
         let stored_accounts_info = storage
             .accounts
             .append_accounts(&storable_accounts, 0)
+            .unwrap()
             .unwrap();
         if mark_alive {
             // updates 'alive_bytes' on the storage
@@ -12662,6 +12770,28 @@ pub mod tests {
         storage_entry.remove_account(0, true);
     }
 
+    #[test]
+    fn test_storage_remove_last_account_not_recyclable() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_storage_remove_last_account_not_recyclable");
+        write_hot_storage(&path, &[1]);
+        let accounts = AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        let storage_entry = AccountStorageEntry::new_existing(0, 0, accounts, 1);
+        storage_entry.add_account(1);
+        storage_entry.set_status(AccountStorageStatus::Full);
+
+        // test: removing the last account of a full storage that cannot be
+        // reset keeps it full instead of offering it for new stores
+        storage_entry.remove_account(1, true);
+        assert_eq!(storage_entry.count(), 0);
+        assert_eq!(storage_entry.status(), AccountStorageStatus::Full);
+        storage_entry.set_status(AccountStorageStatus::Full);
+        assert_eq!(storage_entry.status(), AccountStorageStatus::Full);
+        assert!(!storage_entry.try_available());
+    }
+
     fn do_full_clean_refcount(store1_first: bool, store_size: u64) {
         let pubkey1 = Pubkey::from_str("My11111111111111111111111111111111111111111").unwrap();
         let pubkey2 = Pubkey::from_str("My22211111111111111111111111111111111111111").unwrap();
@@ -15129,14 +15259,17 @@ pub mod tests {
         let storage = accounts.create_and_insert_store(slot0, 4_000, "flush_slot_cache");
         let hashes = vec![AccountHash(Hash::default()); 1];
         let write_version = vec![0; 1];
-        storage.accounts.append_accounts(
-            &StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
-                &(slot0, &[(&shared_key, &account)][..]),
-                hashes,
-                write_version,
-            ),
-            0,
-        );
+        storage
+            .accounts
+            .append_accounts(
+                &StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                    &(slot0, &[(&shared_key, &account)][..]),
+                    hashes,
+                    write_version,
+                ),
+                0,
+            )
+            .unwrap();
 
         let storage = accounts.storage.get_slot_storage_entry(slot0).unwrap();
         let storage_info = StorageSizeAndCountMap::default();
@@ -15194,14 +15327,17 @@ pub mod tests {
         let storage = accounts.create_and_insert_store(slot0, 4_000, "flush_slot_cache");
         let hashes = vec![AccountHash(Hash::default()); 2];
         let write_version = vec![0; 2];
-        storage.accounts.append_accounts(
-            &StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
-                &(slot0, &[(&keys[0], &account), (&keys[1], &account_big)][..]),
-                hashes,
-                write_version,
-            ),
-            0,
-        );
+        storage
+            .accounts
+            .append_accounts(
+                &StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                    &(slot0, &[(&keys[0], &account), (&keys[1], &account_big)][..]),
+                    hashes,
+                    write_version,
+                ),
+                0,
+            )
+            .unwrap();
 
         let storage_info = StorageSizeAndCountMap::default();
         accounts.generate_index_for_slot(&storage, 0, 0, &RentCollector::default(), &storage_info);
@@ -17765,4 +17901,93 @@ pub mod tests {
         let hashes = hashes.into_iter().collect();
         AccountsHasher::compute_merkle_root_recurse(hashes, MERKLE_FANOUT)
     }
+
+    #[test_case(AccountsFileProvider::AlwaysAppendVec, [AccountsFileKind::AppendVec; 2]; "append_vec")]
+    #[test_case(
+        AccountsFileProvider::AlwaysTieredHot { write_options: WriteOptions::default() },
+        [AccountsFileKind::TieredStorage; 2];
+        "tiered_hot"
+    )]
+    #[test_case(
+        AccountsFileProvider::ThresholdBySlotAge {
+            boundary_slots: 10,
+            write_options: WriteOptions::LATEST,
+        },
+        [AccountsFileKind::TieredStorage, AccountsFileKind::AppendVec];
+        "threshold_by_slot_age"
+    )]
+    fn test_accounts_file_provider(
+        accounts_file_provider: AccountsFileProvider,
+        expected_kinds: [AccountsFileKind; 2],
+    ) {
+        let mut db = AccountsDb::new_single_for_tests();
+        db.accounts_file_provider = accounts_file_provider;
+
+        let slots = [0, 10];
+        let accounts: Vec<_> = slots
+            .iter()
+            .map(|slot| {
+                let account = AccountSharedData::new(slot + 1, 8, &Pubkey::new_unique());
+                (Pubkey::new_unique(), account)
+            })
+            .collect();
+        for (slot, (key, account)) in slots.iter().zip(&accounts) {
+            db.store_cached((*slot, &[(key, account)][..]), None);
+            db.add_root(*slot);
+        }
+        // both slots are flushed once the last one is rooted, so the age of
+        // each slot is measured against the same root
+        db.flush_root_write_cache(slots[1]);
+
+        for ((slot, (key, account)), expected_kind) in
+            slots.into_iter().zip(accounts).zip(expected_kinds)
+        {
+            let storage = db.storage.get_slot_storage_entry(slot).unwrap();
+            assert_eq!(storage.accounts.kind(), expected_kind);
+            assert_eq!(
+                db.load_without_fixed_root(&Ancestors::default(), &key),
+                Some((account, slot))
+            );
+        }
+    }
+
+    #[test]
+    fn test_store_accounts_tiered_write_failure() {
+        let mut db = AccountsDb::new_single_for_tests();
+        db.accounts_file_provider = AccountsFileProvider::AlwaysTieredHot {
+            write_options: WriteOptions::default(),
+        };
+        let slot = 0;
+        let key = Pubkey::new_unique();
+        let account = AccountSharedData::new(1, 8, &Pubkey::new_unique());
+        db.store_cached((slot, &[(&key, &account)][..]), None);
+
+        // the file of the next storage already exists in every accounts
+        // directory, so the first write of the slot fails
+        let failed_id = db.next_id.load(Ordering::Acquire);
+        let existing_paths: Vec<_> = db
+            .paths
+            .iter()
+            .map(|path| path.join(AccountsFile::file_name(slot, failed_id)))
+            .collect();
+        for path in &existing_paths {
+            std::fs::write(path, b"existing").unwrap();
+        }
+        db.add_root_and_flush_write_cache(slot);
+
+        // test: the accounts are stored in a new storage of the slot
+        let storage = db.storage.get_slot_storage_entry(slot).unwrap();
+        assert_ne!(storage.append_vec_id(), failed_id);
+        assert_eq!(storage.accounts.kind(), AccountsFileKind::TieredStorage);
+        assert_eq!(storage.count(), 1);
+        assert_eq!(
+            db.load_without_fixed_root(&Ancestors::default(), &key),
+            Some((account, slot))
+        );
+
+        // test: the existing files are left untouched
+        for path in &existing_paths {
+            assert_eq!(std::fs::read(path).unwrap(), b"existing");
+        }
+    }
 }
@@ -8,11 +8,18 @@ use {
         append_vec::{AppendVec, AppendVecError},
         storable_accounts::StorableAccounts,
         tiered_storage::{
-            error::TieredStorageError, hot::HOT_FORMAT, index::IndexOffset, TieredStorage,
+            error::TieredStorageError, file::MmapAccessPattern, footer::AccountMetaFormat,
+            generation::WriteOptions, hot::HOT_FORMAT, index::IndexOffset, is_tiered_storage_file,
+            probe_account_meta_format, readable::TieredStorageAccountIter, TieredStorage,
         },
     },
+    log::*,
     solana_sdk::{account::ReadableAccount, clock::Slot, pubkey::Pubkey},
-    std::{borrow::Borrow, mem, path::PathBuf},
+    std::{
+        borrow::Borrow,
+        mem,
+        path::{Path, PathBuf},
+    },
     thiserror::Error,
 };
 
@@ -49,6 +56,142 @@ pub enum MatchAccountOwnerError {
 
 pub type Result<T> = std::result::Result<T, AccountsFileError>;
 
+/// Returns the IndexOffset of the TieredStorage account at the specified
+/// offset of its AccountsFile, or None if no account can be at the offset.
+///
+/// AccountInfo assumes every offset is a multiple of ALIGN_BOUNDARY_OFFSET,
+/// so the offset of a TieredStorage account is its IndexOffset scaled by
+/// ALIGN_BOUNDARY_OFFSET, i.e. the IndexOffset is the reduced offset of its
+/// AccountInfo.  Every offset taken or returned by AccountsFile for a
+/// TieredStorage follows this convention.
+pub(crate) fn tiered_index_offset(offset: usize) -> Option<IndexOffset> {
+    if offset % ALIGN_BOUNDARY_OFFSET != 0 {
+        return None;
+    }
+    u32::try_from(offset / ALIGN_BOUNDARY_OFFSET)
+        .ok()
+        .map(IndexOffset)
+}
+
+/// Returns the offset in its AccountsFile of the TieredStorage account at
+/// the specified IndexOffset.  See tiered_index_offset().
+pub(crate) fn tiered_offset(index_offset: IndexOffset) -> usize {
+    index_offset.0 as usize * ALIGN_BOUNDARY_OFFSET
+}
+
+/// The fields of an account that building the accounts index needs, along
+/// with the offset of the account in its AccountsFile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountIndexEntry {
+    pub pubkey: Pubkey,
+    pub offset: usize,
+    pub lamports: u64,
+    pub data_len: usize,
+}
+
+/// The kind of the underlying storage of an AccountsFile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountsFileKind {
+    AppendVec,
+    TieredStorage,
+}
+
+/// The on-disk format of an AccountsFile.
+///
+/// Unlike AccountsFileKind, this also tells apart the account meta formats
+/// of tiered storage files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountsFileFormat {
+    AppendVec,
+    TieredHot,
+}
+
+impl From<AccountMetaFormat> for AccountsFileFormat {
+    fn from(account_meta_format: AccountMetaFormat) -> Self {
+        match account_meta_format {
+            AccountMetaFormat::Hot => Self::TieredHot,
+        }
+    }
+}
+
+/// Detects the format of the accounts file at the specified path.
+///
+/// Only the tail of the file is read, so this is cheap enough for tooling to
+/// run over a whole snapshot.  An AppendVec has no magic number, so any file
+/// that does not end with the tiered storage magic number is reported as an
+/// AppendVec without validating its content.  A file that does end with the
+/// magic number but whose footer cannot be parsed is an error.
+pub fn detect_format(path: impl AsRef<Path>) -> Result<AccountsFileFormat> {
+    Ok(probe_account_meta_format(path)?
+        .map_or(AccountsFileFormat::AppendVec, AccountsFileFormat::from))
+}
+
+/// Decides which kind of AccountsFile is created for a new storage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AccountsFileProvider {
+    /// Always creates an AppendVec.
+    #[default]
+    AlwaysAppendVec,
+    /// Always creates a TieredStorage in the hot format, written with the
+    /// specified options.
+    AlwaysTieredHot { write_options: WriteOptions },
+    /// Creates a TieredStorage in the hot format, written with the specified
+    /// options, for slots that are at least `boundary_slots` older than the
+    /// latest root, and an AppendVec for all the other slots.
+    ThresholdBySlotAge {
+        boundary_slots: Slot,
+        write_options: WriteOptions,
+    },
+}
+
+impl AccountsFileProvider {
+    /// Creates a new writable AccountsFile for the specified slot at the
+    /// specified path.
+    ///
+    /// `max_root` is the latest root, against which the age of the slot is
+    /// measured.  `size_hint` is the capacity of an AppendVec, and is ignored
+    /// for TieredStorage as its size is determined when the accounts are
+    /// written.
+    pub fn create(
+        &self,
+        slot: Slot,
+        max_root: Slot,
+        path: impl Into<PathBuf>,
+        size_hint: usize,
+    ) -> AccountsFile {
+        match self.tiered_write_options(slot, max_root) {
+            None => AccountsFile::AppendVec(AppendVec::new(path, true, size_hint)),
+            // The hot format is used as AccountsFile::append_accounts()
+            // writes tiered storages in HOT_FORMAT.
+            Some(write_options) => AccountsFile::TieredStorage(
+                TieredStorage::new_writable_with_options(path, write_options),
+            ),
+        }
+    }
+
+    /// Returns the kind of AccountsFile that is created for the specified slot
+    /// while `max_root` is the latest root.
+    pub fn kind_for_slot(&self, slot: Slot, max_root: Slot) -> AccountsFileKind {
+        match self.tiered_write_options(slot, max_root) {
+            None => AccountsFileKind::AppendVec,
+            Some(_) => AccountsFileKind::TieredStorage,
+        }
+    }
+
+    /// Returns the options a TieredStorage created for the specified slot is
+    /// written with, or None if an AppendVec is created for the slot.
+    fn tiered_write_options(&self, slot: Slot, max_root: Slot) -> Option<WriteOptions> {
+        match self {
+            Self::AlwaysAppendVec => None,
+            Self::AlwaysTieredHot { write_options } => Some(*write_options),
+            Self::ThresholdBySlotAge {
+                boundary_slots,
+                write_options,
+            } => (max_root.saturating_sub(slot) >= *boundary_slots).then_some(*write_options),
+        }
+    }
+}
+
 #[derive(Debug)]
 /// An enum for accessing an accounts file which can be implemented
 /// under different formats.
@@ -62,11 +205,59 @@ impl AccountsFile {
     ///
     /// The second element of the returned tuple is the number of accounts in the
     /// accounts file.
+    ///
+    /// The format is determined by probing the trailing magic number of the
+    /// file once.  A file that cannot be probed, e.g. one that is too short,
+    /// is opened as an AppendVec, which reports the actual error.
+    ///
+    /// For a tiered storage file, `current_len` is the size of the file when
+    /// it was archived, so a shorter file is reported as truncated.
     pub fn new_from_file(path: impl Into<PathBuf>, current_len: usize) -> Result<(Self, usize)> {
+        let path = path.into();
+        if is_tiered_storage_file(&path).unwrap_or(false) {
+            let mut tiered_storage = TieredStorage::new_readonly(path)?;
+            if tiered_storage.len() < current_len {
+                // the file is left on disk, as the caller still owns it
+                tiered_storage.set_remove_on_drop(false);
+                return Err(TieredStorageError::Truncated {
+                    expected_min: current_len as u64,
+                    actual: tiered_storage.len() as u64,
+                }
+                .into());
+            }
+            let num_accounts = tiered_storage
+                .reader()
+                .map_or(0, |reader| reader.num_accounts());
+            return Ok((Self::TieredStorage(tiered_storage), num_accounts));
+        }
         let (av, num_accounts) = AppendVec::new_from_file(path, current_len)?;
         Ok((Self::AppendVec(av), num_accounts))
     }
 
+    /// Returns the kind of the underlying storage.
+    pub fn kind(&self) -> AccountsFileKind {
+        match self {
+            Self::AppendVec(_) => AccountsFileKind::AppendVec,
+            Self::TieredStorage(_) => AccountsFileKind::TieredStorage,
+        }
+    }
+
+    /// Returns the on-disk format of the underlying storage.
+    ///
+    /// A tiered storage that has not been written yet reports the format it
+    /// will be written in.
+    pub fn format(&self) -> AccountsFileFormat {
+        match self {
+            Self::AppendVec(_) => AccountsFileFormat::AppendVec,
+            Self::TieredStorage(ts) => ts
+                .reader()
+                .map_or(HOT_FORMAT.account_meta_format, |reader| {
+                    reader.footer().account_meta_format
+                })
+                .into(),
+        }
+    }
+
     pub fn flush(&self) -> Result<()> {
         match self {
             Self::AppendVec(av) => av.flush(),
@@ -74,10 +265,26 @@ impl AccountsFile {
         }
     }
 
-    pub fn reset(&self) {
+    /// Returns whether reset() can make this AccountsFile store other
+    /// accounts once all of its accounts are removed.
+    pub fn is_recyclable(&self) -> bool {
+        match self {
+            Self::AppendVec(_) => true,
+            Self::TieredStorage(_) => false,
+        }
+    }
+
+    /// Resets this AccountsFile so that it can store other accounts.
+    ///
+    /// A TieredStorage cannot be reset, in which case an error is returned
+    /// and the caller should create a new AccountsFile instead.
+    pub fn reset(&self) -> Result<()> {
         match self {
-            Self::AppendVec(av) => av.reset(),
-            Self::TieredStorage(_) => {}
+            Self::AppendVec(av) => {
+                av.reset();
+                Ok(())
+            }
+            Self::TieredStorage(ts) => Ok(ts.reset_for_recycle()?),
         }
     }
 
@@ -109,6 +316,117 @@ impl AccountsFile {
         }
     }
 
+    /// Returns the number of bytes each account occupies in the file, in the
+    /// order of the accounts, which allows the alive bytes to be summed up
+    /// without loading any account.
+    ///
+    /// An AppendVec has no index, so its accounts are loaded to obtain their
+    /// stored sizes.
+    pub fn account_sizes(&self) -> Result<Vec<usize>> {
+        match self {
+            Self::AppendVec(av) => Ok(av
+                .accounts(0)
+                .iter()
+                .map(StoredAccountMeta::stored_size)
+                .collect()),
+            Self::TieredStorage(ts) => Ok(ts
+                .reader()
+                .map(|reader| reader.account_sizes())
+                .transpose()?
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Returns the total number of bytes occupied by the accounts in the
+    /// file.  See account_sizes().
+    pub fn total_account_bytes(&self) -> Result<usize> {
+        match self {
+            // the accounts of an AppendVec are stored contiguously
+            Self::AppendVec(av) => Ok(av.len()),
+            Self::TieredStorage(ts) => Ok(ts
+                .reader()
+                .map(|reader| reader.total_account_bytes())
+                .transpose()?
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Returns the address, the offset, the lamports and the data length of
+    /// every account in the file, in the order of the accounts.
+    ///
+    /// A TieredStorage obtains them from its index block and account metas
+    /// without reading the account data, while an AppendVec has no index,
+    /// so its accounts are scanned.
+    pub fn index_entries(&self) -> Result<Vec<AccountIndexEntry>> {
+        match self {
+            Self::AppendVec(av) => Ok(av
+                .account_iter()
+                .map(|account| AccountIndexEntry {
+                    pubkey: *account.pubkey(),
+                    offset: account.offset(),
+                    lamports: account.lamports(),
+                    data_len: account.data_len() as usize,
+                })
+                .collect()),
+            Self::TieredStorage(ts) => Ok(ts
+                .reader()
+                .map(|reader| reader.index_entries())
+                .transpose()?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| AccountIndexEntry {
+                    pubkey: entry.pubkey,
+                    offset: tiered_offset(entry.index_offset),
+                    lamports: entry.lamports,
+                    data_len: entry.data_len,
+                })
+                .collect()),
+        }
+    }
+
+    /// Sets whether the file is removed when this AccountsFile is dropped,
+    /// which is true by default.
+    ///
+    /// Only TieredStorage supports keeping its file.  An AppendVec always
+    /// removes its file on drop, so the toggle is ignored for it.
+    pub fn set_remove_on_drop(&mut self, remove_on_drop: bool) {
+        match self {
+            Self::AppendVec(_) => {}
+            Self::TieredStorage(ts) => ts.set_remove_on_drop(remove_on_drop),
+        }
+    }
+
+    /// Hints the kernel about how the content of the file will be accessed,
+    /// e.g. sequentially before a full scan.
+    ///
+    /// Only TieredStorage acts on the hint, so it is ignored for an
+    /// AppendVec.
+    pub fn advise(&self, access: MmapAccessPattern) -> Result<()> {
+        match self {
+            Self::AppendVec(_) => Ok(()),
+            Self::TieredStorage(ts) => Ok(ts.advise(access)?),
+        }
+    }
+
+    /// Makes the accounts at the specified offsets resident ahead of
+    /// loading them, e.g. for the accounts needed by upcoming transactions.
+    ///
+    /// Offsets that do not point to an account are skipped.  Only
+    /// TieredStorage acts on it, so it is a no-op for an AppendVec.
+    pub fn prefetch(&self, offsets: &[usize]) {
+        let Self::TieredStorage(ts) = self else {
+            return;
+        };
+        let Some(reader) = ts.reader() else {
+            return;
+        };
+        let index_offsets: Vec<_> = offsets
+            .iter()
+            .filter_map(|offset| tiered_index_offset(*offset))
+            .collect();
+        reader.prefetch(&index_offsets);
+    }
+
     pub fn file_name(slot: Slot, id: AccountsFileId) -> String {
         format!("{slot}.{id}")
     }
@@ -116,14 +434,29 @@ impl AccountsFile {
     /// Return (account metadata, next_index) pair for the account at the
     /// specified `index` if any.  Otherwise return None.   Also return the
     /// index of the next entry.
+    ///
+    /// For a TieredStorage, the index is the offset of the account as
+    /// described in tiered_index_offset().
     pub fn get_account(&self, index: usize) -> Option<(StoredAccountMeta<'_>, usize)> {
         match self {
             Self::AppendVec(av) => av.get_account(index),
-            Self::TieredStorage(ts) => ts
-                .reader()?
-                .get_account(IndexOffset(index as u32))
-                .ok()?
-                .map(|(metas, index_offset)| (metas, index_offset.0 as usize)),
+            Self::TieredStorage(ts) => {
+                let index_offset = tiered_index_offset(index)?;
+                match ts.reader()?.get_account(index_offset) {
+                    Ok(account) => {
+                        account.map(|(metas, index_offset)| (metas, tiered_offset(index_offset)))
+                    }
+                    Err(err) => {
+                        // a corrupted file must not bring down the account
+                        // load path, so the account is reported as missing
+                        error!(
+                            "failed to load account {index_offset:?} from {}: {err}",
+                            ts.path().display()
+                        );
+                        None
+                    }
+                }
+            }
         }
     }
 
@@ -138,7 +471,9 @@ impl AccountsFile {
                 let Some(reader) = ts.reader() else {
                     return Err(MatchAccountOwnerError::UnableToLoad);
                 };
-                reader.account_matches_owners(IndexOffset(offset as u32), owners)
+                let index_offset =
+                    tiered_index_offset(offset).ok_or(MatchAccountOwnerError::UnableToLoad)?;
+                reader.account_matches_owners(index_offset, owners)
             }
         }
     }
@@ -162,7 +497,8 @@ impl AccountsFile {
             Self::AppendVec(av) => av.accounts(offset),
             Self::TieredStorage(ts) => ts
                 .reader()
-                .and_then(|reader| reader.accounts(IndexOffset(offset as u32)).ok())
+                .zip(tiered_index_offset(offset))
+                .and_then(|(reader, index_offset)| reader.accounts(index_offset).ok())
                 .unwrap_or_default(),
         }
     }
@@ -174,6 +510,9 @@ impl AccountsFile {
     /// So, return.len() is 1 + (number of accounts written)
     /// After each account is appended, the internal `current_len` is updated
     /// and will be available to other threads.
+    ///
+    /// An error is returned if the accounts cannot be written, which only
+    /// happens to a TieredStorage.
     pub fn append_accounts<
         'a,
         'b,
@@ -184,48 +523,152 @@ impl AccountsFile {
         &self,
         accounts: &StorableAccountsWithHashesAndWriteVersions<'a, 'b, T, U, V>,
         skip: usize,
-    ) -> Option<Vec<StoredAccountInfo>> {
+    ) -> Result<Option<Vec<StoredAccountInfo>>> {
         match self {
-            Self::AppendVec(av) => av.append_accounts(accounts, skip),
+            Self::AppendVec(av) => Ok(av.append_accounts(accounts, skip)),
             // Currently we only support HOT_FORMAT.  If we later want to use
             // a different format, then we will need a way to pass-in it.
             // TODO: consider adding function like write_accounts_to_hot_storage() or something
             // to hide implementation detail.
-            Self::TieredStorage(ts) => ts.write_accounts(accounts, skip, &HOT_FORMAT).ok(),
+            //
+            // A TieredStorage is written only once, so it has no room left
+            // after its first write, which may have written only part of the
+            // accounts.  Like a full AppendVec, None asks the caller to write
+            // the rest into another file.
+            Self::TieredStorage(ts) if ts.is_written() => Ok(None),
+            Self::TieredStorage(ts) => {
+                let mut stored_infos = ts.write_accounts(accounts, skip, &HOT_FORMAT)?;
+                for stored_info in &mut stored_infos {
+                    stored_info.offset = tiered_offset(IndexOffset(stored_info.offset as u32));
+                }
+                Ok(Some(stored_infos))
+            }
         }
     }
 }
 
 pub struct AccountsFileIter<'a> {
-    file_entry: &'a AccountsFile,
-    offset: usize,
+    inner: AccountsFileIterInner<'a>,
+    /// The number of accounts yielded so far.
+    scanned: usize,
+    /// The error that terminated the iteration early, if any.
+    error: Option<AccountsFileError>,
+}
+
+/// The format-specific state of an AccountsFileIter.
+enum AccountsFileIterInner<'a> {
+    /// AppendVec returns the offset to the next account from get_account().
+    AppendVec { av: &'a AppendVec, offset: usize },
+    /// TieredStorage iterates its accounts in index order via its reader.
+    /// None is used when the TieredStorage has not been written yet.
+    TieredStorage(Option<TieredStorageAccountIter<'a>>),
 }
 
 impl<'a> AccountsFileIter<'a> {
     pub fn new(file_entry: &'a AccountsFile) -> Self {
+        let inner = match file_entry {
+            AccountsFile::AppendVec(av) => AccountsFileIterInner::AppendVec { av, offset: 0 },
+            AccountsFile::TieredStorage(ts) => AccountsFileIterInner::TieredStorage(
+                ts.reader().map(|reader| reader.account_iter()),
+            ),
+        };
         Self {
-            file_entry,
-            offset: 0,
+            inner,
+            scanned: 0,
+            error: None,
         }
     }
+
+    /// Returns the error that terminated the iteration before all the
+    /// accounts were visited, if any.
+    ///
+    /// A None return value after the iteration ends indicates the underlying
+    /// accounts file has been fully scanned.
+    pub fn take_error(&mut self) -> Option<AccountsFileError> {
+        self.error.take()
+    }
+
+    /// Returns the number of accounts that have been yielded so far.
+    pub fn scanned(&self) -> usize {
+        self.scanned
+    }
 }
 
 impl<'a> Iterator for AccountsFileIter<'a> {
     type Item = StoredAccountMeta<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((account, next_offset)) = self.file_entry.get_account(self.offset) {
-            self.offset = next_offset;
-            Some(account)
-        } else {
-            None
+        let account = match &mut self.inner {
+            AccountsFileIterInner::AppendVec { av, offset } => match av.get_account(*offset) {
+                Some((account, next_offset)) => {
+                    *offset = next_offset;
+                    Some(account)
+                }
+                None => {
+                    // A well-formed AppendVec is fully consumed once there
+                    // are no more accounts to read.
+                    if *offset < av.len() {
+                        self.error = Some(AppendVecError::IncorrectLayout(av.get_path()).into());
+                    }
+                    None
+                }
+            },
+            AccountsFileIterInner::TieredStorage(iter) => {
+                let iter = iter.as_mut()?;
+                let account = iter.next();
+                if let Some(err) = iter.take_error() {
+                    self.error = Some(err.into());
+                }
+                account
+            }
+        };
+        if account.is_some() {
+            self.scanned += 1;
         }
+        account
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use crate::accounts_file::AccountsFile;
+    use {
+        crate::{
+            account_info::{AccountInfo, StorageLocation},
+            account_storage::meta::DEFAULT_ACCOUNT_HASH,
+            accounts_file::{
+                detect_format, tiered_offset, AccountsFile, AccountsFileError, AccountsFileFormat,
+                AccountsFileKind, AccountsFileProvider, MatchAccountOwnerError,
+                ALIGN_BOUNDARY_OFFSET,
+            },
+            accounts_hash::AccountHash,
+            append_vec::AppendVec,
+            tiered_storage::{
+                error::TieredStorageError,
+                file::{MmapAccessPattern, TieredStorageMagicNumber},
+                generation::{FormatGeneration, WriteOptions},
+                hot::{
+                    tests::{corrupt_owner_offset, overwrite_account_offset},
+                    HotAccountOffset, HOT_ACCOUNT_ALIGNMENT,
+                },
+                index::IndexOffset,
+                is_tiered_storage_file,
+                test_fixtures::remove_bytes_before_footer,
+                test_utils::{
+                    create_executable_account_without_data, create_test_account,
+                    with_storable_accounts, write_hot_storage,
+                },
+                TieredStorage, MAX_TIERED_FILE_SIZE,
+            },
+        },
+        assert_matches::assert_matches,
+        solana_sdk::{
+            account::{AccountSharedData, ReadableAccount},
+            clock::Slot,
+            hash::Hash,
+        },
+        tempfile::TempDir,
+    };
+
     impl AccountsFile {
         pub(crate) fn set_current_len_for_tests(&self, len: usize) {
             match self {
@@ -234,4 +677,799 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_tiered_storage_account_iter() {
+        let temp_dir = TempDir::new().unwrap();
+        for (i, account_data_sizes) in [&[][..], &[7], &[1, 2, 3, 0, 1000, 4, 5, 6, 7, 8]]
+            .into_iter()
+            .enumerate()
+        {
+            let path = temp_dir
+                .path()
+                .join(format!("test_tiered_storage_account_iter_{i}"));
+            write_hot_storage(&path, account_data_sizes);
+            let accounts_file =
+                AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+            let AccountsFile::TieredStorage(ts) = &accounts_file else {
+                unreachable!();
+            };
+            let reader = ts.reader().unwrap();
+            assert_eq!(reader.num_accounts(), account_data_sizes.len());
+            assert_eq!(reader.account_iter().count(), reader.num_accounts());
+            assert_eq!(accounts_file.account_iter().count(), reader.num_accounts());
+
+            // Both iterators should yield the same accounts in index order.
+            for (account, expected) in accounts_file
+                .account_iter()
+                .zip(reader.accounts(IndexOffset(0)).unwrap())
+            {
+                assert_eq!(account, expected);
+            }
+        }
+    }
+
+    /// Both backends must agree on the accessors and the sanitization of
+    /// the same accounts.
+    #[test]
+    fn test_executable_without_data_across_backends() {
+        let accounts = [
+            create_executable_account_without_data(),
+            create_test_account(1),
+            create_test_account(2),
+            // zero-lamport accounts are stored as the default account
+            create_test_account(0),
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("append_vec"),
+            true,
+            1024 * 1024,
+        ));
+        let tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("tiered_storage"),
+        ));
+        for accounts_file in [&append_vec, &tiered_storage] {
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+        }
+
+        let mut num_compared = 0;
+        for (((stored_meta, account), av_account), ts_account) in accounts
+            .iter()
+            .zip(append_vec.account_iter())
+            .zip(tiered_storage.account_iter())
+        {
+            let expected = if account.lamports() == 0 {
+                AccountSharedData::default()
+            } else {
+                account.clone()
+            };
+            for stored_account in [&av_account, &ts_account] {
+                assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+                assert_eq!(stored_account.lamports(), expected.lamports());
+                assert_eq!(stored_account.owner(), expected.owner());
+                assert_eq!(stored_account.data(), expected.data());
+                assert_eq!(stored_account.executable(), expected.executable());
+                // an executable account without data is sane
+                assert!(stored_account.sanitize());
+            }
+            assert_eq!(ts_account.rent_epoch(), av_account.rent_epoch());
+            num_compared += 1;
+        }
+        assert_eq!(num_compared, accounts.len());
+    }
+
+    /// Both backends must return the persisted hash of an account, and the
+    /// default hash of an account that was written without one.
+    #[test]
+    fn test_account_hash_across_backends() {
+        let accounts: Vec<_> = (1..=4).map(create_test_account).collect();
+        let hashes: Vec<_> = (0..accounts.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    AccountHash(Hash::new_unique())
+                } else {
+                    AccountHash(Hash::default())
+                }
+            })
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("append_vec"),
+            true,
+            1024 * 1024,
+        ));
+        let tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable_with_options(
+            temp_dir.path().join("tiered_storage"),
+            WriteOptions::LATEST,
+        ));
+        // the baseline generation does not persist the account hashes
+        let baseline_tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("baseline_tiered_storage"),
+        ));
+        for (accounts_file, has_hashes) in [
+            (&append_vec, true),
+            (&tiered_storage, true),
+            (&baseline_tiered_storage, false),
+        ] {
+            let stored_infos = with_storable_accounts(
+                &accounts,
+                hashes.clone(),
+                vec![0; accounts.len()],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(stored_infos.len(), accounts.len());
+            for ((stored_info, (stored_meta, _)), hash) in
+                stored_infos.iter().zip(&accounts).zip(&hashes)
+            {
+                let (stored_account, _) = accounts_file.get_account(stored_info.offset).unwrap();
+                assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+                if has_hashes {
+                    assert_eq!(stored_account.hash(), hash);
+                } else {
+                    assert_eq!(stored_account.hash(), &*DEFAULT_ACCOUNT_HASH);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_offsets_across_entry_points() {
+        const NUM_ACCOUNTS: usize = 10;
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS as u64).map(create_test_account).collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("append_vec"),
+            true,
+            1024 * 1024,
+        ));
+        let tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("tiered_storage"),
+        ));
+        for accounts_file in [&append_vec, &tiered_storage] {
+            let kind = accounts_file.kind();
+            let stored_infos = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); NUM_ACCOUNTS],
+                vec![0; NUM_ACCOUNTS],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(stored_infos.len(), NUM_ACCOUNTS);
+
+            // test: the offset returned by append_accounts() is accepted by
+            // every entry point, and survives the round trip via AccountInfo
+            for (i, (stored_info, (stored_meta, account))) in
+                stored_infos.iter().zip(&accounts).enumerate()
+            {
+                let offset = stored_info.offset;
+                let account_info =
+                    AccountInfo::new(StorageLocation::AppendVec(0, offset), account.lamports());
+                assert_eq!(account_info.offset(), offset, "{kind:?}");
+
+                let (stored_account, next_offset) = accounts_file.get_account(offset).unwrap();
+                assert_eq!(stored_account.pubkey(), &stored_meta.pubkey, "{kind:?}");
+                assert_eq!(stored_account.offset(), offset, "{kind:?}");
+                if let Some(next_info) = stored_infos.get(i + 1) {
+                    assert_eq!(next_offset, next_info.offset, "{kind:?}");
+                }
+
+                let remaining_accounts = accounts_file.accounts(offset);
+                assert_eq!(remaining_accounts.len(), NUM_ACCOUNTS - i, "{kind:?}");
+                assert_eq!(remaining_accounts[0].pubkey(), &stored_meta.pubkey);
+
+                assert_eq!(
+                    accounts_file.account_matches_owners(offset, &[*account.owner()]),
+                    Ok(0),
+                    "{kind:?}"
+                );
+            }
+        }
+
+        // test: an offset that is not a multiple of ALIGN_BOUNDARY_OFFSET is
+        // gracefully rejected instead of being reduced to another account
+        let invalid_offset = tiered_offset(IndexOffset(1)) + 1;
+        assert!(tiered_storage.get_account(invalid_offset).is_none());
+        assert!(tiered_storage.accounts(invalid_offset).is_empty());
+        assert_eq!(
+            tiered_storage.account_matches_owners(invalid_offset, &[*accounts[1].1.owner()]),
+            Err(MatchAccountOwnerError::UnableToLoad)
+        );
+    }
+
+    #[test]
+    fn test_index_entries_across_backends() {
+        // zero-lamport accounts, and data sizes that need padding
+        let accounts: Vec<_> = (0..20).map(create_test_account).collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("append_vec"),
+            true,
+            1024 * 1024,
+        ));
+        let tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("tiered_storage"),
+        ));
+        for accounts_file in [&append_vec, &tiered_storage] {
+            // nothing is written yet
+            assert!(accounts_file.index_entries().unwrap().is_empty());
+
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+            let entries = accounts_file.index_entries().unwrap();
+            let expected: Vec<_> = accounts_file
+                .account_iter()
+                .map(|account| AccountIndexEntry {
+                    pubkey: *account.pubkey(),
+                    offset: account.offset(),
+                    lamports: account.lamports(),
+                    data_len: account.data().len(),
+                })
+                .collect();
+            assert_eq!(entries, expected);
+            assert_eq!(entries.len(), accounts.len());
+
+            // every offset loads the account of the entry
+            for entry in &entries {
+                let (account, _) = accounts_file.get_account(entry.offset).unwrap();
+                assert_eq!(account.pubkey(), &entry.pubkey);
+            }
+        }
+    }
+
+    #[test]
+    fn test_accounts_file_provider_write_options() {
+        // the latest root, against which the age of each slot is measured
+        const MAX_ROOT: Slot = 20;
+        let accounts: Vec<_> = (0..4).map(create_test_account).collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        for (name, provider, slot, expected_generation) in [
+            (
+                "always_tiered_hot_default",
+                AccountsFileProvider::AlwaysTieredHot {
+                    write_options: WriteOptions::default(),
+                },
+                10,
+                Some(FormatGeneration::V1Baseline),
+            ),
+            (
+                "always_tiered_hot_latest",
+                AccountsFileProvider::AlwaysTieredHot {
+                    write_options: WriteOptions::LATEST,
+                },
+                10,
+                Some(FormatGeneration::V2Footer),
+            ),
+            (
+                "threshold_by_slot_age_tiered",
+                AccountsFileProvider::ThresholdBySlotAge {
+                    boundary_slots: 10,
+                    write_options: WriteOptions::LATEST,
+                },
+                MAX_ROOT - 10,
+                Some(FormatGeneration::V2Footer),
+            ),
+            (
+                "threshold_by_slot_age_append_vec",
+                AccountsFileProvider::ThresholdBySlotAge {
+                    boundary_slots: 10,
+                    write_options: WriteOptions::LATEST,
+                },
+                MAX_ROOT - 9,
+                None,
+            ),
+            (
+                "threshold_by_slot_age_unrooted",
+                AccountsFileProvider::ThresholdBySlotAge {
+                    boundary_slots: 10,
+                    write_options: WriteOptions::LATEST,
+                },
+                MAX_ROOT + 1,
+                None,
+            ),
+        ] {
+            let accounts_file =
+                provider.create(slot, MAX_ROOT, temp_dir.path().join(name), 1024 * 1024);
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+
+            // test: the tiered storages are written with the options of the
+            // provider, which the footer version reflects for HOT_FORMAT
+            let generation = match &accounts_file {
+                AccountsFile::AppendVec(_) => None,
+                AccountsFile::TieredStorage(ts) => {
+                    Some(FormatGeneration::required_by(ts.reader().unwrap().footer()))
+                }
+            };
+            assert_eq!(generation, expected_generation, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_account_sizes_across_backends() {
+        let accounts: Vec<_> = (0..10).map(create_test_account).collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("append_vec"),
+            true,
+            1024 * 1024,
+        ));
+        let tiered_storage = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("tiered_storage"),
+        ));
+        for accounts_file in [&append_vec, &tiered_storage] {
+            // nothing is written yet
+            assert!(accounts_file.account_sizes().unwrap().is_empty());
+            assert_eq!(accounts_file.total_account_bytes().unwrap(), 0);
+
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+            .unwrap();
+            let account_sizes = accounts_file.account_sizes().unwrap();
+            assert_eq!(account_sizes.len(), accounts.len());
+            assert_eq!(
+                account_sizes.iter().sum::<usize>(),
+                accounts_file.total_account_bytes().unwrap()
+            );
+            assert!(accounts_file.total_account_bytes().unwrap() <= accounts_file.len());
+        }
+    }
+
+    #[test]
+    fn test_new_from_file_legacy_append_vec() {
+        let accounts = [create_test_account(1), create_test_account(2)];
+
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec_path = temp_dir.path().join("append_vec");
+        let append_vec = AppendVec::new(&append_vec_path, true, 1024 * 1024);
+        with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| append_vec.append_accounts(storable_accounts, 0),
+        )
+        .unwrap();
+        append_vec.flush().unwrap();
+        let current_len = append_vec.len();
+        // the file of an AppendVec is removed on drop, so open a copy of it
+        let path = temp_dir.path().join("test_new_from_file_legacy_append_vec");
+        std::fs::copy(&append_vec_path, &path).unwrap();
+
+        // a legacy file is identified as not being a tiered storage file
+        assert!(!is_tiered_storage_file(&path).unwrap());
+        assert_matches!(
+            TieredStorage::new_readonly(&path),
+            Err(TieredStorageError::MagicNumberMismatch(_, 0))
+        );
+
+        let (accounts_file, num_accounts) =
+            AccountsFile::new_from_file(&path, current_len).unwrap();
+        assert_eq!(accounts_file.kind(), AccountsFileKind::AppendVec);
+        assert_eq!(accounts_file.format(), AccountsFileFormat::AppendVec);
+        assert_eq!(detect_format(&path).unwrap(), AccountsFileFormat::AppendVec);
+        assert_eq!(num_accounts, accounts.len());
+    }
+
+    #[test]
+    fn test_new_from_file_tiered_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_new_from_file_tiered_storage");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(is_tiered_storage_file(&path).unwrap());
+
+        let (accounts_file, num_accounts) = AccountsFile::new_from_file(&path, file_len).unwrap();
+        assert_eq!(accounts_file.kind(), AccountsFileKind::TieredStorage);
+        assert_eq!(accounts_file.format(), AccountsFileFormat::TieredHot);
+        assert_eq!(detect_format(&path).unwrap(), AccountsFileFormat::TieredHot);
+        assert_eq!(num_accounts, 3);
+        assert_eq!(accounts_file.len(), file_len);
+    }
+
+    #[test]
+    fn test_detect_format_garbage() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // test: a file without the magic number is assumed to be an AppendVec
+        let path = temp_dir.path().join("test_detect_format_garbage");
+        std::fs::write(&path, vec![0xab; 1024]).unwrap();
+        assert_eq!(detect_format(&path).unwrap(), AccountsFileFormat::AppendVec);
+
+        // test: a file that ends with the magic number must have a footer
+        let mut bytes = vec![0xab; 1024];
+        bytes.extend_from_slice(bytemuck::bytes_of(&TieredStorageMagicNumber::default()));
+        std::fs::write(&path, bytes).unwrap();
+        assert!(is_tiered_storage_file(&path).unwrap());
+        assert_matches!(
+            detect_format(&path),
+            Err(AccountsFileError::TieredStorageError(_))
+        );
+
+        // test: a missing file cannot be probed
+        let missing_path = temp_dir.path().join("test_detect_format_missing");
+        assert_matches!(
+            detect_format(missing_path),
+            Err(AccountsFileError::TieredStorageError(
+                TieredStorageError::Io(_)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_new_from_file_truncated_tiered_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_new_from_file_truncated_tiered_storage");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+
+        // test: the file is shorter than the length recorded by the snapshot
+        assert_matches!(
+            AccountsFile::new_from_file(&path, file_len + 8),
+            Err(AccountsFileError::TieredStorageError(
+                TieredStorageError::Truncated {
+                    expected_min,
+                    actual,
+                }
+            )) if expected_min == file_len as u64 + 8 && actual == file_len as u64
+        );
+        assert!(path.try_exists().unwrap());
+
+        // test: the blocks before the intact footer are partially missing
+        for cut in [0, 8, 64] {
+            let cut_path = temp_dir
+                .path()
+                .join(format!("test_new_from_file_truncated_tiered_storage_{cut}"));
+            std::fs::copy(&path, &cut_path).unwrap();
+            remove_bytes_before_footer(&cut_path, cut);
+            let cut_len = std::fs::metadata(&cut_path).unwrap().len() as usize;
+            assert_matches!(
+                AccountsFile::new_from_file(&cut_path, cut_len),
+                Err(AccountsFileError::TieredStorageError(
+                    TieredStorageError::Truncated { actual, .. }
+                )) if actual == cut_len as u64
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_from_file_short_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for len in [0, 4] {
+            let path = temp_dir
+                .path()
+                .join(format!("test_new_from_file_short_files_{len}"));
+            std::fs::write(&path, vec![0xff; len]).unwrap();
+
+            // too short to hold the magic number, so it is not tiered and is
+            // left to the AppendVec to reject
+            assert!(!is_tiered_storage_file(&path).unwrap());
+            assert_matches!(
+                AccountsFile::new_from_file(&path, len),
+                Err(AccountsFileError::AppendVecError(_))
+            );
+        }
+    }
+
+    #[test]
+    fn test_tiered_storage_out_of_range_index() {
+        const NUM_ACCOUNTS: usize = 5;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_tiered_storage_out_of_range_index");
+        write_hot_storage(&path, &[1; NUM_ACCOUNTS]);
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+
+        let last_offset = tiered_offset(IndexOffset(NUM_ACCOUNTS as u32 - 1));
+        let (account, _) = accounts_file.get_account(last_offset).unwrap();
+        assert_eq!(
+            accounts_file.account_matches_owners(last_offset, &[*account.owner()]),
+            Ok(0)
+        );
+
+        // an out-of-range or misaligned offset never aliases another account,
+        // including the ones whose index does not fit in an IndexOffset
+        for offset in [
+            tiered_offset(IndexOffset(NUM_ACCOUNTS as u32)),
+            tiered_offset(IndexOffset(NUM_ACCOUNTS as u32 + 1)),
+            tiered_offset(IndexOffset(u32::MAX)),
+            (u32::MAX as usize + 1) * ALIGN_BOUNDARY_OFFSET,
+            1,
+            last_offset + 1,
+            usize::MAX,
+        ] {
+            assert!(accounts_file.get_account(offset).is_none());
+            assert!(accounts_file.accounts(offset).is_empty());
+            assert_eq!(
+                accounts_file.account_matches_owners(offset, &[*account.owner()]),
+                Err(MatchAccountOwnerError::UnableToLoad)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tiered_storage_corrupted_index_block() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_tiered_storage_corrupted_index_block");
+        write_hot_storage(&path, &[1, 2, 3]);
+        // the largest offset the index block can hold is far beyond the file
+        overwrite_account_offset(
+            &path,
+            IndexOffset(1),
+            HotAccountOffset::new(u32::MAX as usize * HOT_ACCOUNT_ALIGNMENT).unwrap(),
+        );
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+
+        // test: the corrupted account is missing instead of panicking
+        assert!(accounts_file
+            .get_account(tiered_offset(IndexOffset(1)))
+            .is_none());
+        // the other accounts are not affected
+        assert!(accounts_file
+            .get_account(tiered_offset(IndexOffset(2)))
+            .is_some());
+    }
+
+    #[test]
+    fn test_tiered_storage_len_after_file_removal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_tiered_storage_len_after_file_removal");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        assert_eq!(accounts_file.len(), file_len);
+
+        // The sizes are cached once the storage becomes read-only, so they
+        // remain available and unchanged without the file.
+        std::fs::remove_file(&path).unwrap();
+        for _ in 0..3 {
+            assert_eq!(accounts_file.len(), file_len);
+            assert!(!accounts_file.is_empty());
+            assert_eq!(accounts_file.capacity(), file_len as u64);
+            assert_eq!(accounts_file.remaining_bytes(), 0);
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_advise() {
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("test_advise_append_vec"),
+            true,
+            1024,
+        ));
+        let unwritten = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("test_advise_unwritten"),
+        ));
+        let path = temp_dir.path().join("test_advise_tiered_storage");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let tiered_storage =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+
+        for accounts_file in [&append_vec, &unwritten, &tiered_storage] {
+            assert_matches!(
+                accounts_file.advise(MmapAccessPattern::Sequential),
+                Ok(()),
+                "{:?}",
+                accounts_file.kind()
+            );
+            assert_matches!(accounts_file.advise(MmapAccessPattern::Random), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let temp_dir = TempDir::new().unwrap();
+        let append_vec = AccountsFile::AppendVec(AppendVec::new(
+            temp_dir.path().join("test_prefetch_append_vec"),
+            true,
+            1024,
+        ));
+        let unwritten = AccountsFile::TieredStorage(TieredStorage::new_writable(
+            temp_dir.path().join("test_prefetch_unwritten"),
+        ));
+        let path = temp_dir.path().join("test_prefetch_tiered_storage");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let tiered_storage =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+
+        for accounts_file in [&append_vec, &unwritten, &tiered_storage] {
+            let expected: Vec<_> = accounts_file
+                .accounts(0)
+                .iter()
+                .map(|account| (*account.pubkey(), account.to_account_shared_data()))
+                .collect();
+            // invalid offsets are skipped
+            accounts_file.prefetch(&[
+                0,
+                3,
+                tiered_offset(IndexOffset(2)),
+                tiered_offset(IndexOffset(3)),
+                u32::MAX as usize,
+                usize::MAX,
+            ]);
+            let accounts: Vec<_> = accounts_file
+                .accounts(0)
+                .iter()
+                .map(|account| (*account.pubkey(), account.to_account_shared_data()))
+                .collect();
+            assert_eq!(accounts, expected, "{:?}", accounts_file.kind());
+        }
+    }
+
+    #[test]
+    fn test_tiered_storage_set_remove_on_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_tiered_storage_set_remove_on_drop");
+        write_hot_storage(&path, &[1, 2, 3]);
+
+        let mut accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        accounts_file.set_remove_on_drop(false);
+        drop(accounts_file);
+        assert!(path.exists());
+
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        drop(accounts_file);
+        assert!(!path.exists());
+    }
+
+    /// Walks a TieredStorage through its lifecycle via AccountsFile, the way
+    /// AccountsDb creates, writes, reads, recycles and drops its storages.
+    #[test]
+    fn test_tiered_storage_lifecycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_tiered_storage_lifecycle");
+        let accounts: Vec<_> = (1..=3).map(create_test_account).collect();
+
+        // test: a new storage has not been written and accepts any accounts
+        let accounts_file = AccountsFile::TieredStorage(TieredStorage::new_writable(&path));
+        assert!(!accounts_file.is_recyclable());
+        assert_eq!(accounts_file.len(), 0);
+        assert!(accounts_file.is_empty());
+        assert_eq!(accounts_file.capacity(), MAX_TIERED_FILE_SIZE);
+        assert_eq!(accounts_file.remaining_bytes(), MAX_TIERED_FILE_SIZE);
+        assert_eq!(accounts_file.account_iter().count(), 0);
+
+        // test: the written storage is full, so the next stores go elsewhere
+        let stored_infos = with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| accounts_file.append_accounts(storable_accounts, 0),
+        )
+        .unwrap()
+        .unwrap();
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert_eq!(accounts_file.len(), file_len);
+        assert!(!accounts_file.is_empty());
+        assert_eq!(accounts_file.capacity(), file_len as u64);
+        assert_eq!(accounts_file.remaining_bytes(), 0);
+        // like a full AppendVec, there is no room for another append
+        assert!(with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| accounts_file
+                .append_accounts(storable_accounts, stored_infos.len())
+        )
+        .unwrap()
+        .is_none());
+
+        // test: the accounts read back at their stored offsets
+        for (stored_info, (stored_meta, account)) in stored_infos.iter().zip(&accounts) {
+            let (stored_account, _) = accounts_file.get_account(stored_info.offset).unwrap();
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert_eq!(stored_account.data(), account.data());
+        }
+
+        // test: recycling is rejected and leaves the storage readable
+        assert_matches!(
+            accounts_file.reset(),
+            Err(AccountsFileError::TieredStorageError(
+                TieredStorageError::NotRecyclable(_)
+            ))
+        );
+        assert_eq!(accounts_file.len(), file_len);
+        assert_eq!(accounts_file.remaining_bytes(), 0);
+        assert_eq!(accounts_file.account_iter().count(), accounts.len());
+
+        // test: dropping the storage removes its file
+        drop(accounts_file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_tiered_storage_account_iter_not_written() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_tiered_storage_account_iter_not_written");
+        let accounts_file = AccountsFile::TieredStorage(TieredStorage::new_writable(path));
+        assert_eq!(accounts_file.account_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_account_iter_take_error() {
+        const NUM_ACCOUNTS: usize = 10;
+        const NUM_READABLE_ACCOUNTS: usize = 3;
+        let account_data_sizes: Vec<u64> = (1..=NUM_ACCOUNTS as u64).collect();
+
+        let temp_dir = TempDir::new().unwrap();
+
+        // a clean file is fully scanned without any error
+        let path = temp_dir.path().join("test_account_iter_take_error_clean");
+        write_hot_storage(&path, &account_data_sizes);
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        let mut iter = accounts_file.account_iter();
+        assert_eq!(iter.by_ref().count(), NUM_ACCOUNTS);
+        assert_eq!(iter.scanned(), NUM_ACCOUNTS);
+        assert!(iter.take_error().is_none());
+
+        // a file corrupted after its third account stops the iteration early
+        let path = temp_dir
+            .path()
+            .join("test_account_iter_take_error_corrupted");
+        write_hot_storage(&path, &account_data_sizes);
+        corrupt_owner_offset(&path, IndexOffset(NUM_READABLE_ACCOUNTS as u32));
+        let accounts_file =
+            AccountsFile::TieredStorage(TieredStorage::new_readonly(&path).unwrap());
+        let mut iter = accounts_file.account_iter();
+        assert_eq!(iter.by_ref().count(), NUM_READABLE_ACCOUNTS);
+        assert_eq!(iter.scanned(), NUM_READABLE_ACCOUNTS);
+        assert_matches!(
+            iter.take_error(),
+            Some(AccountsFileError::TieredStorageError(_))
+        );
+        // the error is only reported once
+        assert!(iter.take_error().is_none());
+    }
 }
@@ -4,41 +4,101 @@ pub mod byte_block;
 pub mod error;
 pub mod file;
 pub mod footer;
+pub mod generation;
+pub mod hash_feed;
 pub mod hot;
 pub mod index;
 pub mod meta;
 pub mod mmap_utils;
 pub mod owners;
+mod property_tests;
 pub mod readable;
-mod test_utils;
+pub mod replication;
+pub mod shutdown;
+pub(crate) mod test_fixtures;
+pub(crate) mod test_utils;
+pub mod trace;
+pub mod verification;
+mod writer_matrix;
 
 use {
     crate::{
-        account_storage::meta::{StorableAccountsWithHashesAndWriteVersions, StoredAccountInfo},
+        account_storage::meta::{
+            StorableAccountsWithHashesAndWriteVersions, StoredAccountInfo, DEFAULT_ACCOUNT_HASH,
+        },
         accounts_hash::AccountHash,
         storable_accounts::StorableAccounts,
     },
     error::TieredStorageError,
-    footer::{AccountBlockFormat, AccountMetaFormat},
-    hot::{HotStorageWriter, HOT_FORMAT},
+    file::{MmapAccessPattern, ReaderBackend, TieredReadableFile, TieredStorageMagicNumber},
+    footer::{AccountBlockFormat, AccountMetaFormat, FooterHashKind, TieredStorageFooter},
+    generation::{FormatFeature, WriteOptions},
+    hot::{HotStorageWriter, HOT_FORMAT, MAX_HOT_FILE_SIZE},
     index::IndexBlockFormat,
+    log::*,
     owners::OwnersBlockFormat,
+    rayon::prelude::*,
     readable::TieredStorageReader,
-    solana_sdk::account::ReadableAccount,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::Slot,
+        pubkey::Pubkey,
+    },
     std::{
         borrow::Borrow,
-        fs, io,
+        fs,
+        io::{self, Read, Seek, SeekFrom},
+        ops::Deref,
         path::{Path, PathBuf},
         sync::{
             atomic::{AtomicBool, Ordering},
             OnceLock,
         },
     },
+    verification::VerifyReport,
 };
 
 pub type TieredStorageResult<T> = Result<T, TieredStorageError>;
 
-const MAX_TIERED_STORAGE_FILE_SIZE: u64 = 16 * 1024 * 1024 * 1024; // 16 GiB;
+/// The maximum size of a tiered storage file of any format, which is the
+/// tightest of the per-format limits (see AccountMetaFormat::max_file_size).
+pub const MAX_TIERED_FILE_SIZE: u64 = MAX_HOT_FILE_SIZE;
+
+/// Returns true if the specified file ends with the tiered storage magic
+/// number.
+///
+/// Only the trailing magic number is read, so this is cheap enough to probe
+/// every accounts file when loading a snapshot.  A file that is too short to
+/// hold the magic number is not a tiered storage file.
+pub fn is_tiered_storage_file(path: impl AsRef<Path>) -> io::Result<bool> {
+    const MAGIC_NUMBER_SIZE: usize = std::mem::size_of::<TieredStorageMagicNumber>();
+
+    let mut file = fs::File::open(path)?;
+    if file.metadata()?.len() < MAGIC_NUMBER_SIZE as u64 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-(MAGIC_NUMBER_SIZE as i64)))?;
+    let mut buffer = [0u8; MAGIC_NUMBER_SIZE];
+    file.read_exact(&mut buffer)?;
+    Ok(u64::from_ne_bytes(buffer) == TieredStorageMagicNumber::default().0)
+}
+
+/// Returns the account meta format of the specified file, or None if it is
+/// not a tiered storage file.
+///
+/// Like is_tiered_storage_file(), this only reads the tail of the file: the
+/// trailing magic number and, for a tiered storage file, its footer.  No
+/// reader is created and the file is not mapped.
+pub fn probe_account_meta_format(
+    path: impl AsRef<Path>,
+) -> TieredStorageResult<Option<AccountMetaFormat>> {
+    if !is_tiered_storage_file(&path)? {
+        return Ok(None);
+    }
+    let file = TieredReadableFile::new(&path)?;
+    let footer = TieredStorageFooter::new_from_footer_block(&file)?;
+    Ok(Some(footer.account_meta_format))
+}
 
 /// The struct that defines the formats of all building blocks of a
 /// TieredStorage.
@@ -49,26 +109,364 @@ pub struct TieredStorageFormat {
     pub owners_block_format: OwnersBlockFormat,
     pub index_block_format: IndexBlockFormat,
     pub account_block_format: AccountBlockFormat,
+    /// The compression level of the account blocks, or None for the
+    /// default level.  Only used by AccountBlockFormat::Zstd.
+    pub compression_level: Option<i32>,
+    /// The algorithm of the file hash persisted in the footer.
+    pub hash_kind: FooterHashKind,
+    /// The maximum number of accounts written into one file, or None if the
+    /// number of accounts is only limited by the file size.  The accounts
+    /// beyond the cap are left to the caller to write into another file.
+    pub max_accounts_per_file: Option<u32>,
+    /// Whether each account block ends with a checksum of its content.
+    /// The checksums are only written if the format generation of the
+    /// writer supports FormatFeature::BlockChecksum.
+    pub block_checksums: bool,
+}
+
+impl TieredStorageFormat {
+    /// Returns a builder of TieredStorageFormat, which starts from
+    /// HOT_FORMAT.
+    pub fn builder() -> TieredStorageFormatBuilder {
+        TieredStorageFormatBuilder::default()
+    }
+
+    /// Checks that the building blocks of this format can be combined and
+    /// written.
+    ///
+    /// The hot account metas are only written with AlignedRaw account
+    /// blocks, and the meta entry size must match the size of the account
+    /// meta.  Every owners block format and index block format can be
+    /// combined with the others.
+    pub fn validate(&self) -> TieredStorageResult<()> {
+        match (self.account_meta_format, self.account_block_format) {
+            (AccountMetaFormat::Hot, AccountBlockFormat::AlignedRaw) => {}
+            (account_meta_format, account_block_format) => {
+                return Err(TieredStorageError::UnsupportedAccountBlockFormat(
+                    account_meta_format,
+                    account_block_format,
+                ));
+            }
+        }
+        let expected_meta_entry_size = self.account_meta_format.meta_entry_size();
+        if self.meta_entry_size != expected_meta_entry_size {
+            return Err(TieredStorageError::InvalidMetaEntrySize(
+                self.meta_entry_size,
+                expected_meta_entry_size,
+            ));
+        }
+        if let Some(compression_level) = self.compression_level {
+            if self.account_block_format != AccountBlockFormat::Zstd {
+                return Err(TieredStorageError::UnexpectedCompressionLevel(
+                    compression_level,
+                    self.account_block_format,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A TieredStorageFormat whose building blocks are known to be combinable,
+/// which is the only kind of format TieredStorage writes.  It is obtained
+/// via TieredStorageFormatBuilder::build(), TryFrom, or the presets such as
+/// HOT_FORMAT.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidatedTieredStorageFormat(TieredStorageFormat);
+
+impl TryFrom<TieredStorageFormat> for ValidatedTieredStorageFormat {
+    type Error = TieredStorageError;
+
+    /// Returns the validated format, or the reason why its building blocks
+    /// cannot be combined.  See TieredStorageFormat::validate().
+    fn try_from(format: TieredStorageFormat) -> TieredStorageResult<Self> {
+        format.validate()?;
+        Ok(Self(format))
+    }
+}
+
+impl Deref for ValidatedTieredStorageFormat {
+    type Target = TieredStorageFormat;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// The builder of a TieredStorageFormat, which fills the meta entry size
+/// from the account meta format and validates the resulting format.
+#[derive(Clone, Debug)]
+pub struct TieredStorageFormatBuilder {
+    account_meta_format: AccountMetaFormat,
+    owners_block_format: OwnersBlockFormat,
+    index_block_format: IndexBlockFormat,
+    account_block_format: AccountBlockFormat,
+    compression_level: Option<i32>,
+    hash_kind: FooterHashKind,
+    max_accounts_per_file: Option<u32>,
+    block_checksums: bool,
+}
+
+impl Default for TieredStorageFormatBuilder {
+    fn default() -> Self {
+        Self {
+            account_meta_format: HOT_FORMAT.account_meta_format,
+            owners_block_format: HOT_FORMAT.owners_block_format,
+            index_block_format: HOT_FORMAT.index_block_format,
+            account_block_format: HOT_FORMAT.account_block_format,
+            compression_level: HOT_FORMAT.compression_level,
+            hash_kind: HOT_FORMAT.hash_kind,
+            max_accounts_per_file: HOT_FORMAT.max_accounts_per_file,
+            block_checksums: HOT_FORMAT.block_checksums,
+        }
+    }
+}
+
+impl TieredStorageFormatBuilder {
+    pub fn with_account_meta_format(mut self, account_meta_format: AccountMetaFormat) -> Self {
+        self.account_meta_format = account_meta_format;
+        self
+    }
+
+    pub fn with_owners_block_format(mut self, owners_block_format: OwnersBlockFormat) -> Self {
+        self.owners_block_format = owners_block_format;
+        self
+    }
+
+    pub fn with_index_block_format(mut self, index_block_format: IndexBlockFormat) -> Self {
+        self.index_block_format = index_block_format;
+        self
+    }
+
+    pub fn with_account_block_format(mut self, account_block_format: AccountBlockFormat) -> Self {
+        self.account_block_format = account_block_format;
+        self
+    }
+
+    /// Sets the compression level, which is only valid for
+    /// AccountBlockFormat::Zstd.
+    pub fn with_compression_level(mut self, compression_level: i32) -> Self {
+        self.compression_level = Some(compression_level);
+        self
+    }
+
+    pub fn with_hash_kind(mut self, hash_kind: FooterHashKind) -> Self {
+        self.hash_kind = hash_kind;
+        self
+    }
+
+    pub fn with_max_accounts_per_file(mut self, max_accounts_per_file: Option<u32>) -> Self {
+        self.max_accounts_per_file = max_accounts_per_file;
+        self
+    }
+
+    pub fn with_block_checksums(mut self, block_checksums: bool) -> Self {
+        self.block_checksums = block_checksums;
+        self
+    }
+
+    /// Returns the format, or the reason why its building blocks cannot be
+    /// combined.  See TieredStorageFormat::validate().
+    pub fn build(self) -> TieredStorageResult<ValidatedTieredStorageFormat> {
+        TieredStorageFormat {
+            meta_entry_size: self.account_meta_format.meta_entry_size(),
+            account_meta_format: self.account_meta_format,
+            owners_block_format: self.owners_block_format,
+            index_block_format: self.index_block_format,
+            account_block_format: self.account_block_format,
+            compression_level: self.compression_level,
+            hash_kind: self.hash_kind,
+            max_accounts_per_file: self.max_accounts_per_file,
+            block_checksums: self.block_checksums,
+        }
+        .try_into()
+    }
+}
+
+/// The statistics of a read-only TieredStorage for operational debugging.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TieredStorageStats {
+    /// The number of accounts stored in the file.
+    pub num_accounts: usize,
+    /// The number of unique account owners stored in the file.  Comparing
+    /// it against num_accounts shows how effective the owner dedup is.
+    pub owner_count: usize,
+    /// The size of the underlying accounts file.
+    pub file_len: usize,
+    /// The average size of an account block, including its account meta,
+    /// or 0 if the file has no account.
+    pub average_account_block_size: usize,
+}
+
+/// The statistics collected while writing the accounts of a TieredStorage,
+/// which show how the writer behaves under load.
+///
+/// The byte counters add up to the size of the written file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TieredStorageWriterStats {
+    /// The number of accounts written.
+    pub num_accounts: usize,
+    /// The number of bytes of the account metas.
+    pub account_meta_bytes: usize,
+    /// The number of bytes of the account data, excluding their padding.
+    pub account_data_bytes: usize,
+    /// The number of padding bytes after the account data.
+    pub padding_bytes: usize,
+    /// The number of bytes of the optional fields.
+    pub optional_fields_bytes: usize,
+    /// The number of bytes of the index block, including the padding that
+    /// aligns the blocks after it.
+    pub index_block_bytes: usize,
+    /// The number of bytes of the owners block.
+    pub owners_block_bytes: usize,
+    /// The number of bytes of the footer, including the magic number.
+    pub footer_bytes: usize,
+    /// The number of unique owners written into the owners block.
+    pub num_unique_owners: usize,
+    /// The number of owners referenced by the accounts before the dedup,
+    /// which is one per account.
+    pub num_total_owners: usize,
+    /// The time spent on writing the account blocks.
+    pub account_blocks_us: u64,
+    /// The time spent on writing the index block.
+    pub index_block_us: u64,
+    /// The time spent on writing the owners block.
+    pub owners_block_us: u64,
+    /// The time spent on writing the footer.
+    pub footer_us: u64,
+}
+
+impl TieredStorageWriterStats {
+    /// Returns the total number of bytes written, which is the size of the
+    /// written file.
+    pub fn total_bytes(&self) -> usize {
+        self.account_meta_bytes
+            + self.account_data_bytes
+            + self.padding_bytes
+            + self.optional_fields_bytes
+            + self.index_block_bytes
+            + self.owners_block_bytes
+            + self.footer_bytes
+    }
+
+    /// Reports the statistics of the file at the specified path, which
+    /// stores the accounts of the specified slot.
+    pub fn report(&self, path: &Path, slot: Slot) {
+        datapoint_info!(
+            "tiered_storage_writer",
+            "path" => path.display().to_string(),
+            ("slot", slot, i64),
+            ("num_accounts", self.num_accounts, i64),
+            ("account_meta_bytes", self.account_meta_bytes, i64),
+            ("account_data_bytes", self.account_data_bytes, i64),
+            ("padding_bytes", self.padding_bytes, i64),
+            ("optional_fields_bytes", self.optional_fields_bytes, i64),
+            ("index_block_bytes", self.index_block_bytes, i64),
+            ("owners_block_bytes", self.owners_block_bytes, i64),
+            ("footer_bytes", self.footer_bytes, i64),
+            ("num_unique_owners", self.num_unique_owners, i64),
+            ("num_total_owners", self.num_total_owners, i64),
+            ("account_blocks_us", self.account_blocks_us, i64),
+            ("index_block_us", self.index_block_us, i64),
+            ("owners_block_us", self.owners_block_us, i64),
+            ("footer_us", self.footer_us, i64),
+        );
+    }
+}
+
+/// The metadata of a read-only TieredStorage, which is cached on its first
+/// query so that repeated metadata queries do not need to go through the
+/// reader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TieredStorageMeta {
+    /// The size of the underlying accounts file.
+    pub file_len: usize,
+    /// The number of accounts stored in the file.
+    pub num_accounts: usize,
+    /// The number of unique account owners stored in the file.
+    pub owner_count: usize,
+    /// The formats of all the building blocks of the file.
+    pub format: TieredStorageFormat,
+}
+
+impl TieredStorageMeta {
+    fn new(reader: &TieredStorageReader) -> Self {
+        let footer = reader.footer();
+        Self {
+            file_len: reader.len(),
+            num_accounts: reader.num_accounts(),
+            owner_count: reader.owner_count(),
+            format: TieredStorageFormat {
+                meta_entry_size: footer.account_meta_entry_size as usize,
+                account_meta_format: footer.account_meta_format,
+                owners_block_format: footer.owners_block_format,
+                index_block_format: footer.index_block_format,
+                account_block_format: footer.account_block_format,
+                // the level is not needed to decode the blocks, and hence
+                // is not persisted
+                compression_level: None,
+                hash_kind: footer.hash_kind,
+                // the cap only applies while writing the file
+                max_accounts_per_file: None,
+                // the account metas tell whether each account block ends
+                // with a checksum
+                block_checksums: false,
+            },
+        }
+    }
 }
 
 /// The implementation of AccountsFile for tiered-storage.
+///
+/// A TieredStorage is shared across threads, where one thread may write the
+/// accounts while the others read them.  See write_accounts() for how the
+/// readers observe the written accounts.
 #[derive(Debug)]
 pub struct TieredStorage {
     /// The internal reader instance for its accounts file.
     reader: OnceLock<TieredStorageReader>,
+    /// The cached metadata, which is only populated once the TieredStorage
+    /// becomes read-only.
+    meta: OnceLock<TieredStorageMeta>,
+    /// The statistics collected while writing the accounts, which are only
+    /// populated once this TieredStorage has written its accounts.
+    writer_stats: OnceLock<TieredStorageWriterStats>,
     /// A status flag indicating whether its file has been already written.
     already_written: AtomicBool,
+    /// Set when write_accounts() finds another file at the path, which is
+    /// not owned by this TieredStorage and hence must never be removed.
+    found_existing_file: AtomicBool,
     /// The path to the file that stores accounts.
     path: PathBuf,
+    /// What happens to the file when this TieredStorage is dropped.
+    drop_behavior: DropBehavior,
+    /// The options of the writer that writes the accounts.
+    write_options: WriteOptions,
+}
+
+static_assertions::assert_impl_all!(TieredStorage: Send, Sync);
+
+/// What happens to the file of a TieredStorage when it is dropped.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DropBehavior {
+    /// Removes the file, and logs an error if the removal fails.
+    #[default]
+    RemoveFile,
+    /// Leaves the file on disk.
+    KeepFile,
 }
 
 impl Drop for TieredStorage {
     fn drop(&mut self) {
+        if self.drop_behavior == DropBehavior::KeepFile || !self.owns_file() {
+            return;
+        }
         if let Err(err) = fs::remove_file(&self.path) {
-            // Here we bypass NotFound error as the focus of the panic is to
-            // detect any leakage of storage resource.
+            // A file that is already removed, e.g. by the snapshot purger, is
+            // what we want.  Any other failure leaks the file, but is not
+            // worth aborting the process for.
             if err.kind() != io::ErrorKind::NotFound {
-                panic!(
+                error!(
                     "TieredStorage failed to remove backing storage file '{}': {err}",
                     self.path.display(),
                 );
@@ -84,33 +482,127 @@ impl TieredStorage {
     /// Note that the actual file will not be created until write_accounts
     /// is called.
     pub fn new_writable(path: impl Into<PathBuf>) -> Self {
+        Self::new_writable_with_options(path, WriteOptions::default())
+    }
+
+    /// Creates a new writable instance of TieredStorage based on the
+    /// specified path, whose writer uses the specified options.
+    pub fn new_writable_with_options(
+        path: impl Into<PathBuf>,
+        write_options: WriteOptions,
+    ) -> Self {
         Self {
             reader: OnceLock::<TieredStorageReader>::new(),
+            meta: OnceLock::<TieredStorageMeta>::new(),
+            writer_stats: OnceLock::<TieredStorageWriterStats>::new(),
             already_written: false.into(),
+            found_existing_file: false.into(),
             path: path.into(),
+            drop_behavior: DropBehavior::default(),
+            write_options,
         }
     }
 
     /// Creates a new read-only instance of TieredStorage from the
     /// specified path.
     pub fn new_readonly(path: impl Into<PathBuf>) -> TieredStorageResult<Self> {
+        Self::new_readonly_with_backend(path, ReaderBackend::Mmap)
+    }
+
+    /// Creates a new read-only instance of TieredStorage from the
+    /// specified path, which accesses its file via the specified backend.
+    pub fn new_readonly_with_backend(
+        path: impl Into<PathBuf>,
+        backend: ReaderBackend,
+    ) -> TieredStorageResult<Self> {
         let path = path.into();
         Ok(Self {
-            reader: TieredStorageReader::new_from_path(&path).map(OnceLock::from)?,
+            reader: TieredStorageReader::new_from_path_with_backend(&path, backend)
+                .map(OnceLock::from)?,
+            meta: OnceLock::<TieredStorageMeta>::new(),
+            writer_stats: OnceLock::<TieredStorageWriterStats>::new(),
             already_written: true.into(),
+            found_existing_file: false.into(),
             path,
+            drop_behavior: DropBehavior::default(),
+            write_options: WriteOptions::default(),
         })
     }
 
+    /// Opens the specified files as read-only TieredStorages in parallel.
+    ///
+    /// The result of each file is at the same position as its path, so
+    /// that a file which fails to open does not affect the others.
+    pub fn open_many(paths: &[PathBuf]) -> Vec<TieredStorageResult<Self>> {
+        paths
+            .par_iter()
+            .map(|path| Self::new_readonly(path.clone()))
+            .collect()
+    }
+
+    /// Creates a new read-only instance of TieredStorage from the
+    /// specified path, whose file must contain the specified number of
+    /// accounts, e.g. the number recorded by the snapshot it is unpacked
+    /// from.
+    ///
+    /// AccountCountMismatch is returned if the footer disagrees, in which
+    /// case the file is left on disk.
+    pub fn new_readonly_with_expected_accounts(
+        path: impl Into<PathBuf>,
+        expected_num_accounts: usize,
+    ) -> TieredStorageResult<Self> {
+        let mut tiered_storage = Self::new_readonly(path)?;
+        let num_accounts = tiered_storage
+            .reader()
+            .map_or(0, |reader| reader.num_accounts());
+        if num_accounts != expected_num_accounts {
+            tiered_storage.set_remove_on_drop(false);
+            return Err(TieredStorageError::AccountCountMismatch(
+                expected_num_accounts,
+                num_accounts,
+            ));
+        }
+        Ok(tiered_storage)
+    }
+
     /// Returns the path to this TieredStorage.
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
 
+    /// Sets whether the file is removed when this TieredStorage is dropped,
+    /// which is true by default.
+    ///
+    /// A file that is part of a snapshot being archived must outlive its
+    /// TieredStorage, for instance.
+    pub fn set_remove_on_drop(&mut self, remove_on_drop: bool) {
+        self.drop_behavior = if remove_on_drop {
+            DropBehavior::RemoveFile
+        } else {
+            DropBehavior::KeepFile
+        };
+    }
+
+    /// Returns false if write_accounts() found another file at the path,
+    /// which belongs to someone else and hence must never be removed.
+    fn owns_file(&self) -> bool {
+        !self.found_existing_file.load(Ordering::Acquire)
+    }
+
     /// Writes the specified accounts into this TieredStorage.
     ///
-    /// Note that this function can only be called once per a TieredStorage
-    /// instance.  Otherwise, it will trigger panic.
+    /// At most `format.max_accounts_per_file` accounts are written, in which
+    /// case the length of the returned vector tells the caller how many of
+    /// the accounts after `skip` are written.
+    ///
+    /// If there is no account after `skip`, nothing is written and an empty
+    /// vector is returned, so this TieredStorage remains writable, like an
+    /// AppendVec which still has room.  Use finalize_empty() to persist a
+    /// file without any account instead.
+    ///
+    /// Note that once accounts are written, this function can no longer be
+    /// called on the same TieredStorage instance.  Otherwise, it will
+    /// trigger panic.
     pub fn write_accounts<
         'a,
         'b,
@@ -121,32 +613,106 @@ impl TieredStorage {
         &self,
         accounts: &StorableAccountsWithHashesAndWriteVersions<'a, 'b, T, U, V>,
         skip: usize,
-        format: &TieredStorageFormat,
+        format: &ValidatedTieredStorageFormat,
     ) -> TieredStorageResult<Vec<StoredAccountInfo>> {
+        if skip >= accounts.len() {
+            // nothing is written, so this TieredStorage is left untouched
+            assert!(
+                !self.already_written.load(Ordering::Acquire),
+                "cannot write same tiered storage file more than once"
+            );
+            return Ok(vec![]);
+        }
+        self.start_write();
+        self.write_accounts_to_file(accounts, skip, format)
+    }
+
+    /// Persists a file without any account, which has only the footer and
+    /// the other non-account blocks, and makes this TieredStorage read-only.
+    ///
+    /// Like write_accounts(), this function will trigger panic if accounts
+    /// have already been written.
+    pub fn finalize_empty(&self, format: &ValidatedTieredStorageFormat) -> TieredStorageResult<()> {
+        self.start_write();
+        let account_refs = Vec::<(&Pubkey, &AccountSharedData)>::new();
+        // Slot information is not persisted
+        let account_data = (Slot::default(), account_refs.as_slice());
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                Vec::<AccountHash>::new(),
+                vec![],
+            );
+        self.write_accounts_to_file(&storable_accounts, 0, format)?;
+        Ok(())
+    }
+
+    /// Marks this TieredStorage as written, which must only happen once.
+    fn start_write(&self) {
         let was_written = self.already_written.swap(true, Ordering::AcqRel);
 
         if was_written {
             panic!("cannot write same tiered storage file more than once");
         }
+    }
 
-        if format == &HOT_FORMAT {
-            let result = {
-                let mut writer = HotStorageWriter::new(&self.path)?;
-                writer.write_accounts(accounts, skip)
-            };
+    /// Writes the specified accounts into the file of this TieredStorage,
+    /// and installs its reader.
+    fn write_accounts_to_file<
+        'a,
+        'b,
+        T: ReadableAccount + Sync,
+        U: StorableAccounts<'a, T>,
+        V: Borrow<AccountHash>,
+    >(
+        &self,
+        accounts: &StorableAccountsWithHashesAndWriteVersions<'a, 'b, T, U, V>,
+        skip: usize,
+        format: &ValidatedTieredStorageFormat,
+    ) -> TieredStorageResult<Vec<StoredAccountInfo>> {
+        debug_assert!(format.validate().is_ok(), "unvalidated format: {format:?}");
+        let stored_infos = {
+            let mut writer = HotStorageWriter::new_with_options(
+                &self.path,
+                format.index_block_format,
+                format.hash_kind,
+                &self.write_options,
+            )
+            .inspect_err(|err| {
+                // the existing file belongs to someone else, so it is left
+                // untouched, including when this TieredStorage is dropped
+                if matches!(err, TieredStorageError::FileAlreadyExists(_)) {
+                    self.found_existing_file.store(true, Ordering::Release);
+                }
+            })?
+            .with_owners_block_format(format.owners_block_format)
+            .with_max_accounts_per_file(format.max_accounts_per_file)
+            .with_block_checksums(format.block_checksums)
+            .with_compression_level(format.compression_level);
+            let stored_infos = writer.write_accounts(accounts, skip)?;
+            let stats = writer.stats().clone();
+            writer.finish()?;
+            stats.report(&self.path, accounts.accounts.target_slot());
+            self.writer_stats.set(stats).unwrap();
+            stored_infos
+        };
 
-            // panic here if self.reader.get() is not None as self.reader can only be
-            // None since a false-value `was_written` indicates the accounts file has
-            // not been written previously, implying is_read_only() was also false.
-            debug_assert!(!self.is_read_only());
-            self.reader
-                .set(TieredStorageReader::new_from_path(&self.path)?)
-                .unwrap();
+        // The reader is only installed once the file is completely written
+        // and synced to the disk, and before success is returned.  As
+        // OnceLock publishes the reader atomically, concurrent callers of
+        // reader() either observe None or the fully initialized reader, and
+        // is_read_only() never reports true before the accounts are
+        // readable.
+        //
+        // panic here if self.reader.get() is not None as self.reader can only be
+        // None since start_write() has checked the accounts file has not been
+        // written previously, implying is_read_only() was also false.
+        debug_assert!(!self.is_read_only());
+        self.reader
+            .set(TieredStorageReader::new_from_path(&self.path)?)
+            .unwrap();
 
-            result
-        } else {
-            Err(TieredStorageError::UnknownFormat(self.path.to_path_buf()))
-        }
+        Ok(stored_infos)
     }
 
     /// Returns the underlying reader of the TieredStorage.  None will be
@@ -155,14 +721,80 @@ impl TieredStorage {
         self.reader.get()
     }
 
+    /// Returns the statistics collected while writing the accounts of this
+    /// TieredStorage.  None will be returned if this TieredStorage has not
+    /// written its accounts, including when it is opened read-only.
+    pub fn writer_stats(&self) -> Option<&TieredStorageWriterStats> {
+        self.writer_stats.get()
+    }
+
+    /// Hints the kernel about how the content of the file will be accessed,
+    /// e.g. sequentially before a full scan.
+    ///
+    /// This is a no-op until the accounts are written.
+    pub fn advise(&self, access: MmapAccessPattern) -> TieredStorageResult<()> {
+        self.reader().map_or(Ok(()), |reader| reader.advise(access))
+    }
+
     /// Returns true if the TieredStorage instance is read-only.
     pub fn is_read_only(&self) -> bool {
         self.reader.get().is_some()
     }
 
+    /// Returns true if write_accounts() has already been called with any
+    /// account, or finalize_empty() has been called, whether it succeeded
+    /// or not, or if the TieredStorage is opened read-only.  In either case,
+    /// calling write_accounts() again will trigger panic.
+    pub fn is_written(&self) -> bool {
+        self.already_written.load(Ordering::Acquire)
+    }
+
+    /// Returns the cached metadata of the TieredStorage, which is populated
+    /// on its first call.  None is returned while is_read_only() is false,
+    /// as the metadata keeps changing until the accounts are written.
+    pub fn meta(&self) -> Option<&TieredStorageMeta> {
+        let reader = self.reader()?;
+        Some(self.meta.get_or_init(|| TieredStorageMeta::new(reader)))
+    }
+
+    /// Returns the statistics of this TieredStorage.
+    ///
+    /// Like meta(), it returns None while the storage is still writable.
+    pub fn stats(&self) -> Option<TieredStorageStats> {
+        let meta = self.meta()?;
+        // The account blocks are the first blocks of the file.
+        let account_blocks_size = self.reader()?.account_blocks_end();
+        Some(TieredStorageStats {
+            num_accounts: meta.num_accounts,
+            owner_count: meta.owner_count,
+            file_len: meta.file_len,
+            average_account_block_size: account_blocks_size
+                .checked_div(meta.num_accounts)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Checks the consistency of the tiered storage file at the specified
+    /// path without loading it into a bank, e.g. from ledger-tool.
+    ///
+    /// An error is returned only if the file cannot be opened, e.g. when its
+    /// footer is unsanitary.  The inconsistencies of the accounts and the
+    /// file hash are collected into the report instead.  The file is never
+    /// removed.
+    pub fn verify(path: impl AsRef<Path>) -> TieredStorageResult<VerifyReport> {
+        TieredStorageReader::new_from_path(path)?.verify()
+    }
+
+    /// Returns NotRecyclable, as a TieredStorage is written exactly once
+    /// and cannot be reset to store other accounts.  A new TieredStorage
+    /// has to be created instead.
+    pub fn reset_for_recycle(&self) -> TieredStorageResult<()> {
+        Err(TieredStorageError::NotRecyclable(self.path.clone()))
+    }
+
     /// Returns the size of the underlying accounts file.
     pub fn len(&self) -> usize {
-        self.reader().map_or(0, |reader| reader.len())
+        self.meta().map_or(0, |meta| meta.file_len)
     }
 
     /// Returns whether the underlying storage is empty.
@@ -171,8 +803,77 @@ impl TieredStorage {
     }
 
     pub fn capacity(&self) -> u64 {
-        self.reader()
-            .map_or(MAX_TIERED_STORAGE_FILE_SIZE, |reader| reader.capacity())
+        self.meta()
+            .map_or(MAX_TIERED_FILE_SIZE, |meta| meta.file_len as u64)
+    }
+
+    /// Writes the accounts whose addresses satisfy `keep` into a new
+    /// TieredStorage at `dest_path`, and returns the new TieredStorage,
+    /// which is read-only.
+    ///
+    /// The new file has its own owners block, so the owners only referenced
+    /// by the dropped accounts are not carried over.  It is written in the
+    /// same formats and format generation as this TieredStorage, which is
+    /// left untouched.
+    ///
+    /// Returns TieredStorageError::NotYetWritten if this TieredStorage is
+    /// not read-only yet.
+    pub fn rewrite_filtered(
+        &self,
+        dest_path: impl Into<PathBuf>,
+        keep: impl Fn(&Pubkey) -> bool,
+    ) -> TieredStorageResult<TieredStorage> {
+        let (Some(reader), Some(meta)) = (self.reader(), self.meta()) else {
+            return Err(TieredStorageError::NotYetWritten(self.path.clone()));
+        };
+
+        let mut account_iter = reader.account_iter();
+        let accounts: Vec<_> = account_iter
+            .by_ref()
+            .filter(|account| keep(account.pubkey()))
+            .collect();
+        if let Some(err) = account_iter.take_error() {
+            return Err(err);
+        }
+        let account_refs: Vec<_> = accounts.iter().collect();
+        // Slot information is not persisted
+        let account_data = (Slot::default(), &account_refs[..]);
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::<'_, '_, _, _, &AccountHash>::new(
+                &account_data,
+            );
+
+        // the footer does not tell whether the account hashes and the block
+        // checksums are persisted, so they are taken from the kept accounts
+        let format = ValidatedTieredStorageFormat::try_from(TieredStorageFormat {
+            block_checksums: accounts
+                .iter()
+                .any(|account| account.verify_checksum().is_some()),
+            ..meta.format.clone()
+        })?;
+        let has_account_hashes = accounts
+            .iter()
+            .any(|account| *account.hash() != *DEFAULT_ACCOUNT_HASH);
+        let max_format_generation = [
+            (has_account_hashes, FormatFeature::AccountHash),
+            (format.block_checksums, FormatFeature::BlockChecksum),
+        ]
+        .into_iter()
+        .filter(|(is_used, _)| *is_used)
+        .map(|(_, feature)| feature.generation())
+        .fold(reader.required_generation(), Ord::max);
+        let dest = TieredStorage::new_writable_with_options(
+            dest_path,
+            WriteOptions {
+                max_format_generation,
+            },
+        );
+        if storable_accounts.is_empty() {
+            dest.finalize_empty(&format)?;
+        } else {
+            dest.write_accounts(&storable_accounts, 0, &format)?;
+        }
+        Ok(dest)
     }
 }
 
@@ -180,21 +881,38 @@ impl TieredStorage {
 mod tests {
     use {
         super::*,
-        crate::account_storage::meta::StoredMetaWriteVersion,
+        assert_matches::assert_matches,
         file::TieredStorageMagicNumber,
         footer::TieredStorageFooter,
-        hot::HOT_FORMAT,
+        generation::FormatGeneration,
+        hot::{
+            HotAccountMeta, HOT_FORMAT, HOT_OWNER_COUNTS_FORMAT, HOT_SIZED_INDEX_FORMAT,
+            HOT_SORTED_FORMAT,
+        },
         index::IndexOffset,
+        meta::PubkeyRange,
+        owners::OwnersBlockFormat,
         solana_sdk::{
-            account::AccountSharedData, clock::Slot, hash::Hash, pubkey::Pubkey,
+            account::{AccountSharedData, WritableAccount},
+            clock::Slot,
+            hash::Hash,
+            pubkey::Pubkey,
             system_instruction::MAX_PERMITTED_DATA_LENGTH,
         },
         std::{
             collections::{HashMap, HashSet},
+            fs::OpenOptions,
+            io::Write,
             mem::ManuallyDrop,
+            sync::Barrier,
         },
         tempfile::tempdir,
-        test_utils::{create_test_account, verify_test_account_with_footer},
+        test_case::test_case,
+        test_utils::{
+            create_test_account, verify_test_account, verify_test_account_with_footer,
+            with_storable_accounts, write_hot_storage, write_hot_storage_with_hash_kind,
+        },
+        verification::Inconsistency,
     };
 
     impl TieredStorage {
@@ -203,42 +921,35 @@ mod tests {
         }
     }
 
-    /// Simply invoke write_accounts with empty vector to allow the tiered storage
-    /// to persist non-account blocks such as footer, index block, etc.
-    fn write_zero_accounts(
-        tiered_storage: &TieredStorage,
-        expected_result: TieredStorageResult<Vec<StoredAccountInfo>>,
-    ) {
-        let slot_ignored = Slot::MAX;
-        let account_refs = Vec::<(&Pubkey, &AccountSharedData)>::new();
-        let account_data = (slot_ignored, account_refs.as_slice());
-        let storable_accounts =
-            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
-                &account_data,
-                Vec::<AccountHash>::new(),
-                Vec::<StoredMetaWriteVersion>::new(),
-            );
+    /// Invokes write_accounts with an empty vector, which writes nothing and
+    /// leaves the tiered storage writable.
+    fn write_zero_accounts(tiered_storage: &TieredStorage) {
+        let stored_infos = with_storable_accounts(&[], vec![], vec![], |storable_accounts| {
+            tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT)
+        })
+        .unwrap();
 
-        let result = tiered_storage.write_accounts(&storable_accounts, 0, &HOT_FORMAT);
+        assert!(stored_infos.is_empty());
+        assert!(!tiered_storage.is_read_only());
+        assert!(!tiered_storage.is_written());
+        assert!(!tiered_storage.path().try_exists().unwrap());
+    }
 
-        match (&result, &expected_result) {
-            (
-                Err(TieredStorageError::AttemptToUpdateReadOnly(_)),
-                Err(TieredStorageError::AttemptToUpdateReadOnly(_)),
-            ) => {}
-            (Err(TieredStorageError::Unsupported()), Err(TieredStorageError::Unsupported())) => {}
-            (Ok(_), Ok(_)) => {}
-            // we don't expect error type mis-match or other error types here
-            _ => {
-                panic!("actual: {result:?}, expected: {expected_result:?}");
-            }
-        };
+    /// Invokes finalize_empty to allow the tiered storage to persist
+    /// non-account blocks such as footer, index block, etc.
+    fn finalize_empty(tiered_storage: &TieredStorage) {
+        tiered_storage.finalize_empty(&HOT_FORMAT).unwrap();
 
         assert!(tiered_storage.is_read_only());
+        // the default options write a version 1 footer, which is the only
+        // content of a file without any account
+        let reader = tiered_storage.reader().unwrap();
+        assert_eq!(reader.footer().format_version, 1);
         assert_eq!(
             tiered_storage.len(),
             std::mem::size_of::<TieredStorageFooter>()
                 + std::mem::size_of::<TieredStorageMagicNumber>()
+                - std::mem::size_of::<u64>()
         );
     }
 
@@ -256,7 +967,7 @@ mod tests {
             assert_eq!(tiered_storage.path(), tiered_storage_path);
             assert_eq!(tiered_storage.len(), 0);
 
-            write_zero_accounts(&tiered_storage, Ok(vec![]));
+            finalize_empty(&tiered_storage);
         }
 
         let tiered_storage_readonly = TieredStorage::new_readonly(&tiered_storage_path).unwrap();
@@ -267,11 +978,7 @@ mod tests {
         assert_eq!(footer.owners_block_format, HOT_FORMAT.owners_block_format);
         assert_eq!(footer.index_block_format, HOT_FORMAT.index_block_format);
         assert_eq!(footer.account_block_format, HOT_FORMAT.account_block_format);
-        assert_eq!(
-            tiered_storage_readonly.len(),
-            std::mem::size_of::<TieredStorageFooter>()
-                + std::mem::size_of::<TieredStorageMagicNumber>()
-        );
+        assert_eq!(tiered_storage_readonly.len(), footer.footer_size as usize);
     }
 
     #[test]
@@ -282,15 +989,42 @@ mod tests {
         let tiered_storage_path = temp_dir.path().join("test_write_accounts_twice");
 
         let tiered_storage = TieredStorage::new_writable(&tiered_storage_path);
-        write_zero_accounts(&tiered_storage, Ok(vec![]));
-        // Expect AttemptToUpdateReadOnly error as write_accounts can only
-        // be invoked once.
-        write_zero_accounts(
-            &tiered_storage,
-            Err(TieredStorageError::AttemptToUpdateReadOnly(
-                tiered_storage_path,
-            )),
-        );
+        finalize_empty(&tiered_storage);
+        // Expect panic as nothing can be written once the file is written,
+        // even if there is no account to write.
+        write_zero_accounts(&tiered_storage);
+    }
+
+    #[test]
+    fn test_write_accounts_after_zero_accounts() {
+        let temp_dir = tempdir().unwrap();
+        let tiered_storage_path = temp_dir
+            .path()
+            .join("test_write_accounts_after_zero_accounts");
+
+        let tiered_storage = TieredStorage::new_writable(&tiered_storage_path);
+        write_zero_accounts(&tiered_storage);
+        write_zero_accounts(&tiered_storage);
+
+        // the accounts of a later write are persisted as usual
+        let accounts: Vec<_> = (1..4).map(create_test_account).collect();
+        let stored_infos = with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::new_unique()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+        )
+        .unwrap();
+        assert_eq!(stored_infos.len(), accounts.len());
+        assert!(tiered_storage.is_read_only());
+
+        let reader = tiered_storage.reader().unwrap();
+        assert_eq!(reader.num_accounts(), accounts.len());
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let (stored_account, _) = reader.get_account(IndexOffset(i as u32)).unwrap().unwrap();
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert_eq!(stored_account.lamports(), account.lamports());
+        }
     }
 
     #[test]
@@ -300,7 +1034,7 @@ mod tests {
         let tiered_storage_path = temp_dir.path().join("test_remove_on_drop");
         {
             let tiered_storage = TieredStorage::new_writable(&tiered_storage_path);
-            write_zero_accounts(&tiered_storage, Ok(vec![]));
+            finalize_empty(&tiered_storage);
         }
         // expect the file does not exists as it has been removed on drop
         assert!(!tiered_storage_path.try_exists().unwrap());
@@ -308,7 +1042,7 @@ mod tests {
         {
             let tiered_storage =
                 ManuallyDrop::new(TieredStorage::new_writable(&tiered_storage_path));
-            write_zero_accounts(&tiered_storage, Ok(vec![]));
+            finalize_empty(&tiered_storage);
         }
         // expect the file exists as we have ManuallyDrop this time.
         assert!(tiered_storage_path.try_exists().unwrap());
@@ -328,25 +1062,124 @@ mod tests {
         assert!(!tiered_storage_path.try_exists().unwrap());
     }
 
+    #[test]
+    fn test_set_remove_on_drop() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_set_remove_on_drop");
+        write_hot_storage(&path, &[1, 2, 3]);
+
+        // the file is kept when the removal is disabled
+        {
+            let mut tiered_storage = TieredStorage::new_readonly(&path).unwrap();
+            tiered_storage.set_remove_on_drop(false);
+        }
+        assert!(path.try_exists().unwrap());
+
+        // and removed again when the removal is re-enabled
+        {
+            let mut tiered_storage = TieredStorage::new_readonly(&path).unwrap();
+            tiered_storage.set_remove_on_drop(false);
+            tiered_storage.set_remove_on_drop(true);
+        }
+        assert!(!path.try_exists().unwrap());
+    }
+
+    #[test]
+    fn test_new_readonly_with_expected_accounts() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_new_readonly_with_expected_accounts");
+        write_hot_storage(&path, &[1, 2, 3]);
+
+        // test: the footer disagrees, and the file is kept
+        for expected_num_accounts in [0, 2, 4] {
+            assert_matches!(
+                TieredStorage::new_readonly_with_expected_accounts(&path, expected_num_accounts),
+                Err(TieredStorageError::AccountCountMismatch(expected, 3))
+                    if expected == expected_num_accounts
+            );
+            assert!(path.try_exists().unwrap());
+        }
+
+        // test: the footer agrees
+        let tiered_storage = TieredStorage::new_readonly_with_expected_accounts(&path, 3).unwrap();
+        assert_eq!(tiered_storage.reader().unwrap().num_accounts(), 3);
+    }
+
+    #[test]
+    fn test_open_many() {
+        const NUM_FILES: usize = 1000;
+        let temp_dir = tempdir().unwrap();
+        let paths: Vec<_> = (0..NUM_FILES)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("test_open_many_{i}"));
+                match i % 100 {
+                    // a file that is not a tiered storage file
+                    17 => std::fs::write(&path, [0u8; 64]).unwrap(),
+                    // a file that does not exist
+                    42 => {}
+                    _ => write_hot_storage(&path, &vec![1; i % 5]),
+                }
+                path
+            })
+            .collect();
+
+        // test: each result is at the position of its path
+        let results = TieredStorage::open_many(&paths);
+        assert_eq!(results.len(), NUM_FILES);
+        for (i, (path, result)) in paths.iter().zip(results).enumerate() {
+            match i % 100 {
+                17 => assert_matches!(result, Err(TieredStorageError::MagicNumberMismatch(..))),
+                42 => assert_matches!(result, Err(TieredStorageError::Io(_))),
+                _ => {
+                    let tiered_storage = result.unwrap();
+                    assert_eq!(tiered_storage.path(), path);
+                    let reader = tiered_storage.reader().unwrap();
+                    assert_eq!(reader.num_accounts(), i % 5);
+                    assert_eq!(
+                        reader.footer(),
+                        &TieredStorageFooter::new_from_path(path).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_without_file() {
+        let temp_dir = tempdir().unwrap();
+
+        // the file is already removed by someone else
+        let path = temp_dir.path().join("test_drop_without_file");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let tiered_storage = TieredStorage::new_readonly(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        drop(tiered_storage);
+        assert!(!path.try_exists().unwrap());
+
+        // the file cannot be removed, as its path is now a non-empty directory
+        let path = temp_dir.path().join("test_drop_without_file_dir");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let tiered_storage = TieredStorage::new_readonly(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("file"), b"").unwrap();
+        drop(tiered_storage);
+        assert!(path.join("file").try_exists().unwrap());
+    }
+
     /// The helper function for all write_accounts tests.
     /// Currently only supports hot accounts.
     fn do_test_write_accounts(
         path_suffix: &str,
         account_data_sizes: &[u64],
-        format: TieredStorageFormat,
+        format: ValidatedTieredStorageFormat,
     ) {
         let accounts: Vec<_> = account_data_sizes
             .iter()
             .map(|size| create_test_account(*size))
             .collect();
-
-        let account_refs: Vec<_> = accounts
-            .iter()
-            .map(|account| (&account.0.pubkey, &account.1))
-            .collect();
-
-        // Slot information is not used here
-        let account_data = (Slot::MAX, &account_refs[..]);
         let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
             .take(account_data_sizes.len())
             .collect();
@@ -355,26 +1188,26 @@ mod tests {
             .map(|account| account.0.write_version_obsolete)
             .collect();
 
-        let storable_accounts =
-            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
-                &account_data,
-                hashes,
-                write_versions,
-            );
-
         let temp_dir = tempdir().unwrap();
         let tiered_storage_path = temp_dir.path().join(path_suffix);
-        let tiered_storage = TieredStorage::new_writable(tiered_storage_path);
-        _ = tiered_storage.write_accounts(&storable_accounts, 0, &format);
+        let tiered_storage =
+            TieredStorage::new_writable_with_options(tiered_storage_path, WriteOptions::LATEST);
+        _ = with_storable_accounts(
+            &accounts,
+            hashes.clone(),
+            write_versions,
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &format),
+        );
 
         let reader = tiered_storage.reader().unwrap();
-        let num_accounts = storable_accounts.len();
+        let num_accounts = accounts.len();
         assert_eq!(reader.num_accounts(), num_accounts);
 
+        // a zero-lamport account is expected to be read back as the default
         let mut expected_accounts_map = HashMap::new();
-        for i in 0..num_accounts {
-            let (account, address, _account_hash, _write_version) = storable_accounts.get(i);
-            expected_accounts_map.insert(address, account);
+        for ((stored_meta, account), account_hash) in accounts.iter().zip(&hashes) {
+            let account = Some(account).filter(|account| account.lamports() != 0);
+            expected_accounts_map.insert(&stored_meta.pubkey, (account, account_hash));
         }
 
         let mut index_offset = IndexOffset(0);
@@ -387,11 +1220,12 @@ mod tests {
         let mut max_pubkey_ref = &MIN_PUBKEY;
 
         while let Some((stored_meta, next)) = reader.get_account(index_offset).unwrap() {
-            if let Some(account) = expected_accounts_map.get(stored_meta.pubkey()) {
+            if let Some((account, account_hash)) = expected_accounts_map.get(stored_meta.pubkey()) {
                 verify_test_account_with_footer(
                     &stored_meta,
                     *account,
                     stored_meta.pubkey(),
+                    account_hash,
                     footer,
                 );
                 verified_accounts.insert(stored_meta.pubkey());
@@ -428,6 +1262,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_accounts_around_u16_max_len() {
+        // the hot meta does not persist the data size, which is instead
+        // derived from the account block, so no size is special
+        let max = u16::MAX as u64;
+        do_test_write_accounts(
+            "test_write_accounts_around_u16_max_len",
+            &[max - 1, max, max + 1, max - 1],
+            HOT_FORMAT.clone(),
+        );
+    }
+
     #[test]
     fn test_write_accounts_mixed_size() {
         do_test_write_accounts(
@@ -438,4 +1284,984 @@ mod tests {
             HOT_FORMAT.clone(),
         );
     }
+
+    #[test]
+    fn test_write_accounts_executable() {
+        // (lamports, executable)
+        let account_fields = [(1, true), (2, false), (0, true), (0, false), (3, true)];
+        let accounts: Vec<_> = account_fields
+            .iter()
+            .map(|&(lamports, executable)| {
+                let (stored_meta, mut account) = create_test_account(10);
+                account.set_lamports(lamports);
+                account.set_executable(executable);
+                (stored_meta, account)
+            })
+            .collect();
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_write_accounts_executable");
+        let tiered_storage = TieredStorage::new_writable(&path);
+        with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+        )
+        .unwrap();
+
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        for (i, &(lamports, executable)) in account_fields.iter().enumerate() {
+            let (stored_meta, _) = reader.get_account(IndexOffset(i as u32)).unwrap().unwrap();
+            assert_eq!(stored_meta.pubkey(), &accounts[i].0.pubkey);
+            assert_eq!(stored_meta.lamports(), lamports);
+            // a zero-lamport account is stored as the default account,
+            // which is not executable
+            assert_eq!(stored_meta.executable(), executable && lamports > 0);
+        }
+    }
+
+    #[test]
+    fn test_write_accounts_with_skip() {
+        // The owner of each test account is derived from its data size, so
+        // the skipped accounts own owners that the written ones don't.
+        let account_data_sizes = [7, 8, 9, 1, 2, 1, 2, 3];
+        let accounts: Vec<_> = account_data_sizes
+            .iter()
+            .map(|size| create_test_account(*size))
+            .collect();
+        let temp_dir = tempdir().unwrap();
+
+        for skip in [0, 3, accounts.len()] {
+            let hashes = vec![AccountHash(Hash::default()); accounts.len()];
+            let write_versions = vec![0; accounts.len()];
+            let path = temp_dir
+                .path()
+                .join(format!("test_write_accounts_with_skip_{skip}"));
+            let tiered_storage = TieredStorage::new_writable(path);
+            let stored_infos =
+                with_storable_accounts(&accounts, hashes, write_versions, |storable_accounts| {
+                    tiered_storage.write_accounts(storable_accounts, skip, &HOT_FORMAT)
+                })
+                .unwrap();
+            if skip == accounts.len() {
+                // no account is left to write, so the file is not created
+                assert!(stored_infos.is_empty());
+                assert!(!tiered_storage.is_written());
+                assert!(!tiered_storage.path().try_exists().unwrap());
+                continue;
+            }
+
+            // only the accounts in [skip, len) are written, and the returned
+            // offsets correspond one-to-one with them
+            let written = &accounts[skip..];
+            let reader = tiered_storage.reader().unwrap();
+            assert_eq!(reader.num_accounts(), written.len());
+            assert_eq!(stored_infos.len(), written.len());
+            for (i, ((stored_meta, account), stored_info)) in
+                written.iter().zip(stored_infos.iter()).enumerate()
+            {
+                assert_eq!(stored_info.offset, i);
+                let (stored_account, _) = reader
+                    .get_account(IndexOffset(stored_info.offset as u32))
+                    .unwrap()
+                    .unwrap();
+                verify_test_account(
+                    &stored_account,
+                    Some(account),
+                    &stored_meta.pubkey,
+                    &AccountHash(Hash::default()),
+                );
+            }
+
+            // the owners table only covers the written accounts
+            let mut expected_owners: Vec<_> = vec![];
+            for (_, account) in written {
+                if !expected_owners.contains(account.owner()) {
+                    expected_owners.push(*account.owner());
+                }
+            }
+            assert_eq!(reader.owners().unwrap(), expected_owners);
+        }
+    }
+
+    #[test_case(HOT_FORMAT)]
+    #[test_case(HOT_SORTED_FORMAT)]
+    fn test_write_accounts_max_accounts_per_file(format: ValidatedTieredStorageFormat) {
+        const MAX_ACCOUNTS_PER_FILE: usize = 4;
+        let accounts: Vec<_> = (0..10).map(create_test_account).collect();
+        let format = ValidatedTieredStorageFormat::try_from(TieredStorageFormat {
+            max_accounts_per_file: Some(MAX_ACCOUNTS_PER_FILE as u32),
+            ..format.0
+        })
+        .unwrap();
+        let temp_dir = tempdir().unwrap();
+
+        // test: each file takes the next accounts up to the cap, and the
+        // caller resumes with the number of accounts written so far
+        let mut skip = 0;
+        let mut num_files = 0;
+        while skip < accounts.len() {
+            let path = temp_dir.path().join(format!("test_max_accounts_{skip}"));
+            // the sorted index is only written by the newer generations
+            let tiered_storage =
+                TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+            let stored_infos = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| tiered_storage.write_accounts(storable_accounts, skip, &format),
+            )
+            .unwrap();
+            let written = &accounts[skip..accounts.len().min(skip + MAX_ACCOUNTS_PER_FILE)];
+            assert_eq!(stored_infos.len(), written.len());
+
+            let reader = tiered_storage.reader().unwrap();
+            assert_eq!(reader.num_accounts(), written.len());
+            for ((stored_meta, account), stored_info) in written.iter().zip(&stored_infos) {
+                let (stored_account, _) = reader
+                    .get_account(IndexOffset(stored_info.offset as u32))
+                    .unwrap()
+                    .unwrap();
+                verify_test_account(
+                    &stored_account,
+                    Some(account),
+                    &stored_meta.pubkey,
+                    &AccountHash(Hash::default()),
+                );
+            }
+            skip += stored_infos.len();
+            num_files += 1;
+        }
+        assert_eq!(num_files, accounts.len().div_ceil(MAX_ACCOUNTS_PER_FILE));
+    }
+
+    #[test]
+    fn test_format_builder_presets() {
+        assert_eq!(TieredStorageFormat::builder().build().unwrap(), HOT_FORMAT);
+        assert_eq!(
+            TieredStorageFormat::builder()
+                .with_index_block_format(IndexBlockFormat::SortedAddressesThenOffsets)
+                .build()
+                .unwrap(),
+            HOT_SORTED_FORMAT
+        );
+        assert_eq!(
+            TieredStorageFormat::builder()
+                .with_index_block_format(IndexBlockFormat::AddressesThenOffsetsThenSizes)
+                .build()
+                .unwrap(),
+            HOT_SIZED_INDEX_FORMAT
+        );
+        assert_eq!(
+            TieredStorageFormat::builder()
+                .with_owners_block_format(OwnersBlockFormat::LocalIndexWithCounts)
+                .build()
+                .unwrap(),
+            HOT_OWNER_COUNTS_FORMAT
+        );
+    }
+
+    #[test]
+    fn test_format_builder_valid() {
+        let accounts: Vec<_> = (1..6).map(create_test_account).collect();
+        let temp_dir = tempdir().unwrap();
+        for owners_block_format in [
+            OwnersBlockFormat::AddressesOnly,
+            OwnersBlockFormat::LocalIndexWithCounts,
+        ] {
+            for index_block_format in [
+                IndexBlockFormat::AddressesThenOffsets,
+                IndexBlockFormat::SortedAddressesThenOffsets,
+                IndexBlockFormat::AddressesThenOffsetsThenSizes,
+            ] {
+                let format = TieredStorageFormat::builder()
+                    .with_account_meta_format(AccountMetaFormat::Hot)
+                    .with_account_block_format(AccountBlockFormat::AlignedRaw)
+                    .with_owners_block_format(owners_block_format)
+                    .with_index_block_format(index_block_format)
+                    .with_hash_kind(FooterHashKind::Blake3)
+                    .with_max_accounts_per_file(Some(100))
+                    .build()
+                    .unwrap();
+                assert_eq!(
+                    format.meta_entry_size,
+                    std::mem::size_of::<HotAccountMeta>()
+                );
+
+                // every valid format can be written and read back
+                let path = temp_dir.path().join(format!(
+                    "test_format_builder_valid_{owners_block_format:?}_{index_block_format:?}"
+                ));
+                let tiered_storage =
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+                with_storable_accounts(
+                    &accounts,
+                    vec![AccountHash(Hash::default()); accounts.len()],
+                    vec![0; accounts.len()],
+                    |storable_accounts| {
+                        tiered_storage.write_accounts(storable_accounts, 0, &format)
+                    },
+                )
+                .unwrap();
+                let reader = tiered_storage.reader().unwrap();
+                assert_eq!(reader.footer().owners_block_format, owners_block_format);
+                assert_eq!(reader.footer().index_block_format, index_block_format);
+                let addresses: HashSet<_> = reader
+                    .account_iter()
+                    .map(|account| *account.pubkey())
+                    .collect();
+                assert_eq!(
+                    addresses,
+                    accounts
+                        .iter()
+                        .map(|(meta, _)| meta.pubkey)
+                        .collect::<HashSet<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_block_checksums() {
+        let accounts: Vec<_> = (1..6).map(create_test_account).collect();
+        let format = TieredStorageFormat::builder()
+            .with_block_checksums(true)
+            .build()
+            .unwrap();
+        let temp_dir = tempdir().unwrap();
+
+        // the checksums are dropped by the generations that predate them
+        for (max_format_generation, expected) in [
+            (FormatGeneration::V6AccountHash, None),
+            (FormatGeneration::V7BlockChecksum, Some(true)),
+        ] {
+            let path = temp_dir.path().join(format!(
+                "test_format_block_checksums_{max_format_generation:?}"
+            ));
+            let tiered_storage = TieredStorage::new_writable_with_options(
+                &path,
+                WriteOptions {
+                    max_format_generation,
+                },
+            );
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &format),
+            )
+            .unwrap();
+            let reader = tiered_storage.reader().unwrap();
+            assert_eq!(reader.num_accounts(), accounts.len());
+            assert!(reader
+                .account_iter()
+                .all(|account| account.verify_checksum() == expected));
+
+            // a rewritten file keeps the checksums of the source
+            let dest = tiered_storage
+                .rewrite_filtered(path.with_extension("rewritten"), |_| true)
+                .unwrap();
+            let dest_reader = dest.reader().unwrap();
+            assert_eq!(dest_reader.num_accounts(), accounts.len());
+            assert!(dest_reader
+                .account_iter()
+                .all(|account| account.verify_checksum() == expected));
+        }
+    }
+
+    #[test]
+    fn test_format_builder_invalid() {
+        for account_block_format in [AccountBlockFormat::Lz4, AccountBlockFormat::Zstd] {
+            assert_matches!(
+                TieredStorageFormat::builder()
+                    .with_account_block_format(account_block_format)
+                    .build(),
+                Err(TieredStorageError::UnsupportedAccountBlockFormat(
+                    AccountMetaFormat::Hot,
+                    format,
+                )) if format == account_block_format
+            );
+        }
+        assert_matches!(
+            TieredStorageFormat::builder()
+                .with_compression_level(3)
+                .build(),
+            Err(TieredStorageError::UnexpectedCompressionLevel(
+                3,
+                AccountBlockFormat::AlignedRaw
+            ))
+        );
+
+        // the builder always fills the meta entry size, which can only be
+        // wrong in a format built by hand
+        let format = TieredStorageFormat {
+            meta_entry_size: std::mem::size_of::<HotAccountMeta>() + 8,
+            ..HOT_FORMAT.0
+        };
+        assert_matches!(
+            format.validate(),
+            Err(TieredStorageError::InvalidMetaEntrySize(actual, expected))
+                if actual == std::mem::size_of::<HotAccountMeta>() + 8
+                    && expected == std::mem::size_of::<HotAccountMeta>()
+        );
+
+        // such a format can never reach the writer
+        assert_matches!(
+            ValidatedTieredStorageFormat::try_from(format),
+            Err(TieredStorageError::InvalidMetaEntrySize(_, _))
+        );
+    }
+
+    #[test]
+    fn test_meta() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_meta");
+        let account_data_sizes = &[1, 2, 3, 4, 5, 0];
+
+        let tiered_storage = ManuallyDrop::new(TieredStorage::new_writable(&path));
+        // the metadata is never populated while the storage is still writable
+        assert!(tiered_storage.meta().is_none());
+        assert!(tiered_storage.meta.get().is_none());
+        assert_eq!(tiered_storage.len(), 0);
+        assert_eq!(tiered_storage.capacity(), MAX_TIERED_FILE_SIZE);
+
+        let accounts: Vec<_> = account_data_sizes
+            .iter()
+            .map(|size| create_test_account(*size))
+            .collect();
+        let hashes = vec![AccountHash(Hash::default()); accounts.len()];
+        let write_versions = vec![0; accounts.len()];
+        with_storable_accounts(&accounts, hashes, write_versions, |storable_accounts| {
+            tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT)
+        })
+        .unwrap();
+
+        // the metadata matches the values obtained via the reader
+        let reader = tiered_storage.reader().unwrap();
+        let expected_meta = TieredStorageMeta {
+            file_len: reader.len(),
+            num_accounts: account_data_sizes.len(),
+            owner_count: reader.footer().owner_count as usize,
+            // the default options write a version 1 footer, whose hash is
+            // never trusted
+            format: TieredStorageFormat {
+                hash_kind: FooterHashKind::None,
+                ..HOT_FORMAT.0
+            },
+        };
+        let meta = tiered_storage.meta().unwrap();
+        assert_eq!(meta, &expected_meta);
+        assert_eq!(tiered_storage.len(), reader.len());
+        assert_eq!(tiered_storage.capacity(), reader.capacity());
+
+        // repeated queries hit the cache
+        assert!(std::ptr::eq(meta, tiered_storage.meta().unwrap()));
+
+        // a read-only instance populates the same metadata
+        let tiered_storage_readonly = TieredStorage::new_readonly(&path).unwrap();
+        assert_eq!(tiered_storage_readonly.meta().unwrap(), &expected_meta);
+    }
+
+    #[test]
+    fn test_write_accounts_with_hash_kind() {
+        let temp_dir = tempdir().unwrap();
+        for hash_kind in [FooterHashKind::Blake3, FooterHashKind::None] {
+            let path = temp_dir
+                .path()
+                .join(format!("test_hash_kind_{hash_kind:?}"));
+            let format = TieredStorageFormat::builder()
+                .with_hash_kind(hash_kind)
+                .build()
+                .unwrap();
+            let accounts = [create_test_account(7)];
+
+            // the hash kind does not make the format unknown
+            let tiered_storage =
+                TieredStorage::new_writable_with_options(&path, WriteOptions::LATEST);
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+                |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &format),
+            )
+            .unwrap();
+            assert_eq!(tiered_storage.meta().unwrap().format, *format);
+
+            let verification = tiered_storage.reader().unwrap().verify_file_hash();
+            match hash_kind {
+                FooterHashKind::None => {
+                    assert_matches!(verification, Err(TieredStorageError::VerificationSkipped))
+                }
+                _ => assert_matches!(verification, Ok(())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_accounts_format_generation() {
+        let temp_dir = tempdir().unwrap();
+        let accounts = [create_test_account(7)];
+        let blake3_format = TieredStorageFormat::builder()
+            .with_hash_kind(FooterHashKind::Blake3)
+            .build()
+            .unwrap();
+
+        // (format, generation, expected footer version and hash kind, or
+        // None if the write fails)
+        let cases = [
+            (
+                &HOT_FORMAT,
+                FormatGeneration::V1Baseline,
+                Some((1, FooterHashKind::None)),
+            ),
+            // the footer is downgraded along with its hash kind
+            (
+                &blake3_format,
+                FormatGeneration::V1Baseline,
+                Some((1, FooterHashKind::None)),
+            ),
+            // the sorted index cannot be downgraded
+            (&HOT_SORTED_FORMAT, FormatGeneration::V1Baseline, None),
+            (&HOT_SORTED_FORMAT, FormatGeneration::V2Footer, None),
+            (
+                &blake3_format,
+                FormatGeneration::V2Footer,
+                Some((2, FooterHashKind::Blake3)),
+            ),
+            (
+                &HOT_FORMAT,
+                FormatGeneration::V2Footer,
+                Some((2, FooterHashKind::Sha256)),
+            ),
+            (
+                &HOT_SORTED_FORMAT,
+                FormatGeneration::V3SortedIndex,
+                Some((2, FooterHashKind::Sha256)),
+            ),
+        ];
+        for (i, (format, max_format_generation, expected)) in cases.into_iter().enumerate() {
+            let path = temp_dir.path().join(format!("test_format_generation_{i}"));
+            let tiered_storage = TieredStorage::new_writable_with_options(
+                &path,
+                WriteOptions {
+                    max_format_generation,
+                },
+            );
+            let result = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+                |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, format),
+            );
+            let Some((format_version, hash_kind)) = expected else {
+                assert_matches!(
+                    result,
+                    Err(TieredStorageError::FormatGenerationExceeded(
+                        FormatFeature::SortedIndex,
+                        generation,
+                    )) if generation == max_format_generation
+                );
+                continue;
+            };
+            result.unwrap();
+
+            let reader = TieredStorageReader::new_from_path(&path).unwrap();
+            let footer = reader.footer();
+            assert_eq!(footer.format_version, format_version);
+            assert_eq!(footer.hash_kind, hash_kind);
+            assert_eq!(footer.index_block_format, format.index_block_format);
+            // every footer layout describes the compiled meta entry, which
+            // the reader validates on open
+            assert_eq!(
+                footer.account_meta_entry_size as usize,
+                std::mem::size_of::<HotAccountMeta>()
+            );
+            assert!(reader.required_generation() <= max_format_generation);
+            match hash_kind {
+                FooterHashKind::None => assert_matches!(
+                    reader.verify_file_hash(),
+                    Err(TieredStorageError::VerificationSkipped)
+                ),
+                _ => assert_matches!(reader.verify_file_hash(), Ok(())),
+            }
+            assert_eq!(
+                reader
+                    .get_account(IndexOffset(0))
+                    .unwrap()
+                    .unwrap()
+                    .0
+                    .pubkey(),
+                &accounts[0].0.pubkey
+            );
+            if max_format_generation == FormatGeneration::V1Baseline {
+                assert_eq!(reader.required_generation(), FormatGeneration::V1Baseline);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rewrite_filtered() {
+        const NUM_ACCOUNTS: u64 = 100;
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_rewrite_filtered_source");
+        let dest_path = temp_dir.path().join("test_rewrite_filtered_dest");
+
+        // each account has its own owner, derived from its data size
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS).map(create_test_account).collect();
+        // only some of the accounts have their hashes calculated
+        let hashes: Vec<_> = (0..accounts.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    AccountHash(Hash::new_unique())
+                } else {
+                    AccountHash(Hash::default())
+                }
+            })
+            .collect();
+        let kept: Vec<_> = accounts.iter().zip(&hashes).step_by(3).collect();
+        let kept_addresses: HashSet<_> = kept.iter().map(|((meta, _), _)| meta.pubkey).collect();
+
+        // a version 2 footer persists the file hash
+        let tiered_storage = TieredStorage::new_writable_with_options(&path, WriteOptions::LATEST);
+
+        // a writable instance has nothing to rewrite yet
+        assert_matches!(
+            tiered_storage.rewrite_filtered(&dest_path, |_| true),
+            Err(TieredStorageError::NotYetWritten(_))
+        );
+        assert!(!dest_path.exists());
+
+        with_storable_accounts(
+            &accounts,
+            hashes.clone(),
+            vec![0; accounts.len()],
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+        )
+        .unwrap();
+        let source_bytes = fs::read(&path).unwrap();
+
+        let dest = tiered_storage
+            .rewrite_filtered(&dest_path, |address| kept_addresses.contains(address))
+            .unwrap();
+
+        // the source is untouched and still readable
+        assert_eq!(fs::read(&path).unwrap(), source_bytes);
+        let source_reader = tiered_storage.reader().unwrap();
+        assert_eq!(source_reader.num_accounts(), NUM_ACCOUNTS as usize);
+        assert_eq!(source_reader.owner_count(), NUM_ACCOUNTS as usize);
+
+        // the destination has exactly the kept accounts, in their original
+        // order, along with only their owners
+        let reader = dest.reader().unwrap();
+        assert_eq!(reader.num_accounts(), kept.len());
+        assert_eq!(reader.owner_count(), kept.len());
+        assert!(dest.len() < tiered_storage.len());
+        assert_eq!(dest.meta().unwrap().format, *HOT_FORMAT);
+        assert_eq!(
+            reader.required_generation(),
+            source_reader.required_generation()
+        );
+        let stored_accounts: Vec<_> = reader.account_iter().collect();
+        assert_eq!(stored_accounts.len(), kept.len());
+        for (stored_account, ((stored_meta, account), hash)) in stored_accounts.iter().zip(&kept) {
+            verify_test_account_with_footer(
+                stored_account,
+                Some(account),
+                &stored_meta.pubkey,
+                hash,
+                reader.footer(),
+            );
+            assert_eq!(stored_account.rent_epoch(), account.rent_epoch());
+        }
+
+        let footer = reader.footer();
+        let min_address = kept_addresses.iter().min().unwrap();
+        let max_address = kept_addresses.iter().max().unwrap();
+        assert_eq!(
+            footer.account_address_range(),
+            PubkeyRange::new(*min_address, *max_address)
+        );
+        assert_matches!(reader.verify_file_hash(), Ok(()));
+        assert_ne!(footer.hash, source_reader.footer().hash);
+    }
+
+    #[test]
+    fn test_concurrent_readers_during_write() {
+        const NUM_ACCOUNTS: u64 = 200;
+        const NUM_READERS: usize = 4;
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_concurrent_readers_during_write");
+        let tiered_storage = TieredStorage::new_writable(&path);
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS).map(create_test_account).collect();
+        let barrier = Barrier::new(NUM_READERS + 1);
+
+        std::thread::scope(|scope| {
+            for _ in 0..NUM_READERS {
+                scope.spawn(|| {
+                    barrier.wait();
+                    // a reader is either absent or has all the accounts
+                    loop {
+                        let is_read_only = tiered_storage.is_read_only();
+                        match tiered_storage.reader() {
+                            Some(reader) => {
+                                assert_eq!(reader.num_accounts(), NUM_ACCOUNTS as usize);
+                                assert_eq!(
+                                    tiered_storage.meta().unwrap().num_accounts,
+                                    NUM_ACCOUNTS as usize
+                                );
+                                let (stored_meta, account) = accounts.last().unwrap();
+                                let (stored_account, _) = reader
+                                    .get_account(IndexOffset(NUM_ACCOUNTS as u32 - 1))
+                                    .unwrap()
+                                    .unwrap();
+                                verify_test_account(
+                                    &stored_account,
+                                    Some(account),
+                                    &stored_meta.pubkey,
+                                    &AccountHash(Hash::default()),
+                                );
+                                break;
+                            }
+                            None => {
+                                assert!(!is_read_only);
+                                assert!(tiered_storage.meta().is_none());
+                                std::thread::yield_now();
+                            }
+                        }
+                    }
+                });
+            }
+
+            scope.spawn(|| {
+                with_storable_accounts(
+                    &accounts,
+                    vec![AccountHash(Hash::default()); accounts.len()],
+                    vec![0; accounts.len()],
+                    |storable_accounts| {
+                        barrier.wait();
+                        tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT)
+                    },
+                )
+                .unwrap();
+                // the reader is installed before the write returns
+                assert!(tiered_storage.is_read_only());
+            });
+        });
+    }
+
+    #[test]
+    fn test_write_accounts_failure() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_write_accounts_failure");
+        let accounts = [create_test_account(7)];
+
+        // the writer error is returned, and no reader is installed
+        let tiered_storage =
+            TieredStorage::new_writable_with_options(&path, WriteOptions::default());
+        assert_matches!(
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+                |storable_accounts| {
+                    tiered_storage.write_accounts(storable_accounts, 0, &HOT_SORTED_FORMAT)
+                },
+            ),
+            Err(TieredStorageError::FormatGenerationExceeded(..))
+        );
+        assert!(!tiered_storage.is_read_only());
+        assert!(tiered_storage.reader().is_none());
+    }
+
+    #[test]
+    fn test_write_accounts_file_already_exists() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_write_accounts_file_already_exists");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let original_bytes = fs::read(&path).unwrap();
+
+        let accounts = [create_test_account(7)];
+
+        // test: the existing file is reported, and neither the write nor
+        // dropping the TieredStorage touches it
+        let tiered_storage = TieredStorage::new_writable(&path);
+        assert_matches!(
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+                |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+            ),
+            Err(TieredStorageError::FileAlreadyExists(existing_path)) if existing_path == path
+        );
+        assert!(!tiered_storage.is_read_only());
+        drop(tiered_storage);
+        assert_eq!(fs::read(&path).unwrap(), original_bytes);
+        assert_eq!(
+            TieredStorageReader::new_from_path(&path)
+                .unwrap()
+                .num_accounts(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_stats");
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+        // the owner of each account, as indices into `owners`
+        let owner_distribution = [0, 1, 0, 2, 1, 0, 0, 2];
+
+        let tiered_storage = ManuallyDrop::new(TieredStorage::new_writable(&path));
+        assert!(tiered_storage.stats().is_none());
+
+        let accounts: Vec<_> = owner_distribution
+            .iter()
+            .enumerate()
+            .map(|(i, owner_index)| {
+                (
+                    Pubkey::new_unique(),
+                    AccountSharedData::new(i as u64 + 1, i, &owners[*owner_index]),
+                )
+            })
+            .collect();
+        let account_refs: Vec<_> = accounts
+            .iter()
+            .map(|(address, account)| (address, account))
+            .collect();
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let hashes = vec![AccountHash(Hash::default()); accounts.len()];
+        let write_versions = vec![0; accounts.len()];
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                hashes,
+                write_versions,
+            );
+        tiered_storage
+            .write_accounts(&storable_accounts, 0, &HOT_FORMAT)
+            .unwrap();
+
+        // the owners are the distinct owners in their insertion order
+        let reader = tiered_storage.reader().unwrap();
+        assert_eq!(reader.owner_count(), owners.len());
+        assert_eq!(reader.owners().unwrap(), owners);
+
+        let stats = tiered_storage.stats().unwrap();
+        assert_eq!(
+            stats,
+            TieredStorageStats {
+                num_accounts: owner_distribution.len(),
+                owner_count: owners.len(),
+                file_len: reader.len(),
+                average_account_block_size: reader.account_blocks_end() / owner_distribution.len(),
+            }
+        );
+        assert!(stats.average_account_block_size >= std::mem::size_of::<hot::HotAccountMeta>());
+    }
+
+    #[test]
+    fn test_owner_account_count() {
+        let temp_dir = tempdir().unwrap();
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+        // a skewed distribution, as indices into `owners`
+        let owner_distribution = [0, 0, 1, 0, 0, 0, 2, 0, 0, 1, 0];
+        let expected_counts = [8, 2, 1];
+
+        let accounts: Vec<_> = owner_distribution
+            .iter()
+            .enumerate()
+            .map(|(i, owner_index)| {
+                (
+                    Pubkey::new_unique(),
+                    AccountSharedData::new(i as u64 + 1, i, &owners[*owner_index]),
+                )
+            })
+            .collect();
+        let account_refs: Vec<_> = accounts
+            .iter()
+            .map(|(address, account)| (address, account))
+            .collect();
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+            );
+
+        // test: the counts are persisted by the owners block, or derived
+        // from the account metas when the writer cannot persist them
+        for (name, format, write_options, expected_owners_block_format) in [
+            (
+                "addresses_only",
+                HOT_FORMAT,
+                WriteOptions::LATEST,
+                OwnersBlockFormat::AddressesOnly,
+            ),
+            (
+                "with_counts",
+                HOT_OWNER_COUNTS_FORMAT,
+                WriteOptions::LATEST,
+                OwnersBlockFormat::LocalIndexWithCounts,
+            ),
+            (
+                "downgraded",
+                HOT_OWNER_COUNTS_FORMAT,
+                WriteOptions::default(),
+                OwnersBlockFormat::AddressesOnly,
+            ),
+        ] {
+            let path = temp_dir
+                .path()
+                .join(format!("test_owner_account_count_{name}"));
+            let tiered_storage = TieredStorage::new_writable_with_options(&path, write_options);
+            tiered_storage
+                .write_accounts(&storable_accounts, 0, &format)
+                .unwrap();
+
+            let reader = tiered_storage.reader().unwrap();
+            assert_eq!(
+                reader.footer().owners_block_format,
+                expected_owners_block_format
+            );
+            assert_eq!(reader.owners().unwrap(), owners);
+            for (owner, expected_count) in owners.iter().zip(expected_counts) {
+                assert_eq!(
+                    reader.owner_account_count(owner).unwrap(),
+                    Some(expected_count)
+                );
+            }
+            assert_eq!(
+                reader.owner_account_count(&Pubkey::new_unique()).unwrap(),
+                None
+            );
+            assert!(reader.verify().unwrap().is_consistent());
+        }
+    }
+
+    #[test]
+    fn test_writer_stats() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_writer_stats");
+        let tiered_storage = TieredStorage::new_writable(&path);
+        assert!(tiered_storage.writer_stats().is_none());
+
+        let accounts: Vec<_> = (1..=5).map(create_test_account).collect();
+        with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+        )
+        .unwrap();
+
+        let stats = tiered_storage.writer_stats().unwrap();
+        assert_eq!(stats.num_accounts, accounts.len());
+        assert_eq!(stats.total_bytes(), tiered_storage.len());
+
+        // the statistics are not available to a read-only instance
+        let mut tiered_storage_readonly = TieredStorage::new_readonly(&path).unwrap();
+        tiered_storage_readonly.set_remove_on_drop(false);
+        assert!(tiered_storage_readonly.writer_stats().is_none());
+    }
+
+    #[test]
+    fn test_verify() {
+        // the last account has no data, so any padding exceeds its block
+        const DATA_SIZES: &[u64] = &[1, 2, 3, 4, 5, 0];
+        let temp_dir = tempdir().unwrap();
+
+        // test: a healthy file
+        let path = temp_dir.path().join("test_verify_healthy");
+        write_hot_storage_with_hash_kind(&path, DATA_SIZES, FooterHashKind::Sha256);
+        let report = TieredStorage::verify(&path).unwrap();
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        assert_eq!(
+            report,
+            VerifyReport {
+                num_accounts: DATA_SIZES.len(),
+                owner_count: DATA_SIZES.len(),
+                file_len: reader.len(),
+                total_account_bytes: reader.total_account_bytes().unwrap(),
+                // the accounts whose seed is not a multiple of 3
+                num_accounts_with_rent_epoch: 4,
+                num_accounts_with_account_hash: DATA_SIZES.len(),
+                inconsistencies: vec![],
+            }
+        );
+        assert!(report.is_consistent());
+        assert!(path.exists());
+
+        // test: each corruption is reported along with the hash mismatch
+        let corruptions: [(&str, fn(&Path), Inconsistency); 3] = [
+            (
+                "account_block_ends_before_start",
+                |path| hot::tests::corrupt_account_offset(path, IndexOffset(0), IndexOffset(2)),
+                Inconsistency::InvalidAccountBlock(IndexOffset(0)),
+            ),
+            (
+                "owner_offset_out_of_range",
+                |path| hot::tests::corrupt_owner_offset(path, IndexOffset(3)),
+                Inconsistency::OwnerOffsetOutOfRange(
+                    IndexOffset(3),
+                    owners::OwnerOffset((1 << 29) - 1),
+                ),
+            ),
+            (
+                "padding_exceeds_account_block",
+                |path| hot::tests::corrupt_account_data_padding(path, IndexOffset(5), 7),
+                Inconsistency::OptionalFieldsExceedAccountBlock(IndexOffset(5)),
+            ),
+        ];
+        for (name, corrupt, expected) in corruptions {
+            let path = temp_dir.path().join(format!("test_verify_{name}"));
+            write_hot_storage_with_hash_kind(&path, DATA_SIZES, FooterHashKind::Sha256);
+            corrupt(&path);
+            let report = TieredStorage::verify(&path).unwrap();
+            assert_eq!(report.num_accounts, DATA_SIZES.len());
+            assert_eq!(report.inconsistencies.len(), 2, "{name}: {report:?}");
+            assert_eq!(report.inconsistencies[0], expected);
+            assert_matches!(
+                report.inconsistencies[1],
+                Inconsistency::HashMismatch { .. }
+            );
+        }
+
+        // test: all the corruptions are collected instead of stopping at
+        // the first one, and the file without a hash has no hash mismatch
+        let path = temp_dir.path().join("test_verify_all_corruptions");
+        write_hot_storage_with_hash_kind(&path, DATA_SIZES, FooterHashKind::None);
+        for (_, corrupt, _) in corruptions {
+            corrupt(&path);
+        }
+        let report = TieredStorage::verify(&path).unwrap();
+        assert_eq!(
+            report.inconsistencies,
+            corruptions.map(|(_, _, expected)| expected)
+        );
+
+        // test: a corrupted account data byte is only caught by the hash
+        let path = temp_dir.path().join("test_verify_corrupted_data");
+        write_hot_storage_with_hash_kind(&path, DATA_SIZES, FooterHashKind::Sha256);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(std::mem::size_of::<HotAccountMeta>() as u64))
+            .unwrap();
+        file.write_all(&[u8::MAX]).unwrap();
+        drop(file);
+        let report = TieredStorage::verify(&path).unwrap();
+        assert_matches!(
+            report.inconsistencies[..],
+            [Inconsistency::HashMismatch { .. }]
+        );
+
+        // test: a file that cannot be opened is an error
+        let path = temp_dir.path().join("test_verify_empty_file");
+        std::fs::File::create(&path).unwrap();
+        assert_matches!(TieredStorage::verify(&path), Err(TieredStorageError::Io(_)));
+    }
 }
@@ -2,18 +2,33 @@
 //! accounts db tiered storage.
 
 use {
-    crate::tiered_storage::{footer::AccountBlockFormat, meta::AccountMetaOptionalFields},
+    crate::tiered_storage::{
+        error::TieredStorageError, footer::AccountBlockFormat, meta::AccountMetaOptionalFields,
+        TieredStorageResult,
+    },
     std::{
+        fmt,
         io::{Cursor, Read, Result as IoResult, Write},
         mem,
     },
 };
 
 /// The encoder for the byte-block.
-#[derive(Debug)]
 pub enum ByteBlockEncoder {
     Raw(Cursor<Vec<u8>>),
     Lz4(lz4::Encoder<Vec<u8>>),
+    Zstd(zstd::Encoder<'static, Vec<u8>>),
+}
+
+impl fmt::Debug for ByteBlockEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Raw(cursor) => f.debug_tuple("Raw").field(cursor).finish(),
+            Self::Lz4(lz4_encoder) => f.debug_tuple("Lz4").field(lz4_encoder).finish(),
+            // zstd::Encoder does not implement Debug
+            Self::Zstd(_) => f.write_str("Zstd(..)"),
+        }
+    }
 }
 
 /// The byte block writer.
@@ -34,6 +49,17 @@ pub struct ByteBlockWriter {
 impl ByteBlockWriter {
     /// Create a ByteBlockWriter from the specified AccountBlockFormat.
     pub fn new(encoding: AccountBlockFormat) -> Self {
+        Self::new_with_compression_level(encoding, None)
+    }
+
+    /// Create a ByteBlockWriter from the specified AccountBlockFormat and
+    /// compression level, where None means the default level of the format.
+    ///
+    /// The compression level is ignored by the formats that do not support it.
+    pub fn new_with_compression_level(
+        encoding: AccountBlockFormat,
+        compression_level: Option<i32>,
+    ) -> Self {
         Self {
             encoder: match encoding {
                 AccountBlockFormat::AlignedRaw => ByteBlockEncoder::Raw(Cursor::new(Vec::new())),
@@ -43,6 +69,13 @@ impl ByteBlockWriter {
                         .build(Vec::new())
                         .unwrap(),
                 ),
+                AccountBlockFormat::Zstd => ByteBlockEncoder::Zstd(
+                    zstd::Encoder::new(
+                        Vec::new(),
+                        compression_level.unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL),
+                    )
+                    .unwrap(),
+                ),
             },
             len: 0,
         }
@@ -95,6 +128,9 @@ impl ByteBlockWriter {
         if let Some(rent_epoch) = opt_fields.rent_epoch {
             size += self.write_pod(&rent_epoch)?;
         }
+        if let Some(account_hash) = opt_fields.account_hash {
+            size += self.write_pod(&account_hash)?;
+        }
 
         debug_assert_eq!(size, opt_fields.size());
 
@@ -107,11 +143,31 @@ impl ByteBlockWriter {
         match &mut self.encoder {
             ByteBlockEncoder::Raw(cursor) => cursor.write_all(buf)?,
             ByteBlockEncoder::Lz4(lz4_encoder) => lz4_encoder.write_all(buf)?,
+            ByteBlockEncoder::Zstd(zstd_encoder) => zstd_encoder.write_all(buf)?,
         };
         self.len += buf.len();
         Ok(())
     }
 
+    /// Write the specified bytes followed by the zero padding that aligns the
+    /// raw length of the byte block to `align`, and return the number of
+    /// padding bytes written.
+    ///
+    /// Panics if `align` is zero.
+    pub fn write_aligned(&mut self, data: &[u8], align: usize) -> IoResult<usize> {
+        const ZEROS: [u8; 64] = [0u8; 64];
+
+        self.write(data)?;
+        let padding = (align - self.len % align) % align;
+        let mut remaining = padding;
+        while remaining > 0 {
+            let len = remaining.min(ZEROS.len());
+            self.write(&ZEROS[..len])?;
+            remaining -= len;
+        }
+        Ok(padding)
+    }
+
     /// Flush the internal byte buffer that collects all the previous writes
     /// into an encoded byte array.
     pub fn finish(self) -> IoResult<Vec<u8>> {
@@ -122,16 +178,30 @@ impl ByteBlockWriter {
                 result?;
                 Ok(compressed_block)
             }
+            ByteBlockEncoder::Zstd(zstd_encoder) => zstd_encoder.finish(),
         }
     }
 }
 
-/// The util struct for reading byte blocks.
-pub struct ByteBlockReader;
+/// The reader of a decoded byte block, e.g. an account block, which reads
+/// the values of the block one after another.
+///
+/// The reader keeps a cursor to the next value to read.  A read that
+/// overruns the byte block returns an error and leaves the cursor where it
+/// was.
+#[derive(Debug)]
+pub struct ByteBlockReader<'a> {
+    /// the decoded byte block
+    byte_block: &'a [u8],
+    /// the offset of the next value to read
+    offset: usize,
+}
 
 /// Reads the raw part of the input byte_block, at the specified offset, as type T.
 ///
-/// Returns None if `offset` + size_of::<T>() exceeds the size of the input byte_block.
+/// Returns None if `offset` + size_of::<T>() exceeds the size of the input
+/// byte_block, or if the bytes at `offset` are not aligned for T.  See
+/// read_pod_unaligned() for the latter case.
 ///
 /// Type T must be plain ol' data to ensure no undefined behavior.
 pub fn read_pod<T: bytemuck::AnyBitPattern>(byte_block: &[u8], offset: usize) -> Option<&T> {
@@ -143,7 +213,8 @@ pub fn read_pod<T: bytemuck::AnyBitPattern>(byte_block: &[u8], offset: usize) ->
 /// as type T.
 ///
 /// If `offset` + size_of::<T>() exceeds the size of the input byte_block,
-/// then None will be returned.
+/// or the bytes at `offset` are not aligned for T, then None will be
+/// returned.
 ///
 /// Prefer `read_pod()` when possible, because `read_type()` may cause
 /// undefined behavior.
@@ -159,14 +230,93 @@ pub unsafe fn read_type<T>(byte_block: &[u8], offset: usize) -> Option<&T> {
         return None;
     }
     let ptr = byte_block[offset..].as_ptr() as *const T;
-    debug_assert!(ptr as usize % std::mem::align_of::<T>() == 0);
+    if ptr as usize % std::mem::align_of::<T>() != 0 {
+        return None;
+    }
     // SAFETY: The caller ensures it is safe to cast bytes to T,
     // we ensure the size is safe by querying T directly,
     // and we just checked above to ensure the ptr is aligned for T.
     Some(unsafe { &*ptr })
 }
 
-impl ByteBlockReader {
+/// Reads a copy of the raw part of the input byte_block, at the specified
+/// offset, as type T, which does not need to be aligned for T.
+///
+/// Returns None if `offset` + size_of::<T>() exceeds the size of the input
+/// byte_block.
+pub fn read_pod_unaligned<T: bytemuck::AnyBitPattern>(
+    byte_block: &[u8],
+    offset: usize,
+) -> Option<T> {
+    let next = offset.checked_add(std::mem::size_of::<T>())?;
+    byte_block
+        .get(offset..next)
+        .map(bytemuck::pod_read_unaligned)
+}
+
+impl<'a> ByteBlockReader<'a> {
+    /// Creates a reader whose cursor is at the start of the specified byte
+    /// block, which must already be decoded.
+    pub fn new(byte_block: &'a [u8]) -> Self {
+        Self {
+            byte_block,
+            offset: 0,
+        }
+    }
+
+    /// Returns the offset of the cursor inside the byte block.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes after the cursor.
+    pub fn remaining(&self) -> usize {
+        self.byte_block.len() - self.offset
+    }
+
+    /// Reads a value of type T at the cursor, which must be aligned for T.
+    pub fn read_pod<T: bytemuck::AnyBitPattern>(&mut self) -> TieredStorageResult<&'a T> {
+        let bytes = self.peek_slice(mem::size_of::<T>())?;
+        let value = bytemuck::try_from_bytes(bytes).map_err(|_| {
+            TieredStorageError::OffsetAlignmentError(self.offset, mem::align_of::<T>())
+        })?;
+        self.offset += bytes.len();
+        Ok(value)
+    }
+
+    /// Reads a copy of the value of type T at the cursor, which does not
+    /// need to be aligned for T.
+    pub fn read_pod_unaligned<T: bytemuck::AnyBitPattern>(&mut self) -> TieredStorageResult<T> {
+        let bytes = self.read_slice(mem::size_of::<T>())?;
+        Ok(bytemuck::pod_read_unaligned(bytes))
+    }
+
+    /// Reads the next `len` bytes at the cursor.
+    pub fn read_slice(&mut self, len: usize) -> TieredStorageResult<&'a [u8]> {
+        let bytes = self.peek_slice(len)?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Moves the cursor forward by `len` bytes.
+    pub fn skip(&mut self, len: usize) -> TieredStorageResult<()> {
+        self.read_slice(len).map(|_| ())
+    }
+
+    /// Returns the next `len` bytes at the cursor without moving it.
+    fn peek_slice(&self, len: usize) -> TieredStorageResult<&'a [u8]> {
+        self.offset
+            .checked_add(len)
+            .and_then(|end| self.byte_block.get(self.offset..end))
+            .ok_or(TieredStorageError::ReadOutOfBounds(
+                self.offset,
+                len,
+                self.byte_block.len(),
+            ))
+    }
+}
+
+impl ByteBlockReader<'_> {
     /// Decode the input byte array using the specified format.
     ///
     /// Typically, the input byte array is the output of ByteBlockWriter::finish().
@@ -176,19 +326,43 @@ impl ByteBlockReader {
     pub fn decode(encoding: AccountBlockFormat, input: &[u8]) -> IoResult<Vec<u8>> {
         match encoding {
             AccountBlockFormat::Lz4 => {
-                let mut decoder = lz4::Decoder::new(input).unwrap();
+                let mut decoder = lz4::Decoder::new(input)?;
                 let mut output = vec![];
                 decoder.read_to_end(&mut output)?;
                 Ok(output)
             }
+            AccountBlockFormat::Zstd => zstd::decode_all(input),
             AccountBlockFormat::AlignedRaw => panic!("the input buffer is already decoded"),
         }
     }
+
+    /// Decodes the input byte array, which is the block located at
+    /// `block_offset` of its file, using the specified format.
+    ///
+    /// Unlike decode(), a malformed block is reported as BlockDecodeFailed
+    /// along with the offset of the block.
+    pub fn decode_block(
+        encoding: AccountBlockFormat,
+        input: &[u8],
+        block_offset: usize,
+    ) -> TieredStorageResult<Vec<u8>> {
+        Self::decode(encoding, input).map_err(|source| TieredStorageError::BlockDecodeFailed {
+            block_offset,
+            source,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use {super::*, solana_sdk::stake_history::Epoch};
+    use {
+        super::*,
+        crate::accounts_hash::AccountHash,
+        assert_matches::assert_matches,
+        rand::Rng,
+        solana_sdk::{hash::Hash, stake_history::Epoch},
+        test_case::test_case,
+    };
 
     fn read_type_unaligned<T>(buffer: &[u8], offset: usize) -> (T, usize) {
         let size = std::mem::size_of::<T>();
@@ -235,6 +409,41 @@ mod tests {
         write_single(AccountBlockFormat::Lz4);
     }
 
+    #[test]
+    fn test_write_aligned() {
+        const ALIGN: usize = 8;
+        let mut writer = ByteBlockWriter::new(AccountBlockFormat::AlignedRaw);
+        // (offset, data)
+        let mut blocks = vec![];
+        for data_len in 0..16 {
+            let offset = writer.raw_len();
+            assert_eq!(offset % ALIGN, 0);
+            let data = vec![data_len as u8 + 1; data_len];
+            let padding = writer.write_aligned(&data, ALIGN).unwrap();
+            assert_eq!(padding, (ALIGN - data_len % ALIGN) % ALIGN);
+            assert!(padding < ALIGN);
+            assert_eq!(writer.raw_len(), offset + data_len + padding);
+            blocks.push((offset, data));
+        }
+        let total_len = writer.raw_len();
+
+        let buffer = writer.finish().unwrap();
+        assert_eq!(buffer.len(), total_len);
+        let ends = blocks.iter().skip(1).map(|(offset, _)| *offset);
+        for ((offset, data), end) in blocks.iter().zip(ends.chain([total_len])) {
+            let data_end = offset + data.len();
+            assert_eq!(&buffer[*offset..data_end], data.as_slice());
+            // the padding consists of zeros
+            assert!(buffer[data_end..end].iter().all(|byte| *byte == 0));
+        }
+
+        // the alignment is relative to the beginning of the block
+        let mut writer = ByteBlockWriter::new(AccountBlockFormat::AlignedRaw);
+        writer.write(&[1; 3]).unwrap();
+        assert_eq!(writer.write_aligned(&[2; 2], ALIGN).unwrap(), 3);
+        assert_eq!(writer.raw_len(), ALIGN);
+    }
+
     #[derive(Debug, PartialEq)]
     struct TestMetaStruct {
         lamports: u64,
@@ -349,10 +558,15 @@ mod tests {
         // prepare a vector of optional fields that contains all combinations
         // of Some and None.
         for rent_epoch in [None, Some(test_epoch)] {
-            some_count += rent_epoch.iter().count();
-
-            opt_fields_vec.push(AccountMetaOptionalFields { rent_epoch });
-            test_epoch += 1;
+            for account_hash in [None, Some(AccountHash(Hash::new_unique()))] {
+                some_count += rent_epoch.iter().count() + account_hash.iter().count();
+
+                opt_fields_vec.push(AccountMetaOptionalFields {
+                    rent_epoch,
+                    account_hash,
+                });
+                test_epoch += 1;
+            }
         }
 
         // write all the combinations of the optional fields
@@ -383,6 +597,12 @@ mod tests {
                 verified_count += 1;
                 offset += std::mem::size_of::<Epoch>();
             }
+            if let Some(expected_account_hash) = opt_fields.account_hash {
+                let account_hash = read_pod::<AccountHash>(&decoded_buffer, offset).unwrap();
+                assert_eq!(*account_hash, expected_account_hash);
+                verified_count += 1;
+                offset += std::mem::size_of::<AccountHash>();
+            }
         }
 
         // make sure the number of Some fields matches the number of fields we
@@ -399,4 +619,140 @@ mod tests {
     fn test_write_optional_fields_lz4_format() {
         write_optional_fields(AccountBlockFormat::Lz4);
     }
+
+    #[test]
+    fn test_write_single_zstd_format() {
+        write_single(AccountBlockFormat::Zstd);
+    }
+
+    #[test]
+    fn test_write_multiple_zstd_format() {
+        write_multiple(AccountBlockFormat::Zstd);
+    }
+
+    #[test]
+    fn test_write_optional_fields_zstd_format() {
+        write_optional_fields(AccountBlockFormat::Zstd);
+    }
+
+    #[test]
+    fn test_read_pod() {
+        // the buffer of u64s is aligned for u64
+        let values = [1u64, 2, u64::MAX];
+        let buffer: &[u8] = bytemuck::cast_slice(&values);
+
+        assert_eq!(read_pod::<u64>(buffer, 0), Some(&1));
+        assert_eq!(read_pod::<u64>(buffer, 16), Some(&u64::MAX));
+        // test: out of bounds
+        assert_eq!(read_pod::<u64>(buffer, 17), None);
+        assert_eq!(read_pod::<u64>(buffer, 24), None);
+        assert_eq!(read_pod::<u64>(buffer, usize::MAX), None);
+        // test: misaligned
+        assert_eq!(read_pod::<u64>(buffer, 4), None);
+        assert_eq!(
+            read_pod::<u32>(buffer, 4),
+            Some(&u32::from_ne_bytes(buffer[4..8].try_into().unwrap()))
+        );
+
+        // test: the unaligned reads are only bounds checked
+        assert_eq!(read_pod_unaligned::<u64>(buffer, 0), Some(1));
+        assert_eq!(
+            read_pod_unaligned::<u64>(buffer, 12),
+            Some(u64::from_ne_bytes(buffer[12..20].try_into().unwrap()))
+        );
+        assert_eq!(read_pod_unaligned::<u64>(buffer, 17), None);
+        assert_eq!(read_pod_unaligned::<u64>(buffer, usize::MAX), None);
+    }
+
+    #[test_case(AccountBlockFormat::AlignedRaw)]
+    #[test_case(AccountBlockFormat::Lz4)]
+    fn test_byte_block_reader(format: AccountBlockFormat) {
+        let mut writer = ByteBlockWriter::new(format);
+        writer.write_pod(&7u64).unwrap();
+        writer.write(&[1, 2, 3]).unwrap();
+        writer.write_pod(&u64::MAX).unwrap();
+        let buffer = writer.finish().unwrap();
+        let decoded_buffer = if format == AccountBlockFormat::AlignedRaw {
+            buffer
+        } else {
+            ByteBlockReader::decode(format, &buffer).unwrap()
+        };
+
+        let mut reader = ByteBlockReader::new(&decoded_buffer);
+        assert_eq!(reader.remaining(), 19);
+        assert_eq!(reader.read_pod::<u64>().unwrap(), &7);
+        assert_eq!(reader.read_slice(3).unwrap(), &[1, 2, 3]);
+        // test: the u64 that follows is not aligned
+        assert_matches!(
+            reader.read_pod::<u64>(),
+            Err(TieredStorageError::OffsetAlignmentError(11, 8))
+        );
+        assert_eq!(reader.offset(), 11);
+        assert_eq!(reader.read_pod_unaligned::<u64>().unwrap(), u64::MAX);
+        assert_eq!(reader.remaining(), 0);
+
+        // test: the reads that overrun the byte block leave the cursor as is
+        assert_matches!(
+            reader.read_pod_unaligned::<u8>(),
+            Err(TieredStorageError::ReadOutOfBounds(19, 1, 19))
+        );
+        let mut reader = ByteBlockReader::new(&decoded_buffer);
+        reader.skip(8).unwrap();
+        assert_matches!(
+            reader.read_slice(12),
+            Err(TieredStorageError::ReadOutOfBounds(8, 12, 19))
+        );
+        assert_matches!(
+            reader.skip(usize::MAX),
+            Err(TieredStorageError::ReadOutOfBounds(8, usize::MAX, 19))
+        );
+        assert_eq!(reader.offset(), 8);
+        assert_eq!(reader.remaining(), 11);
+        reader.skip(11).unwrap();
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test_case(AccountBlockFormat::Lz4, None)]
+    #[test_case(AccountBlockFormat::Zstd, None)]
+    #[test_case(AccountBlockFormat::Zstd, Some(1))]
+    #[test_case(AccountBlockFormat::Zstd, Some(19))]
+    fn test_round_trip(format: AccountBlockFormat, compression_level: Option<i32>) {
+        let mut rng = rand::thread_rng();
+        let mut incompressible = vec![0u8; 64 * 1024];
+        rng.fill(&mut incompressible[..]);
+        let compressible = vec![7u8; 64 * 1024];
+
+        for data in [&[][..], &incompressible[..], &compressible[..]] {
+            let mut writer = ByteBlockWriter::new_with_compression_level(format, compression_level);
+            writer.write(data).unwrap();
+            assert_eq!(writer.raw_len(), data.len());
+            let encoded = writer.finish().unwrap();
+            if data == &compressible[..] {
+                assert!(encoded.len() < data.len() / 10);
+            }
+            assert_eq!(ByteBlockReader::decode(format, &encoded).unwrap(), data);
+        }
+    }
+
+    #[test_case(AccountBlockFormat::Lz4)]
+    #[test_case(AccountBlockFormat::Zstd)]
+    fn test_decode_block_corrupted(format: AccountBlockFormat) {
+        const BLOCK_OFFSET: usize = 4096;
+        let mut writer = ByteBlockWriter::new(format);
+        writer.write(&[7u8; 1024]).unwrap();
+        let encoded = writer.finish().unwrap();
+        assert_eq!(
+            ByteBlockReader::decode_block(format, &encoded, BLOCK_OFFSET).unwrap(),
+            vec![7u8; 1024]
+        );
+
+        // test: a block whose header is overwritten cannot be decoded
+        let mut corrupted = encoded;
+        corrupted[..4].fill(0xff);
+        assert_matches!(
+            ByteBlockReader::decode_block(format, &corrupted, BLOCK_OFFSET),
+            Err(TieredStorageError::BlockDecodeFailed { block_offset, .. })
+                if block_offset == BLOCK_OFFSET
+        );
+    }
 }
@@ -1,4 +1,12 @@
-use {super::footer::SanitizeFooterError, std::path::PathBuf, thiserror::Error};
+use {
+    super::{
+        footer::{AccountBlockFormat, AccountMetaFormat, FooterHashKind, SanitizeFooterError},
+        generation::{FormatFeature, FormatGeneration},
+    },
+    solana_sdk::hash::Hash,
+    std::path::PathBuf,
+    thiserror::Error,
+};
 
 #[derive(Error, Debug)]
 pub enum TieredStorageError {
@@ -11,9 +19,33 @@ pub enum TieredStorageError {
     #[error("AttemptToUpdateReadOnly: attempted to update read-only file {0}")]
     AttemptToUpdateReadOnly(PathBuf),
 
+    #[error("FileAlreadyExists: cannot write the accounts into existing file {0}")]
+    FileAlreadyExists(PathBuf),
+
+    #[error("NotYetWritten: the accounts of file {0} have not been written yet")]
+    NotYetWritten(PathBuf),
+
+    #[error("NotRecyclable: tiered storage file {0} cannot be reset for reuse")]
+    NotRecyclable(PathBuf),
+
     #[error("UnknownFormat: the tiered storage format is unknown for file {0}")]
     UnknownFormat(PathBuf),
 
+    #[error(
+        "UnsupportedAccountBlockFormat: {0:?} account metas cannot be written with {1:?} account \
+         blocks"
+    )]
+    UnsupportedAccountBlockFormat(AccountMetaFormat, AccountBlockFormat),
+
+    #[error("InvalidMetaEntrySize: meta entry size {0} does not match the account meta size {1}")]
+    InvalidMetaEntrySize(usize, usize),
+
+    #[error(
+        "UnexpectedCompressionLevel: compression level {0} is set for {1:?} account blocks, \
+         which are not compressed with zstd"
+    )]
+    UnexpectedCompressionLevel(i32, AccountBlockFormat),
+
     #[error("Unsupported: the feature is not yet supported")]
     Unsupported(),
 
@@ -23,12 +55,89 @@ pub enum TieredStorageError {
     #[error("invalid footer version: {0}")]
     InvalidFooterVersion(u64),
 
+    #[error("UnpersistableHashKind: hash kind {0:?} cannot be persisted in footer version {1}")]
+    UnpersistableHashKind(FooterHashKind, u64),
+
+    #[error(
+        "UnsupportedFormatVersion: format version {0} is newer than the supported version {1}"
+    )]
+    UnsupportedFormatVersion(u64, u64),
+
+    #[error("FormatGenerationExceeded: {0:?} is not supported by format generation {1:?}")]
+    FormatGenerationExceeded(FormatFeature, FormatGeneration),
+
     #[error("footer is unsanitary: {0}")]
     SanitizeFooter(#[from] SanitizeFooterError),
 
     #[error("OffsetOutOfBounds: offset {0} is larger than the supported size {1}")]
     OffsetOutOfBounds(usize, usize),
 
+    #[error("SizeOverflow: {0} does not fit in the address space of this platform")]
+    SizeOverflow(u64),
+
     #[error("OffsetAlignmentError: offset {0} must be multiple of {1}")]
     OffsetAlignmentError(usize, usize),
+
+    #[error("ReadOutOfBounds: reading {1} bytes at offset {0} exceeds the file size {2}")]
+    ReadOutOfBounds(usize, usize, usize),
+
+    #[error(
+        "OwnerReadOutOfRegion: reading the owner at offset {0} exceeds the owners block end {1}"
+    )]
+    OwnerReadOutOfRegion(usize, usize),
+
+    #[error("OwnerOffsetOutOfRange: owner offset {0} is not less than the owner count {1}")]
+    OwnerOffsetOutOfRange(u32, u32),
+
+    #[error(
+        "InvalidAccountDataPadding: padding {0} exceeds the {1} bytes before the optional fields"
+    )]
+    InvalidAccountDataPadding(u8, usize),
+
+    #[error("InvalidAccountBlock: the account block starting at offset {0} ends at offset {1}")]
+    InvalidAccountBlock(usize, usize),
+
+    #[error(
+        "InvalidAccountBlockSize: the account block of {0} bytes is smaller than its {1} bytes \
+         of optional fields"
+    )]
+    InvalidAccountBlockSize(usize, usize),
+
+    #[error(
+        "AccountBlockSizeExceeded: the account of {0} bytes exceeds the maximum account size {1} \
+         recorded in the footer"
+    )]
+    AccountBlockSizeExceeded(usize, u64),
+
+    #[error("BlockDecodeFailed: the block at offset {block_offset} cannot be decoded: {source}")]
+    BlockDecodeFailed {
+        block_offset: usize,
+        source: std::io::Error,
+    },
+
+    #[error("HashMismatch: the file hash {0} does not match the footer hash {1}")]
+    HashMismatch(Hash, Hash),
+
+    #[error("VerificationSkipped: the file does not persist a hash to verify")]
+    VerificationSkipped,
+
+    #[error("FileSizeLimitExceeded: writing up to offset {0} exceeds the maximum file size {1}")]
+    FileSizeLimitExceeded(u64, u64),
+
+    #[error("PaddingLimitExceeded: padding {0} exceeds the maximum padding {1}")]
+    PaddingLimitExceeded(u8, u8),
+
+    #[error("OwnerOffsetLimitExceeded: owner offset {0} exceeds the maximum owner offset {1}")]
+    OwnerOffsetLimitExceeded(u32, u32),
+
+    #[error("TooManyOwners: {0} distinct owners exceed the maximum of {1} owners per file")]
+    TooManyOwners(usize, usize),
+
+    #[error(
+        "Truncated: the file has {actual} bytes, but at least {expected_min} bytes are expected"
+    )]
+    Truncated { expected_min: u64, actual: u64 },
+
+    #[error("AccountCountMismatch: expected {0} accounts, but the file has {1} accounts")]
+    AccountCountMismatch(usize, usize),
 }
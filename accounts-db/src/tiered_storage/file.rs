@@ -1,9 +1,17 @@
 use {
-    super::{error::TieredStorageError, TieredStorageResult},
+    super::{
+        error::TieredStorageError,
+        footer::FooterHashKind,
+        hash_feed::{FileHasher, HashFeed},
+        TieredStorageResult,
+    },
     bytemuck::{AnyBitPattern, NoUninit, Pod, Zeroable},
+    memmap2::{Mmap, MmapMut, MmapOptions},
+    solana_sdk::hash::Hash,
     std::{
+        fmt,
         fs::{File, OpenOptions},
-        io::{BufWriter, Read, Result as IoResult, Seek, SeekFrom, Write},
+        io::{BufWriter, IntoInnerError, Read, Result as IoResult, Seek, SeekFrom, Write},
         mem,
         path::Path,
     },
@@ -25,6 +33,44 @@ impl Default for TieredStorageMagicNumber {
     }
 }
 
+/// How the content of a read-only tiered storage file is accessed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ReaderBackend {
+    /// Memory-maps the file.
+    #[default]
+    Mmap,
+    /// Reads the whole file into memory on open without mapping the file,
+    /// for deployments where mapping the file fails or is undesirable,
+    /// such as on network filesystems.
+    Buffered,
+}
+
+/// The expected access pattern of the content of a mapped tiered storage
+/// file, which is passed to the kernel as a hint via madvise().
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MmapAccessPattern {
+    /// The content is read at random, e.g. when loading accounts.
+    Random,
+    /// The content is read in order, e.g. when scanning all the accounts.
+    Sequential,
+    /// The content will be read soon, so it may be read ahead.
+    WillNeed,
+    /// The content will not be read soon, so its pages may be released.
+    DontNeed,
+}
+
+#[cfg(unix)]
+impl From<MmapAccessPattern> for memmap2::Advice {
+    fn from(access: MmapAccessPattern) -> Self {
+        match access {
+            MmapAccessPattern::Random => Self::Random,
+            MmapAccessPattern::Sequential => Self::Sequential,
+            MmapAccessPattern::WillNeed => Self::WillNeed,
+            MmapAccessPattern::DontNeed => Self::DontNeed,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TieredReadableFile(pub File);
 
@@ -51,6 +97,22 @@ impl TieredReadableFile {
         ))
     }
 
+    /// Returns the content of the file accessed via the specified backend.
+    pub fn map(&self, backend: ReaderBackend) -> IoResult<Mmap> {
+        match backend {
+            ReaderBackend::Mmap => unsafe { MmapOptions::new().map(&self.0) },
+            ReaderBackend::Buffered => {
+                // The buffer is an anonymous memory region, which keeps the
+                // content page-aligned just like a mapped file.
+                let len = self.0.metadata()?.len() as usize;
+                let mut buffer = MmapMut::map_anon(len)?;
+                self.seek(0)?;
+                self.read_bytes(&mut buffer)?;
+                buffer.make_read_only()
+            }
+        }
+    }
+
     fn check_magic_number(&self) -> TieredStorageResult<()> {
         self.seek_from_end(-(std::mem::size_of::<TieredStorageMagicNumber>() as i64))?;
         let mut magic_number = TieredStorageMagicNumber::zeroed();
@@ -104,17 +166,119 @@ impl TieredReadableFile {
     }
 }
 
+/// The size of the buffer of a TieredWritableFile, which the small writes
+/// of the metas and the optional fields are gathered into.
+pub(crate) const WRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+pub struct TieredWritableFile {
+    file: BufWriter<UnbufferedFile>,
+    /// The running hash of all the bytes written to the file.
+    hasher: FileHasher,
+}
+
+/// The file underneath the buffer of a TieredWritableFile.
+///
+/// In tests, it counts the writes that reach the file, i.e. the write
+/// syscalls issued when the buffer is flushed.
 #[derive(Debug)]
-pub struct TieredWritableFile(pub BufWriter<File>);
+struct UnbufferedFile {
+    file: File,
+    #[cfg(test)]
+    num_writes: usize,
+}
+
+impl Write for UnbufferedFile {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        #[cfg(test)]
+        {
+            self.num_writes += 1;
+        }
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for UnbufferedFile {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl fmt::Debug for TieredWritableFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TieredWritableFile")
+            .field("file", &self.file)
+            .finish_non_exhaustive()
+    }
+}
 
 impl TieredWritableFile {
     pub fn new(file_path: impl AsRef<Path>) -> IoResult<Self> {
-        Ok(Self(BufWriter::new(
-            OpenOptions::new()
-                .create_new(true)
-                .write(true)
-                .open(file_path)?,
-        )))
+        Self::new_with_hash_kind(file_path, FooterHashKind::default())
+    }
+
+    /// Creates a new file whose running hash uses the specified algorithm.
+    pub fn new_with_hash_kind(
+        file_path: impl AsRef<Path>,
+        hash_kind: FooterHashKind,
+    ) -> IoResult<Self> {
+        let file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(file_path)?;
+        Ok(Self {
+            file: BufWriter::with_capacity(
+                WRITE_BUFFER_SIZE,
+                UnbufferedFile {
+                    file,
+                    #[cfg(test)]
+                    num_writes: 0,
+                },
+            ),
+            hasher: FileHasher::new(hash_kind),
+        })
+    }
+
+    /// Flushes the buffered bytes and waits until the whole content of the
+    /// file reaches the disk.
+    ///
+    /// The file is consumed, so nothing can be written once it is synced.
+    /// Dropping the file instead flushes the buffered bytes without syncing
+    /// them, and ignores any error.
+    pub fn finish(self) -> IoResult<()> {
+        let file = self.file.into_inner().map_err(IntoInnerError::into_error)?;
+        file.file.sync_all()
+    }
+
+    /// Writes the buffered bytes to the file.
+    ///
+    /// Unlike finish(), the bytes are not synced to the disk, but they
+    /// survive the process dying before the file is finished.
+    pub fn flush(&mut self) -> IoResult<()> {
+        self.file.flush()
+    }
+
+    /// Returns the number of writes that reached the file so far.
+    #[cfg(test)]
+    pub(crate) fn num_writes(&self) -> usize {
+        self.file.get_ref().num_writes
+    }
+
+    /// Returns the algorithm of the running hash.
+    pub fn hash_kind(&self) -> FooterHashKind {
+        self.hasher.kind()
+    }
+
+    /// Returns the hash of all the bytes written to the file so far.
+    ///
+    /// The bytes are hashed in the order they are written, so the result
+    /// only matches the hash of the file content if the file is written
+    /// sequentially without seeking.
+    pub fn hash(&self) -> Hash {
+        self.hasher.finish()
     }
 
     /// Writes `value` to the file.
@@ -142,15 +306,16 @@ impl TieredWritableFile {
     }
 
     pub fn seek(&mut self, offset: u64) -> IoResult<u64> {
-        self.0.seek(SeekFrom::Start(offset))
+        self.file.seek(SeekFrom::Start(offset))
     }
 
     pub fn seek_from_end(&mut self, offset: i64) -> IoResult<u64> {
-        self.0.seek(SeekFrom::End(offset))
+        self.file.seek(SeekFrom::End(offset))
     }
 
     pub fn write_bytes(&mut self, bytes: &[u8]) -> IoResult<usize> {
-        self.0.write_all(bytes)?;
+        self.file.write_all(bytes)?;
+        self.hasher.feed(bytes);
 
         Ok(bytes.len())
     }
@@ -161,8 +326,9 @@ mod tests {
     use {
         crate::tiered_storage::{
             error::TieredStorageError,
-            file::{TieredReadableFile, TieredWritableFile, FILE_MAGIC_NUMBER},
+            file::{ReaderBackend, TieredReadableFile, TieredWritableFile, FILE_MAGIC_NUMBER},
         },
+        solana_sdk::hash::hashv,
         std::path::Path,
         tempfile::TempDir,
     };
@@ -180,6 +346,50 @@ mod tests {
         assert!(TieredReadableFile::new(&path).is_ok());
     }
 
+    #[test]
+    fn test_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hash");
+        let mut file = TieredWritableFile::new(path).unwrap();
+        assert_eq!(file.hash(), hashv(&[]));
+
+        file.write_pod(&FILE_MAGIC_NUMBER).unwrap();
+        file.write_bytes(&[1, 2, 3]).unwrap();
+        assert_eq!(
+            file.hash(),
+            hashv(&[&FILE_MAGIC_NUMBER.to_ne_bytes(), &[1, 2, 3]])
+        );
+    }
+
+    #[test]
+    fn test_finish() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_finish");
+        let mut file = TieredWritableFile::new(&path).unwrap();
+        file.write_bytes(&[1, 2, 3]).unwrap();
+        file.write_pod(&FILE_MAGIC_NUMBER).unwrap();
+        // the small writes are buffered until the file is finished
+        assert_eq!(file.num_writes(), 0);
+        file.finish().unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        assert_eq!(&content[..3], &[1, 2, 3]);
+        assert_eq!(&content[3..], &FILE_MAGIC_NUMBER.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_map() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_map");
+        generate_test_file_with_number(&path, FILE_MAGIC_NUMBER);
+        let file = TieredReadableFile::new(&path).unwrap();
+
+        let mmap = file.map(ReaderBackend::Mmap).unwrap();
+        let buffered = file.map(ReaderBackend::Buffered).unwrap();
+        assert_eq!(&mmap[..], &FILE_MAGIC_NUMBER.to_ne_bytes());
+        assert_eq!(&buffered[..], &mmap[..]);
+    }
+
     #[test]
     fn test_magic_number_mismatch() {
         let temp_dir = TempDir::new().unwrap();
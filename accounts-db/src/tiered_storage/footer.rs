@@ -2,8 +2,10 @@ use {
     crate::tiered_storage::{
         error::TieredStorageError,
         file::{TieredReadableFile, TieredStorageMagicNumber, TieredWritableFile},
+        hot::{HotAccountMeta, HotAccountOffset, MAX_HOT_FILE_SIZE},
         index::IndexBlockFormat,
-        mmap_utils::{get_pod, get_type},
+        meta::PubkeyRange,
+        mmap_utils::{get_pod, get_slice},
         owners::OwnersBlockFormat,
         TieredStorageResult,
     },
@@ -11,16 +13,29 @@ use {
     memmap2::Mmap,
     num_enum::TryFromPrimitiveError,
     solana_sdk::{hash::Hash, pubkey::Pubkey},
-    std::{mem, path::Path},
+    std::{io, mem, path::Path},
     thiserror::Error,
 };
 
-pub const FOOTER_FORMAT_VERSION: u64 = 1;
+/// The footer format version written by this crate, which is also the
+/// newest version it is able to read.
+///
+/// - 1: the initial version, whose file hash is always a Sha256 hash.
+/// - 2: adds the hash kind of the file hash.
+pub const FOOTER_FORMAT_VERSION: u64 = 2;
 
 /// The size of the footer struct + the magic number at the end.
 pub const FOOTER_SIZE: usize =
     mem::size_of::<TieredStorageFooter>() + mem::size_of::<TieredStorageMagicNumber>();
-static_assertions::const_assert_eq!(mem::size_of::<TieredStorageFooter>(), 160);
+static_assertions::const_assert_eq!(mem::size_of::<TieredStorageFooter>(), 168);
+
+/// The footer size of format version 1, which does not have the hash kind
+/// and its reserved bytes.
+const FOOTER_SIZE_V1: usize = FOOTER_SIZE - mem::size_of::<u64>();
+
+/// The size of the fields that follow the hash kind and its reserved bytes
+/// inside the footer struct, i.e. format_version and footer_size.
+const FOOTER_STRUCT_TAIL_SIZE: usize = 2 * mem::size_of::<u64>();
 
 /// The size of the ending part of the footer.  This size should remain unchanged
 /// even when the footer's format changes.
@@ -41,8 +56,33 @@ pub const FOOTER_TAIL_SIZE: usize = 24;
 pub enum AccountMetaFormat {
     #[default]
     Hot = 0,
-    // Temporarily comment out to avoid unimplemented!() block
-    // Cold = 1,
+}
+
+impl AccountMetaFormat {
+    /// Returns the size of one account meta entry.
+    pub fn meta_entry_size(&self) -> usize {
+        match self {
+            Self::Hot => mem::size_of::<HotAccountMeta>(),
+        }
+    }
+
+    /// Returns the size of one entry in the index block, which depends on
+    /// the type of the account offset used by the account meta format.
+    pub fn index_entry_size(&self, index_block_format: &IndexBlockFormat) -> usize {
+        match self {
+            Self::Hot => index_block_format.entry_size::<HotAccountOffset>(),
+        }
+    }
+
+    /// Returns the maximum size of a file in this format.
+    ///
+    /// - Hot: bounded by the largest offset a HotAccountOffset can
+    ///   represent, so that any account block can start anywhere in the file.
+    pub fn max_file_size(&self) -> u64 {
+        match self {
+            Self::Hot => MAX_HOT_FILE_SIZE,
+        }
+    }
 }
 
 #[repr(u16)]
@@ -61,6 +101,28 @@ pub enum AccountBlockFormat {
     #[default]
     AlignedRaw = 0,
     Lz4 = 1,
+    Zstd = 2,
+}
+
+/// The algorithm of the file hash persisted in the footer.
+#[repr(u8)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    PartialEq,
+    num_enum::IntoPrimitive,
+    num_enum::TryFromPrimitive,
+)]
+pub enum FooterHashKind {
+    /// The file does not persist a hash, so it cannot be verified.
+    None = 0,
+    Blake3 = 1,
+    #[default]
+    Sha256 = 2,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -86,6 +148,11 @@ pub struct TieredStorageFooter {
     /// If the size of one account (meta + data + optional fields) before
     /// compression is bigger than this number, than it is considered a
     /// blob account and it will have its own account block.
+    ///
+    /// Hot storages write one account per block, so this is the size of
+    /// their largest account (meta + data + optional fields).  Readers
+    /// reject any account whose derived size exceeds it, unless it is 0 as
+    /// in files written before it was recorded.
     pub account_block_size: u64,
 
     // Owner-related
@@ -108,7 +175,12 @@ pub struct TieredStorageFooter {
     pub max_account_address: Pubkey,
 
     /// A hash that represents a tiered accounts file for consistency check.
+    /// It is the hash of all the bytes before the footer.
     pub hash: Hash,
+    /// The algorithm of the above hash.
+    pub hash_kind: FooterHashKind,
+    /// Reserved for future use.  Always zero.
+    pub reserved: [u8; 7],
 
     /// The format version of the tiered accounts file.
     pub format_version: u64,
@@ -140,6 +212,8 @@ const _: () = assert!(
          + std::mem::size_of::<Pubkey>() // min_account_address
          + std::mem::size_of::<Pubkey>() // max_account_address
          + std::mem::size_of::<Hash>() // hash
+         + std::mem::size_of::<FooterHashKind>() // hash_kind
+         + std::mem::size_of::<[u8; 7]>() // reserved
          + std::mem::size_of::<u64>() // format_version
          + std::mem::size_of::<u64>(), // footer_size
     "TieredStorageFooter cannot have any padding"
@@ -153,13 +227,17 @@ impl Default for TieredStorageFooter {
             index_block_format: IndexBlockFormat::default(),
             account_block_format: AccountBlockFormat::default(),
             account_entry_count: 0,
-            account_meta_entry_size: 0,
+            account_meta_entry_size: AccountMetaFormat::default().meta_entry_size() as u32,
             account_block_size: 0,
             owner_count: 0,
             owner_entry_size: 0,
             index_block_offset: 0,
             owners_block_offset: 0,
-            hash: Hash::new_unique(),
+            // the writer replaces it with the hash of the file, and any other
+            // default would make the written bytes nondeterministic
+            hash: Hash::default(),
+            hash_kind: FooterHashKind::default(),
+            reserved: [0; 7],
             min_account_address: Pubkey::default(),
             max_account_address: Pubkey::default(),
             format_version: FOOTER_FORMAT_VERSION,
@@ -169,87 +247,213 @@ impl Default for TieredStorageFooter {
 }
 
 impl TieredStorageFooter {
+    /// Returns the range of the account addresses stored in the file.
+    ///
+    /// The footer does not persist whether the range is populated.  Instead,
+    /// it is derived from the account entry count, as a file without any
+    /// account has an unknown address range.
+    pub fn account_address_range(&self) -> PubkeyRange {
+        if self.account_entry_count == 0 {
+            PubkeyRange::default()
+        } else {
+            PubkeyRange::new(self.min_account_address, self.max_account_address)
+        }
+    }
+
+    /// Persists the specified address range into the footer.  Both addresses
+    /// are set to Pubkey::default() when the range is unknown.
+    pub fn set_account_address_range(&mut self, range: &PubkeyRange) {
+        (self.min_account_address, self.max_account_address) = if range.is_unknown() {
+            (Pubkey::default(), Pubkey::default())
+        } else {
+            (range.min, range.max)
+        };
+    }
+
     pub fn new_from_path(path: impl AsRef<Path>) -> TieredStorageResult<Self> {
         let file = TieredReadableFile::new(path)?;
         Self::new_from_footer_block(&file)
     }
 
+    /// Sets the format version of the footer along with its footer size.
+    pub fn set_format_version(&mut self, format_version: u64) -> TieredStorageResult<()> {
+        self.footer_size = Self::footer_size_of_version(format_version)?;
+        self.format_version = format_version;
+        Ok(())
+    }
+
+    /// Writes the footer in the layout of its format version.
+    ///
+    /// A version 1 footer is written without the hash kind and its reserved
+    /// bytes, so its hash kind must be None, which is what it is read back
+    /// as.
+    ///
+    /// The footer body is written first, followed by the format version and
+    /// the footer size.  The file is then flushed before the magic number is
+    /// written last, so a process that dies in the middle of writing the
+    /// footer can't leave a file whose magic number is present while its
+    /// footer is incomplete.
     pub fn write_footer_block(&self, file: &mut TieredWritableFile) -> TieredStorageResult<()> {
         // SAFETY: The footer does not contain any uninitialized bytes.
-        unsafe { file.write_type(self)? };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>())
+        };
+        let (body, tail) = bytes.split_at(mem::size_of::<Self>() - FOOTER_STRUCT_TAIL_SIZE);
+        match self.format_version {
+            1 => {
+                if self.hash_kind != FooterHashKind::None {
+                    return Err(TieredStorageError::UnpersistableHashKind(
+                        self.hash_kind,
+                        self.format_version,
+                    ));
+                }
+                file.write_bytes(&body[..body.len() - mem::size_of::<u64>()])?;
+            }
+            _ => {
+                file.write_bytes(body)?;
+            }
+        }
+        file.write_bytes(tail)?;
+        file.flush()?;
         file.write_pod(&TieredStorageMagicNumber::default())?;
 
         Ok(())
     }
 
+    /// Reads the footer of the specified file.
+    ///
+    /// The footer of every supported format version fits in the last
+    /// FOOTER_SIZE bytes of the file, so they are read at once and both the
+    /// footer tail and the footer are parsed from them.  This keeps opening
+    /// a file to a single seek and read, which matters when many files are
+    /// opened at startup.
     pub fn new_from_footer_block(file: &TieredReadableFile) -> TieredStorageResult<Self> {
-        file.seek_from_end(-(FOOTER_TAIL_SIZE as i64))?;
+        let mut buffer = [0u8; FOOTER_SIZE];
+        let file_len = file.0.metadata()?.len();
+        let read_len = file_len.min(FOOTER_SIZE as u64) as usize;
+        let file_tail = &mut buffer[FOOTER_SIZE - read_len..];
+        file.seek_from_end(-(read_len as i64))?;
+        file.read_bytes(file_tail)?;
+
+        Self::new_from_file_tail(file_tail)
+    }
 
-        let mut footer_version: u64 = 0;
-        file.read_pod(&mut footer_version)?;
-        if footer_version != FOOTER_FORMAT_VERSION {
-            return Err(TieredStorageError::InvalidFooterVersion(footer_version));
+    /// Parses the footer from the last bytes of a file, which are expected
+    /// to contain the whole footer including its magic number.
+    fn new_from_file_tail(file_tail: &[u8]) -> TieredStorageResult<Self> {
+        let unexpected_eof = || io::Error::from(io::ErrorKind::UnexpectedEof);
+        if file_tail.len() < FOOTER_TAIL_SIZE {
+            return Err(unexpected_eof().into());
         }
+        let (_, footer_tail) = file_tail.split_at(file_tail.len() - FOOTER_TAIL_SIZE);
+        let footer_version: u64 = bytemuck::pod_read_unaligned(&footer_tail[..8]);
+        let footer_size: u64 = bytemuck::pod_read_unaligned(&footer_tail[8..16]);
+        let magic_number: TieredStorageMagicNumber =
+            bytemuck::pod_read_unaligned(&footer_tail[16..]);
+        Self::check_footer_tail(footer_version, footer_size, magic_number)?;
 
-        let mut footer_size: u64 = 0;
-        file.read_pod(&mut footer_size)?;
-        if footer_size != FOOTER_SIZE as u64 {
-            return Err(TieredStorageError::InvalidFooterSize(
-                footer_size,
-                FOOTER_SIZE as u64,
-            ));
+        // check_footer_tail() ensures footer_size is at most FOOTER_SIZE
+        let footer_size = footer_size as usize;
+        if file_tail.len() < footer_size {
+            return Err(unexpected_eof().into());
         }
+        let footer_bytes = &file_tail[file_tail.len() - footer_size
+            ..file_tail.len() - mem::size_of::<TieredStorageMagicNumber>()];
 
-        let mut magic_number = TieredStorageMagicNumber::zeroed();
-        file.read_pod(&mut magic_number)?;
-        if magic_number != TieredStorageMagicNumber::default() {
-            return Err(TieredStorageError::MagicNumberMismatch(
-                TieredStorageMagicNumber::default().0,
-                magic_number.0,
-            ));
+        Self::new_from_versioned_bytes(footer_version, footer_bytes)
+    }
+
+    pub fn new_from_mmap(mmap: &Mmap) -> TieredStorageResult<TieredStorageFooter> {
+        let offset = mmap.len().saturating_sub(FOOTER_TAIL_SIZE);
+
+        let (&footer_version, offset) = get_pod::<u64>(mmap, offset)?;
+        let (&footer_size, offset) = get_pod::<u64>(mmap, offset)?;
+        let (&magic_number, _offset) = get_pod::<TieredStorageMagicNumber>(mmap, offset)?;
+        Self::check_footer_tail(footer_version, footer_size, magic_number)?;
+
+        let footer_offset = mmap.len().saturating_sub(footer_size as usize);
+        let (footer_bytes, _offset) = get_slice(
+            mmap,
+            footer_offset,
+            footer_size as usize - mem::size_of::<TieredStorageMagicNumber>(),
+        )?;
+        let footer = Self::new_from_versioned_bytes(footer_version, footer_bytes)?;
+        footer.check_file_len(mmap.len() as u64)?;
+        footer.sanitize_layout(mmap.len() as u64)?;
+
+        Ok(footer)
+    }
+
+    /// Creates the footer from the bytes of its struct persisted in the
+    /// specified format version, whose size has already been checked.
+    ///
+    /// The footer of an older version is converted into the current layout.
+    fn new_from_versioned_bytes(format_version: u64, bytes: &[u8]) -> TieredStorageResult<Self> {
+        let mut footer_bytes = [0u8; mem::size_of::<Self>()];
+        match format_version {
+            FOOTER_FORMAT_VERSION => footer_bytes.copy_from_slice(bytes),
+            1 => {
+                // Version 1 has no hash kind nor reserved bytes.  The baseline
+                // writers did not compute its file hash, so the hash cannot
+                // be trusted and is not verified.
+                let (head, tail) = bytes.split_at(bytes.len() - FOOTER_STRUCT_TAIL_SIZE);
+                footer_bytes[..head.len()].copy_from_slice(head);
+                footer_bytes[head.len()] = FooterHashKind::None.into();
+                footer_bytes[mem::size_of::<Self>() - FOOTER_STRUCT_TAIL_SIZE..]
+                    .copy_from_slice(tail);
+            }
+            version => return Err(TieredStorageError::InvalidFooterVersion(version)),
         }
 
-        let mut footer = Self::default();
-        file.seek_from_end(-(footer_size as i64))?;
         // SAFETY: We sanitize the footer to ensure all the bytes are
         // actually safe to interpret as a TieredStorageFooter.
-        unsafe { file.read_type(&mut footer)? };
+        let footer = unsafe {
+            std::ptr::read_unaligned(footer_bytes.as_ptr() as *const TieredStorageFooter)
+        };
         Self::sanitize(&footer)?;
 
         Ok(footer)
     }
 
-    pub fn new_from_mmap(mmap: &Mmap) -> TieredStorageResult<&TieredStorageFooter> {
-        let offset = mmap.len().saturating_sub(FOOTER_TAIL_SIZE);
-
-        let (footer_version, offset) = get_pod::<u64>(mmap, offset)?;
-        if *footer_version != FOOTER_FORMAT_VERSION {
-            return Err(TieredStorageError::InvalidFooterVersion(*footer_version));
+    /// Checks the fields at the end of the footer.
+    ///
+    /// The magic number is checked first so that a file which is not a
+    /// tiered storage file, e.g. an AppendVec, is reported as
+    /// MagicNumberMismatch regardless of its other trailing bytes.
+    fn check_footer_tail(
+        footer_version: u64,
+        footer_size: u64,
+        magic_number: TieredStorageMagicNumber,
+    ) -> TieredStorageResult<()> {
+        if magic_number != TieredStorageMagicNumber::default() {
+            return Err(TieredStorageError::MagicNumberMismatch(
+                TieredStorageMagicNumber::default().0,
+                magic_number.0,
+            ));
         }
-
-        let (&footer_size, offset) = get_pod::<u64>(mmap, offset)?;
-        if footer_size != FOOTER_SIZE as u64 {
+        let expected_footer_size = Self::footer_size_of_version(footer_version)?;
+        if footer_size != expected_footer_size {
             return Err(TieredStorageError::InvalidFooterSize(
                 footer_size,
-                FOOTER_SIZE as u64,
+                expected_footer_size,
             ));
         }
+        Ok(())
+    }
 
-        let (magic_number, _offset) = get_pod::<TieredStorageMagicNumber>(mmap, offset)?;
-        if *magic_number != TieredStorageMagicNumber::default() {
-            return Err(TieredStorageError::MagicNumberMismatch(
-                TieredStorageMagicNumber::default().0,
-                magic_number.0,
-            ));
+    /// Returns the footer size of the specified footer format version.
+    ///
+    /// Each supported version has its own arm, so that the footer size is
+    /// validated before seeking backward to the start of the footer.
+    pub(crate) fn footer_size_of_version(format_version: u64) -> TieredStorageResult<u64> {
+        match format_version {
+            1 => Ok(FOOTER_SIZE_V1 as u64),
+            FOOTER_FORMAT_VERSION => Ok(FOOTER_SIZE as u64),
+            version if version > FOOTER_FORMAT_VERSION => Err(
+                TieredStorageError::UnsupportedFormatVersion(version, FOOTER_FORMAT_VERSION),
+            ),
+            version => Err(TieredStorageError::InvalidFooterVersion(version)),
         }
-
-        let footer_offset = mmap.len().saturating_sub(footer_size as usize);
-        // SAFETY: We sanitize the footer to ensure all the bytes are
-        // actually safe to interpret as a TieredStorageFooter.
-        let (footer, _offset) = unsafe { get_type::<TieredStorageFooter>(mmap, footer_offset)? };
-        Self::sanitize(footer)?;
-
-        Ok(footer)
     }
 
     /// Sanitizes the footer
@@ -265,6 +469,7 @@ impl TieredStorageFooter {
             unsafe { &*(&footer.index_block_format as *const _ as *const u16) };
         let account_block_format_u16 =
             unsafe { &*(&footer.account_block_format as *const _ as *const u16) };
+        let hash_kind_u8 = unsafe { &*(&footer.hash_kind as *const _ as *const u8) };
 
         _ = AccountMetaFormat::try_from(*account_meta_format_u16)
             .map_err(SanitizeFooterError::InvalidAccountMetaFormat)?;
@@ -274,6 +479,8 @@ impl TieredStorageFooter {
             .map_err(SanitizeFooterError::InvalidIndexBlockFormat)?;
         _ = AccountBlockFormat::try_from(*account_block_format_u16)
             .map_err(SanitizeFooterError::InvalidAccountBlockFormat)?;
+        _ = FooterHashKind::try_from(*hash_kind_u8)
+            .map_err(SanitizeFooterError::UnsupportedHashKind)?;
 
         // Since we just sanitized the formats within the footer,
         // it is now safe to read them as (rust) enums.
@@ -285,6 +492,129 @@ impl TieredStorageFooter {
         // from https://doc.rust-lang.org/reference/items/enumerations.html#pointer-casting:
         // > If the enumeration specifies a primitive representation,
         // > then the discriminant may be reliably accessed via unsafe pointer casting
+
+        // the hot accounts are read in place, so their account blocks are
+        // never compressed
+        if footer.account_meta_format == AccountMetaFormat::Hot
+            && footer.account_block_format != AccountBlockFormat::AlignedRaw
+        {
+            return Err(SanitizeFooterError::UnsupportedAccountBlockFormat(
+                footer.account_meta_format,
+                footer.account_block_format,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the minimum size of the file described by the footer, which
+    /// ends with its owners block followed by the footer.
+    pub fn min_file_len(&self) -> u64 {
+        let owners_block_size = self.owners_block_format.block_size(self.owner_count as u64);
+        self.owners_block_offset
+            .saturating_add(owners_block_size)
+            .saturating_add(self.footer_size)
+    }
+
+    /// Returns Truncated if the file, whose footer is intact, is shorter
+    /// than the blocks described by the footer, e.g. when a snapshot is only
+    /// partially unpacked.
+    ///
+    /// This is checked before sanitize_layout(), so that a missing part of
+    /// the file is reported as such instead of as an inconsistent layout.
+    pub fn check_file_len(&self, file_len: u64) -> TieredStorageResult<()> {
+        let expected_min = self.min_file_len();
+        if file_len < expected_min {
+            return Err(TieredStorageError::Truncated {
+                expected_min,
+                actual: file_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the offset right after the end of the owners block given the
+    /// size of its file.
+    ///
+    /// In the current format version, the owners block is immediately
+    /// followed by the footer.
+    pub fn owners_block_end(&self, file_len: u64) -> u64 {
+        file_len.saturating_sub(self.footer_size)
+    }
+
+    /// Returns the offset where the account blocks end given the size of
+    /// its file.
+    ///
+    /// The account blocks are the first blocks of the file, and they end
+    /// where the first following block begins.  In the current format
+    /// version, that is the index block, or the owners block or the footer
+    /// when the blocks before them are empty.
+    pub fn account_blocks_end(&self, file_len: u64) -> u64 {
+        self.index_block_offset
+            .min(self.owners_block_offset)
+            .min(self.owners_block_end(file_len))
+    }
+
+    /// Sanitizes the block layout described by the footer against the size
+    /// of its file.
+    ///
+    /// This ensures the offsets and counts inside the footer are consistent,
+    /// so that accessing any entry it describes stays inside its block.
+    /// Note that the formats must be sanitized prior to calling this function.
+    pub fn sanitize_layout(&self, file_len: u64) -> Result<(), SanitizeFooterError> {
+        // Once the file is known to be within its maximum size, the block
+        // offsets checked below are all representable in memory.
+        let max_file_size = self.account_meta_format.max_file_size();
+        if file_len > max_file_size {
+            return Err(SanitizeFooterError::FileTooLarge(file_len, max_file_size));
+        }
+
+        let owners_block_end = self.owners_block_end(file_len);
+        if self.index_block_offset > self.owners_block_offset
+            || self.owners_block_offset > owners_block_end
+        {
+            return Err(SanitizeFooterError::InvalidBlockOffsets(
+                self.index_block_offset,
+                self.owners_block_offset,
+                owners_block_end,
+            ));
+        }
+
+        let expected_meta_entry_size = self.account_meta_format.meta_entry_size() as u64;
+        if self.account_meta_entry_size as u64 != expected_meta_entry_size {
+            return Err(SanitizeFooterError::InvalidAccountMetaEntrySize(
+                self.account_meta_entry_size,
+                expected_meta_entry_size,
+            ));
+        }
+
+        let index_block_size = self.owners_block_offset - self.index_block_offset;
+        let index_entry_size = self
+            .account_meta_format
+            .index_entry_size(&self.index_block_format) as u64;
+        if (self.account_entry_count as u64).saturating_mul(index_entry_size) > index_block_size {
+            return Err(SanitizeFooterError::IndexBlockTooSmall(
+                index_block_size,
+                self.account_entry_count,
+            ));
+        }
+
+        // The owners block has no header, so it must be exactly filled with
+        // its owner entries and their padding.
+        let owners_block_size = owners_block_end - self.owners_block_offset;
+        if self.owners_block_format.block_size(self.owner_count as u64) != owners_block_size {
+            return Err(SanitizeFooterError::OwnersBlockSizeMismatch(
+                owners_block_size,
+                self.owner_count,
+            ));
+        }
+
+        if self.min_account_address > self.max_account_address {
+            return Err(SanitizeFooterError::InvalidAccountAddressRange(
+                self.min_account_address,
+                self.max_account_address,
+            ));
+        }
+
         Ok(())
     }
 }
@@ -303,6 +633,32 @@ pub enum SanitizeFooterError {
 
     #[error("invalid account block format: {0}")]
     InvalidAccountBlockFormat(#[from] TryFromPrimitiveError<AccountBlockFormat>),
+
+    #[error("unsupported hash kind: {0}")]
+    UnsupportedHashKind(#[from] TryFromPrimitiveError<FooterHashKind>),
+
+    #[error("account block format {1:?} is not supported by account meta format {0:?}")]
+    UnsupportedAccountBlockFormat(AccountMetaFormat, AccountBlockFormat),
+
+    #[error(
+        "invalid block offsets: index block offset {0}, owners block offset {1}, footer offset {2}"
+    )]
+    InvalidBlockOffsets(u64, u64, u64),
+
+    #[error("invalid account meta entry size: {0}, expected: {1}")]
+    InvalidAccountMetaEntrySize(u32, u64),
+
+    #[error("index block size {0} is too small for {1} accounts")]
+    IndexBlockTooSmall(u64, u32),
+
+    #[error("owners block size {0} does not match {1} owners")]
+    OwnersBlockSizeMismatch(u64, u32),
+
+    #[error("invalid account address range: min {0} is larger than max {1}")]
+    InvalidAccountAddressRange(Pubkey, Pubkey),
+
+    #[error("file size {0} exceeds the maximum file size {1}")]
+    FileTooLarge(u64, u64),
 }
 
 #[cfg(test)]
@@ -310,10 +666,20 @@ mod tests {
     use {
         super::*,
         crate::{
-            append_vec::test_utils::get_append_vec_path, tiered_storage::file::TieredWritableFile,
+            append_vec::test_utils::get_append_vec_path,
+            tiered_storage::{
+                file::{ReaderBackend, TieredWritableFile},
+                index::IndexOffset,
+                readable::TieredStorageReader,
+                test_fixtures::overwrite_footer_bytes,
+                test_utils::write_hot_storage,
+            },
         },
+        assert_matches::assert_matches,
         memoffset::offset_of,
         solana_sdk::hash::Hash,
+        std::fs,
+        tempfile::TempDir,
     };
 
     #[test]
@@ -332,6 +698,8 @@ mod tests {
             index_block_offset: 1069600,
             owners_block_offset: 1081200,
             hash: Hash::new_unique(),
+            hash_kind: FooterHashKind::default(),
+            reserved: [0; 7],
             min_account_address: Pubkey::default(),
             max_account_address: Pubkey::new_unique(),
             format_version: FOOTER_FORMAT_VERSION,
@@ -352,6 +720,423 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_footer_from_file_tail() {
+        let path = get_append_vec_path("test_footer_from_file_tail");
+        let footer = TieredStorageFooter {
+            account_entry_count: 300,
+            ..TieredStorageFooter::default()
+        };
+        {
+            let mut file = TieredWritableFile::new(&path.path).unwrap();
+            file.write_bytes(&[7; 64]).unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+        let bytes = fs::read(&path.path).unwrap();
+        assert_eq!(
+            TieredStorageFooter::new_from_file_tail(&bytes).unwrap(),
+            footer
+        );
+        assert_eq!(
+            TieredStorageFooter::new_from_file_tail(&bytes[bytes.len() - FOOTER_SIZE..]).unwrap(),
+            footer
+        );
+
+        // test: a tail that misses part of the footer is reported as an I/O error
+        for len in [0, FOOTER_TAIL_SIZE - 1, FOOTER_TAIL_SIZE, FOOTER_SIZE - 1] {
+            assert_matches!(
+                TieredStorageFooter::new_from_file_tail(&bytes[bytes.len() - len..]),
+                Err(TieredStorageError::Io(_))
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_footer_block_flushes_before_magic_number() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_write_footer_block_flushes_before_magic_number");
+        let mut file = TieredWritableFile::new(&path).unwrap();
+        TieredStorageFooter::default()
+            .write_footer_block(&mut file)
+            .unwrap();
+        // the footer has reached the file while its magic number is buffered
+        assert_eq!(file.num_writes(), 1);
+        assert_eq!(
+            fs::read(&path).unwrap().len(),
+            FOOTER_SIZE - mem::size_of::<TieredStorageMagicNumber>()
+        );
+        file.finish().unwrap();
+        assert_eq!(
+            TieredStorageFooter::new_from_path(&path).unwrap(),
+            TieredStorageFooter::default()
+        );
+    }
+
+    #[test]
+    fn test_truncated_footer() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_truncated_footer");
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+            file.write_bytes(&[7; 64]).unwrap();
+            TieredStorageFooter::default()
+                .write_footer_block(&mut file)
+                .unwrap();
+            file.finish().unwrap();
+        }
+
+        // test: a file cut 4 bytes before its end is rejected cleanly
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 4);
+        fs::write(&path, &bytes).unwrap();
+        assert_matches!(
+            TieredStorageFooter::new_from_path(&path),
+            Err(TieredStorageError::MagicNumberMismatch(_, _))
+        );
+        let file = TieredReadableFile(fs::File::open(&path).unwrap());
+        let mmap = file.map(ReaderBackend::Mmap).unwrap();
+        assert_matches!(
+            TieredStorageFooter::new_from_mmap(&mmap),
+            Err(TieredStorageError::MagicNumberMismatch(_, _))
+        );
+    }
+
+    #[test]
+    fn test_footer_format_version() {
+        let path = get_append_vec_path("test_footer_format_version");
+        let footer = TieredStorageFooter::default();
+        {
+            let mut file = TieredWritableFile::new(&path.path).unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+        let read_footer = TieredStorageFooter::new_from_path(&path.path).unwrap();
+        assert_eq!(read_footer.format_version, FOOTER_FORMAT_VERSION);
+
+        // a newer version is rejected, with either reader
+        let version_offset = offset_of!(TieredStorageFooter, format_version);
+        overwrite_footer_bytes(&path.path, version_offset, &999u64.to_le_bytes());
+        assert_matches!(
+            TieredStorageFooter::new_from_path(&path.path),
+            Err(TieredStorageError::UnsupportedFormatVersion(
+                999,
+                FOOTER_FORMAT_VERSION
+            ))
+        );
+        let file = TieredReadableFile::new(&path.path).unwrap();
+        let mmap = file.map(ReaderBackend::Mmap).unwrap();
+        assert_matches!(
+            TieredStorageFooter::new_from_mmap(&mmap),
+            Err(TieredStorageError::UnsupportedFormatVersion(
+                999,
+                FOOTER_FORMAT_VERSION
+            ))
+        );
+
+        // versions older than the first one have never existed
+        overwrite_footer_bytes(&path.path, version_offset, &0u64.to_le_bytes());
+        assert_matches!(
+            TieredStorageFooter::new_from_path(&path.path),
+            Err(TieredStorageError::InvalidFooterVersion(0))
+        );
+    }
+
+    #[test]
+    fn test_footer_unsupported_hash_kind() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_footer_unsupported_hash_kind");
+        write_hot_storage(&path, &[1, 2, 3]);
+
+        overwrite_footer_bytes(&path, offset_of!(TieredStorageFooter, hash_kind), &[0x7F]);
+        assert_matches!(
+            TieredStorageFooter::new_from_path(&path),
+            Err(TieredStorageError::SanitizeFooter(
+                SanitizeFooterError::UnsupportedHashKind(_)
+            ))
+        );
+        assert_matches!(
+            TieredStorageReader::new_from_path(&path),
+            Err(TieredStorageError::SanitizeFooter(
+                SanitizeFooterError::UnsupportedHashKind(_)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_footer_format_version_1() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_footer_format_version_1");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let expected_footer = TieredStorageFooter::new_from_path(&path).unwrap();
+
+        // rewrite the footer in the layout of version 1, which has neither the
+        // hash kind nor the reserved bytes
+        let mut bytes = fs::read(&path).unwrap();
+        let footer_offset = bytes.len() - FOOTER_SIZE;
+        let hash_kind_offset = footer_offset + offset_of!(TieredStorageFooter, hash_kind);
+        let format_version_offset = footer_offset + offset_of!(TieredStorageFooter, format_version);
+        bytes.drain(hash_kind_offset..format_version_offset);
+        let tail_offset = bytes.len() - FOOTER_TAIL_SIZE;
+        bytes[tail_offset..tail_offset + 8].copy_from_slice(&1u64.to_le_bytes());
+        bytes[tail_offset + 8..tail_offset + 16]
+            .copy_from_slice(&(FOOTER_SIZE_V1 as u64).to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+
+        // the hash of a version 1 footer is never trusted
+        let footer = TieredStorageFooter::new_from_path(&path).unwrap();
+        let expected_footer = TieredStorageFooter {
+            hash_kind: FooterHashKind::None,
+            format_version: 1,
+            footer_size: FOOTER_SIZE_V1 as u64,
+            ..expected_footer
+        };
+        assert_eq!(footer, expected_footer);
+
+        // the file remains readable, and its hash is not verified
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        assert_eq!(reader.footer(), &expected_footer);
+        assert_eq!(reader.num_accounts(), 3);
+        assert_matches!(
+            reader.verify_file_hash(),
+            Err(TieredStorageError::VerificationSkipped)
+        );
+    }
+
+    #[test]
+    fn test_write_footer_format_version_1() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut footer = TieredStorageFooter {
+            hash_kind: FooterHashKind::None,
+            ..TieredStorageFooter::default()
+        };
+        footer.set_format_version(1).unwrap();
+
+        let path = temp_dir.path().join("test_write_footer_format_version_1");
+        let mut file = TieredWritableFile::new(&path).unwrap();
+        footer.write_footer_block(&mut file).unwrap();
+        file.finish().unwrap();
+        assert_eq!(TieredStorageFooter::new_from_path(&path).unwrap(), footer);
+
+        // test: a hash kind that a version 1 footer cannot persist is an
+        // error rather than a panic
+        for hash_kind in [FooterHashKind::Sha256, FooterHashKind::Blake3] {
+            let footer = TieredStorageFooter {
+                hash_kind,
+                ..footer
+            };
+            let path = temp_dir
+                .path()
+                .join(format!("test_write_footer_format_version_1_{hash_kind:?}"));
+            let mut file = TieredWritableFile::new(&path).unwrap();
+            assert_matches!(
+                footer.write_footer_block(&mut file),
+                Err(TieredStorageError::UnpersistableHashKind(kind, 1)) if kind == hash_kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_address_range() {
+        // An empty file has an unknown address range, regardless of the
+        // persisted addresses.
+        let mut footer = TieredStorageFooter {
+            account_entry_count: 0,
+            min_account_address: Pubkey::new_unique(),
+            max_account_address: Pubkey::new_unique(),
+            ..TieredStorageFooter::default()
+        };
+        assert!(footer.account_address_range().is_unknown());
+        footer.set_account_address_range(&PubkeyRange::default());
+        assert_eq!(footer.min_account_address, Pubkey::default());
+        assert_eq!(footer.max_account_address, Pubkey::default());
+
+        // A single account has min == max.
+        let address = Pubkey::new_unique();
+        let mut range = PubkeyRange::default();
+        range.update(&address);
+        footer.account_entry_count = 1;
+        footer.set_account_address_range(&range);
+        assert_eq!(
+            footer.account_address_range(),
+            PubkeyRange::new(address, address)
+        );
+    }
+
+    #[test]
+    fn test_account_address_range_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        for (i, account_data_sizes) in [&[][..], &[3], &[1, 2, 3, 4, 5, 0, 100]]
+            .into_iter()
+            .enumerate()
+        {
+            let path = temp_dir
+                .path()
+                .join(format!("test_account_address_range_round_trip_{i}"));
+            write_hot_storage(&path, account_data_sizes);
+
+            let footer = TieredStorageFooter::new_from_path(&path).unwrap();
+            let range = footer.account_address_range();
+            assert_eq!(range.is_unknown(), account_data_sizes.is_empty());
+
+            let reader = TieredStorageReader::new_from_path(&path).unwrap();
+            let mut expected_range = PubkeyRange::default();
+            for account in reader.accounts(IndexOffset(0)).unwrap() {
+                expected_range.update(account.pubkey());
+            }
+            assert_eq!(range, expected_range);
+        }
+    }
+
+    #[test]
+    fn test_sanitize_layout() {
+        // test: all good
+        let footer = TieredStorageFooter::default();
+        assert!(footer.sanitize_layout(FOOTER_SIZE as u64).is_ok());
+
+        // test: owners block starts after the footer
+        let footer = TieredStorageFooter {
+            owners_block_offset: 8,
+            ..TieredStorageFooter::default()
+        };
+        assert_matches!(
+            footer.sanitize_layout(FOOTER_SIZE as u64),
+            Err(SanitizeFooterError::InvalidBlockOffsets(0, 8, 0))
+        );
+
+        // test: files of the maximum size are accepted, larger ones are not
+        let footer = TieredStorageFooter {
+            index_block_offset: MAX_HOT_FILE_SIZE - FOOTER_SIZE as u64,
+            owners_block_offset: MAX_HOT_FILE_SIZE - FOOTER_SIZE as u64,
+            ..TieredStorageFooter::default()
+        };
+        assert!(footer.sanitize_layout(MAX_HOT_FILE_SIZE).is_ok());
+        let footer = TieredStorageFooter {
+            index_block_offset: MAX_HOT_FILE_SIZE + 1 - FOOTER_SIZE as u64,
+            owners_block_offset: MAX_HOT_FILE_SIZE + 1 - FOOTER_SIZE as u64,
+            ..TieredStorageFooter::default()
+        };
+        assert_matches!(
+            footer.sanitize_layout(MAX_HOT_FILE_SIZE + 1),
+            Err(SanitizeFooterError::FileTooLarge(len, MAX_HOT_FILE_SIZE))
+                if len == MAX_HOT_FILE_SIZE + 1
+        );
+    }
+
+    #[test]
+    fn test_account_blocks_end() {
+        // test: a file without any block but the footer
+        let footer = TieredStorageFooter::default();
+        assert_eq!(footer.account_blocks_end(FOOTER_SIZE as u64), 0);
+
+        // test: the index block follows the account blocks
+        let footer = TieredStorageFooter {
+            index_block_offset: 64,
+            owners_block_offset: 104,
+            ..TieredStorageFooter::default()
+        };
+        assert_eq!(footer.account_blocks_end(136 + FOOTER_SIZE as u64), 64);
+
+        // test: a zero-owner file with an empty index block, where the
+        // account blocks are directly followed by the footer
+        let footer = TieredStorageFooter {
+            index_block_offset: 64,
+            owners_block_offset: 64,
+            ..TieredStorageFooter::default()
+        };
+        assert_eq!(footer.account_blocks_end(64 + FOOTER_SIZE as u64), 64);
+    }
+
+    #[test]
+    fn test_sanitize_layout_of_corrupted_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let account_data_sizes = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0];
+
+        // Returns the error of opening a valid file after overwriting one of
+        // its footer fields.
+        let open_with_corrupted_field = |name: &str, field_offset: usize, bytes: &[u8]| {
+            let path = temp_dir.path().join(name);
+            write_hot_storage(&path, account_data_sizes);
+            overwrite_footer_bytes(&path, field_offset, bytes);
+            TieredStorageReader::new_from_path(&path).unwrap_err()
+        };
+
+        assert_matches!(
+            open_with_corrupted_field(
+                "index_block_offset",
+                offset_of!(TieredStorageFooter, index_block_offset),
+                &u64::MAX.to_le_bytes(),
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::InvalidBlockOffsets(..))
+        );
+        // an owners block beyond the end of the file is indistinguishable
+        // from a file whose blocks before the footer are missing
+        assert_matches!(
+            open_with_corrupted_field(
+                "owners_block_offset",
+                offset_of!(TieredStorageFooter, owners_block_offset),
+                &u64::MAX.to_le_bytes(),
+            ),
+            TieredStorageError::Truncated {
+                expected_min: u64::MAX,
+                ..
+            }
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "owners_block_offset_zero",
+                offset_of!(TieredStorageFooter, owners_block_offset),
+                &0u64.to_le_bytes(),
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::InvalidBlockOffsets(..))
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "account_meta_entry_size",
+                offset_of!(TieredStorageFooter, account_meta_entry_size),
+                &24u32.to_le_bytes(),
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::InvalidAccountMetaEntrySize(
+                24, 16
+            ))
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "account_entry_count",
+                offset_of!(TieredStorageFooter, account_entry_count),
+                &u32::MAX.to_le_bytes(),
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::IndexBlockTooSmall(
+                _,
+                u32::MAX
+            ))
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "owner_count",
+                offset_of!(TieredStorageFooter, owner_count),
+                &u32::MAX.to_le_bytes(),
+            ),
+            TieredStorageError::Truncated { .. }
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "owner_count_zero",
+                offset_of!(TieredStorageFooter, owner_count),
+                &0u32.to_le_bytes(),
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::OwnersBlockSizeMismatch(_, 0))
+        );
+        assert_matches!(
+            open_with_corrupted_field(
+                "min_account_address",
+                offset_of!(TieredStorageFooter, min_account_address),
+                &[0xFF; 32],
+            ),
+            TieredStorageError::SanitizeFooter(SanitizeFooterError::InvalidAccountAddressRange(..))
+        );
+    }
+
     #[test]
     fn test_footer_layout() {
         assert_eq!(offset_of!(TieredStorageFooter, account_meta_format), 0x00);
@@ -371,8 +1156,10 @@ mod tests {
         assert_eq!(offset_of!(TieredStorageFooter, min_account_address), 0x30);
         assert_eq!(offset_of!(TieredStorageFooter, max_account_address), 0x50);
         assert_eq!(offset_of!(TieredStorageFooter, hash), 0x70);
-        assert_eq!(offset_of!(TieredStorageFooter, format_version), 0x90);
-        assert_eq!(offset_of!(TieredStorageFooter, footer_size), 0x98);
+        assert_eq!(offset_of!(TieredStorageFooter, hash_kind), 0x90);
+        assert_eq!(offset_of!(TieredStorageFooter, reserved), 0x91);
+        assert_eq!(offset_of!(TieredStorageFooter, format_version), 0x98);
+        assert_eq!(offset_of!(TieredStorageFooter, footer_size), 0xA0);
     }
 
     #[test]
@@ -444,5 +1231,39 @@ mod tests {
                 Err(SanitizeFooterError::InvalidAccountBlockFormat(_))
             ));
         }
+
+        // test: account block format added by a future version
+        {
+            let mut footer = TieredStorageFooter::default();
+            let unknown_format = u16::from(AccountBlockFormat::Zstd) + 1;
+            unsafe {
+                std::ptr::write(
+                    &mut footer.account_block_format as *mut _ as *mut u16,
+                    unknown_format,
+                );
+            }
+            let result = TieredStorageFooter::sanitize(&footer);
+            assert_matches!(
+                result,
+                Err(SanitizeFooterError::InvalidAccountBlockFormat(err))
+                    if err.number == unknown_format
+            );
+        }
+
+        // test: compressed account blocks of hot accounts
+        for account_block_format in [AccountBlockFormat::Lz4, AccountBlockFormat::Zstd] {
+            let footer = TieredStorageFooter {
+                account_block_format,
+                ..TieredStorageFooter::default()
+            };
+            let result = TieredStorageFooter::sanitize(&footer);
+            assert_matches!(
+                result,
+                Err(SanitizeFooterError::UnsupportedAccountBlockFormat(
+                    AccountMetaFormat::Hot,
+                    format,
+                )) if format == account_block_format
+            );
+        }
     }
 }
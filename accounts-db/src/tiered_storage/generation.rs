@@ -0,0 +1,236 @@
+//! Format generations, which gate the use of newer on-disk structures.
+//!
+//! Files written by upgraded nodes must remain readable by nodes that are
+//! not yet upgraded until the whole cluster switches over.  The writer is
+//! therefore given a ceiling generation, and only emits the structures that
+//! the ceiling supports.  The reader is unaffected, as it reads anything it
+//! understands.
+use crate::tiered_storage::{
+    footer::TieredStorageFooter, index::IndexBlockFormat, owners::OwnersBlockFormat,
+};
+
+/// A set of on-disk structures, where each generation supports all the
+/// structures of the generations before it.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FormatGeneration {
+    /// Footer format version 1 and the unsorted index block.
+    #[default]
+    V1Baseline,
+    /// Adds footer format version 2, which persists the hash kind.
+    V2Footer,
+    /// Adds the index block sorted by account addresses.
+    V3SortedIndex,
+    /// Adds the index block that stores the size of each account block.
+    V4SizedIndex,
+    /// Adds the owners block that stores the account count of each owner.
+    V5OwnerCounts,
+    /// Adds the account hash as an optional field of the account metas.
+    V6AccountHash,
+    /// Adds the checksum at the end of each account block.
+    V7BlockChecksum,
+}
+
+impl FormatGeneration {
+    /// The newest generation.
+    pub const LATEST: Self = Self::V7BlockChecksum;
+
+    /// Returns true if the specified feature can be used in this generation.
+    pub fn supports(&self, feature: FormatFeature) -> bool {
+        *self >= feature.generation()
+    }
+
+    /// Returns the oldest generation able to read the file described by the
+    /// specified footer.
+    ///
+    /// The footer does not record whether the account metas carry their
+    /// account hashes or block checksums, so FormatFeature::AccountHash and
+    /// FormatFeature::BlockChecksum are not accounted for.
+    pub fn required_by(footer: &TieredStorageFooter) -> Self {
+        [
+            (footer.format_version >= 2, FormatFeature::FooterV2),
+            (
+                footer.index_block_format == IndexBlockFormat::SortedAddressesThenOffsets,
+                FormatFeature::SortedIndex,
+            ),
+            (
+                footer.index_block_format == IndexBlockFormat::AddressesThenOffsetsThenSizes,
+                FormatFeature::SizedIndex,
+            ),
+            (
+                footer.owners_block_format == OwnersBlockFormat::LocalIndexWithCounts,
+                FormatFeature::OwnerCounts,
+            ),
+        ]
+        .into_iter()
+        .filter(|(is_used, _)| *is_used)
+        .map(|(_, feature)| feature.generation())
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// An on-disk structure that is not part of the baseline generation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FormatFeature {
+    /// Footer format version 2, which persists the hash kind.
+    FooterV2,
+    /// The index block sorted by account addresses.
+    SortedIndex,
+    /// The index block that stores the size of each account block.
+    SizedIndex,
+    /// The owners block that stores the account count of each owner.
+    OwnerCounts,
+    /// The account hash as an optional field of the account metas.
+    AccountHash,
+    /// The checksum at the end of each account block.
+    BlockChecksum,
+}
+
+/// What the writer does when an option requires a feature that the ceiling
+/// generation does not support.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GatePolicy {
+    /// Emits the older structure instead.
+    Downgrade,
+    /// Fails the write, as the older structure cannot honor the option.
+    Error,
+}
+
+impl FormatFeature {
+    /// Returns the generation that introduced this feature.
+    pub fn generation(&self) -> FormatGeneration {
+        match self {
+            Self::FooterV2 => FormatGeneration::V2Footer,
+            Self::SortedIndex => FormatGeneration::V3SortedIndex,
+            Self::SizedIndex => FormatGeneration::V4SizedIndex,
+            Self::OwnerCounts => FormatGeneration::V5OwnerCounts,
+            Self::AccountHash => FormatGeneration::V6AccountHash,
+            Self::BlockChecksum => FormatGeneration::V7BlockChecksum,
+        }
+    }
+
+    /// Returns what the writer does when this feature is not supported.
+    ///
+    /// - FooterV2: a version 1 footer is written without a file hash
+    ///   regardless of the requested hash kind, as the reader cannot tell a
+    ///   computed version 1 hash from the placeholder of older writers.
+    /// - SortedIndex: an unsorted index cannot be looked up by address via
+    ///   binary search, so the write fails.
+    /// - SizedIndex: an index without the block sizes is written, as the
+    ///   writer stores the account blocks contiguously.
+    /// - OwnerCounts: an owners block without the counts is written, as the
+    ///   counts can also be derived from the account metas.
+    /// - AccountHash: the accounts are written without their hashes, which
+    ///   read back as the default hash and can be recomputed from the
+    ///   accounts.
+    /// - BlockChecksum: the account blocks are written without checksums,
+    ///   which only allow a corrupted account to be detected without
+    ///   hashing the whole file.
+    pub fn policy(&self) -> GatePolicy {
+        match self {
+            Self::FooterV2
+            | Self::SizedIndex
+            | Self::OwnerCounts
+            | Self::AccountHash
+            | Self::BlockChecksum => GatePolicy::Downgrade,
+            Self::SortedIndex => GatePolicy::Error,
+        }
+    }
+}
+
+/// The options of the tiered storage writers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct WriteOptions {
+    /// The newest generation whose structures the writer may emit.
+    pub max_format_generation: FormatGeneration,
+}
+
+impl WriteOptions {
+    /// The options that allow the newest structures.
+    pub const LATEST: Self = Self {
+        max_format_generation: FormatGeneration::LATEST,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tiered_storage::footer::FOOTER_FORMAT_VERSION};
+
+    #[test]
+    fn test_supports() {
+        use {FormatFeature::*, FormatGeneration::*};
+        for (generation, expected) in [
+            (V1Baseline, [false, false, false, false, false, false]),
+            (V2Footer, [true, false, false, false, false, false]),
+            (V3SortedIndex, [true, true, false, false, false, false]),
+            (V4SizedIndex, [true, true, true, false, false, false]),
+            (V5OwnerCounts, [true, true, true, true, false, false]),
+            (V6AccountHash, [true, true, true, true, true, false]),
+            (V7BlockChecksum, [true, true, true, true, true, true]),
+        ] {
+            let supported = [
+                FooterV2,
+                SortedIndex,
+                SizedIndex,
+                OwnerCounts,
+                AccountHash,
+                BlockChecksum,
+            ]
+            .map(|feature| generation.supports(feature));
+            assert_eq!(supported, expected, "{generation:?}");
+        }
+        assert_eq!(FormatGeneration::default(), V1Baseline);
+        assert_eq!(WriteOptions::default().max_format_generation, V1Baseline);
+    }
+
+    #[test]
+    fn test_required_by() {
+        let footer = TieredStorageFooter {
+            format_version: 1,
+            ..TieredStorageFooter::default()
+        };
+        assert_eq!(
+            FormatGeneration::required_by(&footer),
+            FormatGeneration::V1Baseline
+        );
+
+        let footer = TieredStorageFooter {
+            format_version: FOOTER_FORMAT_VERSION,
+            ..footer
+        };
+        assert_eq!(
+            FormatGeneration::required_by(&footer),
+            FormatGeneration::V2Footer
+        );
+
+        // the sorted index requires the newest generation even with an old
+        // footer
+        let footer = TieredStorageFooter {
+            format_version: 1,
+            index_block_format: IndexBlockFormat::SortedAddressesThenOffsets,
+            ..footer
+        };
+        assert_eq!(
+            FormatGeneration::required_by(&footer),
+            FormatGeneration::V3SortedIndex
+        );
+
+        let footer = TieredStorageFooter {
+            index_block_format: IndexBlockFormat::AddressesThenOffsetsThenSizes,
+            ..footer
+        };
+        assert_eq!(
+            FormatGeneration::required_by(&footer),
+            FormatGeneration::V4SizedIndex
+        );
+
+        let footer = TieredStorageFooter {
+            owners_block_format: OwnersBlockFormat::LocalIndexWithCounts,
+            ..footer
+        };
+        assert_eq!(
+            FormatGeneration::required_by(&footer),
+            FormatGeneration::V5OwnerCounts
+        );
+    }
+}
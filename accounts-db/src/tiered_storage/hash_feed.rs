@@ -0,0 +1,126 @@
+//! Streaming hashers of the file hash persisted in the footer.
+use {
+    crate::tiered_storage::footer::FooterHashKind,
+    solana_sdk::hash::{Hash, Hasher},
+};
+
+/// A hasher that is fed with the bytes of a file piece by piece.
+pub trait HashFeed {
+    /// Feeds the specified bytes into the hasher.
+    fn feed(&mut self, bytes: &[u8]);
+
+    /// Returns the hash of all the bytes fed so far.
+    fn finish(&self) -> Hash;
+}
+
+impl HashFeed for Hasher {
+    fn feed(&mut self, bytes: &[u8]) {
+        self.hash(bytes);
+    }
+
+    fn finish(&self) -> Hash {
+        self.clone().result()
+    }
+}
+
+impl HashFeed for blake3::Hasher {
+    fn feed(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> Hash {
+        Hash::new_from_array(*self.finalize().as_bytes())
+    }
+}
+
+/// The hasher of the file hash, selected by the FooterHashKind persisted
+/// along with the hash.
+#[derive(Clone)]
+pub enum FileHasher {
+    None,
+    Sha256(Hasher),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl FileHasher {
+    /// Creates a hasher of the specified kind.
+    pub fn new(hash_kind: FooterHashKind) -> Self {
+        match hash_kind {
+            FooterHashKind::None => Self::None,
+            FooterHashKind::Sha256 => Self::Sha256(Hasher::default()),
+            FooterHashKind::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    /// Returns the kind of this hasher.
+    pub fn kind(&self) -> FooterHashKind {
+        match self {
+            Self::None => FooterHashKind::None,
+            Self::Sha256(_) => FooterHashKind::Sha256,
+            Self::Blake3(_) => FooterHashKind::Blake3,
+        }
+    }
+}
+
+impl Default for FileHasher {
+    fn default() -> Self {
+        Self::new(FooterHashKind::default())
+    }
+}
+
+impl HashFeed for FileHasher {
+    fn feed(&mut self, bytes: &[u8]) {
+        match self {
+            Self::None => {}
+            Self::Sha256(hasher) => hasher.feed(bytes),
+            Self::Blake3(hasher) => hasher.feed(bytes),
+        }
+    }
+
+    /// Returns the hash of all the bytes fed so far, or Hash::default() if
+    /// the hasher does not hash anything.
+    fn finish(&self) -> Hash {
+        match self {
+            Self::None => Hash::default(),
+            Self::Sha256(hasher) => hasher.finish(),
+            Self::Blake3(hasher) => hasher.finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case(FooterHashKind::None)]
+    #[test_case(FooterHashKind::Sha256)]
+    #[test_case(FooterHashKind::Blake3)]
+    fn test_file_hasher(hash_kind: FooterHashKind) {
+        let bytes: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let expected_hash = match hash_kind {
+            FooterHashKind::None => Hash::default(),
+            FooterHashKind::Sha256 => solana_sdk::hash::hash(&bytes),
+            FooterHashKind::Blake3 => Hash::new_from_array(*blake3::hash(&bytes).as_bytes()),
+        };
+
+        let mut hasher = FileHasher::new(hash_kind);
+        assert_eq!(hasher.kind(), hash_kind);
+        // the hash does not depend on how the bytes are split
+        for chunk in bytes.chunks(77) {
+            hasher.feed(chunk);
+        }
+        assert_eq!(hasher.finish(), expected_hash);
+        // finishing does not consume the hasher
+        assert_eq!(hasher.finish(), expected_hash);
+    }
+
+    #[test]
+    fn test_hash_kinds_differ() {
+        let bytes = [3u8; 100];
+        let mut sha256 = FileHasher::new(FooterHashKind::Sha256);
+        let mut blake3 = FileHasher::new(FooterHashKind::Blake3);
+        sha256.feed(&bytes);
+        blake3.feed(&bytes);
+        assert_ne!(sha256.finish(), blake3.finish());
+    }
+}
@@ -1,41 +1,96 @@
 //! The account meta and related structs for hot accounts.
 
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use {
     crate::{
-        account_storage::meta::{StoredAccountInfo, StoredAccountMeta},
+        account_storage::meta::{StoredAccountInfo, StoredAccountMeta, DEFAULT_ACCOUNT_HASH},
         accounts_file::MatchAccountOwnerError,
         accounts_hash::AccountHash,
         tiered_storage::{
-            byte_block,
-            file::{TieredReadableFile, TieredWritableFile},
-            footer::{AccountBlockFormat, AccountMetaFormat, TieredStorageFooter},
+            byte_block::ByteBlockWriter,
+            file::{MmapAccessPattern, ReaderBackend, TieredReadableFile, TieredWritableFile},
+            footer::{
+                AccountBlockFormat, AccountMetaFormat, FooterHashKind, TieredStorageFooter,
+                FOOTER_FORMAT_VERSION,
+            },
+            generation::{FormatFeature, FormatGeneration, GatePolicy, WriteOptions},
+            hash_feed::{FileHasher, HashFeed},
             index::{AccountIndexWriterEntry, AccountOffset, IndexBlockFormat, IndexOffset},
             meta::{
-                AccountAddressRange, AccountMetaFlags, AccountMetaOptionalFields, TieredAccountMeta,
+                block_checksum_from_hasher, AccountMetaFlags, AccountMetaOptionalFields,
+                BlockChecksum, PubkeyRange, TieredAccountMeta,
             },
-            mmap_utils::{get_pod, get_slice},
-            owners::{OwnerOffset, OwnersBlockFormat, OwnersTable, OWNER_NO_OWNER},
+            mmap_utils::{get_pod, get_slice, to_usize},
+            owners::{
+                OwnerMatchCache, OwnerOffset, OwnersBlockFormat, OwnersTable, OWNER_NO_OWNER,
+            },
+            trace::{WriteTrace, WriteTraceEvent},
+            verification::{Inconsistency, PrefixVerification, VerifyReport},
             StorableAccounts, StorableAccountsWithHashesAndWriteVersions, TieredStorageError,
-            TieredStorageFormat, TieredStorageResult,
+            TieredStorageFormat, TieredStorageResult, TieredStorageWriterStats,
+            ValidatedTieredStorageFormat,
         },
     },
     bytemuck::{Pod, Zeroable},
-    memmap2::{Mmap, MmapOptions},
+    log::*,
+    memmap2::Mmap,
     modular_bitfield::prelude::*,
     solana_sdk::{
-        account::ReadableAccount, pubkey::Pubkey, rent_collector::RENT_EXEMPT_RENT_EPOCH,
+        account::{AccountSharedData, ReadableAccount},
+        pubkey::Pubkey,
+        rent_collector::RENT_EXEMPT_RENT_EPOCH,
         stake_history::Epoch,
     },
-    std::{borrow::Borrow, option::Option, path::Path},
+    std::{
+        borrow::Borrow,
+        collections::HashMap,
+        io,
+        option::Option,
+        path::Path,
+        sync::{Mutex, OnceLock},
+        time::Instant,
+    },
 };
 
-pub const HOT_FORMAT: TieredStorageFormat = TieredStorageFormat {
-    meta_entry_size: std::mem::size_of::<HotAccountMeta>(),
-    account_meta_format: AccountMetaFormat::Hot,
-    owners_block_format: OwnersBlockFormat::AddressesOnly,
-    index_block_format: IndexBlockFormat::AddressesThenOffsets,
-    account_block_format: AccountBlockFormat::AlignedRaw,
-};
+// The presets bypass TryFrom as it cannot be called in a const context.
+// test_hot_format_presets checks that each of them is valid.
+pub const HOT_FORMAT: ValidatedTieredStorageFormat =
+    ValidatedTieredStorageFormat(TieredStorageFormat {
+        meta_entry_size: std::mem::size_of::<HotAccountMeta>(),
+        account_meta_format: AccountMetaFormat::Hot,
+        owners_block_format: OwnersBlockFormat::AddressesOnly,
+        index_block_format: IndexBlockFormat::AddressesThenOffsets,
+        account_block_format: AccountBlockFormat::AlignedRaw,
+        compression_level: None,
+        hash_kind: FooterHashKind::Sha256,
+        max_accounts_per_file: None,
+        block_checksums: false,
+    });
+
+/// The hot format whose accounts are sorted by their addresses, which allows
+/// an account to be looked up by its address via binary search.
+pub const HOT_SORTED_FORMAT: ValidatedTieredStorageFormat =
+    ValidatedTieredStorageFormat(TieredStorageFormat {
+        index_block_format: IndexBlockFormat::SortedAddressesThenOffsets,
+        ..HOT_FORMAT.0
+    });
+
+/// The hot format whose index block stores the size of each account block,
+/// so that the account blocks do not have to be adjacent.
+pub const HOT_SIZED_INDEX_FORMAT: ValidatedTieredStorageFormat =
+    ValidatedTieredStorageFormat(TieredStorageFormat {
+        index_block_format: IndexBlockFormat::AddressesThenOffsetsThenSizes,
+        ..HOT_FORMAT.0
+    });
+
+/// The hot format whose owners block stores the number of accounts of each
+/// owner, so that a scan by owner can skip the files without its accounts.
+pub const HOT_OWNER_COUNTS_FORMAT: ValidatedTieredStorageFormat =
+    ValidatedTieredStorageFormat(TieredStorageFormat {
+        owners_block_format: OwnersBlockFormat::LocalIndexWithCounts,
+        ..HOT_FORMAT.0
+    });
 
 /// An helper function that creates a new default footer for hot
 /// accounts storage.
@@ -69,17 +124,58 @@ pub(crate) const HOT_BLOCK_ALIGNMENT: usize = 8;
 /// The maximum supported offset for hot accounts storage.
 const MAX_HOT_ACCOUNT_OFFSET: usize = u32::MAX as usize * HOT_ACCOUNT_ALIGNMENT;
 
-// returns the required number of padding
-fn padding_bytes(data_len: usize) -> u8 {
-    ((HOT_ACCOUNT_ALIGNMENT - (data_len % HOT_ACCOUNT_ALIGNMENT)) % HOT_ACCOUNT_ALIGNMENT) as u8
+/// The granularity at which HotStorageReader::prefetch() touches the file,
+/// which is the smallest page size of the supported platforms.
+const PREFETCH_PAGE_SIZE: usize = 4 * 1024;
+
+/// The size above which the account data is written straight to the file
+/// instead of being copied into an aligned block first.
+const HOT_BUFFERED_DATA_THRESHOLD: usize = 64 * 1024;
+
+/// The maximum size of a hot accounts file.  As every byte of the file is
+/// addressable by a HotAccountOffset, the account blocks can never outgrow
+/// their u32 offsets.
+pub const MAX_HOT_FILE_SIZE: u64 = MAX_HOT_ACCOUNT_OFFSET as u64;
+
+/// The layout of the account data and its padding inside an account block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DataLayout {
+    /// The length of the account data plus its padding.
+    pub padded_len: usize,
+    /// The number of padding bytes following the account data.
+    pub padding: u8,
+}
+
+/// Returns the layout of the account data of the specified length when it
+/// is padded to the specified alignment.
+///
+/// This is the single place that defines the padding math, and unpad()
+/// is its inverse.
+pub(crate) fn padded_data_layout(data_len: usize, alignment: usize) -> DataLayout {
+    let padding = (alignment - data_len % alignment) % alignment;
+    DataLayout {
+        padded_len: data_len + padding,
+        padding: padding as u8,
+    }
+}
+
+/// Returns the length of the account data given the length of the account
+/// block before its optional fields and the number of padding bytes.
+///
+/// This is the inverse of padded_data_layout(), and returns an error
+/// instead of saturating when the padding exceeds the available length.
+pub(crate) fn unpad(len_before_optional_fields: usize, padding: u8) -> TieredStorageResult<usize> {
+    len_before_optional_fields
+        .checked_sub(padding as usize)
+        .ok_or(TieredStorageError::InvalidAccountDataPadding(
+            padding,
+            len_before_optional_fields,
+        ))
 }
 
 /// The maximum number of padding bytes used in a hot account entry.
 const MAX_HOT_PADDING: u8 = 7;
 
-/// The buffer that is used for padding.
-const PADDING_BUFFER: [u8; 8] = [0u8; HOT_ACCOUNT_ALIGNMENT];
-
 #[bitfield(bits = 32)]
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Pod, Zeroable)]
@@ -154,7 +250,27 @@ pub struct HotAccountMeta {
 // Ensure there are no implicit padding bytes
 const _: () = assert!(std::mem::size_of::<HotAccountMeta>() == 8 + 4 + 4);
 
+impl HotAccountMeta {
+    /// Returns the length of the data associated to this account based on
+    /// the size of its account block, without accessing the account block.
+    ///
+    /// Returns an error if the account block is too small to hold the
+    /// padding and the optional fields described by this meta.
+    fn account_data_size_from_block_size(&self, block_size: usize) -> TieredStorageResult<usize> {
+        let optional_fields_size = AccountMetaOptionalFields::size_from_flags(&self.flags);
+        let Some(padded_data_len) = block_size.checked_sub(optional_fields_size) else {
+            return Err(TieredStorageError::InvalidAccountBlockSize(
+                block_size,
+                optional_fields_size,
+            ));
+        };
+        unpad(padded_data_len, self.account_data_padding())
+    }
+}
+
 impl TieredAccountMeta for HotAccountMeta {
+    const MAX_OWNER_OFFSET: OwnerOffset = MAX_HOT_OWNER_OFFSET;
+
     /// Construct a HotAccountMeta instance.
     fn new() -> Self {
         HotAccountMeta {
@@ -172,21 +288,27 @@ impl TieredAccountMeta for HotAccountMeta {
 
     /// A builder function that initializes the number of padding bytes
     /// for the account data associated with the current meta.
-    fn with_account_data_padding(mut self, padding: u8) -> Self {
+    fn with_account_data_padding(mut self, padding: u8) -> TieredStorageResult<Self> {
         if padding > MAX_HOT_PADDING {
-            panic!("padding exceeds MAX_HOT_PADDING");
+            return Err(TieredStorageError::PaddingLimitExceeded(
+                padding,
+                MAX_HOT_PADDING,
+            ));
         }
         self.packed_fields.set_padding(padding);
-        self
+        Ok(self)
     }
 
     /// A builder function that initializes the owner's index.
-    fn with_owner_offset(mut self, owner_offset: OwnerOffset) -> Self {
-        if owner_offset > MAX_HOT_OWNER_OFFSET {
-            panic!("owner_offset exceeds MAX_HOT_OWNER_OFFSET");
+    fn with_owner_offset(mut self, owner_offset: OwnerOffset) -> TieredStorageResult<Self> {
+        if owner_offset > Self::MAX_OWNER_OFFSET {
+            return Err(TieredStorageError::OwnerOffsetLimitExceeded(
+                owner_offset.0,
+                Self::MAX_OWNER_OFFSET.0,
+            ));
         }
         self.packed_fields.set_owner_offset(owner_offset.0);
-        self
+        Ok(self)
     }
 
     /// A builder function that initializes the account data size.
@@ -233,14 +355,16 @@ impl TieredAccountMeta for HotAccountMeta {
     /// the specified account block.  None will be returned if this account
     /// does not persist this optional field.
     fn rent_epoch(&self, account_block: &[u8]) -> Option<Epoch> {
-        self.flags()
-            .has_rent_epoch()
-            .then(|| {
-                let offset = self.optional_fields_offset(account_block)
-                    + AccountMetaOptionalFields::rent_epoch_offset(self.flags());
-                byte_block::read_pod::<Epoch>(account_block, offset).copied()
-            })
-            .flatten()
+        self.optional_fields(account_block)
+            .ok()?
+            .rent_epoch
+            .copied()
+    }
+
+    /// Returns the account hash by parsing the specified account block.  None
+    /// will be returned if this account does not persist this optional field.
+    fn account_hash<'a>(&self, account_block: &'a [u8]) -> Option<&'a AccountHash> {
+        self.optional_fields(account_block).ok()?.account_hash
     }
 
     /// Returns the offset of the optional fields based on the specified account
@@ -253,15 +377,18 @@ impl TieredAccountMeta for HotAccountMeta {
 
     /// Returns the length of the data associated to this account based on the
     /// specified account block.
-    fn account_data_size(&self, account_block: &[u8]) -> usize {
-        self.optional_fields_offset(account_block)
-            .saturating_sub(self.account_data_padding() as usize)
+    ///
+    /// Returns an error if the account block is too small to hold the
+    /// padding and the optional fields described by this meta, in which
+    /// case the optional fields cannot be read correctly either.
+    fn account_data_size(&self, account_block: &[u8]) -> TieredStorageResult<usize> {
+        self.account_data_size_from_block_size(account_block.len())
     }
 
     /// Returns the data associated to this account based on the specified
     /// account block.
-    fn account_data<'a>(&self, account_block: &'a [u8]) -> &'a [u8] {
-        &account_block[..self.account_data_size(account_block)]
+    fn account_data<'a>(&self, account_block: &'a [u8]) -> TieredStorageResult<&'a [u8]> {
+        Ok(&account_block[..self.account_data_size(account_block)?])
     }
 }
 
@@ -292,9 +419,55 @@ impl<'accounts_file, M: TieredAccountMeta> HotAccount<'accounts_file, M> {
         self.index
     }
 
+    /// Returns the hash of this account.
+    ///
+    /// The account hash is an optional field, so the default account hash
+    /// is returned when it is not persisted, which matches the hash of an
+    /// account whose hash was never calculated.
+    pub fn hash(&self) -> &'accounts_file AccountHash {
+        self.meta
+            .account_hash(self.account_block)
+            .unwrap_or(&DEFAULT_ACCOUNT_HASH)
+    }
+
+    /// Returns whether the checksum persisted at the end of the account
+    /// block matches its content, or None if the account block does not
+    /// persist a checksum.
+    pub fn verify_checksum(&self) -> Option<bool> {
+        self.meta.verify_block_checksum(self.account_block)
+    }
+
     /// Returns the data associated to this account.
+    ///
+    /// Note that the reader validates the account data size when loading
+    /// the account, so the account data is always available here.
     pub fn data(&self) -> &'accounts_file [u8] {
-        self.meta.account_data(self.account_block)
+        self.meta
+            .account_data(self.account_block)
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of bytes this account occupies in the account
+    /// blocks of its file, i.e. its meta and its account block.
+    pub fn stored_size(&self) -> usize {
+        std::mem::size_of::<M>() + self.account_block.len()
+    }
+
+    /// Returns true if this account is sane.
+    ///
+    /// As in AppendVec, a zero-lamport account must be identical to
+    /// AccountSharedData::default().  Unlike AppendVec, the executable bit
+    /// is a flag that cannot hold any value other than true or false, so it
+    /// needs no check.  In particular, an executable account with empty data
+    /// is sane in both backends.
+    pub(crate) fn sanitize(&self) -> bool {
+        !self.is_zero_lamport() || self.to_account_shared_data() == AccountSharedData::default()
+    }
+
+    /// Returns true if this account is a zero-lamport account.  See
+    /// TieredAccountMeta::is_zero_lamport().
+    pub fn is_zero_lamport(&self) -> bool {
+        self.meta.is_zero_lamport()
     }
 }
 
@@ -316,14 +489,15 @@ impl<'accounts_file, M: TieredAccountMeta> ReadableAccount for HotAccount<'accou
 
     /// Returns the epoch that this account will next owe rent by parsing
     /// the specified account block.  RENT_EXEMPT_RENT_EPOCH will be returned
-    /// if the account is rent-exempt.
+    /// if the account is rent-exempt, i.e. it does not persist its rent
+    /// epoch.  See AccountMetaOptionalFields::rent_epoch_to_persist().
     ///
     /// For a zero-lamport account, Epoch::default() will be returned to
     /// default states of an AccountSharedData.
     fn rent_epoch(&self) -> Epoch {
         self.meta
             .rent_epoch(self.account_block)
-            .unwrap_or(if self.lamports() != 0 {
+            .unwrap_or(if !self.is_zero_lamport() {
                 RENT_EXEMPT_RENT_EPOCH
             } else {
                 // While there is no valid-values for any fields of a zero
@@ -340,23 +514,246 @@ impl<'accounts_file, M: TieredAccountMeta> ReadableAccount for HotAccount<'accou
     }
 }
 
+/// The fields of an account that building the accounts index needs.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct StoredAccountIndexEntry {
+    /// The address of the account
+    pub pubkey: Pubkey,
+    /// The index for accessing the account inside its belonging AccountsFile
+    pub index_offset: IndexOffset,
+    /// The balance of the lamports of the account
+    pub lamports: u64,
+    /// The length of the data of the account
+    pub data_len: usize,
+}
+
+/// The fields of a hot account that the accounts hash calculation needs,
+/// where the addresses and the data are borrowed from the underlying file.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct HotAccountForHash<'accounts_file> {
+    /// The address of the account
+    pub pubkey: &'accounts_file Pubkey,
+    /// The balance of the lamports of the account
+    pub lamports: u64,
+    /// The address of the account owner
+    pub owner: &'accounts_file Pubkey,
+    /// Whether the data of the account is executable
+    pub executable: bool,
+    /// The epoch that the account will next owe rent
+    pub rent_epoch: Epoch,
+    /// The data of the account
+    pub data: &'accounts_file [u8],
+}
+
+/// The access pattern of a reader, and the number of the scans in progress,
+/// which override it with the sequential access pattern.
+#[derive(Debug)]
+struct AccessAdvice {
+    /// The latest random or sequential access pattern passed to advise().
+    pattern: MmapAccessPattern,
+    num_scans: usize,
+}
+
+/// A scan in progress, which restores the access pattern of its reader when
+/// dropped if it is the last one.
+struct SequentialScan<'reader> {
+    reader: &'reader HotStorageReader,
+}
+
+impl<'reader> SequentialScan<'reader> {
+    fn enter(reader: &'reader HotStorageReader) -> Self {
+        let mut advice = reader.access_advice.lock().unwrap();
+        advice.num_scans += 1;
+        if advice.num_scans == 1 && advice.pattern != MmapAccessPattern::Sequential {
+            if let Err(err) = reader.apply_advice(MmapAccessPattern::Sequential) {
+                warn!("failed to advise the sequential access pattern: {err}");
+            }
+        }
+        Self { reader }
+    }
+}
+
+impl Drop for SequentialScan<'_> {
+    fn drop(&mut self) {
+        let mut advice = self.reader.access_advice.lock().unwrap();
+        advice.num_scans -= 1;
+        if advice.num_scans == 0 && advice.pattern != MmapAccessPattern::Sequential {
+            if let Err(err) = self.reader.apply_advice(advice.pattern) {
+                error!(
+                    "failed to restore the {:?} access pattern: {err}",
+                    advice.pattern
+                );
+            }
+        }
+    }
+}
+
 /// The reader to a hot accounts file.
 #[derive(Debug)]
 pub struct HotStorageReader {
     mmap: Mmap,
     footer: TieredStorageFooter,
+    backend: ReaderBackend,
+    /// Whether the account offsets are decoded into account_offsets on
+    /// first access, which costs memory proportional to the accounts.
+    cache_account_offsets: bool,
+    /// The account offsets decoded from the index block, or None if the
+    /// index block cannot be decoded, in which case every lookup reads the
+    /// index block.
+    account_offsets: OnceLock<Option<Box<[HotAccountOffset]>>>,
+    /// Serializes the madvise() calls of the concurrent scans and callers.
+    access_advice: Mutex<AccessAdvice>,
+    /// The number of times account_offsets has been built.
+    #[cfg(test)]
+    account_offsets_builds: AtomicUsize,
 }
 
+static_assertions::assert_impl_all!(HotStorageReader: Send, Sync);
+
 impl HotStorageReader {
     pub fn new(file: TieredReadableFile) -> TieredStorageResult<Self> {
-        let mmap = unsafe { MmapOptions::new().map(&file.0)? };
+        Self::new_with_backend(file, ReaderBackend::Mmap)
+    }
+
+    /// Creates a reader that accesses the content of the specified file via
+    /// the specified backend.
+    pub fn new_with_backend(
+        file: TieredReadableFile,
+        backend: ReaderBackend,
+    ) -> TieredStorageResult<Self> {
+        Self::new_with_offset_cache(file, backend, true)
+    }
+
+    /// Creates a reader that accesses the content of the specified file via
+    /// the specified backend.
+    ///
+    /// If `cache_account_offsets` is set, the account offsets are decoded
+    /// from the index block once on first access, which costs 4 bytes per
+    /// account, so that each later lookup is an array access.
+    pub fn new_with_offset_cache(
+        file: TieredReadableFile,
+        backend: ReaderBackend,
+        cache_account_offsets: bool,
+    ) -> TieredStorageResult<Self> {
+        let mmap = file.map(backend)?;
         // Here we are copying the footer, as accessing any data in a
         // TieredStorage instance requires accessing its Footer.
         // This can help improve cache locality and reduce the overhead
         // of indirection associated with memory-mapped accesses.
-        let footer = *TieredStorageFooter::new_from_mmap(&mmap)?;
+        let footer = TieredStorageFooter::new_from_mmap(&mmap)?;
+
+        let reader = Self {
+            mmap,
+            footer,
+            backend,
+            cache_account_offsets,
+            account_offsets: OnceLock::new(),
+            access_advice: Mutex::new(AccessAdvice {
+                pattern: MmapAccessPattern::Random,
+                num_scans: 0,
+            }),
+            #[cfg(test)]
+            account_offsets_builds: AtomicUsize::new(0),
+        };
+        // accounts are mostly loaded one at a time
+        reader.advise(MmapAccessPattern::Random)?;
+        Ok(reader)
+    }
+
+    /// Hints the kernel about how the content of the file will be accessed.
+    ///
+    /// This is a no-op for the content that is not mapped from the file, as
+    /// releasing its pages would discard the content, and on the platforms
+    /// without madvise().
+    ///
+    /// While a scan is in progress, a random or sequential access pattern
+    /// only takes effect once the last scan finishes.
+    pub fn advise(&self, access: MmapAccessPattern) -> TieredStorageResult<()> {
+        match access {
+            MmapAccessPattern::Random | MmapAccessPattern::Sequential => {
+                let mut advice = self.access_advice.lock().unwrap();
+                advice.pattern = access;
+                if advice.num_scans > 0 {
+                    return Ok(());
+                }
+                self.apply_advice(access)
+            }
+            MmapAccessPattern::WillNeed | MmapAccessPattern::DontNeed => self.apply_advice(access),
+        }
+    }
+
+    /// Passes the specified access pattern to madvise().
+    fn apply_advice(&self, access: MmapAccessPattern) -> TieredStorageResult<()> {
+        if self.backend != ReaderBackend::Mmap {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        self.mmap.advise(access.into())?;
+        #[cfg(not(unix))]
+        let _ = access;
+        Ok(())
+    }
+
+    /// Makes the pages of the specified accounts resident, so that loading
+    /// them afterward does not block on reading the file, e.g. for the
+    /// accounts needed by upcoming transactions.
+    ///
+    /// One byte per page of the index entry, the account meta and the
+    /// account block of each account is read, which is cheap for the pages
+    /// that are already resident.  Index offsets that are out of range, or
+    /// whose account block cannot be located, are skipped.  This is a no-op
+    /// for the content that is not mapped from the file.
+    pub fn prefetch(&self, index_offsets: &[IndexOffset]) {
+        if self.backend != ReaderBackend::Mmap {
+            return;
+        }
+        for index_offset in index_offsets {
+            if index_offset.0 >= self.footer.account_entry_count {
+                continue;
+            }
+            // reading the address and the account offset touches the
+            // index entry
+            let Ok(address) = self.get_account_address(*index_offset) else {
+                continue;
+            };
+            std::hint::black_box(address);
+            let Ok(account_offset) = self.get_account_offset(*index_offset) else {
+                continue;
+            };
+            let Ok(block_size) = self.get_account_block_size(account_offset, *index_offset) else {
+                continue;
+            };
+            let start = account_offset.offset();
+            let end = start + std::mem::size_of::<HotAccountMeta>() + block_size;
+            self.touch_pages(start, end);
+        }
+    }
+
+    /// Reads one byte per page of the specified range of the file, plus its
+    /// last byte, so that every page the range spans is resident.
+    fn touch_pages(&self, start: usize, end: usize) {
+        let Some(bytes) = self.mmap.get(start..end) else {
+            return;
+        };
+        let touched = (0..bytes.len())
+            .step_by(PREFETCH_PAGE_SIZE)
+            .chain(bytes.len().checked_sub(1))
+            .fold(0u8, |acc, offset| acc.wrapping_add(bytes[offset]));
+        std::hint::black_box(touched);
+    }
 
-        Ok(Self { mmap, footer })
+    /// Runs the specified scan over the accounts with the sequential access
+    /// pattern.  The access pattern passed to advise() is restored once the
+    /// last of the concurrent scans finishes.
+    ///
+    /// As the access pattern is only a hint, failing to change it does not
+    /// fail the scan.
+    fn scan_sequentially<T>(
+        &self,
+        scan: impl FnOnce() -> TieredStorageResult<T>,
+    ) -> TieredStorageResult<T> {
+        let _scan = SequentialScan::enter(self);
+        scan()
     }
 
     /// Returns the size of the underlying storage.
@@ -384,6 +781,230 @@ impl HotStorageReader {
         self.footer.account_entry_count as usize
     }
 
+    /// Returns the number of bytes each account occupies in this file, in
+    /// index order.
+    ///
+    /// The size of an account consists of its meta, its account block (the
+    /// data, the padding, and the optional fields), and its index entry,
+    /// which is the same size as the StoredAccountInfo returned when the
+    /// account was written.  Only the index block is read, in one pass.
+    pub fn account_sizes(&self) -> TieredStorageResult<Vec<usize>> {
+        let index_entry_size = self
+            .footer
+            .index_block_format
+            .entry_size::<HotAccountOffset>();
+        (0..self.footer.account_entry_count)
+            .map(|i| {
+                let index_offset = IndexOffset(i);
+                let account_offset = self.get_account_offset(index_offset)?;
+                let block_size = self.get_account_block_size(account_offset, index_offset)?;
+                Ok(std::mem::size_of::<HotAccountMeta>() + block_size + index_entry_size)
+            })
+            .collect()
+    }
+
+    /// Returns the total number of bytes occupied by the accounts in this
+    /// file.  See account_sizes().
+    ///
+    /// The rest of the file consists of the padding after the index block,
+    /// the owners block, and the footer.
+    pub fn total_account_bytes(&self) -> TieredStorageResult<usize> {
+        Ok(self.account_sizes()?.into_iter().sum())
+    }
+
+    /// Returns the offset where the account blocks end.
+    ///
+    /// This is the single place that decides what follows the account
+    /// blocks, and every reader of the account blocks should go through it.
+    pub fn account_blocks_end(&self) -> usize {
+        // The end is capped by the length of the file, so it always fits.
+        to_usize(self.footer.account_blocks_end(self.mmap.len() as u64)).unwrap_or(self.mmap.len())
+    }
+
+    /// Returns the number of unique account owners.
+    pub fn owner_count(&self) -> usize {
+        self.footer.owner_count as usize
+    }
+
+    /// Returns the addresses of all the unique account owners, ordered by
+    /// their owner offsets.
+    pub fn owners(&self) -> TieredStorageResult<&[Pubkey]> {
+        self.footer
+            .owners_block_format
+            .get_owner_addresses(&self.mmap, &self.footer)
+    }
+
+    /// Returns the number of accounts owned by the specified owner, or None
+    /// if the owner is not in the owners block.
+    ///
+    /// The owners block is small, so it is scanned for the owner.  For a file
+    /// whose owners block does not persist the counts, the count is derived
+    /// from the account metas instead, which reads every account.
+    pub fn owner_account_count(&self, owner: &Pubkey) -> TieredStorageResult<Option<u32>> {
+        let Some(position) = self.owners()?.iter().position(|address| address == owner) else {
+            return Ok(None);
+        };
+
+        if let Some(counts) = self
+            .footer
+            .owners_block_format
+            .get_owner_account_counts(&self.mmap, &self.footer)?
+        {
+            return Ok(Some(counts[position]));
+        }
+
+        let owner_offset = OwnerOffset(position as u32);
+        let mut count = 0;
+        for i in 0..self.footer.account_entry_count {
+            let account_offset = self.get_account_offset(IndexOffset(i))?;
+            if self
+                .get_account_meta_from_offset(account_offset)?
+                .owner_offset()
+                == owner_offset
+            {
+                count += 1;
+            }
+        }
+        Ok(Some(count))
+    }
+
+    /// Returns whether the specified pubkey falls inside the address range
+    /// of the stored accounts.
+    ///
+    /// When it returns false, the pubkey is guaranteed not to be stored in
+    /// this file, so the index block does not need to be searched.
+    pub fn pubkey_in_range(&self, pubkey: &Pubkey) -> bool {
+        self.footer.account_address_range().contains(pubkey)
+    }
+
+    /// Verifies the hash of all the bytes before the footer against the
+    /// hash stored inside the footer, using the algorithm recorded in the
+    /// footer.
+    ///
+    /// VerificationSkipped is returned if the file does not persist a hash.
+    pub fn verify_file_hash(&self) -> TieredStorageResult<()> {
+        if self.footer.hash_kind == FooterHashKind::None {
+            return Err(TieredStorageError::VerificationSkipped);
+        }
+        let hashed_len = self
+            .mmap
+            .len()
+            .saturating_sub(self.footer.footer_size as usize);
+        let (bytes, _) = get_slice(&self.mmap, 0, hashed_len)?;
+        let mut hasher = FileHasher::new(self.footer.hash_kind);
+        hasher.feed(bytes);
+        let hash = hasher.finish();
+        if hash != self.footer.hash {
+            return Err(TieredStorageError::HashMismatch(hash, self.footer.hash));
+        }
+        Ok(())
+    }
+
+    /// Starts an incremental verification of the file hash by hashing up
+    /// to `budget_bytes` from the beginning of the file.
+    pub fn verify_prefix(&self, budget_bytes: u64) -> PrefixVerification {
+        self.resume_verification(PrefixVerification::new(self.footer.hash_kind), budget_bytes)
+    }
+
+    /// Continues the incremental verification of the file hash by hashing
+    /// up to `budget_bytes` right after the bytes covered by `state`.
+    pub fn resume_verification(
+        &self,
+        state: PrefixVerification,
+        budget_bytes: u64,
+    ) -> PrefixVerification {
+        let hashed_len = self
+            .mmap
+            .len()
+            .saturating_sub(self.footer.footer_size as usize);
+        state.advance(&self.mmap[..hashed_len], self.footer.hash, budget_bytes)
+    }
+
+    /// Checks the consistency of every account and of the file hash, and
+    /// returns what was found.
+    ///
+    /// Unlike the other read APIs, an inconsistent account does not stop
+    /// the check, so the report includes every inconsistency found.  The
+    /// accounts are visited one by one in index order.
+    pub fn verify(&self) -> TieredStorageResult<VerifyReport> {
+        let mut report = VerifyReport {
+            num_accounts: self.num_accounts(),
+            owner_count: self.owner_count(),
+            file_len: self.len(),
+            ..VerifyReport::default()
+        };
+        let index_entry_size = self
+            .footer
+            .index_block_format
+            .entry_size::<HotAccountOffset>();
+
+        self.scan_sequentially(|| {
+            for i in 0..self.footer.account_entry_count {
+                let index_offset = IndexOffset(i);
+                let account_offset_and_meta =
+                    self.get_account_offset(index_offset)
+                        .and_then(|account_offset| {
+                            self.get_account_meta_from_offset(account_offset)
+                                .map(|meta| (account_offset, meta))
+                        });
+                let Ok((account_offset, meta)) = account_offset_and_meta else {
+                    report
+                        .inconsistencies
+                        .push(Inconsistency::AccountOffsetOutOfRange(index_offset));
+                    continue;
+                };
+
+                if meta.owner_offset().0 >= self.footer.owner_count {
+                    report
+                        .inconsistencies
+                        .push(Inconsistency::OwnerOffsetOutOfRange(
+                            index_offset,
+                            meta.owner_offset(),
+                        ));
+                }
+
+                let Ok(account_block) = self.get_account_block(account_offset, index_offset) else {
+                    report
+                        .inconsistencies
+                        .push(Inconsistency::InvalidAccountBlock(index_offset));
+                    continue;
+                };
+                report.total_account_bytes +=
+                    std::mem::size_of::<HotAccountMeta>() + account_block.len() + index_entry_size;
+
+                if meta.account_data_size(account_block).is_err() {
+                    report
+                        .inconsistencies
+                        .push(Inconsistency::OptionalFieldsExceedAccountBlock(
+                            index_offset,
+                        ));
+                    continue;
+                }
+                if meta.verify_block_checksum(account_block) == Some(false) {
+                    report
+                        .inconsistencies
+                        .push(Inconsistency::BlockChecksumMismatch(index_offset));
+                }
+                if meta.flags().has_rent_epoch() {
+                    report.num_accounts_with_rent_epoch += 1;
+                }
+                if meta.flags().has_account_hash() {
+                    report.num_accounts_with_account_hash += 1;
+                }
+            }
+            Ok(())
+        })?;
+
+        match self.verify_file_hash() {
+            Ok(()) | Err(TieredStorageError::VerificationSkipped) => {}
+            Err(TieredStorageError::HashMismatch(hash, footer_hash)) => report
+                .inconsistencies
+                .push(Inconsistency::HashMismatch { hash, footer_hash }),
+            Err(err) => return Err(err),
+        }
+        Ok(report)
+    }
+
     /// Returns the account meta located at the specified offset.
     fn get_account_meta_from_offset(
         &self,
@@ -391,13 +1012,14 @@ impl HotStorageReader {
     ) -> TieredStorageResult<&HotAccountMeta> {
         let offset = account_offset.offset();
 
-        assert!(
-            offset.saturating_add(std::mem::size_of::<HotAccountMeta>())
-                <= self.footer.index_block_offset as usize,
-            "reading HotAccountOffset ({}) would exceed accounts blocks offset boundary ({}).",
-            offset,
-            self.footer.index_block_offset,
-        );
+        // A corrupted account offset must not be read as an account meta.
+        let meta_end = offset.saturating_add(std::mem::size_of::<HotAccountMeta>());
+        if meta_end > self.account_blocks_end() {
+            return Err(TieredStorageError::OffsetOutOfBounds(
+                meta_end,
+                self.account_blocks_end(),
+            ));
+        }
         let (meta, _) = get_pod::<HotAccountMeta>(&self.mmap, offset)?;
         Ok(meta)
     }
@@ -407,11 +1029,36 @@ impl HotStorageReader {
         &self,
         index_offset: IndexOffset,
     ) -> TieredStorageResult<HotAccountOffset> {
+        if let Some(account_offset) = self
+            .cached_account_offsets()
+            .and_then(|account_offsets| account_offsets.get(index_offset.0 as usize))
+        {
+            return Ok(*account_offset);
+        }
         self.footer
             .index_block_format
             .get_account_offset::<HotAccountOffset>(&self.mmap, &self.footer, index_offset)
     }
 
+    /// Returns the account offsets decoded from the index block, decoding
+    /// them on the first call, or None if the offsets are not cached.
+    fn cached_account_offsets(&self) -> Option<&[HotAccountOffset]> {
+        if !self.cache_account_offsets {
+            return None;
+        }
+        self.account_offsets
+            .get_or_init(|| {
+                #[cfg(test)]
+                self.account_offsets_builds.fetch_add(1, Ordering::Relaxed);
+                self.footer
+                    .index_block_format
+                    .get_account_offsets::<HotAccountOffset>(&self.mmap, &self.footer)
+                    .ok()
+                    .map(Box::from)
+            })
+            .as_deref()
+    }
+
     /// Returns the address of the account associated with the specified index.
     fn get_account_address(&self, index: IndexOffset) -> TieredStorageResult<&Pubkey> {
         self.footer
@@ -419,6 +1066,26 @@ impl HotStorageReader {
             .get_account_address(&self.mmap, &self.footer, index)
     }
 
+    /// Returns the addresses of all the accounts, ordered by their index.
+    pub fn account_addresses(&self) -> TieredStorageResult<&[Pubkey]> {
+        self.footer
+            .index_block_format
+            .get_account_addresses(&self.mmap, &self.footer)
+    }
+
+    /// Returns the index of the first account whose address matches the
+    /// specified address, or None if no account has the specified address.
+    pub fn get_account_index_by_address(
+        &self,
+        address: &Pubkey,
+    ) -> TieredStorageResult<Option<IndexOffset>> {
+        self.footer.index_block_format.get_account_index_by_address(
+            &self.mmap,
+            &self.footer,
+            address,
+        )
+    }
+
     /// Returns the address of the account owner given the specified
     /// owner_offset.
     fn get_owner_address(&self, owner_offset: OwnerOffset) -> TieredStorageResult<&Pubkey> {
@@ -445,7 +1112,7 @@ impl HotStorageReader {
             .get_account_meta_from_offset(account_offset)
             .map_err(|_| MatchAccountOwnerError::UnableToLoad)?;
 
-        if account_meta.lamports() == 0 {
+        if account_meta.is_zero_lamport() {
             Err(MatchAccountOwnerError::NoMatch)
         } else {
             let account_owner = self
@@ -459,26 +1126,102 @@ impl HotStorageReader {
         }
     }
 
+    /// Resolves, once, the positions of the owners of this file inside the
+    /// specified candidate owners, which account_matches_owners_cached()
+    /// answers from afterwards.
+    pub fn build_owner_match_cache(
+        &self,
+        owners: &[Pubkey],
+    ) -> TieredStorageResult<OwnerMatchCache> {
+        Ok(OwnerMatchCache::new(self.owners()?, owners))
+    }
+
+    /// Same as account_matches_owners(), but answers from the specified
+    /// cache built by build_owner_match_cache() of this reader, so the owner
+    /// address is neither read nor compared.
+    ///
+    /// Returns Err(MatchAccountOwnerError::UnableToLoad) if the owner offset
+    /// of the account is beyond the owners covered by the cache.
+    pub fn account_matches_owners_cached(
+        &self,
+        account_offset: HotAccountOffset,
+        cache: &OwnerMatchCache,
+    ) -> Result<usize, MatchAccountOwnerError> {
+        let account_meta = self
+            .get_account_meta_from_offset(account_offset)
+            .map_err(|_| MatchAccountOwnerError::UnableToLoad)?;
+
+        if account_meta.is_zero_lamport() {
+            return Err(MatchAccountOwnerError::NoMatch);
+        }
+        cache
+            .position(account_meta.owner_offset())
+            .ok_or(MatchAccountOwnerError::UnableToLoad)?
+            .ok_or(MatchAccountOwnerError::NoMatch)
+    }
+
     /// Returns the size of the account block based on its account offset
     /// and index offset.
     ///
-    /// The account block size information is omitted in the hot accounts file
+    /// Unless the index block stores the size of each account block, the
+    /// account block size information is omitted in the hot accounts file
     /// as it can be derived by comparing the offset of the next hot account
     /// meta in the index block.
     fn get_account_block_size(
         &self,
         account_offset: HotAccountOffset,
         index_offset: IndexOffset,
+    ) -> TieredStorageResult<usize> {
+        let block_size = self.get_derived_account_block_size(account_offset, index_offset)?;
+
+        // The footer records the largest account (meta + block) of the
+        // file, so a larger derived block can only come from a broken index.
+        // Files that predate the recorded size persist 0, which is not
+        // enforced.
+        let account_size = std::mem::size_of::<HotAccountMeta>().saturating_add(block_size);
+        if self.footer.account_block_size != 0
+            && account_size as u64 > self.footer.account_block_size
+        {
+            return Err(TieredStorageError::AccountBlockSizeExceeded(
+                account_size,
+                self.footer.account_block_size,
+            ));
+        }
+        Ok(block_size)
+    }
+
+    /// Returns the size of the account block of the specified account, as
+    /// derived from the index block and the account offsets.
+    fn get_derived_account_block_size(
+        &self,
+        account_offset: HotAccountOffset,
+        index_offset: IndexOffset,
     ) -> TieredStorageResult<usize> {
         // the offset that points to the hot account meta.
         let account_meta_offset = account_offset.offset();
+        let account_block_offset = account_meta_offset + std::mem::size_of::<HotAccountMeta>();
+
+        if let Some(block_size) = self
+            .footer
+            .index_block_format
+            .get_account_block_size::<HotAccountOffset>(&self.mmap, &self.footer, index_offset)?
+        {
+            let account_block_ending_offset = account_block_offset.saturating_add(block_size);
+            if account_block_ending_offset > self.account_blocks_end() {
+                return Err(TieredStorageError::InvalidAccountBlock(
+                    account_block_offset,
+                    account_block_ending_offset,
+                ));
+            }
+            return Ok(block_size);
+        }
 
         // Obtain the ending offset of the account block.  If the current
-        // account is the last account, then the ending offset is the
-        // index_block_offset.
+        // account is the last account, then the ending offset is the end
+        // of all the account blocks.
         let account_block_ending_offset =
             if index_offset.0.saturating_add(1) == self.footer.account_entry_count {
-                self.footer.index_block_offset as usize
+                self.account_blocks_end()
             } else {
                 self.get_account_offset(IndexOffset(index_offset.0.saturating_add(1)))?
                     .offset()
@@ -487,9 +1230,12 @@ impl HotStorageReader {
         // With the ending offset, minus the starting offset (i.e.,
         // the account meta offset) and the HotAccountMeta size, the reminder
         // is the account block size (account data + optional fields).
-        Ok(account_block_ending_offset
-            .saturating_sub(account_meta_offset)
-            .saturating_sub(std::mem::size_of::<HotAccountMeta>()))
+        account_block_ending_offset
+            .checked_sub(account_block_offset)
+            .ok_or(TieredStorageError::InvalidAccountBlock(
+                account_block_offset,
+                account_block_ending_offset,
+            ))
     }
 
     /// Returns the account block that contains the account associated with
@@ -517,103 +1263,697 @@ impl HotStorageReader {
             return Ok(None);
         }
 
-        let account_offset = self.get_account_offset(index_offset)?;
-
-        let meta = self.get_account_meta_from_offset(account_offset)?;
         let address = self.get_account_address(index_offset)?;
-        let owner = self.get_owner_address(meta.owner_offset())?;
-        let account_block = self.get_account_block(account_offset, index_offset)?;
+        self.get_account_with_address(index_offset, address)
+            .map(|account| Some((account, IndexOffset(index_offset.0.saturating_add(1)))))
+    }
 
-        Ok(Some((
-            StoredAccountMeta::Hot(HotAccount {
-                meta,
-                address,
-                owner,
-                index: index_offset,
-                account_block,
-            }),
-            IndexOffset(index_offset.0.saturating_add(1)),
-        )))
+    /// Returns the lamports of the account located at the specified index
+    /// offset, or None if the index offset is out of range.
+    ///
+    /// Only the index entry and the account meta are read, which makes it
+    /// cheaper than get_account() for the queries that only need the balance.
+    pub fn get_lamports(&self, index_offset: IndexOffset) -> TieredStorageResult<Option<u64>> {
+        if index_offset.0 >= self.footer.account_entry_count {
+            return Ok(None);
+        }
+
+        let account_offset = self.get_account_offset(index_offset)?;
+        let meta = self.get_account_meta_from_offset(account_offset)?;
+        Ok(Some(meta.lamports()))
     }
 
-    /// Return a vector of account metadata for each account, starting from
-    /// `index_offset`
-    pub fn accounts(
+    /// Returns the accounts located at the specified index offsets, in the
+    /// order of the specified index offsets.
+    ///
+    /// The accounts are read in the order of their index offsets, so that
+    /// the index block and the account blocks are visited in one forward
+    /// pass.  An index offset that is out of range maps to None instead of
+    /// failing the whole batch.
+    pub fn get_accounts(
         &self,
-        mut index_offset: IndexOffset,
-    ) -> TieredStorageResult<Vec<StoredAccountMeta>> {
-        let mut accounts = Vec::with_capacity(
-            self.footer
-                .account_entry_count
-                .saturating_sub(index_offset.0) as usize,
-        );
-        while let Some((account, next)) = self.get_account(index_offset)? {
-            accounts.push(account);
-            index_offset = next;
+        index_offsets: &[IndexOffset],
+    ) -> TieredStorageResult<Vec<Option<StoredAccountMeta<'_>>>> {
+        let addresses = self.account_addresses()?;
+        let mut read_order: Vec<_> = (0..index_offsets.len()).collect();
+        read_order.sort_unstable_by_key(|&i| index_offsets[i].0);
+
+        let mut accounts: Vec<_> = std::iter::repeat_with(|| None)
+            .take(index_offsets.len())
+            .collect();
+        for i in read_order {
+            let index_offset = index_offsets[i];
+            let Some(address) = addresses.get(index_offset.0 as usize) else {
+                // the rest of the index offsets are out of range as well
+                break;
+            };
+            accounts[i] = Some(self.get_account_with_address(index_offset, address)?);
         }
         Ok(accounts)
     }
-}
 
-fn write_optional_fields(
-    file: &mut TieredWritableFile,
-    opt_fields: &AccountMetaOptionalFields,
-) -> TieredStorageResult<usize> {
-    let mut size = 0;
-    if let Some(rent_epoch) = opt_fields.rent_epoch {
-        size += file.write_pod(&rent_epoch)?;
+    /// Returns the account located at the specified index offset, whose
+    /// address has already been read from the index block.
+    fn get_account_with_address<'a>(
+        &'a self,
+        index_offset: IndexOffset,
+        address: &'a Pubkey,
+    ) -> TieredStorageResult<StoredAccountMeta<'a>> {
+        let (meta, account_block) = self.get_raw_account(index_offset)?;
+        self.to_stored_account_meta(index_offset, address, meta, account_block)
     }
 
-    debug_assert_eq!(size, opt_fields.size());
-
-    Ok(size)
-}
+    /// Returns the account meta and the account block of the account located
+    /// at the specified index offset.
+    pub(super) fn get_raw_account(
+        &self,
+        index_offset: IndexOffset,
+    ) -> TieredStorageResult<(&HotAccountMeta, &[u8])> {
+        let account_offset = self.get_account_offset(index_offset)?;
 
-/// The writer that creates a hot accounts file.
-#[derive(Debug)]
-pub struct HotStorageWriter {
-    storage: TieredWritableFile,
-}
+        let meta = self.get_account_meta_from_offset(account_offset)?;
+        let account_block = self.get_account_block(account_offset, index_offset)?;
+        // Validates the padding so that the account data is always available.
+        meta.account_data_size(account_block)?;
 
-impl HotStorageWriter {
-    /// Create a new HotStorageWriter with the specified path.
-    pub fn new(file_path: impl AsRef<Path>) -> TieredStorageResult<Self> {
-        Ok(Self {
-            storage: TieredWritableFile::new(file_path)?,
-        })
+        Ok((meta, account_block))
     }
 
-    /// Persists an account with the specified information and returns
-    /// the stored size of the account.
-    fn write_account(
-        &mut self,
-        lamports: u64,
-        owner_offset: OwnerOffset,
-        account_data: &[u8],
-        executable: bool,
-        rent_epoch: Option<Epoch>,
-    ) -> TieredStorageResult<usize> {
-        let optional_fields = AccountMetaOptionalFields { rent_epoch };
+    /// Assembles the public StoredAccountMeta from the raw parts of an
+    /// account obtained via get_raw_account() or scan_accounts_raw().
+    fn to_stored_account_meta<'a>(
+        &'a self,
+        index_offset: IndexOffset,
+        address: &'a Pubkey,
+        meta: &'a HotAccountMeta,
+        account_block: &'a [u8],
+    ) -> TieredStorageResult<StoredAccountMeta<'a>> {
+        let owner = self.get_owner_address(meta.owner_offset())?;
 
-        let mut flags = AccountMetaFlags::new_from(&optional_fields);
-        flags.set_executable(executable);
+        Ok(StoredAccountMeta::Hot(HotAccount {
+            meta,
+            address,
+            owner,
+            index: index_offset,
+            account_block,
+        }))
+    }
 
-        let padding_len = padding_bytes(account_data.len());
-        let meta = HotAccountMeta::new()
-            .with_lamports(lamports)
-            .with_owner_offset(owner_offset)
+    /// Visits the raw parts of every account starting from the specified
+    /// index offset in index order, without assembling a StoredAccountMeta
+    /// for each of them.
+    ///
+    /// This is for internal consumers that scan a large number of accounts
+    /// and only need some of their fields.  The scan stops at the first
+    /// error returned by either the reader or the visitor.
+    pub fn scan_accounts_raw<'a>(
+        &'a self,
+        index_offset: IndexOffset,
+        mut visitor: impl FnMut(
+            IndexOffset,
+            &'a Pubkey,
+            &'a HotAccountMeta,
+            &'a [u8],
+        ) -> TieredStorageResult<()>,
+    ) -> TieredStorageResult<()> {
+        // Read all the addresses at once instead of one by one.
+        let addresses = self.account_addresses()?;
+        for (i, address) in addresses.iter().enumerate().skip(index_offset.0 as usize) {
+            let index_offset = IndexOffset(i as u32);
+            let (meta, account_block) = self.get_raw_account(index_offset)?;
+            visitor(index_offset, address, meta, account_block)?;
+        }
+        Ok(())
+    }
+
+    /// Visits the address, the data and the lamports of every account in
+    /// index order, where the address and the data are borrowed from the
+    /// underlying file.
+    ///
+    /// This is for full-file scans such as the accounts hash verification.
+    /// Neither the owner nor the optional fields are resolved, and the data
+    /// never includes the padding or the optional fields that follow it.
+    /// The scan stops at the first account that cannot be read.
+    pub fn scan_account_data<'a>(
+        &'a self,
+        mut callback: impl FnMut(&'a Pubkey, &'a [u8], u64),
+    ) -> TieredStorageResult<()> {
+        self.scan_sequentially(|| {
+            self.scan_accounts_raw(IndexOffset(0), |_, address, meta, account_block| {
+                callback(address, meta.account_data(account_block)?, meta.lamports());
+                Ok(())
+            })
+        })
+    }
+
+    /// Visits the fields that the accounts hash calculation needs of every
+    /// account in index order, in one sequential pass.
+    ///
+    /// Unlike accounts(), no StoredAccountMeta is assembled, and neither the
+    /// account hash nor the write version is read.  The fields match those
+    /// of the same account loaded via get_account(), including the rent
+    /// epoch of a zero-lamport account.  The scan stops at the first account
+    /// that cannot be read.
+    pub fn scan_for_hash<'a>(
+        &'a self,
+        mut callback: impl FnMut(HotAccountForHash<'a>),
+    ) -> TieredStorageResult<()> {
+        let owners = self.owners()?;
+        self.scan_sequentially(|| {
+            self.scan_accounts_raw(IndexOffset(0), |index, address, meta, account_block| {
+                let owner_offset = meta.owner_offset();
+                let Some(owner) = owners.get(owner_offset.0 as usize) else {
+                    return Err(TieredStorageError::OwnerOffsetOutOfRange(
+                        owner_offset.0,
+                        self.footer.owner_count,
+                    ));
+                };
+                let account = HotAccount {
+                    meta,
+                    address,
+                    owner,
+                    index,
+                    account_block,
+                };
+                callback(HotAccountForHash {
+                    pubkey: address,
+                    lamports: account.lamports(),
+                    owner,
+                    executable: account.executable(),
+                    rent_epoch: account.rent_epoch(),
+                    data: account.data(),
+                });
+                Ok(())
+            })
+        })
+    }
+
+    /// Visits the address, the owner and the lamports of every account in
+    /// index order, along with its index offset.
+    ///
+    /// This is for scans that do not need the account data, such as clean
+    /// and rent collection.  Only the index block, the account metas and the
+    /// owners block are read: the account block after each meta is never
+    /// located, so the pages of the account data are not touched.  The scan
+    /// stops at the first account that cannot be read.
+    pub fn scan_metas<'a>(
+        &'a self,
+        mut callback: impl FnMut(&'a Pubkey, &'a Pubkey, u64, IndexOffset),
+    ) -> TieredStorageResult<()> {
+        let addresses = self.account_addresses()?;
+        let owners = self.owners()?;
+        for (i, address) in addresses.iter().enumerate() {
+            let index_offset = IndexOffset(i as u32);
+            let meta = self.get_account_meta_from_offset(self.get_account_offset(index_offset)?)?;
+            let owner_offset = meta.owner_offset();
+            let Some(owner) = owners.get(owner_offset.0 as usize) else {
+                return Err(TieredStorageError::OwnerOffsetOutOfRange(
+                    owner_offset.0,
+                    self.footer.owner_count,
+                ));
+            };
+            callback(address, owner, meta.lamports(), index_offset);
+        }
+        Ok(())
+    }
+
+    /// Returns the address, the lamports and the data length of every
+    /// account in index order, along with its index offset.
+    ///
+    /// This is for building the accounts index at startup.  Only the index
+    /// block and the account metas are read: the data length is derived
+    /// from the size of each account block, which comes from the account
+    /// offsets (or the persisted sizes), so the pages of the account data
+    /// are not touched.
+    pub fn index_entries(&self) -> TieredStorageResult<Vec<StoredAccountIndexEntry>> {
+        self.account_addresses()?
+            .iter()
+            .enumerate()
+            .map(|(i, address)| {
+                let index_offset = IndexOffset(i as u32);
+                let account_offset = self.get_account_offset(index_offset)?;
+                let meta = self.get_account_meta_from_offset(account_offset)?;
+                let block_size = self.get_account_block_size(account_offset, index_offset)?;
+                Ok(StoredAccountIndexEntry {
+                    pubkey: *address,
+                    index_offset,
+                    lamports: meta.lamports(),
+                    data_len: meta.account_data_size_from_block_size(block_size)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the first account whose address matches the specified address,
+    /// along with its index offset.
+    ///
+    /// Returns None if no account in this file has the specified address.
+    pub fn find_account(
+        &self,
+        address: &Pubkey,
+    ) -> TieredStorageResult<Option<(StoredAccountMeta<'_>, IndexOffset)>> {
+        if !self.pubkey_in_range(address) {
+            return Ok(None);
+        }
+
+        let Some(index_offset) = self.get_account_index_by_address(address)? else {
+            return Ok(None);
+        };
+        let address = self.get_account_address(index_offset)?;
+        let account = self.get_account_with_address(index_offset, address)?;
+        Ok(Some((account, index_offset)))
+    }
+
+    /// Returns whether each of the specified pubkeys is the address of an
+    /// account in this file, in the order of the specified pubkeys.
+    ///
+    /// No account is loaded.  With an unsorted index block, the addresses
+    /// are scanned once regardless of the number of pubkeys, while with a
+    /// sorted index block, each pubkey is binary searched.
+    pub fn contains_any(&self, pubkeys: &[Pubkey]) -> TieredStorageResult<Vec<bool>> {
+        match self.footer.index_block_format {
+            IndexBlockFormat::AddressesThenOffsets
+            | IndexBlockFormat::AddressesThenOffsetsThenSizes => {
+                let mut found: HashMap<&Pubkey, bool> = pubkeys
+                    .iter()
+                    .filter(|pubkey| self.pubkey_in_range(pubkey))
+                    .map(|pubkey| (pubkey, false))
+                    .collect();
+                let mut num_unfound = found.len();
+                if num_unfound > 0 {
+                    for address in self.account_addresses()? {
+                        if let Some(is_found @ false) = found.get_mut(address) {
+                            *is_found = true;
+                            num_unfound -= 1;
+                            if num_unfound == 0 {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Ok(pubkeys
+                    .iter()
+                    .map(|pubkey| found.get(pubkey).copied().unwrap_or(false))
+                    .collect())
+            }
+            IndexBlockFormat::SortedAddressesThenOffsets => pubkeys
+                .iter()
+                .map(|pubkey| {
+                    Ok(self.pubkey_in_range(pubkey)
+                        && self.get_account_index_by_address(pubkey)?.is_some())
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if the specified pubkey is the address of an account in
+    /// this file.
+    ///
+    /// Returns false if the index block cannot be read.
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.contains_any(std::slice::from_ref(pubkey))
+            .is_ok_and(|found| found[0])
+    }
+
+    /// Return a vector of account metadata for each account, starting from
+    /// `index_offset`
+    pub fn accounts(
+        &self,
+        index_offset: IndexOffset,
+    ) -> TieredStorageResult<Vec<StoredAccountMeta>> {
+        let mut accounts = vec![];
+        self.scan_sequentially(|| {
+            self.scan_accounts_raw(
+                index_offset,
+                |index_offset, address, meta, account_block| {
+                    accounts.push(self.to_stored_account_meta(
+                        index_offset,
+                        address,
+                        meta,
+                        account_block,
+                    )?);
+                    Ok(())
+                },
+            )
+        })?;
+        Ok(accounts)
+    }
+}
+
+/// The writer that creates a hot accounts file.
+#[derive(Debug)]
+pub struct HotStorageWriter {
+    storage: TieredWritableFile,
+    index_block_format: IndexBlockFormat,
+    owners_block_format: OwnersBlockFormat,
+    max_format_generation: FormatGeneration,
+    footer_format_version: u64,
+    trace: Option<WriteTrace>,
+    stats: TieredStorageWriterStats,
+    max_file_size: u64,
+    max_owner_count: usize,
+    /// The maximum number of accounts written by write_accounts(), or None
+    /// if the number of accounts is only limited by the file size.
+    max_accounts_per_file: Option<u32>,
+    /// Whether each account block ends with a checksum of its content.
+    block_checksums: bool,
+    /// The compression level of the account blocks, or None for the default
+    /// level of their format.
+    compression_level: Option<i32>,
+    /// Whether the non-default account hashes are persisted, which the
+    /// format generation may not allow.
+    account_hashes: bool,
+    /// The owners that precede the owners of the accounts in the owners
+    /// block, which is replaced by the owners of the file once the accounts
+    /// are written.
+    owners_table: OwnersTable,
+    /// The size of the largest account data block buffered in memory.
+    #[cfg(test)]
+    peak_buffered_bytes: usize,
+}
+
+impl HotStorageWriter {
+    /// Create a new HotStorageWriter with the specified path, which writes
+    /// the structures of the default, i.e. the oldest, format generation.
+    pub fn new(file_path: impl AsRef<Path>) -> TieredStorageResult<Self> {
+        Self::new_with_index_block_format(file_path, HOT_FORMAT.index_block_format)
+    }
+
+    /// Create a new HotStorageWriter with the specified path that persists
+    /// its index block in the specified format.
+    pub fn new_with_index_block_format(
+        file_path: impl AsRef<Path>,
+        index_block_format: IndexBlockFormat,
+    ) -> TieredStorageResult<Self> {
+        Self::new_with_formats(file_path, index_block_format, HOT_FORMAT.hash_kind)
+    }
+
+    /// Create a new HotStorageWriter with the specified path that persists
+    /// its index block in the specified format, and the hash of the file
+    /// computed by the specified algorithm.
+    ///
+    /// Like TieredStorage, the writer is gated by the default WriteOptions,
+    /// so a format newer than the oldest generation is either downgraded or
+    /// rejected.  Use new_with_options() to allow the newer structures.
+    pub fn new_with_formats(
+        file_path: impl AsRef<Path>,
+        index_block_format: IndexBlockFormat,
+        hash_kind: FooterHashKind,
+    ) -> TieredStorageResult<Self> {
+        Self::new_with_options(
+            file_path,
+            index_block_format,
+            hash_kind,
+            &WriteOptions::default(),
+        )
+    }
+
+    /// Create a new HotStorageWriter with the specified path and formats,
+    /// which only emits the structures allowed by the format generation of
+    /// the specified options.
+    ///
+    /// A format that requires a newer generation is either downgraded or
+    /// rejected with FormatGenerationExceeded, per the policy of the
+    /// feature it requires.
+    pub fn new_with_options(
+        file_path: impl AsRef<Path>,
+        index_block_format: IndexBlockFormat,
+        hash_kind: FooterHashKind,
+        options: &WriteOptions,
+    ) -> TieredStorageResult<Self> {
+        let generation = options.max_format_generation;
+        let gate = |feature: FormatFeature| match (generation.supports(feature), feature.policy()) {
+            (true, _) => Ok(true),
+            (false, GatePolicy::Downgrade) => Ok(false),
+            (false, GatePolicy::Error) => Err(TieredStorageError::FormatGenerationExceeded(
+                feature, generation,
+            )),
+        };
+
+        if index_block_format == IndexBlockFormat::SortedAddressesThenOffsets {
+            gate(FormatFeature::SortedIndex)?;
+        }
+        // the accounts are written contiguously, so the block sizes can be
+        // derived from the block offsets without losing anything
+        let index_block_format = if index_block_format
+            == IndexBlockFormat::AddressesThenOffsetsThenSizes
+            && !gate(FormatFeature::SizedIndex)?
+        {
+            IndexBlockFormat::AddressesThenOffsets
+        } else {
+            index_block_format
+        };
+        // the hash of a version 1 footer is never trusted by the reader, so
+        // it is not computed
+        let (footer_format_version, hash_kind) = if gate(FormatFeature::FooterV2)? {
+            (FOOTER_FORMAT_VERSION, hash_kind)
+        } else {
+            (1, FooterHashKind::None)
+        };
+        let account_hashes = gate(FormatFeature::AccountHash)?;
+
+        let storage =
+            TieredWritableFile::new_with_hash_kind(&file_path, hash_kind).map_err(|err| {
+                if err.kind() == io::ErrorKind::AlreadyExists {
+                    TieredStorageError::FileAlreadyExists(file_path.as_ref().to_path_buf())
+                } else {
+                    err.into()
+                }
+            })?;
+
+        Ok(Self {
+            storage,
+            index_block_format,
+            owners_block_format: HOT_FORMAT.owners_block_format,
+            max_format_generation: generation,
+            footer_format_version,
+            trace: None,
+            stats: TieredStorageWriterStats::default(),
+            max_file_size: MAX_HOT_FILE_SIZE,
+            max_owner_count: HotAccountMeta::MAX_OWNER_OFFSET.0 as usize + 1,
+            max_accounts_per_file: None,
+            block_checksums: false,
+            compression_level: None,
+            account_hashes,
+            owners_table: OwnersTable::default(),
+            #[cfg(test)]
+            peak_buffered_bytes: 0,
+        })
+    }
+
+    /// Persists the owners block in the specified format.
+    ///
+    /// The counts of OwnersBlockFormat::LocalIndexWithCounts are dropped if
+    /// the format generation of the writer does not support them.
+    pub fn with_owners_block_format(mut self, owners_block_format: OwnersBlockFormat) -> Self {
+        self.owners_block_format = if owners_block_format == OwnersBlockFormat::LocalIndexWithCounts
+            && !self
+                .max_format_generation
+                .supports(FormatFeature::OwnerCounts)
+        {
+            OwnersBlockFormat::AddressesOnly
+        } else {
+            owners_block_format
+        };
+        self
+    }
+
+    /// Ends each account block with a checksum of the account data, its
+    /// padding and its optional fields, which allows a corrupted account to
+    /// be detected without hashing the whole file.  Disabled by default.
+    ///
+    /// The checksums are dropped if the format generation of the writer
+    /// does not support them.
+    pub fn with_block_checksums(mut self, block_checksums: bool) -> Self {
+        self.block_checksums = block_checksums
+            && self
+                .max_format_generation
+                .supports(FormatFeature::BlockChecksum);
+        self
+    }
+
+    /// Encodes the account blocks with the specified compression level, or
+    /// the default level of their format if None.  The level is ignored
+    /// while the account blocks are AccountBlockFormat::AlignedRaw.
+    pub fn with_compression_level(mut self, compression_level: Option<i32>) -> Self {
+        self.compression_level = compression_level;
+        self
+    }
+
+    /// Caps the number of accounts written by write_accounts(), which
+    /// keeps the file a manageable unit for shrink.  None, the default,
+    /// leaves the number of accounts only limited by the file size.
+    pub fn with_max_accounts_per_file(mut self, max_accounts_per_file: Option<u32>) -> Self {
+        self.max_accounts_per_file = max_accounts_per_file;
+        self
+    }
+
+    /// Seeds the owners block with the owners of the specified table, in
+    /// their order, before the owners of the accounts.  This allows several
+    /// related files to share the offsets of their common owners, e.g. by
+    /// seeding each writer with owners_table() of the previous one.
+    ///
+    /// Only the owners are taken from the table: the accounts of each owner
+    /// are counted from zero.
+    pub fn with_owners_table(mut self, mut owners_table: OwnersTable) -> Self {
+        owners_table.clear_account_counts();
+        self.owners_table = owners_table;
+        self
+    }
+
+    /// Returns the owners of the file in the order of its owners block once
+    /// the accounts are written, or the seeded owners before that.
+    pub fn owners_table(&self) -> &OwnersTable {
+        &self.owners_table
+    }
+
+    /// Lowers the maximum size of the file, which allows testing the size
+    /// limit without actually writing a multi-GiB file.
+    #[cfg(test)]
+    fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        assert!(max_file_size <= MAX_HOT_FILE_SIZE);
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Lowers the maximum number of distinct owners, which allows testing
+    /// the owner limit without actually writing millions of owners.
+    #[cfg(test)]
+    fn with_max_owner_count(mut self, max_owner_count: usize) -> Self {
+        assert!(max_owner_count <= HotAccountMeta::MAX_OWNER_OFFSET.0 as usize + 1);
+        self.max_owner_count = max_owner_count;
+        self
+    }
+
+    /// Returns the size of the largest account data block the writer has
+    /// buffered in memory so far.
+    #[cfg(test)]
+    fn peak_buffered_bytes(&self) -> usize {
+        self.peak_buffered_bytes
+    }
+
+    /// Returns the offset right after `len` more bytes are written at
+    /// `cursor`, or FileSizeLimitExceeded if it is beyond the maximum size
+    /// of the file.
+    fn check_file_size(&self, cursor: usize, len: usize) -> TieredStorageResult<usize> {
+        let end = (cursor as u64).saturating_add(len as u64);
+        if end > self.max_file_size {
+            return Err(TieredStorageError::FileSizeLimitExceeded(
+                end,
+                self.max_file_size,
+            ));
+        }
+        Ok(end as usize)
+    }
+
+    /// Records where each persisted account is written, keeping the most
+    /// recent `capacity` events.  The events can be obtained from
+    /// take_trace() once the accounts are written.
+    pub fn enable_trace(&mut self, capacity: usize) {
+        self.trace = Some(WriteTrace::with_capacity(capacity));
+    }
+
+    /// Returns the trace of the persisted accounts, if enabled, and disables
+    /// further tracing.
+    pub fn take_trace(&mut self) -> Option<WriteTrace> {
+        self.trace.take()
+    }
+
+    /// Returns the statistics collected by the last write_accounts() call.
+    pub fn stats(&self) -> &TieredStorageWriterStats {
+        &self.stats
+    }
+
+    /// Flushes the buffered bytes of the file and syncs it to the disk.
+    ///
+    /// This must be called after write_accounts() returns, otherwise any
+    /// error in flushing the buffer is ignored when the writer is dropped.
+    pub fn finish(self) -> TieredStorageResult<()> {
+        Ok(self.storage.finish()?)
+    }
+
+    /// Returns a ByteBlockWriter for the parts of an account block.
+    fn new_account_block_writer(&self) -> ByteBlockWriter {
+        ByteBlockWriter::new_with_compression_level(
+            HOT_FORMAT.account_block_format,
+            self.compression_level,
+        )
+    }
+
+    /// Persists an account with the specified information and returns
+    /// the stored size of the account.
+    fn write_account(
+        &mut self,
+        lamports: u64,
+        owner_offset: OwnerOffset,
+        account_data: &[u8],
+        executable: bool,
+        rent_epoch: Option<Epoch>,
+        account_hash: Option<AccountHash>,
+    ) -> TieredStorageResult<usize> {
+        let optional_fields = AccountMetaOptionalFields {
+            rent_epoch,
+            account_hash,
+        };
+
+        let mut flags = AccountMetaFlags::new_from(&optional_fields);
+        flags.set_executable(executable);
+        flags.set_has_block_checksum(self.block_checksums);
+
+        // The padding recorded in the meta is the padding actually written.
+        // Large account data is written to the file as is, so that the
+        // writer does not hold a second copy of it in memory.
+        let (data_block, padding) = if account_data.len() > HOT_BUFFERED_DATA_THRESHOLD {
+            (
+                None,
+                padded_data_layout(account_data.len(), HOT_ACCOUNT_ALIGNMENT).padding,
+            )
+        } else {
+            let mut data_block = self.new_account_block_writer();
+            let padding = data_block.write_aligned(account_data, HOT_ACCOUNT_ALIGNMENT)?;
+            let data_block = data_block.finish()?;
+            #[cfg(test)]
+            {
+                self.peak_buffered_bytes = self.peak_buffered_bytes.max(data_block.len());
+            }
+            (Some(data_block), padding as u8)
+        };
+        let meta = HotAccountMeta::new()
+            .with_lamports(lamports)
+            .with_owner_offset(owner_offset)?
             .with_account_data_size(account_data.len() as u64)
-            .with_account_data_padding(padding_len)
+            .with_account_data_padding(padding)?
             .with_flags(&flags);
 
+        let mut optional_fields_block = self.new_account_block_writer();
+        optional_fields_block.write_optional_fields(&optional_fields)?;
+        let optional_fields_block = optional_fields_block.finish()?;
+
+        let padding_bytes = &[0u8; HOT_ACCOUNT_ALIGNMENT][..padding as usize];
+        let account_block_parts: [&[u8]; 3] = match &data_block {
+            Some(data_block) => [data_block.as_slice(), &[], optional_fields_block.as_slice()],
+            None => [
+                account_data,
+                padding_bytes,
+                optional_fields_block.as_slice(),
+            ],
+        };
+
         let mut stored_size = 0;
 
         stored_size += self.storage.write_pod(&meta)?;
-        stored_size += self.storage.write_bytes(account_data)?;
-        stored_size += self
-            .storage
-            .write_bytes(&PADDING_BUFFER[0..(padding_len as usize)])?;
-        stored_size += write_optional_fields(&mut self.storage, &optional_fields)?;
+        let mut checksum_hasher = self.block_checksums.then(blake3::Hasher::new);
+        for part in account_block_parts {
+            stored_size += self.storage.write_bytes(part)?;
+            if let Some(checksum_hasher) = checksum_hasher.as_mut() {
+                checksum_hasher.update(part);
+            }
+        }
+        if let Some(checksum_hasher) = checksum_hasher {
+            stored_size += self
+                .storage
+                .write_pod(&block_checksum_from_hasher(&checksum_hasher))?;
+        }
 
         Ok(stored_size)
     }
@@ -621,6 +1961,15 @@ impl HotStorageWriter {
     /// Persists `accounts` into the underlying hot accounts file associated
     /// with this HotStorageWriter.  The first `skip` number of accounts are
     /// *not* persisted.
+    ///
+    /// If the remaining accounts exceed the maximum number of accounts per
+    /// file, only the ones in front are persisted.  Like AppendVec, the
+    /// length of the returned vector tells the caller how far it got, and
+    /// the caller retries the rest with a larger `skip` in another file.
+    ///
+    /// If the index block format is sorted, the accounts are persisted in the
+    /// order of their addresses, and the returned StoredAccountInfo of each
+    /// account remains at the position of its input account.
     pub fn write_accounts<
         'a,
         'b,
@@ -632,22 +1981,51 @@ impl HotStorageWriter {
         accounts: &StorableAccountsWithHashesAndWriteVersions<'a, 'b, T, U, V>,
         skip: usize,
     ) -> TieredStorageResult<Vec<StoredAccountInfo>> {
-        let mut footer = new_hot_footer();
+        let mut footer = TieredStorageFooter {
+            index_block_format: self.index_block_format,
+            owners_block_format: self.owners_block_format,
+            ..new_hot_footer()
+        };
         let mut index = vec![];
-        let mut owners_table = OwnersTable::default();
+        let mut owners_table = self.owners_table.clone();
         let mut cursor = 0;
-        let mut address_range = AccountAddressRange::default();
+        let mut address_range = PubkeyRange::default();
+        let mut stats = TieredStorageWriterStats::default();
 
         // writing accounts blocks
-        let len = accounts.accounts.len();
-        let total_input_accounts = len - skip;
-        let mut stored_infos = Vec::with_capacity(total_input_accounts);
-        for i in skip..len {
-            let (account, address, _account_hash, _write_version) = accounts.get(i);
-            let index_entry = AccountIndexWriterEntry {
-                address,
-                offset: HotAccountOffset::new(cursor)?,
-            };
+        let start = Instant::now();
+        let len = self
+            .max_accounts_per_file
+            .map_or(accounts.accounts.len(), |max| {
+                accounts
+                    .accounts
+                    .len()
+                    .min(skip.saturating_add(max as usize))
+            });
+        let total_input_accounts = len.saturating_sub(skip);
+        let mut input_order: Vec<_> = (skip..len).collect();
+        if footer.index_block_format.is_sorted() {
+            // The sort is stable, so accounts with the same address keep
+            // their input order.
+            input_order.sort_by(|a, b| accounts.get(*a).1.cmp(accounts.get(*b).1));
+        }
+
+        // Every owner offset must be representable in the account meta, so
+        // the owners are collected before any account is written.  This
+        // allows the caller to split the accounts into multiple files.  The
+        // owners are inserted in the same order as below, so their offsets
+        // are unchanged.  This is also where the accounts of each owner are
+        // counted.
+        for &i in &input_order {
+            let (account, ..) = accounts.get(i);
+            owners_table.insert_account_owner(account.map_or(&OWNER_NO_OWNER, |acc| acc.owner()));
+        }
+        owners_table.check_owner_count(self.max_owner_count)?;
+
+        let mut stored_infos = vec![StoredAccountInfo { offset: 0, size: 0 }; total_input_accounts];
+        for i in input_order {
+            let (account, address, account_hash, _write_version) = accounts.get(i);
+            let offset = HotAccountOffset::new(cursor)?;
             address_range.update(address);
 
             // Obtain necessary fields from the account, or default fields
@@ -660,16 +2038,61 @@ impl HotStorageWriter {
                         acc.data(),
                         acc.executable(),
                         // only persist rent_epoch for those rent-paying accounts
-                        (acc.rent_epoch() != RENT_EXEMPT_RENT_EPOCH).then_some(acc.rent_epoch()),
+                        AccountMetaOptionalFields::rent_epoch_to_persist(acc.rent_epoch()),
                     )
                 })
                 .unwrap_or((0, &OWNER_NO_OWNER, &[], false, None));
+            // only persist the account hash when it has been calculated
+            let account_hash = (self.account_hashes && *account_hash != *DEFAULT_ACCOUNT_HASH)
+                .then_some(*account_hash);
+            let data_layout = padded_data_layout(data.len(), HOT_ACCOUNT_ALIGNMENT);
+            let optional_fields_size = AccountMetaOptionalFields {
+                rent_epoch,
+                account_hash,
+            }
+            .size()
+                + if self.block_checksums {
+                    std::mem::size_of::<BlockChecksum>()
+                } else {
+                    0
+                };
+            let block_len = std::mem::size_of::<HotAccountMeta>()
+                + data_layout.padded_len
+                + optional_fields_size;
+            self.check_file_size(cursor, block_len)?;
             let owner_offset = owners_table.insert(owner);
-            let stored_size =
-                self.write_account(lamports, owner_offset, data, executable, rent_epoch)?;
+            let stored_size = self.write_account(
+                lamports,
+                owner_offset,
+                data,
+                executable,
+                rent_epoch,
+                account_hash,
+            )?;
+            debug_assert_eq!(stored_size, block_len);
+            footer.account_block_size = footer.account_block_size.max(stored_size as u64);
+            let index_entry = AccountIndexWriterEntry {
+                address,
+                offset,
+                block_size: (stored_size - std::mem::size_of::<HotAccountMeta>()) as u32,
+            };
+            if let Some(trace) = self.trace.as_mut() {
+                trace.record(WriteTraceEvent {
+                    index: i,
+                    address: *address,
+                    cursor_before: cursor,
+                    cursor_after: cursor + stored_size,
+                    block_len: stored_size,
+                    padding: data_layout.padding,
+                });
+            }
             cursor += stored_size;
+            stats.account_meta_bytes += std::mem::size_of::<HotAccountMeta>();
+            stats.account_data_bytes += data.len();
+            stats.padding_bytes += data_layout.padding as usize;
+            stats.optional_fields_bytes += optional_fields_size;
 
-            stored_infos.push(StoredAccountInfo {
+            stored_infos[i - skip] = StoredAccountInfo {
                 // Here we pass the IndexOffset as the get_account() API
                 // takes IndexOffset.  Given the account address is also
                 // maintained outside the TieredStorage, a potential optimization
@@ -682,14 +2105,33 @@ impl HotStorageWriter {
                 // Storage size from those shared blocks like footer and owners block
                 // is not included.
                 size: stored_size + footer.index_block_format.entry_size::<HotAccountOffset>(),
-            });
+            };
             index.push(index_entry);
         }
         footer.account_entry_count = total_input_accounts as u32;
+        stats.num_accounts = total_input_accounts;
+        stats.account_blocks_us = start.elapsed().as_micros() as u64;
+
+        // The rest of the file has a known size, so check it before writing
+        // any of it.
+        let index_block_size =
+            footer.index_block_format.entry_size::<HotAccountOffset>() * index.len();
+        let owners_block_size = footer
+            .owners_block_format
+            .block_size(owners_table.len() as u64) as usize;
+        let footer_size =
+            TieredStorageFooter::footer_size_of_version(self.footer_format_version)? as usize;
+        self.check_file_size(
+            cursor,
+            index_block_size.next_multiple_of(HOT_BLOCK_ALIGNMENT)
+                + owners_block_size
+                + footer_size,
+        )?;
 
         // writing index block
         // expect the offset of each block aligned.
         assert!(cursor % HOT_BLOCK_ALIGNMENT == 0);
+        let start = Instant::now();
         footer.index_block_offset = cursor as u64;
         cursor += footer
             .index_block_format
@@ -701,18 +2143,33 @@ impl HotStorageWriter {
             assert_eq!(cursor % HOT_BLOCK_ALIGNMENT, 4);
             cursor += self.storage.write_pod(&0u32)?;
         }
+        stats.index_block_bytes = cursor - footer.index_block_offset as usize;
+        stats.index_block_us = start.elapsed().as_micros() as u64;
 
         // writing owners block
         assert!(cursor % HOT_BLOCK_ALIGNMENT == 0);
+        let start = Instant::now();
         footer.owners_block_offset = cursor as u64;
         footer.owner_count = owners_table.len() as u32;
-        footer
+        stats.owners_block_bytes = footer
             .owners_block_format
             .write_owners_block(&mut self.storage, &owners_table)?;
-        footer.min_account_address = *address_range.min;
-        footer.max_account_address = *address_range.max;
+        stats.num_unique_owners = owners_table.len();
+        stats.num_total_owners = total_input_accounts;
+        stats.owners_block_us = start.elapsed().as_micros() as u64;
+
+        let start = Instant::now();
+        footer.set_account_address_range(&address_range);
+        // the hash covers every byte written before the footer
+        footer.hash = self.storage.hash();
+        footer.hash_kind = self.storage.hash_kind();
+        footer.set_format_version(self.footer_format_version)?;
         footer.write_footer_block(&mut self.storage)?;
+        stats.footer_bytes = footer.footer_size as usize;
+        stats.footer_us = start.elapsed().as_micros() as u64;
 
+        self.stats = stats;
+        self.owners_table = owners_table;
         Ok(stored_infos)
     }
 }
@@ -722,25 +2179,169 @@ pub mod tests {
     use {
         super::*,
         crate::tiered_storage::{
-            byte_block::ByteBlockWriter,
-            file::{TieredStorageMagicNumber, TieredWritableFile},
-            footer::{AccountBlockFormat, AccountMetaFormat, TieredStorageFooter, FOOTER_SIZE},
+            byte_block,
+            file::{TieredStorageMagicNumber, TieredWritableFile, WRITE_BUFFER_SIZE},
+            footer::{
+                AccountBlockFormat, AccountMetaFormat, FooterHashKind, TieredStorageFooter,
+                FOOTER_FORMAT_VERSION, FOOTER_SIZE,
+            },
+            generation::FormatGeneration,
             hot::{HotAccountMeta, HotStorageReader},
             index::{AccountIndexWriterEntry, IndexBlockFormat, IndexOffset},
             meta::{AccountMetaFlags, AccountMetaOptionalFields, TieredAccountMeta},
             owners::{OwnersBlockFormat, OwnersTable},
-            test_utils::{create_test_account, verify_test_account},
+            readable::TieredStorageReader,
+            test_fixtures::overwrite_footer_bytes,
+            test_utils::{
+                create_test_account, verify_test_account, with_storable_accounts,
+                write_hot_storage, write_hot_storage_with_accounts,
+                write_hot_storage_with_hash_kind,
+            },
+            verification::VerificationStatus,
+            TieredStorage,
         },
         assert_matches::assert_matches,
         memoffset::offset_of,
         rand::{seq::SliceRandom, Rng},
         solana_sdk::{
-            account::ReadableAccount, hash::Hash, pubkey::Pubkey, slot_history::Slot,
+            account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
+            hash::Hash,
+            pubkey::Pubkey,
+            slot_history::Slot,
             stake_history::Epoch,
+            system_instruction::MAX_PERMITTED_DATA_LENGTH,
+        },
+        std::{
+            fs::{self, OpenOptions},
+            io::{Seek, SeekFrom, Write},
         },
         tempfile::TempDir,
+        test_case::test_case,
     };
 
+    /// Overwrites the owner offset of the account at the specified index
+    /// with MAX_HOT_OWNER_OFFSET, which points far beyond the owners block
+    /// of any test file.
+    pub(crate) fn corrupt_owner_offset(path: impl AsRef<Path>, index_offset: IndexOffset) {
+        corrupt_account_meta(path, index_offset, |meta| {
+            meta.packed_fields.set_owner_offset(MAX_HOT_OWNER_OFFSET.0)
+        });
+    }
+
+    /// Overwrites the padding of the account at the specified index with
+    /// the specified value.
+    pub(crate) fn corrupt_account_data_padding(
+        path: impl AsRef<Path>,
+        index_offset: IndexOffset,
+        padding: u8,
+    ) {
+        corrupt_account_meta(path, index_offset, |meta| {
+            meta.packed_fields.set_padding(padding)
+        });
+    }
+
+    /// Overwrites the meta of the account at the specified index with the
+    /// meta modified by `corrupt`.
+    fn corrupt_account_meta(
+        path: impl AsRef<Path>,
+        index_offset: IndexOffset,
+        corrupt: impl FnOnce(&mut HotAccountMeta),
+    ) {
+        let (account_offset, mut meta) = {
+            let file = TieredReadableFile::new(&path).unwrap();
+            let hot_storage = HotStorageReader::new(file).unwrap();
+            let account_offset = hot_storage.get_account_offset(index_offset).unwrap();
+            let meta = *hot_storage
+                .get_account_meta_from_offset(account_offset)
+                .unwrap();
+            (account_offset, meta)
+        };
+        corrupt(&mut meta);
+
+        let mut file = OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(account_offset.offset() as u64))
+            .unwrap();
+        file.write_all(bytemuck::bytes_of(&meta)).unwrap();
+    }
+
+    /// Overwrites the account offset of the specified index with the
+    /// account offset of `target_index`.
+    pub(crate) fn corrupt_account_offset(
+        path: impl AsRef<Path>,
+        index_offset: IndexOffset,
+        target_index_offset: IndexOffset,
+    ) {
+        let target_account_offset = {
+            let file = TieredReadableFile::new(&path).unwrap();
+            let hot_storage = HotStorageReader::new(file).unwrap();
+            hot_storage.get_account_offset(target_index_offset).unwrap()
+        };
+        overwrite_account_offset(path, index_offset, target_account_offset);
+    }
+
+    /// Overwrites the index block entry of the account at `index_offset`
+    /// with the specified account offset.
+    pub(crate) fn overwrite_account_offset(
+        path: impl AsRef<Path>,
+        index_offset: IndexOffset,
+        account_offset: HotAccountOffset,
+    ) {
+        let entry_offset = {
+            let footer = TieredStorageFooter::new_from_path(&path).unwrap();
+            footer.index_block_offset as usize
+                + std::mem::size_of::<Pubkey>() * footer.account_entry_count as usize
+                + std::mem::size_of::<HotAccountOffset>() * index_offset.0 as usize
+        };
+
+        let mut file = OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(entry_offset as u64)).unwrap();
+        file.write_all(bytemuck::bytes_of(&account_offset)).unwrap();
+    }
+
+    #[test]
+    fn test_hot_format_presets() {
+        for format in [
+            HOT_FORMAT,
+            HOT_SORTED_FORMAT,
+            HOT_SIZED_INDEX_FORMAT,
+            HOT_OWNER_COUNTS_FORMAT,
+        ] {
+            assert_matches!(format.validate(), Ok(()), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_padded_data_layout() {
+        for alignment in [8, 16] {
+            for data_len in 0..1024 {
+                let layout = padded_data_layout(data_len, alignment);
+                // padded_len is the smallest aligned value >= data_len
+                assert!(layout.padded_len >= data_len);
+                assert_eq!(layout.padded_len % alignment, 0);
+                assert!(layout.padded_len - data_len < alignment);
+                assert_eq!(layout.padded_len - data_len, layout.padding as usize);
+                // unpad inverts it
+                assert_eq!(unpad(layout.padded_len, layout.padding).unwrap(), data_len);
+            }
+        }
+        assert!(padded_data_layout(7, HOT_ACCOUNT_ALIGNMENT).padding <= MAX_HOT_PADDING);
+    }
+
+    #[test]
+    fn test_unpad_invalid_padding() {
+        assert_matches!(
+            unpad(3, 4),
+            Err(TieredStorageError::InvalidAccountDataPadding(4, 3))
+        );
+        assert_matches!(
+            unpad(0, MAX_HOT_PADDING),
+            Err(TieredStorageError::InvalidAccountDataPadding(
+                MAX_HOT_PADDING,
+                0
+            ))
+        );
+    }
+
     #[test]
     fn test_hot_account_meta_layout() {
         assert_eq!(offset_of!(HotAccountMeta, lamports), 0x00);
@@ -773,7 +2374,9 @@ pub mod tests {
     fn test_hot_meta_max_values() {
         let meta = HotAccountMeta::new()
             .with_account_data_padding(MAX_HOT_PADDING)
-            .with_owner_offset(MAX_HOT_OWNER_OFFSET);
+            .unwrap()
+            .with_owner_offset(MAX_HOT_OWNER_OFFSET)
+            .unwrap();
 
         assert_eq!(meta.account_data_padding(), MAX_HOT_PADDING);
         assert_eq!(meta.owner_offset(), MAX_HOT_OWNER_OFFSET);
@@ -802,15 +2405,21 @@ pub mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "padding exceeds MAX_HOT_PADDING")]
     fn test_hot_meta_padding_exceeds_limit() {
-        HotAccountMeta::new().with_account_data_padding(MAX_HOT_PADDING + 1);
+        assert_matches!(
+            HotAccountMeta::new().with_account_data_padding(MAX_HOT_PADDING + 1),
+            Err(TieredStorageError::PaddingLimitExceeded(padding, MAX_HOT_PADDING))
+                if padding == MAX_HOT_PADDING + 1
+        );
     }
 
     #[test]
-    #[should_panic(expected = "owner_offset exceeds MAX_HOT_OWNER_OFFSET")]
     fn test_hot_meta_owner_offset_exceeds_limit() {
-        HotAccountMeta::new().with_owner_offset(OwnerOffset(MAX_HOT_OWNER_OFFSET.0 + 1));
+        assert_matches!(
+            HotAccountMeta::new().with_owner_offset(OwnerOffset(MAX_HOT_OWNER_OFFSET.0 + 1)),
+            Err(TieredStorageError::OwnerOffsetLimitExceeded(offset, max_offset))
+                if offset == MAX_HOT_OWNER_OFFSET.0 + 1 && max_offset == MAX_HOT_OWNER_OFFSET.0
+        );
     }
 
     #[test]
@@ -819,16 +2428,20 @@ pub mod tests {
         const TEST_PADDING: u8 = 5;
         const TEST_OWNER_OFFSET: OwnerOffset = OwnerOffset(0x1fef_1234);
         const TEST_RENT_EPOCH: Epoch = 7;
+        let acc_hash = AccountHash(Hash::new_unique());
 
         let optional_fields = AccountMetaOptionalFields {
             rent_epoch: Some(TEST_RENT_EPOCH),
+            account_hash: Some(acc_hash),
         };
 
         let flags = AccountMetaFlags::new_from(&optional_fields);
         let meta = HotAccountMeta::new()
             .with_lamports(TEST_LAMPORTS)
             .with_account_data_padding(TEST_PADDING)
+            .unwrap()
             .with_owner_offset(TEST_OWNER_OFFSET)
+            .unwrap()
             .with_flags(&flags);
 
         assert_eq!(meta.lamports(), TEST_LAMPORTS);
@@ -837,6 +2450,58 @@ pub mod tests {
         assert_eq!(*meta.flags(), flags);
     }
 
+    #[test]
+    fn test_hot_account_meta_defaults() {
+        let meta = HotAccountMeta::new();
+        assert_eq!(meta.lamports(), 0);
+        assert_eq!(meta.account_data_padding(), 0);
+        assert_eq!(meta.owner_offset(), OwnerOffset(0));
+        assert_eq!(*meta.flags(), AccountMetaFlags::new());
+        assert!(!HotAccountMeta::supports_shared_account_block());
+
+        // the data size of a hot account is derived from its account block
+        assert_eq!(meta.with_account_data_size(1234), meta);
+        let account_block = [7u8; 24];
+        assert_eq!(meta.account_data_size(&account_block).unwrap(), 24);
+        assert_eq!(meta.optional_fields_offset(&account_block), 24);
+        assert_eq!(meta.rent_epoch(&account_block), None);
+        assert_eq!(meta.account_hash(&account_block), None);
+    }
+
+    #[test]
+    fn test_hot_account_meta_short_account_block() {
+        let optional_fields = AccountMetaOptionalFields {
+            rent_epoch: Some(7),
+            account_hash: Some(AccountHash(Hash::new_unique())),
+        };
+        let meta = HotAccountMeta::new().with_flags(&AccountMetaFlags::new_from(&optional_fields));
+        let optional_fields_size = optional_fields.size();
+
+        // test: the account block is too small for the optional fields its
+        // flags claim, so none of them is read from the bytes in front
+        for len in [0, 1, 8, optional_fields_size - 1] {
+            let account_block = vec![7u8; len];
+            assert_eq!(meta.rent_epoch(&account_block), None, "{len}");
+            assert_eq!(meta.account_hash(&account_block), None, "{len}");
+            assert_matches!(
+                meta.account_data_size(&account_block),
+                Err(TieredStorageError::InvalidAccountBlockSize(..))
+            );
+        }
+
+        // test: the account block holds exactly the optional fields
+        let mut writer = ByteBlockWriter::new(AccountBlockFormat::AlignedRaw);
+        writer.write_optional_fields(&optional_fields).unwrap();
+        let account_block = writer.finish().unwrap();
+        assert_eq!(account_block.len(), optional_fields_size);
+        assert_eq!(meta.rent_epoch(&account_block), optional_fields.rent_epoch);
+        assert_eq!(
+            meta.account_hash(&account_block),
+            optional_fields.account_hash.as_ref()
+        );
+        assert_eq!(meta.account_data_size(&account_block).unwrap(), 0);
+    }
+
     #[test]
     fn test_hot_account_meta_full() {
         let account_data = [11u8; 83];
@@ -845,16 +2510,20 @@ pub mod tests {
         const TEST_LAMPORT: u64 = 2314232137;
         const OWNER_OFFSET: u32 = 0x1fef_1234;
         const TEST_RENT_EPOCH: Epoch = 7;
+        let acc_hash = AccountHash(Hash::new_unique());
 
         let optional_fields = AccountMetaOptionalFields {
             rent_epoch: Some(TEST_RENT_EPOCH),
+            account_hash: Some(acc_hash),
         };
 
         let flags = AccountMetaFlags::new_from(&optional_fields);
         let expected_meta = HotAccountMeta::new()
             .with_lamports(TEST_LAMPORT)
             .with_account_data_padding(padding.len().try_into().unwrap())
+            .unwrap()
             .with_owner_offset(OwnerOffset(OWNER_OFFSET))
+            .unwrap()
             .with_flags(&flags);
 
         let mut writer = ByteBlockWriter::new(AccountBlockFormat::AlignedRaw);
@@ -870,6 +2539,7 @@ pub mod tests {
         let meta = byte_block::read_pod::<HotAccountMeta>(&buffer, 0).unwrap();
         assert_eq!(expected_meta, *meta);
         assert!(meta.flags().has_rent_epoch());
+        assert!(meta.flags().has_account_hash());
         assert_eq!(meta.account_data_padding() as usize, padding.len());
 
         let account_block = &buffer[std::mem::size_of::<HotAccountMeta>()..];
@@ -879,9 +2549,16 @@ pub mod tests {
                 .len()
                 .saturating_sub(AccountMetaOptionalFields::size_from_flags(&flags))
         );
-        assert_eq!(account_data.len(), meta.account_data_size(account_block));
-        assert_eq!(account_data, meta.account_data(account_block));
+        assert_eq!(
+            account_data.len(),
+            meta.account_data_size(account_block).unwrap()
+        );
+        assert_eq!(account_data, meta.account_data(account_block).unwrap());
         assert_eq!(meta.rent_epoch(account_block), optional_fields.rent_epoch);
+        assert_eq!(
+            *(meta.account_hash(account_block).unwrap()),
+            optional_fields.account_hash.unwrap()
+        );
     }
 
     #[test]
@@ -894,18 +2571,22 @@ pub mod tests {
             owners_block_format: OwnersBlockFormat::AddressesOnly,
             index_block_format: IndexBlockFormat::AddressesThenOffsets,
             account_block_format: AccountBlockFormat::AlignedRaw,
-            account_entry_count: 300,
+            // the footer must describe a consistent layout, and this file
+            // contains nothing but the footer.
+            account_entry_count: 0,
             account_meta_entry_size: 16,
             account_block_size: 4096,
-            owner_count: 250,
+            owner_count: 0,
             owner_entry_size: 32,
-            index_block_offset: 1069600,
-            owners_block_offset: 1081200,
+            index_block_offset: 0,
+            owners_block_offset: 0,
             hash: Hash::new_unique(),
+            hash_kind: FooterHashKind::Sha256,
+            reserved: [0; 7],
             min_account_address: Pubkey::default(),
             max_account_address: Pubkey::new_unique(),
             footer_size: FOOTER_SIZE as u64,
-            format_version: 1,
+            format_version: FOOTER_FORMAT_VERSION,
         };
 
         {
@@ -936,6 +2617,7 @@ pub mod tests {
                 HotAccountMeta::new()
                     .with_lamports(rng.gen_range(0..u64::MAX))
                     .with_owner_offset(OwnerOffset(rng.gen_range(0..NUM_ACCOUNTS)))
+                    .unwrap()
             })
             .collect();
 
@@ -957,9 +2639,27 @@ pub mod tests {
                     HotAccountOffset::new(prev_offset).unwrap()
                 })
                 .collect();
-            // while the test only focuses on account metas, writing a footer
-            // here is necessary to make it a valid tiered-storage file.
+            // while the test only focuses on account metas, writing an index
+            // block and a footer here is necessary to make it a valid
+            // tiered-storage file.
             footer.index_block_offset = current_offset as u64;
+            let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(NUM_ACCOUNTS as usize)
+                .collect();
+            let index_writer_entries: Vec<_> = addresses
+                .iter()
+                .zip(account_offsets.iter())
+                .map(|(address, offset)| AccountIndexWriterEntry {
+                    address,
+                    offset: *offset,
+                    block_size: 0,
+                })
+                .collect();
+            current_offset += footer
+                .index_block_format
+                .write_index_block(&mut file, &index_writer_entries)
+                .unwrap();
+            footer.owners_block_offset = current_offset as u64;
             footer.write_footer_block(&mut file).unwrap();
         }
 
@@ -975,7 +2675,6 @@ pub mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "would exceed accounts blocks offset boundary")]
     fn test_get_acount_meta_from_offset_out_of_bounds() {
         // Generate a new temp path that is guaranteed to NOT already have a file.
         let temp_dir = TempDir::new().unwrap();
@@ -986,11 +2685,13 @@ pub mod tests {
         let footer = TieredStorageFooter {
             account_meta_format: AccountMetaFormat::Hot,
             index_block_offset: 160,
+            owners_block_offset: 160,
             ..TieredStorageFooter::default()
         };
 
         {
             let mut file = TieredWritableFile::new(&path).unwrap();
+            file.write_bytes(&[0u8; 160]).unwrap();
             footer.write_footer_block(&mut file).unwrap();
         }
 
@@ -998,8 +2699,11 @@ pub mod tests {
         let hot_storage = HotStorageReader::new(file).unwrap();
         let offset = HotAccountOffset::new(footer.index_block_offset as usize).unwrap();
         // Read from index_block_offset, which offset doesn't belong to
-        // account blocks.  Expect assert failure here
-        hot_storage.get_account_meta_from_offset(offset).unwrap();
+        // account blocks.  Expect an error here
+        assert_matches!(
+            hot_storage.get_account_meta_from_offset(offset),
+            Err(TieredStorageError::OffsetOutOfBounds(_, 160))
+        );
     }
 
     #[test]
@@ -1024,6 +2728,7 @@ pub mod tests {
                     rng.gen_range(0..u32::MAX) as usize * HOT_ACCOUNT_ALIGNMENT,
                 )
                 .unwrap(),
+                block_size: 0,
             })
             .collect();
 
@@ -1076,6 +2781,7 @@ pub mod tests {
             account_meta_format: AccountMetaFormat::Hot,
             // meta/data nor index block in this test
             owners_block_offset: 0,
+            owner_count: NUM_OWNERS as u32,
             ..TieredStorageFooter::default()
         };
 
@@ -1127,6 +2833,7 @@ pub mod tests {
                 HotAccountMeta::new()
                     .with_lamports(rng.gen_range(1..u64::MAX))
                     .with_owner_offset(OwnerOffset(rng.gen_range(0..NUM_OWNERS)))
+                    .unwrap()
             }
         })
         .take(NUM_ACCOUNTS as usize)
@@ -1152,10 +2859,25 @@ pub mod tests {
                 })
                 .collect();
             footer.index_block_offset = current_offset as u64;
-            // Typically, the owners block is stored after index block, but
-            // since we don't write index block in this test, so we have
-            // the owners_block_offset set to the end of the accounts blocks.
-            footer.owners_block_offset = footer.index_block_offset;
+            // while the test doesn't read the index block, it is necessary
+            // to make the footer describe a valid tiered-storage file.
+            let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(NUM_ACCOUNTS as usize)
+                .collect();
+            let index_writer_entries: Vec<_> = addresses
+                .iter()
+                .zip(account_offsets.iter())
+                .map(|(address, offset)| AccountIndexWriterEntry {
+                    address,
+                    offset: *offset,
+                    block_size: 0,
+                })
+                .collect();
+            current_offset += footer
+                .index_block_format
+                .write_index_block(&mut file, &index_writer_entries)
+                .unwrap();
+            footer.owners_block_offset = current_offset as u64;
 
             let mut owners_table = OwnersTable::default();
             owner_addresses.iter().for_each(|owner_address| {
@@ -1217,6 +2939,339 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_hot_storage_zero_lamport_accounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_zero_lamport_accounts");
+        // every other account has its lamports drained, while its other
+        // fields remain
+        let accounts: Vec<_> = (1..=8)
+            .map(|i| {
+                let (stored_meta, mut account) = create_test_account(i);
+                if i % 2 == 0 {
+                    account.set_lamports(0);
+                }
+                (stored_meta, account)
+            })
+            .collect();
+        write_hot_storage_with_accounts(&path, &accounts);
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let index_offset = IndexOffset(i as u32);
+            let (stored_account, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+            let StoredAccountMeta::Hot(hot_account) = &stored_account else {
+                panic!("unexpected account type");
+            };
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert!(hot_account.sanitize());
+            let account_offset = hot_storage.get_account_offset(index_offset).unwrap();
+            let candidates = [*account.owner(), *OWNER_NO_OWNER];
+
+            if account.lamports() == 0 {
+                // a zero-lamport account reads back as the default account,
+                // which never matches any owner
+                assert!(hot_account.is_zero_lamport());
+                assert_eq!(
+                    hot_account.to_account_shared_data(),
+                    AccountSharedData::default()
+                );
+                assert_eq!(stored_account.lamports(), 0);
+                assert!(stored_account.data().is_empty());
+                assert_eq!(
+                    hot_storage.account_matches_owners(account_offset, &candidates),
+                    Err(MatchAccountOwnerError::NoMatch)
+                );
+            } else {
+                assert!(!hot_account.is_zero_lamport());
+                assert_eq!(stored_account.lamports(), account.lamports());
+                assert_eq!(stored_account.data(), account.data());
+                assert_eq!(
+                    hot_storage.account_matches_owners(account_offset, &candidates),
+                    Ok(0)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_account_matches_owners_edge_cases() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_account_matches_owners_edge_cases");
+        // the first account has zero lamports
+        write_hot_storage(&path, &[0, 1, 2, 3]);
+
+        let (owner_count, account_offsets) = {
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            let account_offsets: Vec<_> = (0..4)
+                .map(|i| hot_storage.get_account_offset(IndexOffset(i)).unwrap())
+                .collect();
+
+            // test: a zero-lamport account never matches, even its own owner
+            let zero_lamport_owner = hot_storage
+                .get_owner_address(
+                    hot_storage
+                        .get_account_meta_from_offset(account_offsets[0])
+                        .unwrap()
+                        .owner_offset(),
+                )
+                .unwrap();
+            assert_eq!(
+                hot_storage.account_matches_owners(account_offsets[0], &[*zero_lamport_owner]),
+                Err(MatchAccountOwnerError::NoMatch)
+            );
+
+            // test: the owner is present or absent in the candidates
+            for (i, account_offset) in account_offsets.iter().enumerate().skip(1) {
+                let (account, _) = hot_storage
+                    .get_account(IndexOffset(i as u32))
+                    .unwrap()
+                    .unwrap();
+                let candidates = [Pubkey::new_unique(), *account.owner()];
+                assert_eq!(
+                    hot_storage.account_matches_owners(*account_offset, &candidates),
+                    Ok(1)
+                );
+                assert_eq!(
+                    hot_storage.account_matches_owners(*account_offset, &candidates[..1]),
+                    Err(MatchAccountOwnerError::NoMatch)
+                );
+                assert_eq!(
+                    hot_storage.account_matches_owners(*account_offset, &[]),
+                    Err(MatchAccountOwnerError::NoMatch)
+                );
+            }
+            (hot_storage.owner_count() as u32, account_offsets)
+        };
+
+        // test: owner offsets at and beyond the owner count fail to load
+        // instead of panicking
+        corrupt_account_meta(&path, IndexOffset(1), |meta| {
+            meta.packed_fields.set_owner_offset(owner_count)
+        });
+        corrupt_owner_offset(&path, IndexOffset(2));
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for account_offset in &account_offsets[1..3] {
+            assert_eq!(
+                hot_storage.account_matches_owners(*account_offset, &[Pubkey::new_unique()]),
+                Err(MatchAccountOwnerError::UnableToLoad)
+            );
+        }
+        assert_matches!(
+            hot_storage.account_matches_owners(account_offsets[3], &[Pubkey::new_unique()]),
+            Err(MatchAccountOwnerError::NoMatch)
+        );
+    }
+
+    #[test]
+    fn test_account_matches_owners_cached() {
+        const NUM_ACCOUNTS: usize = 200;
+        const NUM_OWNERS: usize = 8;
+        let mut rng = rand::thread_rng();
+        let temp_dir = TempDir::new().unwrap();
+
+        for round in 0..5 {
+            let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(NUM_OWNERS)
+                .collect();
+            let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(NUM_ACCOUNTS)
+                .collect();
+            // about a tenth of the accounts have zero lamports
+            let accounts: Vec<_> = (0..NUM_ACCOUNTS)
+                .map(|_| {
+                    let lamports = if rng.gen_ratio(1, 10) {
+                        0
+                    } else {
+                        rng.gen_range(1..u64::MAX)
+                    };
+                    let owner = owners.choose(&mut rng).unwrap();
+                    AccountSharedData::new(lamports, rng.gen_range(0..64), owner)
+                })
+                .collect();
+            let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+            let account_data = (Slot::MAX, &account_refs[..]);
+            let storable_accounts =
+                StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                    &account_data,
+                    vec![AccountHash(Hash::default()); NUM_ACCOUNTS],
+                    vec![0; NUM_ACCOUNTS],
+                );
+            let path = temp_dir
+                .path()
+                .join(format!("test_account_matches_owners_cached_{round}"));
+            HotStorageWriter::new(&path)
+                .unwrap()
+                .write_accounts(&storable_accounts, 0)
+                .unwrap();
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+            // the candidates mix owners of the file, duplicates of them, and
+            // owners that are not in the file
+            let mut candidates: Vec<_> = owners
+                .choose_multiple(&mut rng, NUM_OWNERS / 2)
+                .copied()
+                .chain(std::iter::repeat_with(Pubkey::new_unique).take(2))
+                .collect();
+            candidates.push(candidates[0]);
+            candidates.shuffle(&mut rng);
+
+            // test: the cached and the uncached matches agree for every account
+            let cache = hot_storage.build_owner_match_cache(&candidates).unwrap();
+            for i in 0..NUM_ACCOUNTS {
+                let account_offset = hot_storage
+                    .get_account_offset(IndexOffset(i as u32))
+                    .unwrap();
+                assert_eq!(
+                    hot_storage.account_matches_owners_cached(account_offset, &cache),
+                    hot_storage.account_matches_owners(account_offset, &candidates),
+                );
+            }
+
+            // test: a cache without any candidate never matches
+            let cache = hot_storage.build_owner_match_cache(&[]).unwrap();
+            let account_offset = hot_storage.get_account_offset(IndexOffset(0)).unwrap();
+            assert_eq!(
+                hot_storage.account_matches_owners_cached(account_offset, &cache),
+                Err(MatchAccountOwnerError::NoMatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_matches_owners_cached_invalid_owner_offset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_account_matches_owners_cached_invalid_owner_offset");
+        write_hot_storage(&path, &[1, 2]);
+        let owner_count = {
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            hot_storage.owner_count() as u32
+        };
+
+        // test: an owner offset beyond the cache fails to load
+        corrupt_account_meta(&path, IndexOffset(0), |meta| {
+            meta.packed_fields.set_owner_offset(owner_count)
+        });
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let cache = hot_storage
+            .build_owner_match_cache(&[Pubkey::new_unique()])
+            .unwrap();
+        let account_offset = hot_storage.get_account_offset(IndexOffset(0)).unwrap();
+        assert_eq!(
+            hot_storage.account_matches_owners_cached(account_offset, &cache),
+            Err(MatchAccountOwnerError::UnableToLoad)
+        );
+    }
+
+    #[test_case(ReaderBackend::Mmap)]
+    #[test_case(ReaderBackend::Buffered)]
+    fn test_hot_storage_prefetch(backend: ReaderBackend) {
+        const NUM_ACCOUNTS: u32 = 10;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_prefetch");
+        // include accounts that span multiple pages
+        let account_data_sizes: Vec<_> = (1..=NUM_ACCOUNTS as u64)
+            .map(|seed| seed * PREFETCH_PAGE_SIZE as u64 / 3)
+            .collect();
+        write_hot_storage(&path, &account_data_sizes);
+        let hot_storage =
+            HotStorageReader::new_with_backend(TieredReadableFile::new(&path).unwrap(), backend)
+                .unwrap();
+        let expected: Vec<_> = (0..NUM_ACCOUNTS)
+            .map(|i| {
+                let (account, _) = hot_storage.get_account(IndexOffset(i)).unwrap().unwrap();
+                (*account.pubkey(), account.to_account_shared_data())
+            })
+            .collect();
+
+        // test: out-of-range offsets are skipped, interleaved with duplicated
+        // and unsorted offsets
+        hot_storage.prefetch(&[]);
+        hot_storage.prefetch(
+            &[
+                NUM_ACCOUNTS,
+                7,
+                u32::MAX,
+                0,
+                7,
+                NUM_ACCOUNTS - 1,
+                NUM_ACCOUNTS + 1,
+            ]
+            .map(IndexOffset),
+        );
+        // prefetching the resident pages again is fine
+        hot_storage.prefetch(&(0..NUM_ACCOUNTS).map(IndexOffset).collect::<Vec<_>>());
+
+        // test: the accounts read after prefetching are unchanged
+        for (i, (address, account)) in expected.iter().enumerate() {
+            let (stored_account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored_account.pubkey(), address);
+            assert_eq!(&stored_account.to_account_shared_data(), account);
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_get_accounts() {
+        const NUM_ACCOUNTS: u32 = 10;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_get_accounts");
+        let account_data_sizes: Vec<_> = (1..=NUM_ACCOUNTS as u64).collect();
+        write_hot_storage(&path, &account_data_sizes);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+        // test: no offset
+        assert!(hot_storage.get_accounts(&[]).unwrap().is_empty());
+
+        // test: unsorted, duplicated and out-of-range offsets interleaved
+        let index_offsets = [
+            7,
+            NUM_ACCOUNTS,
+            2,
+            7,
+            u32::MAX,
+            0,
+            NUM_ACCOUNTS - 1,
+            2,
+            NUM_ACCOUNTS + 1,
+        ]
+        .map(IndexOffset);
+        let accounts = hot_storage.get_accounts(&index_offsets).unwrap();
+        assert_eq!(accounts.len(), index_offsets.len());
+        for (index_offset, account) in index_offsets.iter().zip(accounts.iter()) {
+            let expected = hot_storage
+                .get_account(*index_offset)
+                .unwrap()
+                .map(|(account, _)| account);
+            match (account, expected) {
+                (Some(account), Some(expected)) => {
+                    assert_eq!(account.pubkey(), expected.pubkey());
+                    assert_eq!(account.owner(), expected.owner());
+                    assert_eq!(account.lamports(), expected.lamports());
+                    assert_eq!(account.data(), expected.data());
+                    assert_eq!(account.offset(), expected.offset());
+                }
+                (None, None) => assert!(index_offset.0 >= NUM_ACCOUNTS),
+                (account, expected) => panic!(
+                    "{index_offset:?}: {:?} != {:?}",
+                    account.is_some(),
+                    expected.is_some()
+                ),
+            }
+        }
+    }
+
     #[test]
     fn test_hot_storage_get_account() {
         // Generate a new temp path that is guaranteed to NOT already have a file.
@@ -1243,7 +3298,11 @@ pub mod tests {
                 HotAccountMeta::new()
                     .with_lamports(rng.gen_range(0..u64::MAX))
                     .with_owner_offset(OwnerOffset(rng.gen_range(0..NUM_OWNERS) as u32))
-                    .with_account_data_padding(padding_bytes(account_datas[i].len()))
+                    .unwrap()
+                    .with_account_data_padding(
+                        padded_data_layout(account_datas[i].len(), HOT_ACCOUNT_ALIGNMENT).padding,
+                    )
+                    .unwrap()
             })
             .collect();
 
@@ -1274,11 +3333,16 @@ pub mod tests {
                     current_offset += file.write_pod(meta).unwrap();
                     current_offset += file.write_bytes(data).unwrap();
                     current_offset += file
-                        .write_bytes(&padding_buffer[0..padding_bytes(data.len()) as usize])
+                        .write_bytes(
+                            &padding_buffer[0
+                                ..padded_data_layout(data.len(), HOT_ACCOUNT_ALIGNMENT).padding
+                                    as usize],
+                        )
                         .unwrap();
                     AccountIndexWriterEntry {
                         address,
                         offset: HotAccountOffset::new(prev_offset).unwrap(),
+                        block_size: 0,
                     }
                 })
                 .collect();
@@ -1356,13 +3420,6 @@ pub mod tests {
             .map(|size| create_test_account(*size))
             .collect();
 
-        let account_refs: Vec<_> = accounts
-            .iter()
-            .map(|account| (&account.0.pubkey, &account.1))
-            .collect();
-
-        // Slot information is not used here
-        let account_data = (Slot::MAX, &account_refs[..]);
         let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
             .take(account_data_sizes.len())
             .collect();
@@ -1372,76 +3429,2316 @@ pub mod tests {
             .map(|account| account.0.write_version_obsolete)
             .collect();
 
-        let storable_accounts =
-            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
-                &account_data,
-                hashes.clone(),
-                write_versions.clone(),
+        with_storable_accounts(&accounts, hashes, write_versions, |storable_accounts| {
+            let temp_dir = TempDir::new().unwrap();
+            let path = temp_dir.path().join("test_write_account_and_index_blocks");
+            let (stored_infos, trace) = {
+                let mut writer = HotStorageWriter::new_with_options(
+                    &path,
+                    HOT_FORMAT.index_block_format,
+                    HOT_FORMAT.hash_kind,
+                    &WriteOptions::LATEST,
+                )
+                .unwrap();
+                writer.enable_trace(account_data_sizes.len());
+                let stored_infos = writer.write_accounts(storable_accounts, 0).unwrap();
+                (stored_infos, writer.take_trace().unwrap())
+            };
+
+            // each account block starts where the previous one ends, and
+            // consists of the meta, the padded data, and the optional fields
+            assert_eq!(trace.events().len(), account_data_sizes.len());
+            assert_eq!(trace.num_dropped(), 0);
+            let mut expected_cursor = 0;
+            for (event, stored_info) in trace.events().iter().zip(&stored_infos) {
+                let (account, address, _account_hash, _write_version) =
+                    storable_accounts.get(event.index);
+                let data_len = account.map_or(0, |account| account.data().len());
+                let data_layout = padded_data_layout(data_len, HOT_ACCOUNT_ALIGNMENT);
+                // every account has a non-default hash, which is persisted
+                let optional_fields_size = account
+                    .and_then(|account| {
+                        AccountMetaOptionalFields::rent_epoch_to_persist(account.rent_epoch())
+                    })
+                    .map_or(0, |_| std::mem::size_of::<Epoch>())
+                    + std::mem::size_of::<AccountHash>();
+                assert_eq!(event.address, *address);
+                assert_eq!(event.cursor_before, expected_cursor);
+                assert_eq!(event.padding, data_layout.padding);
+                assert_eq!(
+                    event.block_len,
+                    std::mem::size_of::<HotAccountMeta>()
+                        + data_layout.padded_len
+                        + optional_fields_size
+                );
+                assert_eq!(event.cursor_after, event.cursor_before + event.block_len);
+                assert_eq!(
+                    stored_info.size,
+                    event.block_len
+                        + HOT_FORMAT
+                            .index_block_format
+                            .entry_size::<HotAccountOffset>()
+                );
+                expected_cursor = event.cursor_after;
+            }
+
+            let file = TieredReadableFile::new(&path).unwrap();
+            let hot_storage = HotStorageReader::new(file).unwrap();
+
+            let num_accounts = account_data_sizes.len();
+            for i in 0..num_accounts {
+                let (stored_meta, next) = hot_storage
+                    .get_account(IndexOffset(i as u32))
+                    .unwrap()
+                    .unwrap();
+
+                let (account, address, account_hash, _write_version) = storable_accounts.get(i);
+                verify_test_account(&stored_meta, account, address, account_hash);
+
+                assert_eq!(i + 1, next.0 as usize);
+            }
+            // Make sure it returns None on NUM_ACCOUNTS to allow termination on
+            // while loop in actual accounts-db read case.
+            assert_matches!(
+                hot_storage.get_account(IndexOffset(num_accounts as u32)),
+                Ok(None)
             );
 
+            for stored_info in stored_infos {
+                let (stored_meta, _) = hot_storage
+                    .get_account(IndexOffset(stored_info.offset as u32))
+                    .unwrap()
+                    .unwrap();
+
+                let (account, address, account_hash, _write_version) =
+                    storable_accounts.get(stored_info.offset);
+                verify_test_account(&stored_meta, account, address, account_hash);
+            }
+
+            // verify get_accounts
+            let accounts = hot_storage.accounts(IndexOffset(0)).unwrap();
+
+            // first, we verify everything
+            for (i, stored_meta) in accounts.iter().enumerate() {
+                let (account, address, account_hash, _write_version) = storable_accounts.get(i);
+                verify_test_account(stored_meta, account, address, account_hash);
+            }
+
+            // second, we verify various initial position
+            let total_stored_accounts = accounts.len();
+            for i in 0..total_stored_accounts {
+                let partial_accounts = hot_storage.accounts(IndexOffset(i as u32)).unwrap();
+                assert_eq!(&partial_accounts, &accounts[i..]);
+            }
+            let footer = hot_storage.footer();
+
+            let expected_size = footer.owners_block_offset as usize
+                + std::mem::size_of::<Pubkey>() * footer.owner_count as usize
+                + std::mem::size_of::<TieredStorageFooter>()
+                + std::mem::size_of::<TieredStorageMagicNumber>();
+
+            assert!(!hot_storage.is_empty());
+            assert_eq!(expected_size, hot_storage.len());
+        });
+    }
+
+    #[test]
+    fn test_hot_storage_accounts() {
         let temp_dir = TempDir::new().unwrap();
-        let path = temp_dir.path().join("test_write_account_and_index_blocks");
-        let stored_infos = {
-            let mut writer = HotStorageWriter::new(&path).unwrap();
-            writer.write_accounts(&storable_accounts, 0).unwrap()
-        };
+        let path = temp_dir.path().join("test_hot_storage_accounts");
+        let account_data_sizes: Vec<_> = (0..300).collect();
+        write_hot_storage(&path, &account_data_sizes);
 
         let file = TieredReadableFile::new(&path).unwrap();
         let hot_storage = HotStorageReader::new(file).unwrap();
 
-        let num_accounts = account_data_sizes.len();
-        for i in 0..num_accounts {
-            let (stored_meta, next) = hot_storage
-                .get_account(IndexOffset(i as u32))
-                .unwrap()
-                .unwrap();
+        let addresses = hot_storage.account_addresses().unwrap();
+        assert_eq!(addresses.len(), account_data_sizes.len());
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(
+                hot_storage
+                    .get_account_address(IndexOffset(i as u32))
+                    .unwrap(),
+                address
+            );
+        }
+
+        // accounts() reads all the addresses at once, and its results must
+        // be the same as reading each account one by one.
+        for start in [0, 1, 150, 299, 300, 301] {
+            let accounts = hot_storage.accounts(IndexOffset(start)).unwrap();
+            assert_eq!(
+                accounts.len(),
+                account_data_sizes.len().saturating_sub(start as usize)
+            );
+            for (i, account) in accounts.iter().enumerate() {
+                let index_offset = IndexOffset(start + i as u32);
+                let (expected, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+                assert_eq!(account.offset(), expected.offset());
+                assert_eq!(account.pubkey(), expected.pubkey());
+                assert_eq!(account.lamports(), expected.lamports());
+                assert_eq!(account.owner(), expected.owner());
+                assert_eq!(account.data(), expected.data());
+            }
+        }
+    }
 
-            let (account, address, _account_hash, _write_version) = storable_accounts.get(i);
-            verify_test_account(&stored_meta, account, address);
+    #[test]
+    fn test_hot_storage_scan_accounts_raw() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_scan_accounts_raw");
+        let account_data_sizes: Vec<_> = (0..100).collect();
+        write_hot_storage(&path, &account_data_sizes);
 
-            assert_eq!(i + 1, next.0 as usize);
+        let file = TieredReadableFile::new(&path).unwrap();
+        let hot_storage = HotStorageReader::new(file).unwrap();
+
+        // the raw visitor yields the same data as the public accounts()
+        for start in [0, 1, 99, 100] {
+            let accounts = hot_storage.accounts(IndexOffset(start)).unwrap();
+            let mut num_visited = 0;
+            hot_storage
+                .scan_accounts_raw(
+                    IndexOffset(start),
+                    |index_offset, address, meta, account_block| {
+                        let StoredAccountMeta::Hot(account) =
+                            &accounts[(index_offset.0 - start) as usize]
+                        else {
+                            unreachable!();
+                        };
+                        assert_eq!(index_offset, account.index);
+                        assert_eq!(address, account.address());
+                        assert_eq!(meta, account.meta);
+                        assert_eq!(
+                            hot_storage.get_owner_address(meta.owner_offset())?,
+                            account.owner()
+                        );
+                        assert_eq!(meta.lamports(), account.lamports());
+                        assert_eq!(meta.account_data(account_block)?, account.data());
+                        assert_eq!(account_block, account.account_block);
+                        assert_eq!(meta.flags().executable(), account.executable());
+                        num_visited += 1;
+                        Ok(())
+                    },
+                )
+                .unwrap();
+            assert_eq!(num_visited, accounts.len());
         }
-        // Make sure it returns None on NUM_ACCOUNTS to allow termination on
-        // while loop in actual accounts-db read case.
+
+        // the scan stops at the first error returned by the visitor
+        let mut num_visited = 0;
         assert_matches!(
-            hot_storage.get_account(IndexOffset(num_accounts as u32)),
-            Ok(None)
+            hot_storage.scan_accounts_raw(IndexOffset(0), |_, _, _, _| {
+                num_visited += 1;
+                if num_visited == 10 {
+                    return Err(TieredStorageError::Unsupported());
+                }
+                Ok(())
+            }),
+            Err(TieredStorageError::Unsupported())
         );
+        assert_eq!(num_visited, 10);
+    }
 
-        for stored_info in stored_infos {
-            let (stored_meta, _) = hot_storage
-                .get_account(IndexOffset(stored_info.offset as u32))
-                .unwrap()
-                .unwrap();
+    #[test]
+    fn test_hot_storage_index_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_index_entries");
+        // data sizes that need padding, along with accounts with rent epochs,
+        // whose bytes follow the data
+        let account_data_sizes: Vec<_> = (0..50).chain([0, 1000, 1001]).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let file = TieredReadableFile::new(&path).unwrap();
+        let hot_storage = HotStorageReader::new(file).unwrap();
+        let accounts = hot_storage.accounts(IndexOffset(0)).unwrap();
+        let entries = hot_storage.index_entries().unwrap();
+        assert_eq!(entries.len(), accounts.len());
+        for (i, (entry, account)) in entries.iter().zip(&accounts).enumerate() {
+            assert_eq!(
+                *entry,
+                StoredAccountIndexEntry {
+                    pubkey: *account.pubkey(),
+                    index_offset: IndexOffset(i as u32),
+                    lamports: account.lamports(),
+                    data_len: account.data().len(),
+                }
+            );
+        }
+
+        drop(hot_storage);
+
+        // test: a padding that exceeds the account block is an error
+        corrupt_account_data_padding(&path, IndexOffset(0), MAX_HOT_PADDING);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.index_entries(),
+            Err(TieredStorageError::InvalidAccountDataPadding(..))
+        );
+    }
 
-            let (account, address, _account_hash, _write_version) =
-                storable_accounts.get(stored_info.offset);
-            verify_test_account(&stored_meta, account, address);
+    #[test]
+    fn test_hot_storage_scan_for_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_scan_for_hash");
+        // the first account has zero lamports, and every other account is
+        // executable, while some accounts persist their rent epochs
+        let account_data_sizes: Vec<_> = (0..50).chain([0, 1000, 1001]).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        let accounts: Vec<_> = reader.account_iter().collect();
+        assert_eq!(accounts.len(), account_data_sizes.len());
+        assert!(accounts.iter().any(|account| account.lamports() == 0));
+        assert!(accounts.iter().any(|account| account.executable()));
+
+        let mut scanned = vec![];
+        reader
+            .scan_for_hash(|account| scanned.push(account))
+            .unwrap();
+        assert_eq!(scanned.len(), accounts.len());
+        for (scanned, account) in scanned.iter().zip(&accounts) {
+            assert_eq!(
+                *scanned,
+                HotAccountForHash {
+                    pubkey: account.pubkey(),
+                    lamports: account.lamports(),
+                    owner: account.owner(),
+                    executable: account.executable(),
+                    rent_epoch: account.rent_epoch(),
+                    data: account.data(),
+                }
+            );
         }
+    }
+
+    #[test]
+    fn test_hot_storage_scan_account_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_scan_account_data");
+        // data sizes that need padding, along with zero-lamport accounts and
+        // accounts with rent epochs, whose bytes follow the data
+        let account_data_sizes: Vec<_> = (0..50).chain([0, 1000, 1001]).collect();
+        write_hot_storage(&path, &account_data_sizes);
 
-        // verify get_accounts
+        let file = TieredReadableFile::new(&path).unwrap();
+        let hot_storage = HotStorageReader::new(file).unwrap();
         let accounts = hot_storage.accounts(IndexOffset(0)).unwrap();
 
-        // first, we verify everything
-        for (i, stored_meta) in accounts.iter().enumerate() {
-            let (account, address, _account_hash, _write_version) = storable_accounts.get(i);
-            verify_test_account(stored_meta, account, address);
+        let mut scanned = vec![];
+        hot_storage
+            .scan_account_data(|address, data, lamports| scanned.push((address, data, lamports)))
+            .unwrap();
+        assert_eq!(scanned.len(), accounts.len());
+        for ((address, data, lamports), account) in scanned.into_iter().zip(&accounts) {
+            assert_eq!(address, account.pubkey());
+            assert_eq!(data, account.data());
+            assert_eq!(lamports, account.lamports());
+
+            // the data is borrowed from the account block, and excludes the
+            // padding and the optional fields that follow it
+            let StoredAccountMeta::Hot(hot_account) = account else {
+                unreachable!();
+            };
+            assert_eq!(data.as_ptr(), hot_account.account_block.as_ptr());
+            assert_eq!(
+                data.len(),
+                hot_account
+                    .meta
+                    .account_data_size(hot_account.account_block)
+                    .unwrap()
+            );
+            let optional_fields_size =
+                AccountMetaOptionalFields::size_from_flags(hot_account.meta.flags());
+            assert_eq!(
+                hot_account.account_block.len(),
+                data.len()
+                    + hot_account.meta.account_data_padding() as usize
+                    + optional_fields_size,
+            );
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_scan_metas() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_scan_metas");
+        // the first account is a zero-lamport account without any data
+        let account_data_sizes: Vec<_> = (0..20).chain([1000]).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let expected: Vec<_> = hot_storage
+            .accounts(IndexOffset(0))
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, account)| {
+                (
+                    *account.pubkey(),
+                    *account.owner(),
+                    account.lamports(),
+                    IndexOffset(i as u32),
+                )
+            })
+            .collect();
+        assert_eq!(expected.len(), account_data_sizes.len());
+        let mut scanned = vec![];
+        hot_storage
+            .scan_metas(|address, owner, lamports, index_offset| {
+                scanned.push((*address, *owner, lamports, index_offset))
+            })
+            .unwrap();
+        assert_eq!(scanned, expected);
+        drop(hot_storage);
+
+        // test: an account block that cannot be read does not affect the
+        // scan, as the account blocks are never read
+        corrupt_account_data_padding(&path, IndexOffset(0), MAX_HOT_PADDING);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(0)),
+            Err(TieredStorageError::InvalidAccountDataPadding(..))
+        );
+        let mut scanned = vec![];
+        hot_storage
+            .scan_metas(|address, owner, lamports, index_offset| {
+                scanned.push((*address, *owner, lamports, index_offset))
+            })
+            .unwrap();
+        assert_eq!(scanned, expected);
+    }
+
+    #[test]
+    fn test_hot_storage_get_lamports() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_get_lamports");
+        // the first account is a zero-lamport account without any data
+        let account_data_sizes: Vec<_> = (0..20).chain([1000]).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for i in 0..account_data_sizes.len() {
+            let index_offset = IndexOffset(i as u32);
+            let (account, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+            assert_eq!(
+                hot_storage.get_lamports(index_offset).unwrap(),
+                Some(account.lamports())
+            );
         }
+        assert_eq!(hot_storage.get_lamports(IndexOffset(0)).unwrap(), Some(0));
 
-        // second, we verify various initial position
-        let total_stored_accounts = accounts.len();
-        for i in 0..total_stored_accounts {
-            let partial_accounts = hot_storage.accounts(IndexOffset(i as u32)).unwrap();
-            assert_eq!(&partial_accounts, &accounts[i..]);
+        // test: an out-of-range index offset has no lamports
+        for index_offset in [account_data_sizes.len() as u32, u32::MAX] {
+            assert_eq!(
+                hot_storage.get_lamports(IndexOffset(index_offset)).unwrap(),
+                None
+            );
         }
-        let footer = hot_storage.footer();
+        drop(hot_storage);
+
+        // test: the lamports are read without the account block
+        corrupt_account_data_padding(&path, IndexOffset(1), MAX_HOT_PADDING);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(1)),
+            Err(TieredStorageError::InvalidAccountDataPadding(..))
+        );
+        assert_matches!(hot_storage.get_lamports(IndexOffset(1)), Ok(Some(_)));
+    }
+
+    #[test]
+    fn test_hot_storage_write_version_not_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let accounts: Vec<_> = (1..=10).map(create_test_account).collect();
+
+        // files that only differ in the write versions of their accounts are
+        // identical, as the hot format does not persist write versions
+        let files: Vec<_> = [0, u64::MAX]
+            .into_iter()
+            .map(|write_version| {
+                let path = temp_dir
+                    .path()
+                    .join(format!("test_write_version_{write_version}"));
+                let mut writer = HotStorageWriter::new(&path).unwrap();
+                with_storable_accounts(
+                    &accounts,
+                    vec![AccountHash(Hash::default()); accounts.len()],
+                    vec![write_version; accounts.len()],
+                    |storable_accounts| writer.write_accounts(storable_accounts, 0),
+                )
+                .unwrap();
+                std::fs::read(&path).unwrap()
+            })
+            .collect();
+        assert_eq!(files[0], files[1]);
+    }
+
+    #[test]
+    fn test_write_account_owner_offset_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_write_account_owner_offset_limit");
+        let mut writer = HotStorageWriter::new(&path).unwrap();
+
+        // the owner offset of the last owner of a full owners table
+        let stored_size = writer
+            .write_account(1, MAX_HOT_OWNER_OFFSET, &[1, 2, 3], false, None)
+            .unwrap();
+        assert_eq!(
+            stored_size,
+            std::mem::size_of::<HotAccountMeta>() + HOT_ACCOUNT_ALIGNMENT
+        );
 
-        let expected_size = footer.owners_block_offset as usize
-            + std::mem::size_of::<Pubkey>() * footer.owner_count as usize
-            + std::mem::size_of::<TieredStorageFooter>()
-            + std::mem::size_of::<TieredStorageMagicNumber>();
+        // an owners table with one more owner is a recoverable error
+        assert_matches!(
+            writer.write_account(
+                1,
+                OwnerOffset(MAX_HOT_OWNER_OFFSET.0 + 1),
+                &[1, 2, 3],
+                false,
+                None
+            ),
+            Err(TieredStorageError::OwnerOffsetLimitExceeded(_, _))
+        );
+    }
 
-        assert!(!hot_storage.is_empty());
-        assert_eq!(expected_size, hot_storage.len());
+    #[test]
+    fn test_write_accounts_file_size_limit() {
+        let account_data_sizes = &[1, 2, 3, 1000, 2000, 0];
+        let accounts: Vec<_> = account_data_sizes
+            .iter()
+            .map(|size| create_test_account(*size))
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, max_file_size: u64| {
+            let path = temp_dir.path().join(name);
+            let mut writer = HotStorageWriter::new(&path)
+                .unwrap()
+                .with_max_file_size(max_file_size);
+            let result = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); account_data_sizes.len()],
+                vec![0; account_data_sizes.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            );
+            drop(writer);
+            (result, std::fs::metadata(&path).unwrap().len())
+        };
+
+        let (result, file_len) = write("unlimited", MAX_HOT_FILE_SIZE);
+        result.unwrap();
+
+        // test: a file of exactly the maximum size can be written
+        let (result, len) = write("at_limit", file_len);
+        result.unwrap();
+        assert_eq!(len, file_len);
+
+        // test: the writer refuses to cross the limit before writing the
+        // trailing blocks
+        let (result, len) = write("below_limit", file_len - 1);
+        assert_matches!(
+            result,
+            Err(TieredStorageError::FileSizeLimitExceeded(end, max))
+                if end == file_len && max == file_len - 1
+        );
+        assert!(len <= file_len - FOOTER_SIZE as u64);
+
+        // test: the writer refuses to write an account block crossing the
+        // limit, and stops writing right there
+        let (result, len) = write("first_account", 10);
+        assert_matches!(
+            result,
+            Err(TieredStorageError::FileSizeLimitExceeded(_, 10))
+        );
+        assert_eq!(len, 0);
+
+        // test: the offsets around the limit
+        assert_matches!(
+            HotAccountOffset::new(MAX_HOT_FILE_SIZE as usize),
+            Ok(offset) if offset.offset() == MAX_HOT_FILE_SIZE as usize
+        );
+        assert_matches!(
+            HotAccountOffset::new(MAX_HOT_FILE_SIZE as usize + HOT_ACCOUNT_ALIGNMENT),
+            Err(TieredStorageError::OffsetOutOfBounds(..))
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_owner_limit() {
+        // the first and the fourth accounts share the same owner
+        let seeds = [1, 2, 3, 1, 4];
+        let accounts: Vec<_> = seeds
+            .iter()
+            .map(|seed| create_test_account(*seed))
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, max_owner_count: usize| {
+            let path = temp_dir.path().join(name);
+            let mut writer = HotStorageWriter::new(&path)
+                .unwrap()
+                .with_max_owner_count(max_owner_count);
+            let result = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); seeds.len()],
+                vec![0; seeds.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            );
+            drop(writer);
+            (result, std::fs::metadata(&path).unwrap().len())
+        };
+
+        // test: the distinct owners exactly fill the limit
+        let (result, len) = write("at_limit", 4);
+        result.unwrap();
+        let hot_storage = HotStorageReader::new(
+            TieredReadableFile::new(temp_dir.path().join("at_limit")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(hot_storage.footer().owner_count, 4);
+        assert!(len > 0);
+
+        // test: one more owner is rejected before any account is written
+        let (result, len) = write("above_limit", 3);
+        assert_matches!(result, Err(TieredStorageError::TooManyOwners(4, 3)));
+        assert_eq!(len, 0);
+    }
+
+    #[test_case(Some(5), 0; "rent_epoch_and_zero_data")]
+    #[test_case(None, 0; "zero_data")]
+    #[test_case(Some(5), 13; "rent_epoch_and_data")]
+    #[test_case(None, 13; "data")]
+    fn test_hot_storage_last_account_block(rent_epoch: Option<Epoch>, data_len: usize) {
+        let mut accounts: Vec<_> = [1, 2, 3]
+            .iter()
+            .map(|seed| create_test_account(*seed))
+            .collect();
+        let (stored_meta, _) = create_test_account(0);
+        let last_account = Account {
+            lamports: 10,
+            data: vec![7; data_len],
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: rent_epoch.unwrap_or(RENT_EXEMPT_RENT_EPOCH),
+        };
+        accounts.push((stored_meta, AccountSharedData::from(last_account)));
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_last_account_block");
+        {
+            let mut writer = HotStorageWriter::new(&path).unwrap();
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+        }
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let (stored_account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            verify_test_account(
+                &stored_account,
+                Some(account),
+                &stored_meta.pubkey,
+                &AccountHash(Hash::default()),
+            );
+            assert_eq!(stored_account.rent_epoch(), account.rent_epoch());
+        }
+        let (meta, account_block) = hot_storage.get_raw_account(IndexOffset(3)).unwrap();
+        assert_eq!(meta.rent_epoch(account_block), rent_epoch);
+        assert_eq!(meta.account_data(account_block).unwrap().len(), data_len);
+    }
+
+    #[test_case(0)]
+    #[test_case(1)]
+    #[test_case(12345)]
+    #[test_case(Epoch::MAX - 1)]
+    #[test_case(Epoch::MAX)]
+    fn test_hot_storage_rent_epoch_round_trip(rent_epoch: Epoch) {
+        let address = Pubkey::new_unique();
+        let account = AccountSharedData::from(Account {
+            lamports: 10,
+            data: vec![7; 13],
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch,
+        });
+        let account_refs = [(&address, &account)];
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+            );
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_rent_epoch_round_trip");
+        let stored_infos = {
+            let mut writer = HotStorageWriter::new(&path).unwrap();
+            writer.write_accounts(&storable_accounts, 0).unwrap()
+        };
+
+        // the field is present if and only if the rent epoch is not
+        // Epoch::MAX, and the size accounting follows the same rule
+        let expected_rent_epoch = (rent_epoch != Epoch::MAX).then_some(rent_epoch);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let (meta, account_block) = hot_storage.get_raw_account(IndexOffset(0)).unwrap();
+        assert_eq!(meta.flags().has_rent_epoch(), expected_rent_epoch.is_some());
+        assert_eq!(meta.rent_epoch(account_block), expected_rent_epoch);
+        assert_eq!(
+            stored_infos[0].size,
+            std::mem::size_of::<HotAccountMeta>()
+                + padded_data_layout(13, HOT_ACCOUNT_ALIGNMENT).padded_len
+                + expected_rent_epoch.map_or(0, |_| std::mem::size_of::<Epoch>())
+                + HOT_FORMAT
+                    .index_block_format
+                    .entry_size::<HotAccountOffset>()
+        );
+
+        // the reader returns exactly the stored rent epoch, where an absent
+        // rent epoch is read back as Epoch::MAX
+        let (stored_account, _) = hot_storage.get_account(IndexOffset(0)).unwrap().unwrap();
+        assert_eq!(stored_account.rent_epoch(), rent_epoch);
+        assert_eq!(stored_account.to_account_shared_data(), account);
+    }
+
+    #[test]
+    fn test_hot_storage_account_block_size() {
+        const DATA_SIZES: &[u64] = &[1, 200, 3, 0];
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_account_block_size");
+        write_hot_storage(&path, DATA_SIZES);
+
+        // test: the footer records the largest account, which no account of
+        // a normal file exceeds
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let account_sizes: Vec<_> = (0..DATA_SIZES.len() as u32)
+            .map(|i| {
+                let index_offset = IndexOffset(i);
+                let account_offset = hot_storage.get_account_offset(index_offset).unwrap();
+                let account_block = hot_storage
+                    .get_account_block(account_offset, index_offset)
+                    .unwrap();
+                std::mem::size_of::<HotAccountMeta>() + account_block.len()
+            })
+            .collect();
+        let max_account_size = *account_sizes.iter().max().unwrap();
+        assert_eq!(account_sizes[1], max_account_size);
+        assert_eq!(
+            hot_storage.footer().account_block_size,
+            max_account_size as u64
+        );
+        assert!(hot_storage.verify().unwrap().is_consistent());
+        drop(hot_storage);
+
+        // test: a footer that understates the block size rejects the larger
+        // accounts, as their derived sizes can only come from a broken index
+        let understated_size = max_account_size as u64 - 8;
+        overwrite_footer_bytes(
+            &path,
+            offset_of!(TieredStorageFooter, account_block_size),
+            &understated_size.to_le_bytes(),
+        );
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(1)),
+            Err(TieredStorageError::AccountBlockSizeExceeded(size, cap))
+                if size == max_account_size && cap == understated_size
+        );
+        hot_storage.get_account(IndexOffset(0)).unwrap().unwrap();
+        assert_eq!(
+            hot_storage.verify().unwrap().inconsistencies,
+            vec![Inconsistency::InvalidAccountBlock(IndexOffset(1))]
+        );
+        drop(hot_storage);
+
+        // test: a footer without the recorded size does not enforce it
+        overwrite_footer_bytes(
+            &path,
+            offset_of!(TieredStorageFooter, account_block_size),
+            &0u64.to_le_bytes(),
+        );
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert!(hot_storage.verify().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_hot_storage_invalid_account_block() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // test: an account block that ends before it starts
+        let path = temp_dir.path().join("test_account_block_ends_before_start");
+        write_hot_storage(&path, &[1, 2, 3]);
+        corrupt_account_offset(&path, IndexOffset(0), IndexOffset(2));
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let account_offset = hot_storage.get_account_offset(IndexOffset(2)).unwrap();
+        let next_account_offset = hot_storage.get_account_offset(IndexOffset(1)).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(0)),
+            Err(TieredStorageError::InvalidAccountBlock(start, end))
+                if start == account_offset.offset() + std::mem::size_of::<HotAccountMeta>()
+                    && end == next_account_offset.offset()
+        );
+
+        // test: the last account block is too small for the optional fields
+        // of its meta
+        let path = temp_dir.path().join("test_account_block_too_small");
+        write_hot_storage(&path, &[1, 2, 0]);
+        corrupt_account_meta(&path, IndexOffset(2), |meta| {
+            let mut flags = *meta.flags();
+            flags.set_has_rent_epoch(true);
+            *meta = meta.with_flags(&flags);
+        });
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(2)),
+            Err(TieredStorageError::InvalidAccountBlockSize(0, 8))
+        );
+        // the other accounts are not affected
+        assert_matches!(hot_storage.get_account(IndexOffset(1)), Ok(Some(_)));
+
+        // test: the owner offset is beyond the owner count
+        let path = temp_dir.path().join("test_owner_offset_out_of_range");
+        write_hot_storage(&path, &[1, 2]);
+        corrupt_account_meta(&path, IndexOffset(0), |meta| {
+            *meta = meta.with_owner_offset(OwnerOffset(5)).unwrap();
+        });
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_eq!(hot_storage.footer().owner_count, 2);
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(0)),
+            Err(TieredStorageError::OwnerOffsetOutOfRange(5, 2))
+        );
+        assert_matches!(hot_storage.get_account(IndexOffset(1)), Ok(Some(_)));
+
+        // test: the index block points beyond the end of the account blocks
+        let path = temp_dir.path().join("test_account_offset_out_of_bounds");
+        write_hot_storage(&path, &[1, 2, 3]);
+        overwrite_account_offset(
+            &path,
+            IndexOffset(1),
+            HotAccountOffset::new(MAX_HOT_ACCOUNT_OFFSET).unwrap(),
+        );
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(1)),
+            Err(TieredStorageError::OffsetOutOfBounds(..))
+        );
+        assert_matches!(hot_storage.get_account(IndexOffset(2)), Ok(Some(_)));
+    }
+
+    #[test]
+    fn test_hot_storage_buffered_backend() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_buffered_backend");
+        let account_data_sizes: Vec<_> = (0..100).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let mmap_storage = HotStorageReader::new_with_backend(
+            TieredReadableFile::new(&path).unwrap(),
+            ReaderBackend::Mmap,
+        )
+        .unwrap();
+        let buffered_storage = HotStorageReader::new_with_backend(
+            TieredReadableFile::new(&path).unwrap(),
+            ReaderBackend::Buffered,
+        )
+        .unwrap();
+
+        assert_eq!(buffered_storage.footer(), mmap_storage.footer());
+        assert_eq!(buffered_storage.len(), mmap_storage.len());
+        buffered_storage.verify_file_hash().unwrap();
+
+        let mmap_accounts = mmap_storage.accounts(IndexOffset(0)).unwrap();
+        let buffered_accounts = buffered_storage.accounts(IndexOffset(0)).unwrap();
+        assert_eq!(buffered_accounts.len(), account_data_sizes.len());
+        assert_eq!(buffered_accounts.len(), mmap_accounts.len());
+        for (buffered, mmap) in buffered_accounts.iter().zip(mmap_accounts.iter()) {
+            assert_eq!(buffered.pubkey(), mmap.pubkey());
+            assert_eq!(buffered.owner(), mmap.owner());
+            assert_eq!(buffered.lamports(), mmap.lamports());
+            assert_eq!(buffered.data(), mmap.data());
+            assert_eq!(buffered.executable(), mmap.executable());
+            assert_eq!(buffered.rent_epoch(), mmap.rent_epoch());
+            assert_eq!(buffered.hash(), mmap.hash());
+        }
+    }
+
+    #[test_case(ReaderBackend::Mmap)]
+    #[test_case(ReaderBackend::Buffered)]
+    fn test_hot_storage_advise(backend: ReaderBackend) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join(format!("test_hot_storage_advise_{backend:?}"));
+        let account_data_sizes: Vec<_> = (0..10).collect();
+        write_hot_storage(&path, &account_data_sizes);
+        let hot_storage =
+            HotStorageReader::new_with_backend(TieredReadableFile::new(&path).unwrap(), backend)
+                .unwrap();
+        let expected_accounts: Vec<_> = hot_storage
+            .accounts(IndexOffset(0))
+            .unwrap()
+            .iter()
+            .map(|account| (*account.pubkey(), account.data().to_vec()))
+            .collect();
+
+        // The effect of the hints cannot be observed, but none of them may
+        // fail nor change the content, including the released pages.
+        for access in [
+            MmapAccessPattern::Random,
+            MmapAccessPattern::Sequential,
+            MmapAccessPattern::WillNeed,
+            MmapAccessPattern::DontNeed,
+        ] {
+            hot_storage.advise(access).unwrap();
+            let accounts: Vec<_> = hot_storage
+                .accounts(IndexOffset(0))
+                .unwrap()
+                .iter()
+                .map(|account| (*account.pubkey(), account.data().to_vec()))
+                .collect();
+            assert_eq!(accounts, expected_accounts, "{access:?}");
+        }
+    }
+
+    #[test_case(ReaderBackend::Mmap)]
+    #[test_case(ReaderBackend::Buffered)]
+    fn test_hot_storage_scan_sequentially(backend: ReaderBackend) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join(format!("test_hot_storage_scan_sequentially_{backend:?}"));
+        write_hot_storage(&path, &[1, 2, 3]);
+        let hot_storage =
+            HotStorageReader::new_with_backend(TieredReadableFile::new(&path).unwrap(), backend)
+                .unwrap();
+        let advice = || {
+            let advice = hot_storage.access_advice.lock().unwrap();
+            (advice.pattern, advice.num_scans)
+        };
+
+        // nested scans restore the access pattern only after the outer one
+        let num_accounts = hot_storage
+            .scan_sequentially(|| {
+                assert_eq!(advice(), (MmapAccessPattern::Random, 1));
+                let accounts = hot_storage.scan_sequentially(|| {
+                    assert_eq!(advice(), (MmapAccessPattern::Random, 2));
+                    hot_storage.accounts(IndexOffset(0))
+                })?;
+                assert_eq!(advice(), (MmapAccessPattern::Random, 1));
+                Ok(accounts.len())
+            })
+            .unwrap();
+        assert_eq!(num_accounts, 3);
+        assert_eq!(advice(), (MmapAccessPattern::Random, 0));
+
+        // a scan keeps the access pattern set by the caller
+        hot_storage.advise(MmapAccessPattern::Sequential).unwrap();
+        hot_storage.accounts(IndexOffset(0)).unwrap();
+        assert_eq!(advice(), (MmapAccessPattern::Sequential, 0));
+
+        // the access pattern set during a scan takes effect after the scan
+        hot_storage
+            .scan_sequentially(|| {
+                hot_storage.advise(MmapAccessPattern::Random)?;
+                assert_eq!(advice(), (MmapAccessPattern::Random, 1));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(advice(), (MmapAccessPattern::Random, 0));
+
+        // a failed scan also restores the access pattern
+        assert_matches!(
+            hot_storage.scan_sequentially(|| -> TieredStorageResult<()> {
+                Err(TieredStorageError::VerificationSkipped)
+            }),
+            Err(TieredStorageError::VerificationSkipped)
+        );
+        assert_eq!(advice(), (MmapAccessPattern::Random, 0));
+    }
+
+    #[cfg(not(unix))]
+    #[test]
+    fn test_hot_storage_advise_unsupported() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_advise_unsupported");
+        write_hot_storage(&path, &[1, 2, 3]);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+        // without madvise(), every hint is a no-op
+        for access in [
+            MmapAccessPattern::Random,
+            MmapAccessPattern::Sequential,
+            MmapAccessPattern::WillNeed,
+            MmapAccessPattern::DontNeed,
+        ] {
+            assert_matches!(hot_storage.advise(access), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_pubkey_in_range() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // test: no pubkey is in the range of a file without any account
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_pubkey_in_range_empty");
+        write_hot_storage(&path, &[]);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert!(!hot_storage.pubkey_in_range(&Pubkey::default()));
+        assert!(!hot_storage.pubkey_in_range(&Pubkey::new_unique()));
+
+        let path = temp_dir.path().join("test_hot_storage_pubkey_in_range");
+        write_hot_storage(&path, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0]);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+        // the footer range matches the actual min and max of the stored pubkeys
+        let addresses = hot_storage.account_addresses().unwrap();
+        let min = addresses.iter().min().unwrap();
+        let max = addresses.iter().max().unwrap();
+        assert_eq!(hot_storage.footer().min_account_address, *min);
+        assert_eq!(hot_storage.footer().max_account_address, *max);
+
+        for address in addresses {
+            assert!(hot_storage.pubkey_in_range(address));
+        }
+
+        // test: pubkeys right outside the range
+        let mut below_min = min.to_bytes();
+        let mut above_max = max.to_bytes();
+        assert!(below_min != [0u8; 32] && above_max != [0xFF; 32]);
+        *below_min.iter_mut().rev().find(|byte| **byte > 0).unwrap() -= 1;
+        *above_max
+            .iter_mut()
+            .rev()
+            .find(|byte| **byte < 0xFF)
+            .unwrap() += 1;
+        assert!(!hot_storage.pubkey_in_range(&Pubkey::from(below_min)));
+        assert!(!hot_storage.pubkey_in_range(&Pubkey::from(above_max)));
+    }
+
+    /// Asserts the data size of every account inside the specified hot
+    /// storage matches the data size the writer was given.
+    fn assert_account_data_sizes(path: impl AsRef<Path>, account_data_sizes: &[u64]) {
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_eq!(hot_storage.num_accounts(), account_data_sizes.len());
+        for (i, data_size) in account_data_sizes.iter().enumerate() {
+            let (account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(account.data().len() as u64, *data_size);
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_account_blocks_end() {
+        let temp_dir = TempDir::new().unwrap();
+        for (name, account_data_sizes) in [
+            ("empty", &[][..]),
+            ("single_account", &[9]),
+            ("last_account_with_data", &[0, 7, 1000]),
+            ("last_account_without_data", &[1000, 7, 0]),
+        ] {
+            let path = temp_dir
+                .path()
+                .join(format!("test_hot_storage_account_blocks_end_{name}"));
+            write_hot_storage(&path, account_data_sizes);
+
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            assert_eq!(
+                hot_storage.account_blocks_end(),
+                hot_storage.footer().index_block_offset as usize
+            );
+            assert_account_data_sizes(&path, account_data_sizes);
+
+            // the accounts exactly cover the account blocks
+            let stored_size: usize = hot_storage
+                .accounts(IndexOffset(0))
+                .unwrap()
+                .iter()
+                .map(|account| account.stored_size())
+                .sum();
+            assert_eq!(stored_size, hot_storage.account_blocks_end());
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_verify_file_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_verify_file_hash");
+        write_hot_storage(&path, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0]);
+        {
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            hot_storage.verify_file_hash().unwrap();
+        }
+
+        // flip a byte of the first account's data
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[std::mem::size_of::<HotAccountMeta>()] ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.verify_file_hash(),
+            Err(TieredStorageError::HashMismatch(hash, footer_hash))
+                if hash != footer_hash && footer_hash == hot_storage.footer().hash
+        );
+    }
+
+    #[test_case(FooterHashKind::Sha256)]
+    #[test_case(FooterHashKind::Blake3)]
+    fn test_hot_storage_hash_kind(hash_kind: FooterHashKind) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_hash_kind");
+        write_hot_storage_with_hash_kind(&path, &[1, 2, 3, 0, 100], hash_kind);
+        {
+            // the persisted kind selects the algorithm to verify with
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            assert_eq!(hot_storage.footer().hash_kind, hash_kind);
+            hot_storage.verify_file_hash().unwrap();
+            let state = hot_storage.verify_prefix(u64::MAX);
+            assert_eq!(state.status(), VerificationStatus::Verified);
+        }
+
+        // flip a byte of the first account's data
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[std::mem::size_of::<HotAccountMeta>()] ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.verify_file_hash(),
+            Err(TieredStorageError::HashMismatch(..))
+        );
+        let state = hot_storage.verify_prefix(u64::MAX);
+        assert_matches!(state.status(), VerificationStatus::Mismatch { .. });
+    }
+
+    #[test]
+    fn test_hot_storage_hash_kind_none() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_hash_kind_none");
+        write_hot_storage_with_hash_kind(&path, &[1, 2, 3, 0, 100], FooterHashKind::None);
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_eq!(hot_storage.footer().hash_kind, FooterHashKind::None);
+        assert_eq!(hot_storage.footer().hash, Hash::default());
+        assert_matches!(
+            hot_storage.verify_file_hash(),
+            Err(TieredStorageError::VerificationSkipped)
+        );
+        let state = hot_storage.verify_prefix(0);
+        assert_eq!(state.status(), VerificationStatus::Skipped);
+        assert!(state.is_complete());
+        assert_eq!(state.verified_len(), 0);
+    }
+
+    #[test]
+    fn test_hot_storage_incremental_verification() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_incremental_verification");
+        let account_data_sizes: Vec<_> = (0..100).collect();
+        write_hot_storage(&path, &account_data_sizes);
+
+        let verify_in_steps = |hot_storage: &HotStorageReader, budget: u64| {
+            let mut state = hot_storage.verify_prefix(budget);
+            let mut num_steps = 1;
+            while !state.is_complete() {
+                state = hot_storage.resume_verification(state, budget);
+                num_steps += 1;
+            }
+            (state, num_steps)
+        };
+
+        let hashed_len = {
+            let hot_storage =
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+            let hashed_len = hot_storage.len() - hot_storage.footer().footer_size as usize;
+            for budget in [1, 7, 100, 4096, hashed_len as u64, u64::MAX] {
+                let (state, _) = verify_in_steps(&hot_storage, budget);
+                assert_eq!(state.verified_len(), hashed_len);
+                assert_eq!(state.status(), VerificationStatus::Verified);
+            }
+            hashed_len
+        };
+
+        // flip a byte beyond the first budget
+        let budget = 1024;
+        let corrupted_offset = budget * 2 + 5;
+        assert!(corrupted_offset < hashed_len);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[corrupted_offset] ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.verify_file_hash(),
+            Err(TieredStorageError::HashMismatch(..))
+        );
+
+        // the first step does not cover the corruption
+        let state = hot_storage.verify_prefix(budget as u64);
+        assert_eq!(state.verified_len(), budget);
+        assert_eq!(state.status(), VerificationStatus::InProgress);
+
+        // zero-budget steps keep the state as is
+        let state = hot_storage.resume_verification(state, 0);
+        assert_eq!(state.verified_len(), budget);
+        assert_eq!(state.status(), VerificationStatus::InProgress);
+
+        // the step covering the rest of the file reports the mismatch
+        let state = hot_storage.resume_verification(state, u64::MAX);
+        assert_eq!(state.verified_len(), hashed_len);
+        assert_matches!(
+            state.status(),
+            VerificationStatus::Mismatch { offset, hash, footer_hash }
+                if offset == budget
+                    && offset <= corrupted_offset
+                    && hash != footer_hash
+                    && footer_hash == hot_storage.footer().hash
+        );
+
+        let (state, num_steps) = verify_in_steps(&hot_storage, budget as u64);
+        assert_eq!(num_steps, hashed_len.div_ceil(budget));
+        assert_matches!(state.status(), VerificationStatus::Mismatch { .. });
+    }
+
+    #[test]
+    fn test_hot_storage_find_account() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // test: empty storage
+        let path = temp_dir.path().join("test_hot_storage_find_account_empty");
+        write_hot_storage(&path, &[]);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert!(hot_storage
+            .find_account(&Pubkey::default())
+            .unwrap()
+            .is_none());
+        assert!(hot_storage
+            .find_account(&Pubkey::new_unique())
+            .unwrap()
+            .is_none());
+
+        let path = temp_dir.path().join("test_hot_storage_find_account");
+        let account_data_sizes = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0];
+        write_hot_storage(&path, account_data_sizes);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+
+        // test: hit, including the first and the last entries
+        let addresses = hot_storage.account_addresses().unwrap();
+        for (i, address) in addresses.iter().enumerate() {
+            let (account, index_offset) = hot_storage.find_account(address).unwrap().unwrap();
+            let (expected, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(index_offset, IndexOffset(i as u32));
+            assert_eq!(account.pubkey(), address);
+            assert_eq!(account.lamports(), expected.lamports());
+            assert_eq!(account.data(), expected.data());
+        }
+
+        // test: miss
+        assert!(hot_storage
+            .find_account(&Pubkey::new_unique())
+            .unwrap()
+            .is_none());
+        assert!(hot_storage
+            .find_account(&Pubkey::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_hot_storage_find_account_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_find_account_duplicates");
+
+        // The same address is written three times with different lamports.
+        let address = Pubkey::new_unique();
+        let other_address = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let accounts: Vec<_> = (1..=4)
+            .map(|lamports| AccountSharedData::new(lamports, 0, &owner))
+            .collect();
+        let addresses = [other_address, address, address, address];
+        let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+            .take(account_refs.len())
+            .collect();
+        let write_versions: Vec<_> = (0..account_refs.len() as u64).collect();
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                hashes,
+                write_versions,
+            );
+        {
+            let mut writer = HotStorageWriter::new(&path).unwrap();
+            writer.write_accounts(&storable_accounts, 0).unwrap();
+        }
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let (account, index_offset) = hot_storage.find_account(&address).unwrap().unwrap();
+        assert_eq!(index_offset, IndexOffset(1));
+        assert_eq!(account.lamports(), 2);
+    }
+
+    #[test]
+    fn test_hot_storage_self_owned_accounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_self_owned_accounts");
+
+        // A self-owned loader, many accounts owned by it, and accounts that
+        // each own themselves.
+        let loader = Pubkey::new_unique();
+        let mut addresses = vec![loader];
+        let mut accounts = vec![AccountSharedData::new(1, 8, &loader)];
+        for i in 0..20 {
+            let address = Pubkey::new_unique();
+            let owner = if i % 4 == 0 { address } else { loader };
+            addresses.push(address);
+            accounts.push(AccountSharedData::new(i + 2, i as usize, &owner));
+        }
+        let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let hashes = vec![AccountHash(Hash::default()); account_refs.len()];
+        let write_versions = vec![0; account_refs.len()];
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                hashes,
+                write_versions,
+            );
+        {
+            let mut writer = HotStorageWriter::new(&path).unwrap();
+            writer.write_accounts(&storable_accounts, 0).unwrap();
+        }
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        // the loader plus the 5 accounts that own themselves
+        assert_eq!(hot_storage.owner_count(), 6);
+        assert_eq!(hot_storage.owners().unwrap()[0], loader);
+
+        let stored_accounts = hot_storage.accounts(IndexOffset(0)).unwrap();
+        assert_eq!(stored_accounts.len(), accounts.len());
+        for ((stored_account, address), account) in
+            stored_accounts.iter().zip(&addresses).zip(&accounts)
+        {
+            assert_eq!(stored_account.pubkey(), address);
+            assert_eq!(stored_account.owner(), account.owner());
+            assert_eq!(stored_account.lamports(), account.lamports());
+            assert_eq!(stored_account.data(), account.data());
+        }
+    }
+
+    #[test_case(HOT_FORMAT)]
+    #[test_case(HOT_SORTED_FORMAT)]
+    #[test_case(HOT_SIZED_INDEX_FORMAT)]
+    #[test_case(HOT_OWNER_COUNTS_FORMAT)]
+    #[test_case(TieredStorageFormat::builder().with_hash_kind(FooterHashKind::Blake3).build().unwrap())]
+    #[test_case(TieredStorageFormat::builder().with_hash_kind(FooterHashKind::None).build().unwrap())]
+    fn test_hot_storage_deterministic_output(format: ValidatedTieredStorageFormat) {
+        const NUM_ACCOUNTS: u64 = 20;
+        let temp_dir = TempDir::new().unwrap();
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS)
+            .map(|seed| {
+                let (stored_meta, mut account) = create_test_account(seed);
+                account.set_owner(owners[seed as usize % owners.len()]);
+                (stored_meta, account)
+            })
+            .collect();
+
+        // test: the same batch written twice results in identical files
+        let paths = ["first", "second"].map(|name| {
+            let path = temp_dir
+                .path()
+                .join(format!("test_hot_storage_deterministic_output_{name}"));
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                format.index_block_format,
+                format.hash_kind,
+                &WriteOptions::LATEST,
+            )
+            .unwrap()
+            .with_owners_block_format(format.owners_block_format);
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); accounts.len()],
+                vec![0; accounts.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+            path
+        });
+        assert_eq!(fs::read(&paths[0]).unwrap(), fs::read(&paths[1]).unwrap());
+
+        let hot_storage =
+            HotStorageReader::new(TieredReadableFile::new(&paths[0]).unwrap()).unwrap();
+        assert_eq!(
+            hot_storage.footer().owners_block_format,
+            format.owners_block_format
+        );
+        // the owners are ordered by their first occurrence in the file
+        let mut written_accounts: Vec<_> = accounts.iter().collect();
+        if format.index_block_format.is_sorted() {
+            written_accounts.sort_by_key(|(stored_meta, _)| stored_meta.pubkey);
+        }
+        let mut expected_owners = vec![];
+        for (_, account) in written_accounts {
+            if !expected_owners.contains(account.owner()) {
+                expected_owners.push(*account.owner());
+            }
+        }
+        assert_eq!(hot_storage.owners().unwrap(), expected_owners);
+    }
+
+    #[test_case(IndexBlockFormat::AddressesThenOffsets)]
+    #[test_case(IndexBlockFormat::SortedAddressesThenOffsets)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsetsThenSizes)]
+    fn test_hot_storage_account_offset_cache(index_block_format: IndexBlockFormat) {
+        const NUM_ACCOUNTS: u64 = 30;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(format!(
+            "test_hot_storage_account_offset_cache_{index_block_format:?}"
+        ));
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS).map(create_test_account).collect();
+        let mut writer = HotStorageWriter::new_with_options(
+            &path,
+            index_block_format,
+            HOT_FORMAT.hash_kind,
+            &WriteOptions::LATEST,
+        )
+        .unwrap();
+        with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::default()); accounts.len()],
+            vec![0; accounts.len()],
+            |storable_accounts| writer.write_accounts(storable_accounts, 0),
+        )
+        .unwrap();
+        drop(writer);
+
+        let new_reader = |cache_account_offsets| {
+            HotStorageReader::new_with_offset_cache(
+                TieredReadableFile::new(&path).unwrap(),
+                ReaderBackend::Mmap,
+                cache_account_offsets,
+            )
+            .unwrap()
+        };
+        let cached = new_reader(true);
+        let direct = new_reader(false);
+
+        // test: the cached lookups return what the index block stores
+        for i in 0..NUM_ACCOUNTS as u32 {
+            let index_offset = IndexOffset(i);
+            let account_offset = direct.get_account_offset(index_offset).unwrap();
+            assert_eq!(
+                cached.get_account_offset(index_offset).unwrap(),
+                account_offset
+            );
+            assert_eq!(
+                cached
+                    .get_account_block_size(account_offset, index_offset)
+                    .unwrap(),
+                direct
+                    .get_account_block_size(account_offset, index_offset)
+                    .unwrap()
+            );
+            let (cached_account, cached_next) = cached.get_account(index_offset).unwrap().unwrap();
+            let (direct_account, direct_next) = direct.get_account(index_offset).unwrap().unwrap();
+            assert_eq!(cached_account.pubkey(), direct_account.pubkey());
+            assert_eq!(cached_account.data(), direct_account.data());
+            assert_eq!(cached_next, direct_next);
+        }
+        assert_matches!(
+            cached.get_account(IndexOffset(NUM_ACCOUNTS as u32)),
+            Ok(None)
+        );
+        assert_eq!(cached.account_offsets_builds.load(Ordering::Relaxed), 1);
+        assert_eq!(direct.account_offsets_builds.load(Ordering::Relaxed), 0);
+
+        // test: concurrent first accesses build the cache only once
+        let cached = new_reader(true);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for i in 0..NUM_ACCOUNTS as u32 {
+                        cached.get_account_offset(IndexOffset(i)).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(cached.account_offsets_builds.load(Ordering::Relaxed), 1);
+    }
+
+    #[test_case(HOT_FORMAT)]
+    #[test_case(HOT_SIZED_INDEX_FORMAT)]
+    fn test_hot_storage_large_accounts(format: ValidatedTieredStorageFormat) {
+        let data_sizes = [
+            MAX_PERMITTED_DATA_LENGTH as usize,
+            1,
+            0,
+            HOT_BUFFERED_DATA_THRESHOLD,
+            HOT_BUFFERED_DATA_THRESHOLD + 3,
+            100,
+        ];
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_large_accounts");
+        let accounts: Vec<_> = data_sizes
+            .iter()
+            .enumerate()
+            .map(|(i, data_size)| {
+                let (stored_meta, mut account) = create_test_account(i as u64 + 1);
+                account.set_data((0..*data_size).map(|j| (i + j) as u8).collect());
+                (stored_meta, account)
+            })
+            .collect();
+        let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+            .take(accounts.len())
+            .collect();
+
+        let mut writer = HotStorageWriter::new_with_options(
+            &path,
+            format.index_block_format,
+            format.hash_kind,
+            &WriteOptions::LATEST,
+        )
+        .unwrap();
+        let stored_infos = with_storable_accounts(
+            &accounts,
+            hashes.clone(),
+            vec![0; accounts.len()],
+            |storable_accounts| writer.write_accounts(storable_accounts, 0),
+        )
+        .unwrap();
+        // test: the data of the large accounts is never buffered
+        assert_eq!(writer.peak_buffered_bytes(), HOT_BUFFERED_DATA_THRESHOLD);
+        drop(writer);
+
+        // test: every account, including its padding and optional fields,
+        // reads back as written
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let account_sizes = hot_storage.account_sizes().unwrap();
+        for (i, ((stored_meta, account), stored_info)) in
+            accounts.iter().zip(&stored_infos).enumerate()
+        {
+            let index_offset = IndexOffset(i as u32);
+            let (stored_account, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert_eq!(stored_account.lamports(), account.lamports());
+            assert_eq!(stored_account.data(), account.data());
+            assert_eq!(stored_account.rent_epoch(), account.rent_epoch());
+            assert_eq!(stored_account.hash(), &hashes[i]);
+
+            let account_offset = hot_storage.get_account_offset(index_offset).unwrap();
+            let meta = hot_storage
+                .get_account_meta_from_offset(account_offset)
+                .unwrap();
+            assert_eq!(
+                meta.account_data_padding(),
+                padded_data_layout(account.data().len(), HOT_ACCOUNT_ALIGNMENT).padding
+            );
+            assert_eq!(account_sizes[i], stored_info.size);
+        }
+        // the 10MB account is the largest account of the file
+        let index_entry_size = format.index_block_format.entry_size::<HotAccountOffset>();
+        assert_eq!(
+            hot_storage.footer().account_block_size as usize,
+            stored_infos[0].size - index_entry_size
+        );
+        assert!(hot_storage.verify().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_hot_storage_buffered_writes() {
+        const NUM_ACCOUNTS: usize = 1000;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_buffered_writes");
+        let accounts: Vec<_> = (0..NUM_ACCOUNTS)
+            .map(|i| create_test_account(i as u64 + 1))
+            .collect();
+
+        let mut writer = HotStorageWriter::new(&path).unwrap();
+        with_storable_accounts(
+            &accounts,
+            vec![AccountHash(Hash::new_unique()); NUM_ACCOUNTS],
+            vec![0; NUM_ACCOUNTS],
+            |storable_accounts| writer.write_accounts(storable_accounts, 0),
+        )
+        .unwrap();
+        let num_writes = writer.storage.num_writes();
+        writer.finish().unwrap();
+
+        // test: the small writes of each account are gathered into writes
+        // of the buffer size instead of reaching the file one by one
+        let file_size = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(num_writes <= 2 * file_size.div_ceil(WRITE_BUFFER_SIZE));
+
+        // test: every account is readable once the writer is finished
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_eq!(hot_storage.num_accounts(), NUM_ACCOUNTS);
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let (stored_account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert_eq!(stored_account.data(), account.data());
+        }
+        assert!(hot_storage.verify().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_hot_storage_shared_owners_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+        let write = |name: &str, owner_indexes: &[usize], seed: OwnersTable| {
+            let accounts: Vec<_> = owner_indexes
+                .iter()
+                .map(|&i| AccountSharedData::new(1, 0, &owners[i]))
+                .collect();
+            let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(accounts.len())
+                .collect();
+            let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+            let account_data = (Slot::MAX, &account_refs[..]);
+            let storable_accounts =
+                StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                    &account_data,
+                    vec![AccountHash(Hash::default()); accounts.len()],
+                    vec![0; accounts.len()],
+                );
+            let path = temp_dir.path().join(name);
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                HOT_FORMAT.index_block_format,
+                HOT_FORMAT.hash_kind,
+                &WriteOptions::LATEST,
+            )
+            .unwrap()
+            .with_owners_block_format(OwnersBlockFormat::LocalIndexWithCounts)
+            .with_owners_table(seed);
+            writer.write_accounts(&storable_accounts, 0).unwrap();
+            let owners_table = writer.owners_table().clone();
+            writer.finish().unwrap();
+            (
+                HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap(),
+                owners_table,
+            )
+        };
+
+        let (first, owners_table) = write("first", &[0, 1, 1], OwnersTable::default());
+        assert_eq!(first.owners().unwrap(), &owners[..2]);
+        assert_eq!(owners_table.len(), 2);
+
+        // test: the second file keeps the owner offsets of the first file,
+        // while its owner counts only cover its own accounts
+        let (second, _) = write("second", &[2, 1], owners_table);
+        assert_eq!(second.owners().unwrap(), &[owners[0], owners[1], owners[2]]);
+        assert_eq!(second.owner_account_count(&owners[0]).unwrap(), Some(0));
+        assert_eq!(second.owner_account_count(&owners[1]).unwrap(), Some(1));
+        assert_eq!(second.owner_account_count(&owners[2]).unwrap(), Some(1));
+        for (i, owner) in [owners[2], owners[1]].iter().enumerate() {
+            let (account, _) = second.get_account(IndexOffset(i as u32)).unwrap().unwrap();
+            assert_eq!(account.owner(), owner);
+        }
+        assert!(second.verify().unwrap().is_consistent());
+    }
+
+    #[test_case(false)]
+    #[test_case(true)]
+    fn test_hot_storage_block_checksums(block_checksums: bool) {
+        let data_sizes = [0, 1, 8, 100, HOT_BUFFERED_DATA_THRESHOLD + 5];
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(format!(
+            "test_hot_storage_block_checksums_{block_checksums}"
+        ));
+        let accounts: Vec<_> = data_sizes
+            .iter()
+            .enumerate()
+            .map(|(i, data_size)| {
+                // the rent epochs and the hashes vary, so the accounts
+                // cover all the combinations of the optional fields
+                let (stored_meta, mut account) = create_test_account(i as u64 + 1);
+                account.set_data(vec![i as u8; *data_size]);
+                (stored_meta, account)
+            })
+            .collect();
+        let hashes: Vec<_> = (0..accounts.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    AccountHash(Hash::new_unique())
+                } else {
+                    AccountHash(Hash::default())
+                }
+            })
+            .collect();
+        let mut writer = HotStorageWriter::new_with_options(
+            &path,
+            HOT_FORMAT.index_block_format,
+            HOT_FORMAT.hash_kind,
+            &WriteOptions::LATEST,
+        )
+        .unwrap()
+        .with_block_checksums(block_checksums);
+        with_storable_accounts(
+            &accounts,
+            hashes.clone(),
+            vec![0; accounts.len()],
+            |storable_accounts| writer.write_accounts(storable_accounts, 0),
+        )
+        .unwrap();
+        drop(writer);
+
+        // test: every account reads back as written, and its checksum is
+        // verified only if it is persisted
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let (stored_account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored_account.pubkey(), &stored_meta.pubkey);
+            assert_eq!(stored_account.data(), account.data());
+            assert_eq!(stored_account.rent_epoch(), account.rent_epoch());
+            assert_eq!(stored_account.hash(), &hashes[i]);
+            assert_eq!(
+                stored_account.verify_checksum(),
+                block_checksums.then_some(true)
+            );
+        }
+        assert!(hot_storage.verify().unwrap().is_consistent());
+        let account_offset = hot_storage.get_account_offset(IndexOffset(3)).unwrap();
+        drop(hot_storage);
+
+        // test: flipping a data byte fails the checksum of its account only
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(
+            (account_offset.offset() + std::mem::size_of::<HotAccountMeta>()) as u64,
+        ))
+        .unwrap();
+        file.write_all(&[!3u8]).unwrap();
+        drop(file);
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        for i in 0..accounts.len() {
+            let (stored_account, _) = hot_storage
+                .get_account(IndexOffset(i as u32))
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                stored_account.verify_checksum(),
+                block_checksums.then_some(i != 3)
+            );
+        }
+        let inconsistencies = hot_storage.verify().unwrap().inconsistencies;
+        assert_eq!(
+            inconsistencies.contains(&Inconsistency::BlockChecksumMismatch(IndexOffset(3))),
+            block_checksums
+        );
+    }
+
+    #[test_case(IndexBlockFormat::AddressesThenOffsets)]
+    #[test_case(IndexBlockFormat::SortedAddressesThenOffsets)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsetsThenSizes)]
+    fn test_hot_storage_contains_any(index_block_format: IndexBlockFormat) {
+        const NUM_ACCOUNTS: usize = 20;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(format!(
+            "test_hot_storage_contains_any_{index_block_format:?}"
+        ));
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS as u64).map(create_test_account).collect();
+        {
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                index_block_format,
+                HOT_FORMAT.hash_kind,
+                &WriteOptions::LATEST,
+            )
+            .unwrap();
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); NUM_ACCOUNTS],
+                vec![0; NUM_ACCOUNTS],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+        }
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_eq!(hot_storage.footer().index_block_format, index_block_format);
+        let stored: Vec<_> = accounts
+            .iter()
+            .map(|(stored_meta, _)| stored_meta.pubkey)
+            .collect();
+        let absent: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(5).collect();
+
+        // test: no query
+        assert!(hot_storage.contains_any(&[]).unwrap().is_empty());
+
+        // test: none of the queries are stored
+        assert_eq!(hot_storage.contains_any(&absent).unwrap(), vec![false; 5]);
+        assert!(absent.iter().all(|pubkey| !hot_storage.contains(pubkey)));
+
+        // test: all of the queries are stored, in a different order
+        let mut queries = stored.clone();
+        queries.reverse();
+        assert_eq!(
+            hot_storage.contains_any(&queries).unwrap(),
+            vec![true; NUM_ACCOUNTS]
+        );
+        assert!(stored.iter().all(|pubkey| hot_storage.contains(pubkey)));
+
+        // test: some of the queries are stored, with duplicates
+        let queries = [
+            stored[3], absent[0], stored[3], absent[0], stored[19], stored[0],
+        ];
+        assert_eq!(
+            hot_storage.contains_any(&queries).unwrap(),
+            vec![true, false, true, false, true, true]
+        );
+    }
+
+    #[test_case(IndexBlockFormat::AddressesThenOffsets, 10)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsets, 11)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsetsThenSizes, 11)]
+    fn test_hot_storage_account_sizes(index_block_format: IndexBlockFormat, num_accounts: u64) {
+        // zero-lamport accounts and accounts with rent epochs are included
+        let accounts: Vec<_> = (0..num_accounts).map(create_test_account).collect();
+        let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+            .take(accounts.len())
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_account_sizes");
+        let stored_infos = {
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                index_block_format,
+                HOT_FORMAT.hash_kind,
+                &WriteOptions::LATEST,
+            )
+            .unwrap();
+            with_storable_accounts(
+                &accounts,
+                hashes,
+                vec![0; accounts.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap()
+        };
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let account_sizes = hot_storage.account_sizes().unwrap();
+        assert_eq!(account_sizes.len(), accounts.len());
+        for (i, (account_size, stored_info)) in account_sizes.iter().zip(&stored_infos).enumerate()
+        {
+            assert_eq!(stored_info.offset, i);
+            assert_eq!(*account_size, stored_info.size);
+        }
+
+        // the accounts, the padding after the index block, the owners block,
+        // and the footer (which includes the magic number) make up the file
+        let footer = hot_storage.footer();
+        let index_block_size = index_block_format.entry_size::<HotAccountOffset>() * accounts.len();
+        let index_block_padding =
+            index_block_size.next_multiple_of(HOT_BLOCK_ALIGNMENT) - index_block_size;
+        let owners_block_size =
+            footer.owners_block_format.entry_size() * footer.owner_count as usize;
+        assert_eq!(
+            hot_storage.total_account_bytes().unwrap()
+                + index_block_padding
+                + owners_block_size
+                + footer.footer_size as usize,
+            fs::metadata(&path).unwrap().len() as usize
+        );
+    }
+
+    #[test_case(IndexBlockFormat::AddressesThenOffsets, 0)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsets, 11)]
+    #[test_case(IndexBlockFormat::AddressesThenOffsetsThenSizes, 11)]
+    fn test_hot_storage_writer_stats(index_block_format: IndexBlockFormat, num_accounts: u64) {
+        // zero-lamport accounts and accounts with rent epochs are included,
+        // and every other account persists its account hash
+        let accounts: Vec<_> = (0..num_accounts).map(create_test_account).collect();
+        let hashes: Vec<_> = (0..num_accounts)
+            .map(|i| {
+                if i % 2 == 0 {
+                    AccountHash(Hash::new_unique())
+                } else {
+                    AccountHash(Hash::default())
+                }
+            })
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_writer_stats");
+        let stats = {
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                index_block_format,
+                HOT_FORMAT.hash_kind,
+                &WriteOptions::LATEST,
+            )
+            .unwrap();
+            with_storable_accounts(
+                &accounts,
+                hashes,
+                vec![0; accounts.len()],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+            writer.stats().clone()
+        };
+
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let footer = hot_storage.footer();
+        let num_accounts = num_accounts as usize;
+        assert_eq!(stats.num_accounts, num_accounts);
+        assert_eq!(
+            stats.account_meta_bytes,
+            std::mem::size_of::<HotAccountMeta>() * num_accounts
+        );
+        assert_eq!(
+            stats.account_data_bytes,
+            accounts
+                .iter()
+                .map(|(_, account)| account.data().len())
+                .sum::<usize>()
+        );
+        assert_eq!(
+            stats.padding_bytes,
+            accounts
+                .iter()
+                .map(|(_, account)| {
+                    padded_data_layout(account.data().len(), HOT_ACCOUNT_ALIGNMENT).padding as usize
+                })
+                .sum::<usize>()
+        );
+        // the optional fields are what follows the padding
+        let optional_fields_bytes: usize = (0..num_accounts)
+            .map(|i| {
+                let (meta, account_block) =
+                    hot_storage.get_raw_account(IndexOffset(i as u32)).unwrap();
+                account_block.len()
+                    - meta.account_data(account_block).unwrap().len()
+                    - meta.account_data_padding() as usize
+            })
+            .sum();
+        assert_eq!(stats.optional_fields_bytes, optional_fields_bytes);
+        assert_eq!(stats.num_unique_owners, footer.owner_count as usize);
+        assert_eq!(stats.num_total_owners, num_accounts);
+
+        // each block is accounted for, and all of them make up the file
+        assert_eq!(
+            stats.account_meta_bytes
+                + stats.account_data_bytes
+                + stats.padding_bytes
+                + stats.optional_fields_bytes,
+            footer.index_block_offset as usize
+        );
+        assert_eq!(
+            stats.index_block_bytes,
+            (footer.owners_block_offset - footer.index_block_offset) as usize
+        );
+        assert_eq!(
+            stats.owners_block_bytes,
+            footer.owners_block_format.entry_size() * footer.owner_count as usize
+        );
+        assert_eq!(stats.footer_bytes, footer.footer_size as usize);
+        assert_eq!(
+            stats.total_bytes(),
+            fs::metadata(&path).unwrap().len() as usize
+        );
+    }
+
+    #[test]
+    fn test_hot_storage_sized_index() {
+        const NUM_ACCOUNTS: usize = 20;
+        let temp_dir = TempDir::new().unwrap();
+        let accounts: Vec<_> = (1..=NUM_ACCOUNTS as u64).map(create_test_account).collect();
+        let write = |name: &str, index_block_format, options: &WriteOptions| {
+            let path = temp_dir.path().join(name);
+            let mut writer = HotStorageWriter::new_with_options(
+                &path,
+                index_block_format,
+                HOT_FORMAT.hash_kind,
+                options,
+            )
+            .unwrap();
+            let stored_infos = with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default()); NUM_ACCOUNTS],
+                vec![0; NUM_ACCOUNTS],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+            (path, stored_infos)
+        };
+
+        let (path, stored_infos) = write(
+            "sized",
+            IndexBlockFormat::AddressesThenOffsetsThenSizes,
+            &WriteOptions::LATEST,
+        );
+        let (baseline_path, baseline_infos) = write(
+            "baseline",
+            IndexBlockFormat::AddressesThenOffsets,
+            &WriteOptions::LATEST,
+        );
+        for (stored_info, baseline_info) in stored_infos.iter().zip(baseline_infos.iter()) {
+            assert_eq!(stored_info.offset, baseline_info.offset);
+            assert_eq!(
+                stored_info.size,
+                baseline_info.size + std::mem::size_of::<u32>()
+            );
+        }
+
+        // both formats produce the same accounts
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        let baseline =
+            HotStorageReader::new(TieredReadableFile::new(&baseline_path).unwrap()).unwrap();
+        assert_eq!(
+            hot_storage.footer().index_block_format,
+            IndexBlockFormat::AddressesThenOffsetsThenSizes
+        );
+        for (i, (stored_meta, account)) in accounts.iter().enumerate() {
+            let index_offset = IndexOffset(i as u32);
+            assert_eq!(
+                hot_storage.get_raw_account(index_offset).unwrap(),
+                baseline.get_raw_account(index_offset).unwrap()
+            );
+            let (account_meta, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+            verify_test_account(
+                &account_meta,
+                Some(account),
+                &stored_meta.pubkey,
+                &AccountHash(Hash::default()),
+            );
+        }
+
+        // only the index block differs
+        let index_block_offset = hot_storage.footer().index_block_offset as usize;
+        assert_eq!(
+            index_block_offset,
+            baseline.footer().index_block_offset as usize
+        );
+        assert_eq!(
+            fs::read(&path).unwrap()[..index_block_offset],
+            fs::read(&baseline_path).unwrap()[..index_block_offset]
+        );
+
+        // an older generation gets the index block without the sizes
+        let (downgraded_path, _) = write(
+            "downgraded",
+            IndexBlockFormat::AddressesThenOffsetsThenSizes,
+            &WriteOptions {
+                max_format_generation: FormatGeneration::V3SortedIndex,
+            },
+        );
+        assert_eq!(
+            fs::read(&downgraded_path).unwrap(),
+            fs::read(&baseline_path).unwrap()
+        );
+
+        // a stored size that exceeds the account blocks is rejected
+        let last_size_offset = hot_storage.footer().owners_block_offset - 4;
+        drop(hot_storage);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(last_size_offset)).unwrap();
+        file.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        drop(file);
+        let hot_storage = HotStorageReader::new(TieredReadableFile::new(&path).unwrap()).unwrap();
+        assert_matches!(
+            hot_storage.get_account(IndexOffset(NUM_ACCOUNTS as u32 - 1)),
+            Err(TieredStorageError::InvalidAccountBlock(..))
+        );
+    }
+
+    #[test]
+    fn test_hot_storage_writer_default_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_writer_default_options");
+        let accounts = [create_test_account(7)];
+        let write = |mut writer: HotStorageWriter| {
+            with_storable_accounts(
+                &accounts,
+                vec![AccountHash(Hash::default())],
+                vec![0],
+                |storable_accounts| writer.write_accounts(storable_accounts, 0),
+            )
+            .unwrap();
+        };
+
+        // test: the plain constructors write the baseline generation, like
+        // TieredStorage with the default options
+        write(HotStorageWriter::new(&path).unwrap());
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        assert_eq!(reader.footer().format_version, 1);
+        assert_eq!(reader.required_generation(), FormatGeneration::V1Baseline);
+
+        // test: a format of a newer generation is downgraded or rejected
+        let path = temp_dir.path().join("test_hot_storage_writer_blake3");
+        write(
+            HotStorageWriter::new_with_formats(
+                &path,
+                HOT_FORMAT.index_block_format,
+                FooterHashKind::Blake3,
+            )
+            .unwrap(),
+        );
+        let reader = TieredStorageReader::new_from_path(&path).unwrap();
+        assert_eq!(reader.footer().format_version, 1);
+        assert_eq!(reader.footer().hash_kind, FooterHashKind::None);
+        assert_matches!(
+            HotStorageWriter::new_with_index_block_format(
+                temp_dir.path().join("test_hot_storage_writer_sorted"),
+                IndexBlockFormat::SortedAddressesThenOffsets,
+            ),
+            Err(TieredStorageError::FormatGenerationExceeded(
+                FormatFeature::SortedIndex,
+                FormatGeneration::V1Baseline,
+            ))
+        );
+    }
+
+    #[test]
+    fn test_hot_storage_baseline_generation_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_hot_storage_baseline_generation_flags");
+        // every optional structure of the account blocks is requested, and
+        // every account has its hash calculated
+        let format = TieredStorageFormat::builder()
+            .with_block_checksums(true)
+            .build()
+            .unwrap();
+        let accounts: Vec<_> = (0..20).map(create_test_account).collect();
+        let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+            .take(accounts.len())
+            .collect();
+
+        // the default options only allow the baseline generation
+        let tiered_storage = TieredStorage::new_writable(path);
+        with_storable_accounts(
+            &accounts,
+            hashes,
+            vec![0; accounts.len()],
+            |storable_accounts| tiered_storage.write_accounts(storable_accounts, 0, &format),
+        )
+        .unwrap();
+
+        // test: the accounts only carry the flags that the baseline readers
+        // understand
+        let TieredStorageReader::Hot(reader) = tiered_storage.reader().unwrap();
+        for i in 0..accounts.len() {
+            let (meta, _) = reader.get_raw_account(IndexOffset(i as u32)).unwrap();
+            let flags = *meta.flags();
+            assert_eq!(
+                flags,
+                AccountMetaFlags::new()
+                    .with_has_rent_epoch(flags.has_rent_epoch())
+                    .with_executable(flags.executable()),
+                "account {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hot_storage_sorted_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let owner = Pubkey::new_unique();
+
+        for num_accounts in [1, 2, 100] {
+            let path = temp_dir
+                .path()
+                .join(format!("test_hot_storage_sorted_index_{num_accounts}"));
+            // the input addresses are in descending order
+            let mut addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+                .take(num_accounts)
+                .collect();
+            addresses.reverse();
+            let accounts: Vec<_> = (0..num_accounts)
+                .map(|i| AccountSharedData::new(i as u64 + 1, i, &owner))
+                .collect();
+            let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+            let account_data = (Slot::MAX, &account_refs[..]);
+            let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+                .take(num_accounts)
+                .collect();
+            let write_versions: Vec<_> = (0..num_accounts as u64).collect();
+            let storable_accounts =
+                StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                    &account_data,
+                    hashes,
+                    write_versions,
+                );
+            let stored_infos = {
+                let mut writer = HotStorageWriter::new_with_options(
+                    &path,
+                    IndexBlockFormat::SortedAddressesThenOffsets,
+                    HOT_FORMAT.hash_kind,
+                    &WriteOptions::LATEST,
+                )
+                .unwrap();
+                writer.write_accounts(&storable_accounts, 0).unwrap()
+            };
+
+            let file = TieredReadableFile::new(&path).unwrap();
+            let hot_storage = HotStorageReader::new(file).unwrap();
+            assert_eq!(
+                hot_storage.footer().index_block_format,
+                IndexBlockFormat::SortedAddressesThenOffsets
+            );
+
+            // the accounts are stored in the order of their addresses
+            let stored_addresses = hot_storage.account_addresses().unwrap();
+            assert!(stored_addresses.windows(2).all(|pair| pair[0] < pair[1]));
+
+            // each stored info still points to its input account
+            for (i, stored_info) in stored_infos.iter().enumerate() {
+                let (stored_meta, _) = hot_storage
+                    .get_account(IndexOffset(stored_info.offset as u32))
+                    .unwrap()
+                    .unwrap();
+                verify_test_account(
+                    &stored_meta,
+                    Some(&accounts[i]),
+                    &addresses[i],
+                    storable_accounts.get(i).2,
+                );
+            }
+
+            // test: hit
+            for (i, address) in addresses.iter().enumerate() {
+                let index_offset = hot_storage
+                    .get_account_index_by_address(address)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(index_offset.0 as usize, stored_infos[i].offset);
+                let (stored_meta, _) = hot_storage.find_account(address).unwrap().unwrap();
+                verify_test_account(
+                    &stored_meta,
+                    Some(&accounts[i]),
+                    address,
+                    storable_accounts.get(i).2,
+                );
+            }
+
+            // test: miss, including an address between two stored addresses
+            assert_eq!(
+                hot_storage
+                    .get_account_index_by_address(&Pubkey::new_unique())
+                    .unwrap(),
+                None
+            );
+            if num_accounts > 1 {
+                let mut between = stored_addresses[0].to_bytes();
+                *between.last_mut().unwrap() += 1;
+                let between = Pubkey::from(between);
+                assert!(stored_addresses[0] < between && between < stored_addresses[1]);
+                assert_eq!(
+                    hot_storage.get_account_index_by_address(&between).unwrap(),
+                    None
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_accounts_with_shared_owners() {
+        const NUM_ACCOUNTS: usize = 10;
+        const NUM_OWNERS: usize = 3;
+
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS)
+            .collect();
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_ACCOUNTS)
+            .collect();
+        // Each account has non-zero lamports so that its owner is persisted
+        // and can be matched via account_matches_owners().
+        let accounts: Vec<_> = (0..NUM_ACCOUNTS)
+            .map(|i| AccountSharedData::new(i as u64 + 1, i, &owners[i % NUM_OWNERS]))
+            .collect();
+        let account_refs: Vec<_> = addresses.iter().zip(accounts.iter()).collect();
+
+        // Slot information is not used here
+        let account_data = (Slot::MAX, &account_refs[..]);
+        let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+            .take(NUM_ACCOUNTS)
+            .collect();
+        let write_versions = vec![0; NUM_ACCOUNTS];
+        let storable_accounts =
+            StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+                &account_data,
+                hashes,
+                write_versions,
+            );
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_write_accounts_with_shared_owners");
+        {
+            let mut writer = HotStorageWriter::new(&path).unwrap();
+            writer.write_accounts(&storable_accounts, 0).unwrap();
+        }
+
+        let file = TieredReadableFile::new(&path).unwrap();
+        let hot_storage = HotStorageReader::new(file).unwrap();
+
+        // Owners are deduplicated in the owners block.
+        assert_eq!(hot_storage.footer().owner_count as usize, NUM_OWNERS);
+
+        for i in 0..NUM_ACCOUNTS {
+            let index_offset = IndexOffset(i as u32);
+            let account_offset = hot_storage.get_account_offset(index_offset).unwrap();
+            let meta = hot_storage
+                .get_account_meta_from_offset(account_offset)
+                .unwrap();
+            assert_eq!(
+                hot_storage.get_owner_address(meta.owner_offset()).unwrap(),
+                &owners[i % NUM_OWNERS]
+            );
+
+            let (stored_meta, _) = hot_storage.get_account(index_offset).unwrap().unwrap();
+            assert_eq!(stored_meta.owner(), &owners[i % NUM_OWNERS]);
+
+            assert_eq!(
+                hot_storage.account_matches_owners(account_offset, &owners),
+                Ok(i % NUM_OWNERS)
+            );
+        }
     }
 }
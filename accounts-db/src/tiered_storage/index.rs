@@ -1,6 +1,9 @@
 use {
     crate::tiered_storage::{
-        file::TieredWritableFile, footer::TieredStorageFooter, mmap_utils::get_pod,
+        error::TieredStorageError,
+        file::TieredWritableFile,
+        footer::TieredStorageFooter,
+        mmap_utils::{get_pod, get_pod_slice, to_usize},
         TieredStorageResult,
     },
     bytemuck::{Pod, Zeroable},
@@ -15,6 +18,9 @@ pub struct AccountIndexWriterEntry<'a, Offset: AccountOffset> {
     pub address: &'a Pubkey,
     /// The offset to the account.
     pub offset: Offset,
+    /// The size of the account block, which excludes the account meta.
+    /// Only persisted by the index formats that store per-account sizes.
+    pub block_size: u32,
 }
 
 /// The offset to an account.
@@ -49,6 +55,18 @@ pub enum IndexBlockFormat {
     /// account block entries and index block entries in the same order.
     #[default]
     AddressesThenOffsets = 0,
+    /// This format has the same layout as AddressesThenOffsets, except that
+    /// its entries are sorted by account address, which allows an account
+    /// to be looked up by its address via binary search.  As the account
+    /// block entries are stored in the same order as the index entries, the
+    /// account blocks are sorted by account address as well.
+    SortedAddressesThenOffsets = 1,
+    /// This format extends AddressesThenOffsets by storing the size of each
+    /// account block after the block offsets.  As the size of an account
+    /// block no longer depends on the offset of the next index entry, the
+    /// account blocks are not required to be adjacent nor stored in the
+    /// same order as the index entries.
+    AddressesThenOffsetsThenSizes = 2,
 }
 
 // Ensure there are no implicit padding bytes
@@ -63,7 +81,14 @@ impl IndexBlockFormat {
         index_entries: &[AccountIndexWriterEntry<impl AccountOffset>],
     ) -> TieredStorageResult<usize> {
         match self {
-            Self::AddressesThenOffsets => {
+            Self::AddressesThenOffsets | Self::SortedAddressesThenOffsets => {
+                debug_assert!(
+                    !self.is_sorted()
+                        || index_entries
+                            .windows(2)
+                            .all(|entries| entries[0].address <= entries[1].address),
+                    "index entries must be sorted by address for {self:?}",
+                );
                 let mut bytes_written = 0;
                 for index_entry in index_entries {
                     bytes_written += file.write_pod(index_entry.address)?;
@@ -73,6 +98,14 @@ impl IndexBlockFormat {
                 }
                 Ok(bytes_written)
             }
+            Self::AddressesThenOffsetsThenSizes => {
+                let mut bytes_written =
+                    Self::AddressesThenOffsets.write_index_block(file, index_entries)?;
+                for index_entry in index_entries {
+                    bytes_written += file.write_pod(&index_entry.block_size)?;
+                }
+                Ok(bytes_written)
+            }
         }
     }
 
@@ -84,7 +117,9 @@ impl IndexBlockFormat {
         index_offset: IndexOffset,
     ) -> TieredStorageResult<&'a Pubkey> {
         let offset = match self {
-            Self::AddressesThenOffsets => {
+            Self::AddressesThenOffsets
+            | Self::SortedAddressesThenOffsets
+            | Self::AddressesThenOffsetsThenSizes => {
                 debug_assert!(index_offset.0 < footer.account_entry_count);
                 footer.index_block_offset as usize
                     + std::mem::size_of::<Pubkey>() * (index_offset.0 as usize)
@@ -103,6 +138,61 @@ impl IndexBlockFormat {
         Ok(address)
     }
 
+    /// Returns the addresses of all the accounts inside the index block,
+    /// ordered by their index.
+    ///
+    /// Unlike get_account_address(), the address array is bounds-checked
+    /// only once, which makes it the preferred way to scan all the addresses.
+    pub fn get_account_addresses<'a>(
+        &self,
+        mmap: &'a Mmap,
+        footer: &TieredStorageFooter,
+    ) -> TieredStorageResult<&'a [Pubkey]> {
+        let (offset, count) = match self {
+            Self::AddressesThenOffsets
+            | Self::SortedAddressesThenOffsets
+            | Self::AddressesThenOffsetsThenSizes => (
+                footer.index_block_offset as usize,
+                footer.account_entry_count as usize,
+            ),
+        };
+
+        let end = offset.saturating_add(std::mem::size_of::<Pubkey>().saturating_mul(count));
+        if end > footer.owners_block_offset as usize {
+            return Err(TieredStorageError::OffsetOutOfBounds(
+                end,
+                footer.owners_block_offset as usize,
+            ));
+        }
+
+        let (addresses, _) = get_pod_slice::<Pubkey>(mmap, offset, count)?;
+        Ok(addresses)
+    }
+
+    /// Returns the index of the first account whose address matches the
+    /// specified address, or None if no account has the specified address.
+    ///
+    /// The lookup is a binary search for the sorted format, and a linear
+    /// scan otherwise.
+    pub fn get_account_index_by_address(
+        &self,
+        mmap: &Mmap,
+        footer: &TieredStorageFooter,
+        address: &Pubkey,
+    ) -> TieredStorageResult<Option<IndexOffset>> {
+        let addresses = self.get_account_addresses(mmap, footer)?;
+        let index = match self {
+            Self::AddressesThenOffsets | Self::AddressesThenOffsetsThenSizes => {
+                addresses.iter().position(|candidate| candidate == address)
+            }
+            Self::SortedAddressesThenOffsets => {
+                let index = addresses.partition_point(|candidate| candidate < address);
+                (addresses.get(index) == Some(address)).then_some(index)
+            }
+        };
+        Ok(index.map(|index| IndexOffset(index as u32)))
+    }
+
     /// Returns the offset to the account given the specified index.
     pub fn get_account_offset<Offset: AccountOffset>(
         &self,
@@ -111,7 +201,9 @@ impl IndexBlockFormat {
         index_offset: IndexOffset,
     ) -> TieredStorageResult<Offset> {
         let offset = match self {
-            Self::AddressesThenOffsets => {
+            Self::AddressesThenOffsets
+            | Self::SortedAddressesThenOffsets
+            | Self::AddressesThenOffsetsThenSizes => {
                 debug_assert!(index_offset.0 < footer.account_entry_count);
                 footer.index_block_offset as usize
                     + std::mem::size_of::<Pubkey>() * footer.account_entry_count as usize
@@ -132,12 +224,92 @@ impl IndexBlockFormat {
         Ok(*account_offset)
     }
 
+    /// Returns the offsets to all the accounts inside the index block,
+    /// ordered by their index.
+    ///
+    /// Like get_account_addresses(), the offset array is bounds-checked only
+    /// once.
+    pub fn get_account_offsets<'a, Offset: AccountOffset>(
+        &self,
+        mmap: &'a Mmap,
+        footer: &TieredStorageFooter,
+    ) -> TieredStorageResult<&'a [Offset]> {
+        let (offset, count) = match self {
+            Self::AddressesThenOffsets
+            | Self::SortedAddressesThenOffsets
+            | Self::AddressesThenOffsetsThenSizes => {
+                let count = to_usize(footer.account_entry_count)?;
+                (
+                    to_usize(footer.index_block_offset)? + std::mem::size_of::<Pubkey>() * count,
+                    count,
+                )
+            }
+        };
+
+        let end = offset.saturating_add(std::mem::size_of::<Offset>().saturating_mul(count));
+        if end > footer.owners_block_offset as usize {
+            return Err(TieredStorageError::OffsetOutOfBounds(
+                end,
+                footer.owners_block_offset as usize,
+            ));
+        }
+
+        let (offsets, _) = get_pod_slice::<Offset>(mmap, offset, count)?;
+        Ok(offsets)
+    }
+
+    /// Returns the size of the account block given the specified index, or
+    /// None if this format does not store per-account sizes, in which case
+    /// the size is derived from the offset of the next account.
+    pub fn get_account_block_size<Offset: AccountOffset>(
+        &self,
+        mmap: &Mmap,
+        footer: &TieredStorageFooter,
+        index_offset: IndexOffset,
+    ) -> TieredStorageResult<Option<usize>> {
+        let offset = match self {
+            Self::AddressesThenOffsets | Self::SortedAddressesThenOffsets => return Ok(None),
+            Self::AddressesThenOffsetsThenSizes => {
+                debug_assert!(index_offset.0 < footer.account_entry_count);
+                to_usize(footer.index_block_offset)?
+                    + (std::mem::size_of::<Pubkey>() + std::mem::size_of::<Offset>())
+                        * to_usize(footer.account_entry_count)?
+                    + std::mem::size_of::<u32>() * to_usize(index_offset.0)?
+            }
+        };
+
+        debug_assert!(
+            offset.saturating_add(std::mem::size_of::<u32>())
+                <= footer.owners_block_offset as usize,
+            "reading IndexOffset ({}) would exceed index block boundary ({}).",
+            offset,
+            footer.owners_block_offset,
+        );
+
+        let (block_size, _) = get_pod::<u32>(mmap, offset)?;
+
+        Ok(Some(to_usize(*block_size)?))
+    }
+
+    /// Returns true if the index entries are sorted by account address.
+    pub fn is_sorted(&self) -> bool {
+        match self {
+            Self::AddressesThenOffsets | Self::AddressesThenOffsetsThenSizes => false,
+            Self::SortedAddressesThenOffsets => true,
+        }
+    }
+
     /// Returns the size of one index entry.
     pub fn entry_size<Offset: AccountOffset>(&self) -> usize {
         match self {
-            Self::AddressesThenOffsets => {
+            Self::AddressesThenOffsets | Self::SortedAddressesThenOffsets => {
                 std::mem::size_of::<Pubkey>() + std::mem::size_of::<Offset>()
             }
+            Self::AddressesThenOffsetsThenSizes => {
+                std::mem::size_of::<Pubkey>()
+                    + std::mem::size_of::<Offset>()
+                    + std::mem::size_of::<u32>()
+            }
         }
     }
 }
@@ -150,6 +322,7 @@ mod tests {
             file::TieredWritableFile,
             hot::{HotAccountOffset, HOT_ACCOUNT_ALIGNMENT},
         },
+        assert_matches::assert_matches,
         memmap2::MmapOptions,
         rand::Rng,
         std::fs::OpenOptions,
@@ -177,6 +350,7 @@ mod tests {
                     rng.gen_range(0..u32::MAX) as usize * HOT_ACCOUNT_ALIGNMENT,
                 )
                 .unwrap(),
+                block_size: 0,
             })
             .collect();
 
@@ -208,6 +382,269 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_account_block_size() {
+        const ENTRY_COUNT: usize = 7;
+        let temp_dir = TempDir::new().unwrap();
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(ENTRY_COUNT)
+            .collect();
+        let index_entries: Vec<_> = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| AccountIndexWriterEntry {
+                address,
+                offset: HotAccountOffset::new(i * 1024).unwrap(),
+                block_size: (i * 100) as u32,
+            })
+            .collect();
+
+        for index_block_format in [
+            IndexBlockFormat::AddressesThenOffsets,
+            IndexBlockFormat::AddressesThenOffsetsThenSizes,
+        ] {
+            let path = temp_dir.path().join(format!("{index_block_format:?}"));
+            let mut footer = TieredStorageFooter {
+                account_entry_count: ENTRY_COUNT as u32,
+                index_block_format,
+                ..TieredStorageFooter::default()
+            };
+            {
+                let mut file = TieredWritableFile::new(&path).unwrap();
+                let cursor = index_block_format
+                    .write_index_block(&mut file, &index_entries)
+                    .unwrap();
+                assert_eq!(
+                    cursor,
+                    index_block_format.entry_size::<HotAccountOffset>() * ENTRY_COUNT
+                );
+                footer.owners_block_offset = cursor as u64;
+            }
+
+            let file = OpenOptions::new().read(true).open(&path).unwrap();
+            let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+            for (i, index_entry) in index_entries.iter().enumerate() {
+                let index_offset = IndexOffset(i as u32);
+                assert_eq!(
+                    index_block_format
+                        .get_account_address(&mmap, &footer, index_offset)
+                        .unwrap(),
+                    index_entry.address
+                );
+                assert_eq!(
+                    index_block_format
+                        .get_account_offset::<HotAccountOffset>(&mmap, &footer, index_offset)
+                        .unwrap(),
+                    index_entry.offset
+                );
+                let expected_block_size = (index_block_format
+                    == IndexBlockFormat::AddressesThenOffsetsThenSizes)
+                    .then_some(index_entry.block_size as usize);
+                assert_eq!(
+                    index_block_format
+                        .get_account_block_size::<HotAccountOffset>(&mmap, &footer, index_offset)
+                        .unwrap(),
+                    expected_block_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_account_addresses() {
+        const ENTRY_COUNT: usize = 300;
+        let mut footer = TieredStorageFooter {
+            account_entry_count: ENTRY_COUNT as u32,
+            ..TieredStorageFooter::default()
+        };
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_get_account_addresses");
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(ENTRY_COUNT)
+            .collect();
+        let index_entries: Vec<_> = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| AccountIndexWriterEntry {
+                address,
+                offset: HotAccountOffset::new(i * HOT_ACCOUNT_ALIGNMENT).unwrap(),
+                block_size: 0,
+            })
+            .collect();
+
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+            let cursor = footer
+                .index_block_format
+                .write_index_block(&mut file, &index_entries)
+                .unwrap();
+            footer.owners_block_offset = cursor as u64;
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .create(false)
+            .open(&path)
+            .unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+        let account_addresses = footer
+            .index_block_format
+            .get_account_addresses(&mmap, &footer)
+            .unwrap();
+        assert_eq!(account_addresses, addresses);
+        let account_offsets = footer
+            .index_block_format
+            .get_account_offsets::<HotAccountOffset>(&mmap, &footer)
+            .unwrap();
+        assert_eq!(
+            account_offsets,
+            index_entries
+                .iter()
+                .map(|index_entry| index_entry.offset)
+                .collect::<Vec<_>>()
+        );
+        for (i, address) in account_addresses.iter().enumerate() {
+            assert_eq!(
+                footer
+                    .index_block_format
+                    .get_account_address(&mmap, &footer, IndexOffset(i as u32))
+                    .unwrap(),
+                address,
+            );
+        }
+
+        // test: the addresses of the entry count overrun the index block
+        let footer = TieredStorageFooter {
+            account_entry_count: ENTRY_COUNT as u32 * 2,
+            ..footer
+        };
+        assert_matches!(
+            footer
+                .index_block_format
+                .get_account_addresses(&mmap, &footer),
+            Err(TieredStorageError::OffsetOutOfBounds(_, _))
+        );
+        assert_matches!(
+            footer
+                .index_block_format
+                .get_account_offsets::<HotAccountOffset>(&mmap, &footer),
+            Err(TieredStorageError::OffsetOutOfBounds(_, _))
+        );
+    }
+
+    #[test]
+    fn test_get_account_index_by_address() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(10)
+            .collect();
+        // duplicated addresses resolve to their first index
+        addresses.push(addresses[3]);
+        let missing_address = Pubkey::new_unique();
+
+        for index_block_format in [
+            IndexBlockFormat::AddressesThenOffsets,
+            IndexBlockFormat::SortedAddressesThenOffsets,
+        ] {
+            let mut addresses = addresses.clone();
+            if index_block_format.is_sorted() {
+                addresses.sort();
+            }
+            let mut footer = TieredStorageFooter {
+                account_entry_count: addresses.len() as u32,
+                index_block_format,
+                ..TieredStorageFooter::default()
+            };
+            let path = temp_dir.path().join(format!("{index_block_format:?}"));
+            let index_entries: Vec<_> = addresses
+                .iter()
+                .enumerate()
+                .map(|(i, address)| AccountIndexWriterEntry {
+                    address,
+                    offset: HotAccountOffset::new(i * HOT_ACCOUNT_ALIGNMENT).unwrap(),
+                    block_size: 0,
+                })
+                .collect();
+            {
+                let mut file = TieredWritableFile::new(&path).unwrap();
+                let cursor = index_block_format
+                    .write_index_block(&mut file, &index_entries)
+                    .unwrap();
+                footer.owners_block_offset = cursor as u64;
+            }
+
+            let file = OpenOptions::new()
+                .read(true)
+                .create(false)
+                .open(&path)
+                .unwrap();
+            let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+            for address in &addresses {
+                let expected = addresses.iter().position(|a| a == address).unwrap();
+                assert_eq!(
+                    index_block_format
+                        .get_account_index_by_address(&mmap, &footer, address)
+                        .unwrap(),
+                    Some(IndexOffset(expected as u32)),
+                );
+            }
+            assert_eq!(
+                index_block_format
+                    .get_account_index_by_address(&mmap, &footer, &missing_address)
+                    .unwrap(),
+                None,
+            );
+        }
+    }
+
+    #[test]
+    fn test_bogus_index_block_offset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_bogus_index_block_offset");
+
+        // The index block offset points far beyond the end of the file, which
+        // only contains the footer.
+        let index_block_offset = 1u64 << 40;
+        let footer = TieredStorageFooter {
+            account_entry_count: 1,
+            index_block_format: IndexBlockFormat::AddressesThenOffsets,
+            index_block_offset,
+            owners_block_offset: index_block_offset
+                + IndexBlockFormat::AddressesThenOffsets.entry_size::<HotAccountOffset>() as u64,
+            ..TieredStorageFooter::default()
+        };
+
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .create(false)
+            .open(&path)
+            .unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+        assert_matches!(
+            footer
+                .index_block_format
+                .get_account_address(&mmap, &footer, IndexOffset(0)),
+            Err(TieredStorageError::ReadOutOfBounds(_, _, _))
+        );
+        assert_matches!(
+            footer
+                .index_block_format
+                .get_account_offset::<HotAccountOffset>(&mmap, &footer, IndexOffset(0)),
+            Err(TieredStorageError::ReadOutOfBounds(_, _, _))
+        );
+        assert_matches!(
+            footer
+                .index_block_format
+                .get_account_addresses(&mmap, &footer),
+            Err(TieredStorageError::ReadOutOfBounds(_, _, _))
+        );
+    }
+
     #[test]
     #[should_panic(expected = "index_offset.0 < footer.account_entry_count")]
     fn test_get_account_address_out_of_bounds() {
@@ -1,10 +1,14 @@
 //! The account meta and related structs for the tiered storage.
 
 use {
-    crate::tiered_storage::owners::OwnerOffset,
+    crate::{
+        accounts_hash::AccountHash,
+        tiered_storage::{byte_block::ByteBlockReader, owners::OwnerOffset, TieredStorageResult},
+    },
     bytemuck::{Pod, Zeroable},
     modular_bitfield::prelude::*,
     solana_sdk::{pubkey::Pubkey, stake_history::Epoch},
+    std::ops::RangeInclusive,
 };
 
 /// The struct that handles the account meta flags.
@@ -16,8 +20,12 @@ pub struct AccountMetaFlags {
     pub has_rent_epoch: bool,
     /// whether the account is executable
     pub executable: bool,
+    /// whether the account meta has account hash
+    pub has_account_hash: bool,
+    /// whether the account block ends with a checksum of its content
+    pub has_block_checksum: bool,
     /// the reserved bits.
-    reserved: B30,
+    reserved: B28,
 }
 
 // Ensure there are no implicit padding bytes
@@ -26,6 +34,10 @@ const _: () = assert!(std::mem::size_of::<AccountMetaFlags>() == 4);
 /// A trait that allows different implementations of the account meta that
 /// support different tiers of the accounts storage.
 pub trait TieredAccountMeta: Sized {
+    /// The maximum owner offset that the meta can persist, which also caps
+    /// the number of distinct owners in a file of this meta format.
+    const MAX_OWNER_OFFSET: OwnerOffset;
+
     /// Constructs a TieredAcountMeta instance.
     fn new() -> Self;
 
@@ -33,11 +45,13 @@ pub trait TieredAccountMeta: Sized {
     fn with_lamports(self, lamports: u64) -> Self;
 
     /// A builder function that initializes the number of padding bytes
-    /// for the account data associated with the current meta.
-    fn with_account_data_padding(self, padding: u8) -> Self;
+    /// for the account data associated with the current meta.  An error is
+    /// returned if the padding exceeds what the meta can persist.
+    fn with_account_data_padding(self, padding: u8) -> TieredStorageResult<Self>;
 
-    /// A builder function that initializes the owner offset.
-    fn with_owner_offset(self, owner_offset: OwnerOffset) -> Self;
+    /// A builder function that initializes the owner offset.  An error is
+    /// returned if the offset exceeds what the meta can persist.
+    fn with_owner_offset(self, owner_offset: OwnerOffset) -> TieredStorageResult<Self>;
 
     /// A builder function that initializes the account data size.
     /// The size here represents the logical data size without compression.
@@ -50,6 +64,16 @@ pub trait TieredAccountMeta: Sized {
     /// Returns the balance of the lamports associated with the account.
     fn lamports(&self) -> u64;
 
+    /// Returns true if the account is a zero-lamport account, i.e. the
+    /// tombstone of a removed account.
+    ///
+    /// There is no flag for it.  Instead, the writers persist every
+    /// zero-lamport account as AccountSharedData::default(), so an account
+    /// is a tombstone if and only if its lamports are zero.
+    fn is_zero_lamport(&self) -> bool {
+        self.lamports() == 0
+    }
+
     /// Returns the number of padding bytes for the associated account data
     fn account_data_padding(&self) -> u8;
 
@@ -68,23 +92,82 @@ pub trait TieredAccountMeta: Sized {
     /// does not persist this optional field.
     fn rent_epoch(&self, _account_block: &[u8]) -> Option<Epoch>;
 
+    /// Returns the account hash by parsing the specified account block.  None
+    /// will be returned if this account does not persist this optional field.
+    fn account_hash<'a>(&self, _account_block: &'a [u8]) -> Option<&'a AccountHash>;
+
     /// Returns the offset of the optional fields based on the specified account
     /// block.
     fn optional_fields_offset(&self, _account_block: &[u8]) -> usize;
 
     /// Returns the length of the data associated to this account based on the
-    /// specified account block.
-    fn account_data_size(&self, _account_block: &[u8]) -> usize;
+    /// specified account block, or an error if the account block is too short
+    /// for the recorded padding.
+    fn account_data_size(&self, _account_block: &[u8]) -> TieredStorageResult<usize>;
 
     /// Returns the data associated to this account based on the specified
     /// account block.
-    fn account_data<'a>(&self, _account_block: &'a [u8]) -> &'a [u8];
+    fn account_data<'a>(&self, _account_block: &'a [u8]) -> TieredStorageResult<&'a [u8]>;
+
+    /// Reads the optional fields persisted in the specified account block.
+    ///
+    /// Returns an error if the account block is too small to hold all the
+    /// optional fields described by the flags of this meta.
+    fn optional_fields<'a>(
+        &self,
+        account_block: &'a [u8],
+    ) -> TieredStorageResult<AccountMetaOptionalFieldsRef<'a>> {
+        let mut reader = ByteBlockReader::new(account_block);
+        reader.skip(self.optional_fields_offset(account_block))?;
+        AccountMetaOptionalFields::read(self.flags(), &mut reader)
+    }
+
+    /// Returns whether the checksum at the end of the specified account
+    /// block matches the rest of the block, or None if this account does
+    /// not persist a block checksum.
+    fn verify_block_checksum(&self, account_block: &[u8]) -> Option<bool> {
+        if !self.flags().has_block_checksum() {
+            return None;
+        }
+        let checksum = self
+            .optional_fields(account_block)
+            .ok()
+            .and_then(|optional_fields| optional_fields.block_checksum);
+        // the checksum is the last optional field
+        Some(checksum.is_some_and(|checksum| {
+            let checksum_offset = account_block.len() - std::mem::size_of::<BlockChecksum>();
+            checksum == block_checksum(&account_block[..checksum_offset])
+        }))
+    }
+}
+
+/// The checksum that ends an account block when its meta has the
+/// has_block_checksum flag.
+///
+/// The checksum covers the account data, its padding, and the optional
+/// fields.  It is 8 bytes so that the account blocks stay aligned.
+pub type BlockChecksum = u64;
+
+/// Returns the checksum of the specified bytes of an account block.
+pub fn block_checksum(bytes: &[u8]) -> BlockChecksum {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(bytes);
+    block_checksum_from_hasher(&hasher)
+}
+
+/// Returns the checksum of the bytes of an account block that were fed to
+/// the specified hasher, which allows the block to be checksummed while it
+/// is written piece by piece.
+pub fn block_checksum_from_hasher(hasher: &blake3::Hasher) -> BlockChecksum {
+    let hash = hasher.finalize();
+    BlockChecksum::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
 }
 
 impl AccountMetaFlags {
     pub fn new_from(optional_fields: &AccountMetaOptionalFields) -> Self {
         let mut flags = AccountMetaFlags::default();
         flags.set_has_rent_epoch(optional_fields.rent_epoch.is_some());
+        flags.set_has_account_hash(optional_fields.account_hash.is_some());
         flags.set_executable(false);
         flags
     }
@@ -96,14 +179,70 @@ impl AccountMetaFlags {
 /// different from its in-memory representation.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AccountMetaOptionalFields {
-    /// the epoch at which its associated account will next owe rent
+    /// the epoch at which its associated account will next owe rent, which
+    /// is present if and only if it is not Epoch::MAX.  See
+    /// rent_epoch_to_persist().
     pub rent_epoch: Option<Epoch>,
+    /// the hash of its associated account
+    pub account_hash: Option<AccountHash>,
+}
+
+/// The optional fields of an account as they are persisted in its account
+/// block.  A field is None if the meta of the account does not have it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AccountMetaOptionalFieldsRef<'a> {
+    pub rent_epoch: Option<&'a Epoch>,
+    pub account_hash: Option<&'a AccountHash>,
+    pub block_checksum: Option<BlockChecksum>,
 }
 
 impl AccountMetaOptionalFields {
+    /// Reads the optional fields described by the specified flags, starting
+    /// at the cursor of the specified reader.
+    ///
+    /// This defines the layout in which the optional fields are read, which
+    /// must match ByteBlockWriter::write_optional_fields() followed by the
+    /// block checksum.
+    pub fn read<'a>(
+        flags: &AccountMetaFlags,
+        reader: &mut ByteBlockReader<'a>,
+    ) -> TieredStorageResult<AccountMetaOptionalFieldsRef<'a>> {
+        let rent_epoch = flags
+            .has_rent_epoch()
+            .then(|| reader.read_pod::<Epoch>())
+            .transpose()?;
+        let account_hash = flags
+            .has_account_hash()
+            .then(|| reader.read_pod::<AccountHash>())
+            .transpose()?;
+        let block_checksum = flags
+            .has_block_checksum()
+            .then(|| reader.read_pod_unaligned::<BlockChecksum>())
+            .transpose()?;
+        Ok(AccountMetaOptionalFieldsRef {
+            rent_epoch,
+            account_hash,
+            block_checksum,
+        })
+    }
+
+    /// Returns the rent epoch to persist for an account with the specified
+    /// rent epoch.
+    ///
+    /// Epoch::MAX, i.e. RENT_EXEMPT_RENT_EPOCH, is the rent epoch of every
+    /// rent-exempt account, so it is encoded by the absence of the field,
+    /// and a reader returns Epoch::MAX for an account without the field.
+    /// Every other value, including 0, is persisted as is.
+    pub fn rent_epoch_to_persist(rent_epoch: Epoch) -> Option<Epoch> {
+        (rent_epoch != Epoch::MAX).then_some(rent_epoch)
+    }
+
     /// The size of the optional fields in bytes (excluding the boolean flags).
     pub fn size(&self) -> usize {
         self.rent_epoch.map_or(0, |_| std::mem::size_of::<Epoch>())
+            + self
+                .account_hash
+                .map_or(0, |_| std::mem::size_of::<AccountHash>())
     }
 
     /// Given the specified AccountMetaFlags, returns the size of its
@@ -113,6 +252,12 @@ impl AccountMetaOptionalFields {
         if flags.has_rent_epoch() {
             fields_size += std::mem::size_of::<Epoch>();
         }
+        if flags.has_account_hash() {
+            fields_size += std::mem::size_of::<AccountHash>();
+        }
+        if flags.has_block_checksum() {
+            fields_size += std::mem::size_of::<BlockChecksum>();
+        }
 
         fields_size
     }
@@ -122,49 +267,120 @@ impl AccountMetaOptionalFields {
     pub fn rent_epoch_offset(_flags: &AccountMetaFlags) -> usize {
         0
     }
+
+    /// Given the specified AccountMetaFlags, returns the relative offset
+    /// of its account_hash field to the offset of its optional fields entry.
+    pub fn account_hash_offset(flags: &AccountMetaFlags) -> usize {
+        let mut offset = Self::rent_epoch_offset(flags);
+        // rent_epoch is the previous field to account hash
+        if flags.has_rent_epoch() {
+            offset += std::mem::size_of::<Epoch>();
+        }
+        offset
+    }
+
+    /// Given the specified AccountMetaFlags, returns the relative offset
+    /// of the block checksum to the offset of its optional fields entry.
+    ///
+    /// The block checksum follows all the other optional fields.
+    pub fn block_checksum_offset(flags: &AccountMetaFlags) -> usize {
+        let mut offset = Self::account_hash_offset(flags);
+        if flags.has_account_hash() {
+            offset += std::mem::size_of::<AccountHash>();
+        }
+        offset
+    }
 }
 
-const MIN_ACCOUNT_ADDRESS: Pubkey = Pubkey::new_from_array([0x00u8; 32]);
-const MAX_ACCOUNT_ADDRESS: Pubkey = Pubkey::new_from_array([0xFFu8; 32]);
+pub const MIN_ACCOUNT_ADDRESS: Pubkey = Pubkey::new_from_array([0x00u8; 32]);
+pub const MAX_ACCOUNT_ADDRESS: Pubkey = Pubkey::new_from_array([0xFFu8; 32]);
 
-#[derive(Debug)]
-/// A struct that maintains an address-range using its min and max fields.
-pub struct AccountAddressRange<'a> {
-    /// The minimum address observed via update()
-    pub min: &'a Pubkey,
-    /// The maximum address observed via update()
-    pub max: &'a Pubkey,
+/// A range of account addresses maintained by its min and max fields.
+///
+/// A range that has not observed any address is unknown (i.e. empty), and
+/// its min and max fields must be ignored.  This is tracked explicitly via
+/// the populated flag, as Pubkey::default() is a legitimate address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PubkeyRange {
+    /// The minimum address of the range (inclusive).
+    pub min: Pubkey,
+    /// The maximum address of the range (inclusive).
+    pub max: Pubkey,
+    /// Whether the range has observed at least one address.
+    pub populated: bool,
 }
 
-impl Default for AccountAddressRange<'_> {
-    fn default() -> Self {
+impl PubkeyRange {
+    /// Creates a populated range covering [min, max].
+    pub fn new(min: Pubkey, max: Pubkey) -> Self {
+        debug_assert!(min <= max);
         Self {
-            min: &MAX_ACCOUNT_ADDRESS,
-            max: &MIN_ACCOUNT_ADDRESS,
+            min,
+            max,
+            populated: true,
         }
     }
-}
 
-impl<'a> AccountAddressRange<'a> {
-    pub fn update(&mut self, address: &'a Pubkey) {
-        if *self.min > *address {
-            self.min = address;
+    /// Extends the range so that it includes the specified address.
+    pub fn update(&mut self, address: &Pubkey) {
+        if !self.populated {
+            *self = Self::new(*address, *address);
+            return;
+        }
+        if self.min > *address {
+            self.min = *address;
         }
-        if *self.max < *address {
-            self.max = address;
+        if self.max < *address {
+            self.max = *address;
+        }
+    }
+
+    /// Returns true if the range has not observed any address.
+    pub fn is_unknown(&self) -> bool {
+        !self.populated
+    }
+
+    /// Returns true if the specified address falls inside the range.
+    pub fn contains(&self, address: &Pubkey) -> bool {
+        self.populated && self.min <= *address && *address <= self.max
+    }
+
+    /// Returns true if the range shares at least one address with the
+    /// specified range.
+    pub fn intersects(&self, range: &RangeInclusive<Pubkey>) -> bool {
+        self.populated
+            && !range.is_empty()
+            && *range.start() <= self.max
+            && self.min <= *range.end()
+    }
+
+    /// Returns the smallest range that covers both ranges.
+    pub fn union(&self, other: &Self) -> Self {
+        match (self.populated, other.populated) {
+            (false, _) => *other,
+            (_, false) => *self,
+            (true, true) => Self::new(self.min.min(other.min), self.max.max(other.max)),
         }
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::tiered_storage::{
+            byte_block::ByteBlockWriter, error::TieredStorageError, footer::AccountBlockFormat,
+        },
+        assert_matches::assert_matches,
+        solana_sdk::hash::Hash,
+    };
 
     #[test]
     fn test_account_meta_flags_new() {
         let flags = AccountMetaFlags::new();
 
         assert!(!flags.has_rent_epoch());
+        assert!(!flags.has_account_hash());
         assert_eq!(flags.reserved(), 0u32);
 
         assert_eq!(
@@ -192,6 +408,19 @@ pub mod tests {
         assert!(flags.executable());
         verify_flags_serialization(&flags);
 
+        flags.set_has_account_hash(true);
+        assert!(flags.has_rent_epoch());
+        assert!(flags.executable());
+        assert!(flags.has_account_hash());
+        verify_flags_serialization(&flags);
+
+        flags.set_has_block_checksum(true);
+        assert!(flags.has_rent_epoch());
+        assert!(flags.executable());
+        assert!(flags.has_account_hash());
+        assert!(flags.has_block_checksum());
+        verify_flags_serialization(&flags);
+
         // make sure the reserved bits are untouched.
         assert_eq!(flags.reserved(), 0u32);
     }
@@ -199,6 +428,8 @@ pub mod tests {
     fn update_and_verify_flags(opt_fields: &AccountMetaOptionalFields) {
         let flags: AccountMetaFlags = AccountMetaFlags::new_from(opt_fields);
         assert_eq!(flags.has_rent_epoch(), opt_fields.rent_epoch.is_some());
+        assert_eq!(flags.has_account_hash(), opt_fields.account_hash.is_some());
+        assert!(!flags.has_block_checksum());
         assert_eq!(flags.reserved(), 0u32);
     }
 
@@ -207,7 +438,12 @@ pub mod tests {
         let test_epoch = 5432312;
 
         for rent_epoch in [None, Some(test_epoch)] {
-            update_and_verify_flags(&AccountMetaOptionalFields { rent_epoch });
+            for account_hash in [None, Some(AccountHash(Hash::new_unique()))] {
+                update_and_verify_flags(&AccountMetaOptionalFields {
+                    rent_epoch,
+                    account_hash,
+                });
+            }
         }
     }
 
@@ -216,18 +452,38 @@ pub mod tests {
         let test_epoch = 5432312;
 
         for rent_epoch in [None, Some(test_epoch)] {
-            let opt_fields = AccountMetaOptionalFields { rent_epoch };
-            assert_eq!(
-                opt_fields.size(),
-                rent_epoch.map_or(0, |_| std::mem::size_of::<Epoch>()),
-            );
+            for account_hash in [None, Some(AccountHash(Hash::new_unique()))] {
+                let opt_fields = AccountMetaOptionalFields {
+                    rent_epoch,
+                    account_hash,
+                };
+                assert_eq!(
+                    opt_fields.size(),
+                    rent_epoch.map_or(0, |_| std::mem::size_of::<Epoch>())
+                        + account_hash.map_or(0, |_| std::mem::size_of::<AccountHash>()),
+                );
+                assert_eq!(
+                    opt_fields.size(),
+                    AccountMetaOptionalFields::size_from_flags(&AccountMetaFlags::new_from(
+                        &opt_fields
+                    ))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rent_epoch_to_persist() {
+        for rent_epoch in [0, 1, 12345, Epoch::MAX - 1] {
             assert_eq!(
-                opt_fields.size(),
-                AccountMetaOptionalFields::size_from_flags(&AccountMetaFlags::new_from(
-                    &opt_fields
-                ))
+                AccountMetaOptionalFields::rent_epoch_to_persist(rent_epoch),
+                Some(rent_epoch)
             );
         }
+        assert_eq!(
+            AccountMetaOptionalFields::rent_epoch_to_persist(Epoch::MAX),
+            None
+        );
     }
 
     #[test]
@@ -236,40 +492,152 @@ pub mod tests {
 
         for rent_epoch in [None, Some(test_epoch)] {
             let rent_epoch_offset = 0;
-            let derived_size = if rent_epoch.is_some() {
-                std::mem::size_of::<Epoch>()
-            } else {
-                0
-            };
-            let opt_fields = AccountMetaOptionalFields { rent_epoch };
-            let flags = AccountMetaFlags::new_from(&opt_fields);
-            assert_eq!(
-                AccountMetaOptionalFields::rent_epoch_offset(&flags),
-                rent_epoch_offset
-            );
-            assert_eq!(
-                AccountMetaOptionalFields::size_from_flags(&flags),
-                derived_size
-            );
+            for account_hash in [None, Some(AccountHash(Hash::new_unique()))] {
+                let mut derived_size = 0;
+                if rent_epoch.is_some() {
+                    derived_size += std::mem::size_of::<Epoch>();
+                }
+                let account_hash_offset = derived_size;
+                if account_hash.is_some() {
+                    derived_size += std::mem::size_of::<AccountHash>();
+                }
+                let opt_fields = AccountMetaOptionalFields {
+                    rent_epoch,
+                    account_hash,
+                };
+                let flags = AccountMetaFlags::new_from(&opt_fields);
+                assert_eq!(
+                    AccountMetaOptionalFields::rent_epoch_offset(&flags),
+                    rent_epoch_offset
+                );
+                assert_eq!(
+                    AccountMetaOptionalFields::account_hash_offset(&flags),
+                    account_hash_offset
+                );
+                assert_eq!(
+                    AccountMetaOptionalFields::size_from_flags(&flags),
+                    derived_size
+                );
+
+                // the block checksum follows all the other optional fields
+                let mut flags = flags;
+                flags.set_has_block_checksum(true);
+                assert_eq!(
+                    AccountMetaOptionalFields::block_checksum_offset(&flags),
+                    derived_size
+                );
+                assert_eq!(
+                    AccountMetaOptionalFields::size_from_flags(&flags),
+                    derived_size + std::mem::size_of::<BlockChecksum>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_optional_fields() {
+        for rent_epoch in [None, Some(5432312)] {
+            for account_hash in [None, Some(AccountHash(Hash::new_unique()))] {
+                for has_block_checksum in [false, true] {
+                    let opt_fields = AccountMetaOptionalFields {
+                        rent_epoch,
+                        account_hash,
+                    };
+                    let mut flags = AccountMetaFlags::new_from(&opt_fields);
+                    flags.set_has_block_checksum(has_block_checksum);
+                    let mut writer = ByteBlockWriter::new(AccountBlockFormat::AlignedRaw);
+                    writer.write_optional_fields(&opt_fields).unwrap();
+                    if has_block_checksum {
+                        writer.write_pod(&BlockChecksum::MAX).unwrap();
+                    }
+                    let byte_block = writer.finish().unwrap();
+                    assert_eq!(
+                        byte_block.len(),
+                        AccountMetaOptionalFields::size_from_flags(&flags)
+                    );
+
+                    let mut reader = ByteBlockReader::new(&byte_block);
+                    let fields = AccountMetaOptionalFields::read(&flags, &mut reader).unwrap();
+                    assert_eq!(fields.rent_epoch, rent_epoch.as_ref());
+                    assert_eq!(fields.account_hash, account_hash.as_ref());
+                    assert_eq!(
+                        fields.block_checksum,
+                        has_block_checksum.then_some(BlockChecksum::MAX)
+                    );
+                    assert_eq!(reader.remaining(), 0);
+
+                    // test: the fields overrun a block that is one byte short
+                    if !byte_block.is_empty() {
+                        let short_block = &byte_block[..byte_block.len() - 1];
+                        let mut reader = ByteBlockReader::new(short_block);
+                        assert_matches!(
+                            AccountMetaOptionalFields::read(&flags, &mut reader),
+                            Err(TieredStorageError::ReadOutOfBounds(..))
+                        );
+                    }
+                }
+            }
         }
     }
 
+    #[test]
+    fn test_block_checksum() {
+        let bytes: Vec<_> = (0..100u8).collect();
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&bytes[..40]);
+        hasher.update(&bytes[40..]);
+        assert_eq!(block_checksum_from_hasher(&hasher), block_checksum(&bytes));
+
+        let mut corrupted = bytes.clone();
+        corrupted[7] ^= 1;
+        assert_ne!(block_checksum(&corrupted), block_checksum(&bytes));
+    }
+
+    #[test]
+    fn test_pubkey_range_empty() {
+        let range = PubkeyRange::default();
+        assert!(range.is_unknown());
+        assert!(!range.contains(&Pubkey::default()));
+        assert!(!range.contains(&MAX_ACCOUNT_ADDRESS));
+        assert!(!range.intersects(&(MIN_ACCOUNT_ADDRESS..=MAX_ACCOUNT_ADDRESS)));
+
+        let other = PubkeyRange::new(Pubkey::new_unique(), MAX_ACCOUNT_ADDRESS);
+        assert_eq!(range.union(&other), other);
+        assert_eq!(other.union(&range), other);
+        assert!(range.union(&range).is_unknown());
+    }
+
     #[test]
     fn test_pubkey_range_update_single() {
         let address = solana_sdk::pubkey::new_rand();
-        let mut address_range = AccountAddressRange::default();
+        let mut address_range = PubkeyRange::default();
 
         address_range.update(&address);
         // For a single update, the min and max should equal to the address
-        assert_eq!(*address_range.min, address);
-        assert_eq!(*address_range.max, address);
+        assert!(!address_range.is_unknown());
+        assert_eq!(address_range.min, address);
+        assert_eq!(address_range.max, address);
+        assert!(address_range.contains(&address));
+        assert!(!address_range.contains(&MIN_ACCOUNT_ADDRESS));
+        assert!(!address_range.contains(&MAX_ACCOUNT_ADDRESS));
+        assert!(address_range.intersects(&(address..=address)));
+        assert!(address_range.intersects(&(MIN_ACCOUNT_ADDRESS..=MAX_ACCOUNT_ADDRESS)));
+    }
+
+    #[test]
+    fn test_pubkey_range_default_address() {
+        // Pubkey::default() is a legitimate address once observed.
+        let mut address_range = PubkeyRange::default();
+        address_range.update(&Pubkey::default());
+        assert!(!address_range.is_unknown());
+        assert!(address_range.contains(&Pubkey::default()));
     }
 
     #[test]
     fn test_pubkey_range_update_multiple() {
         const NUM_PUBKEYS: usize = 20;
 
-        let mut address_range = AccountAddressRange::default();
+        let mut address_range = PubkeyRange::default();
         let mut addresses = Vec::with_capacity(NUM_PUBKEYS);
 
         let mut min_index = 0;
@@ -293,7 +661,59 @@ pub mod tests {
             .iter()
             .for_each(|address| address_range.update(address));
 
-        assert_eq!(*address_range.min, addresses[min_index]);
-        assert_eq!(*address_range.max, addresses[max_index]);
+        assert_eq!(address_range.min, addresses[min_index]);
+        assert_eq!(address_range.max, addresses[max_index]);
+        assert!(addresses
+            .iter()
+            .all(|address| address_range.contains(address)));
+    }
+
+    #[test]
+    fn test_pubkey_range_normal() {
+        let min = Pubkey::new_from_array([0x10u8; 32]);
+        let max = Pubkey::new_from_array([0x20u8; 32]);
+        let below = Pubkey::new_from_array([0x08u8; 32]);
+        let inside = Pubkey::new_from_array([0x18u8; 32]);
+        let above = Pubkey::new_from_array([0x28u8; 32]);
+        let range = PubkeyRange::new(min, max);
+
+        assert!(range.contains(&min));
+        assert!(range.contains(&inside));
+        assert!(range.contains(&max));
+        assert!(!range.contains(&below));
+        assert!(!range.contains(&above));
+
+        assert!(range.intersects(&(below..=min)));
+        assert!(range.intersects(&(inside..=inside)));
+        assert!(range.intersects(&(max..=above)));
+        assert!(range.intersects(&(below..=above)));
+        assert!(!range.intersects(&(below..=below)));
+        assert!(!range.intersects(&(above..=MAX_ACCOUNT_ADDRESS)));
+        // an empty RangeInclusive never intersects
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = max..=min;
+        assert!(!range.intersects(&empty));
+
+        assert_eq!(
+            range.union(&PubkeyRange::new(below, inside)),
+            PubkeyRange::new(below, max)
+        );
+        assert_eq!(
+            range.union(&PubkeyRange::new(above, above)),
+            PubkeyRange::new(min, above)
+        );
+    }
+
+    #[test]
+    fn test_pubkey_range_full() {
+        let range = PubkeyRange::new(MIN_ACCOUNT_ADDRESS, MAX_ACCOUNT_ADDRESS);
+        assert!(range.contains(&MIN_ACCOUNT_ADDRESS));
+        assert!(range.contains(&MAX_ACCOUNT_ADDRESS));
+        assert!(range.contains(&Pubkey::new_unique()));
+        assert!(range.intersects(&(MAX_ACCOUNT_ADDRESS..=MAX_ACCOUNT_ADDRESS)));
+        assert_eq!(
+            range.union(&PubkeyRange::new(Pubkey::new_unique(), MAX_ACCOUNT_ADDRESS)),
+            range
+        );
     }
 }
@@ -1,14 +1,27 @@
 use {
-    crate::{accounts_file::ALIGN_BOUNDARY_OFFSET, u64_align},
+    crate::{
+        accounts_file::ALIGN_BOUNDARY_OFFSET,
+        tiered_storage::{error::TieredStorageError, TieredStorageResult},
+        u64_align,
+    },
     log::*,
     memmap2::Mmap,
-    std::io::Result as IoResult,
 };
 
+/// Converts a size or an offset read from the file into usize, or returns
+/// SizeOverflow if it cannot be addressed on this platform.
+pub fn to_usize(value: impl Into<u64>) -> TieredStorageResult<usize> {
+    let value = value.into();
+    usize::try_from(value).map_err(|_| TieredStorageError::SizeOverflow(value))
+}
+
 /// Borrows a value of type `T` from `mmap`
 ///
 /// Type T must be plain ol' data to ensure no undefined behavior.
-pub fn get_pod<T: bytemuck::AnyBitPattern>(mmap: &Mmap, offset: usize) -> IoResult<(&T, usize)> {
+pub fn get_pod<T: bytemuck::AnyBitPattern>(
+    mmap: &Mmap,
+    offset: usize,
+) -> TieredStorageResult<(&T, usize)> {
     // SAFETY: Since T is AnyBitPattern, it is safe to cast bytes to T.
     unsafe { get_type::<T>(mmap, offset) }
 }
@@ -22,21 +35,55 @@ pub fn get_pod<T: bytemuck::AnyBitPattern>(mmap: &Mmap, offset: usize) -> IoResu
 /// Caller must ensure casting bytes to T is safe.
 /// Refer to the Safety sections in std::slice::from_raw_parts()
 /// and bytemuck's Pod and AnyBitPattern for more information.
-pub unsafe fn get_type<T>(mmap: &Mmap, offset: usize) -> IoResult<(&T, usize)> {
+pub unsafe fn get_type<T>(mmap: &Mmap, offset: usize) -> TieredStorageResult<(&T, usize)> {
     let (data, next) = get_slice(mmap, offset, std::mem::size_of::<T>())?;
     let ptr = data.as_ptr() as *const T;
-    debug_assert!(ptr as usize % std::mem::align_of::<T>() == 0);
+    check_alignment::<T>(ptr, offset)?;
     // SAFETY: The caller ensures it is safe to cast bytes to T,
     // we ensure the size is safe by querying T directly,
     // and we just checked above to ensure the ptr is aligned for T.
     Ok((unsafe { &*ptr }, next))
 }
 
+/// Borrows a slice of `count` values of type `T` from `mmap`
+///
+/// The whole slice is bounds-checked once, which is cheaper than borrowing
+/// each value with `get_pod()` when reading many consecutive values.
+pub fn get_pod_slice<T: bytemuck::AnyBitPattern>(
+    mmap: &Mmap,
+    offset: usize,
+    count: usize,
+) -> TieredStorageResult<(&[T], usize)> {
+    let (data, next) = get_slice(mmap, offset, count.saturating_mul(std::mem::size_of::<T>()))?;
+    let ptr = data.as_ptr() as *const T;
+    check_alignment::<T>(ptr, offset)?;
+    // SAFETY: Since T is AnyBitPattern, it is safe to cast bytes to T,
+    // the size is checked by get_slice() above, and we just checked above
+    // to ensure the ptr is aligned for T.
+    Ok((unsafe { std::slice::from_raw_parts(ptr, count) }, next))
+}
+
+/// Returns an error if the specified pointer, which points to the data at
+/// `offset`, is not properly aligned for type `T`.
+fn check_alignment<T>(ptr: *const T, offset: usize) -> TieredStorageResult<()> {
+    let align = std::mem::align_of::<T>();
+    if ptr as usize % align != 0 {
+        error!(
+            "Requested offset {} is not aligned to {} bytes for type {}",
+            offset,
+            align,
+            std::any::type_name::<T>(),
+        );
+        return Err(TieredStorageError::OffsetAlignmentError(offset, align));
+    }
+    Ok(())
+}
+
 /// Get a reference to the data at `offset` of `size` bytes if that slice
 /// doesn't overrun the internal buffer. Otherwise return an Error.
 /// Also return the offset of the first byte after the requested data that
 /// falls on a 64-byte boundary.
-pub fn get_slice(mmap: &Mmap, offset: usize, size: usize) -> IoResult<(&[u8], usize)> {
+pub fn get_slice(mmap: &Mmap, offset: usize, size: usize) -> TieredStorageResult<(&[u8], usize)> {
     let (next, overflow) = offset.overflowing_add(size);
     if overflow || next > mmap.len() {
         error!(
@@ -45,9 +92,10 @@ pub fn get_slice(mmap: &Mmap, offset: usize, size: usize) -> IoResult<(&[u8], us
             size,
             mmap.len()
         );
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::AddrNotAvailable,
-            "Requested offset and data length exceeds the mmap slice",
+        return Err(TieredStorageError::ReadOutOfBounds(
+            offset,
+            size,
+            mmap.len(),
         ));
     }
     let data = &mmap[offset..next];
@@ -58,3 +106,82 @@ pub fn get_slice(mmap: &Mmap, offset: usize, size: usize) -> IoResult<(&[u8], us
     // to ensure we don't read past the end of the internal buffer.
     Ok((unsafe { std::slice::from_raw_parts(ptr, size) }, next))
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        assert_matches::assert_matches,
+        memmap2::MmapOptions,
+        std::{fs::File, io::Write},
+        tempfile::TempDir,
+    };
+
+    fn new_test_mmap(temp_dir: &TempDir, len: usize) -> Mmap {
+        let path = temp_dir.path().join("test_mmap_utils");
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&vec![0xAB; len]).unwrap();
+        let file = File::open(&path).unwrap();
+        unsafe { MmapOptions::new().map(&file).unwrap() }
+    }
+
+    #[test]
+    fn test_get_pod() {
+        let temp_dir = TempDir::new().unwrap();
+        let mmap = new_test_mmap(&temp_dir, 64);
+
+        let (value, next) = get_pod::<u64>(&mmap, 8).unwrap();
+        assert_eq!(*value, u64::from_le_bytes([0xAB; 8]));
+        assert_eq!(next, 16);
+
+        // the last value that fits in the mmap
+        assert!(get_pod::<u64>(&mmap, 56).is_ok());
+    }
+
+    #[test]
+    fn test_get_pod_out_of_bounds() {
+        let temp_dir = TempDir::new().unwrap();
+        let mmap = new_test_mmap(&temp_dir, 64);
+
+        assert_matches!(
+            get_pod::<u64>(&mmap, 64),
+            Err(TieredStorageError::ReadOutOfBounds(64, 8, 64))
+        );
+        assert_matches!(
+            get_pod::<u64>(&mmap, 60),
+            Err(TieredStorageError::ReadOutOfBounds(60, 8, 64))
+        );
+        // offset + size overflows
+        assert_matches!(
+            get_pod::<u64>(&mmap, usize::MAX - 4),
+            Err(TieredStorageError::ReadOutOfBounds(_, 8, 64))
+        );
+        assert_matches!(
+            get_pod_slice::<u64>(&mmap, 0, 9),
+            Err(TieredStorageError::ReadOutOfBounds(0, 72, 64))
+        );
+        assert_matches!(
+            get_slice(&mmap, 0, 65),
+            Err(TieredStorageError::ReadOutOfBounds(0, 65, 64))
+        );
+    }
+
+    #[test]
+    fn test_get_pod_unaligned() {
+        let temp_dir = TempDir::new().unwrap();
+        let mmap = new_test_mmap(&temp_dir, 64);
+
+        assert_matches!(
+            get_pod::<u64>(&mmap, 1),
+            Err(TieredStorageError::OffsetAlignmentError(1, 8))
+        );
+        assert_matches!(
+            get_pod_slice::<u64>(&mmap, 4, 2),
+            Err(TieredStorageError::OffsetAlignmentError(4, 8))
+        );
+
+        // types with alignment 1 can be read at any offset
+        assert!(get_pod::<[u8; 32]>(&mmap, 1).is_ok());
+        assert!(get_pod_slice::<[u8; 32]>(&mmap, 3, 1).is_ok());
+    }
+}
@@ -1,6 +1,9 @@
 use {
     crate::tiered_storage::{
-        file::TieredWritableFile, footer::TieredStorageFooter, mmap_utils::get_pod,
+        error::TieredStorageError,
+        file::TieredWritableFile,
+        footer::TieredStorageFooter,
+        mmap_utils::{get_pod, get_pod_slice},
         TieredStorageResult,
     },
     indexmap::set::IndexSet,
@@ -41,6 +44,12 @@ pub enum OwnersBlockFormat {
     /// field to access its owner's address in the OwnersBlock.
     #[default]
     AddressesOnly = 0,
+    /// This format persists the addresses in the same way as AddressesOnly,
+    /// followed by a parallel array of u32 that holds the number of accounts
+    /// owned by each owner, which allows checking whether a file has any
+    /// account of a program without scanning its accounts.  The block is
+    /// padded to a multiple of 8 bytes.
+    LocalIndexWithCounts = 1,
 }
 
 impl OwnersBlockFormat {
@@ -54,52 +63,244 @@ impl OwnersBlockFormat {
             Self::AddressesOnly => {
                 let mut bytes_written = 0;
                 for address in &owners_table.owners_set {
-                    bytes_written += file.write_pod(*address)?;
+                    bytes_written += file.write_pod(address)?;
                 }
 
+                Ok(bytes_written)
+            }
+            Self::LocalIndexWithCounts => {
+                let mut bytes_written = 0;
+                for address in &owners_table.owners_set {
+                    bytes_written += file.write_pod(address)?;
+                }
+                for count in &owners_table.account_counts {
+                    bytes_written += file.write_pod(count)?;
+                }
+                // keep the following footer aligned
+                if owners_table.len() % 2 != 0 {
+                    bytes_written += file.write_pod(&0u32)?;
+                }
+                debug_assert_eq!(
+                    bytes_written as u64,
+                    self.block_size(owners_table.len() as u64)
+                );
+
                 Ok(bytes_written)
             }
         }
     }
 
+    /// Returns the size of one owner entry.
+    pub fn entry_size(&self) -> usize {
+        match self {
+            Self::AddressesOnly => std::mem::size_of::<Pubkey>(),
+            Self::LocalIndexWithCounts => {
+                std::mem::size_of::<Pubkey>() + std::mem::size_of::<u32>()
+            }
+        }
+    }
+
+    /// Returns the size of the owners block that holds the specified number
+    /// of owners, including any padding.
+    pub fn block_size(&self, owner_count: u64) -> u64 {
+        let entries_size = owner_count.saturating_mul(self.entry_size() as u64);
+        match self {
+            Self::AddressesOnly => entries_size,
+            Self::LocalIndexWithCounts => entries_size.next_multiple_of(8),
+        }
+    }
+
     /// Returns the owner address associated with the specified owner_offset
     /// and footer inside the input mmap.
+    ///
+    /// Returns TieredStorageError::OwnerOffsetOutOfRange if the owner_offset
+    /// is not less than the owner count of the footer, or
+    /// TieredStorageError::OwnerReadOutOfRegion if the owner entry does not
+    /// fully reside inside the owners block.
     pub fn get_owner_address<'a>(
         &self,
         mmap: &'a Mmap,
         footer: &TieredStorageFooter,
         owner_offset: OwnerOffset,
     ) -> TieredStorageResult<&'a Pubkey> {
+        if owner_offset.0 >= footer.owner_count {
+            return Err(TieredStorageError::OwnerOffsetOutOfRange(
+                owner_offset.0,
+                footer.owner_count,
+            ));
+        }
         match self {
-            Self::AddressesOnly => {
-                let offset = footer.owners_block_offset as usize
-                    + (std::mem::size_of::<Pubkey>() * owner_offset.0 as usize);
+            Self::AddressesOnly | Self::LocalIndexWithCounts => {
+                let offset = (footer.owners_block_offset as usize).saturating_add(
+                    std::mem::size_of::<Pubkey>().saturating_mul(owner_offset.0 as usize),
+                );
+                let owners_block_end = footer.owners_block_end(mmap.len() as u64) as usize;
+                if offset.saturating_add(std::mem::size_of::<Pubkey>()) > owners_block_end {
+                    return Err(TieredStorageError::OwnerReadOutOfRegion(
+                        offset,
+                        owners_block_end,
+                    ));
+                }
                 let (pubkey, _) = get_pod::<Pubkey>(mmap, offset)?;
 
                 Ok(pubkey)
             }
         }
     }
+
+    /// Returns the addresses of all the owners inside the owners block,
+    /// ordered by their owner offsets.
+    ///
+    /// Returns TieredStorageError::OwnerReadOutOfRegion if the owners
+    /// described by the footer do not fully reside inside the owners block.
+    pub fn get_owner_addresses<'a>(
+        &self,
+        mmap: &'a Mmap,
+        footer: &TieredStorageFooter,
+    ) -> TieredStorageResult<&'a [Pubkey]> {
+        match self {
+            Self::AddressesOnly | Self::LocalIndexWithCounts => {
+                let offset = footer.owners_block_offset as usize;
+                let count = footer.owner_count as usize;
+                let end =
+                    offset.saturating_add(std::mem::size_of::<Pubkey>().saturating_mul(count));
+                let owners_block_end = footer.owners_block_end(mmap.len() as u64) as usize;
+                if end > owners_block_end {
+                    return Err(TieredStorageError::OwnerReadOutOfRegion(
+                        end,
+                        owners_block_end,
+                    ));
+                }
+                let (pubkeys, _) = get_pod_slice::<Pubkey>(mmap, offset, count)?;
+
+                Ok(pubkeys)
+            }
+        }
+    }
+
+    /// Returns the number of accounts owned by each owner inside the owners
+    /// block, ordered by their owner offsets, or None if the format does not
+    /// persist the counts.
+    ///
+    /// Returns TieredStorageError::OwnerReadOutOfRegion if the counts
+    /// described by the footer do not fully reside inside the owners block.
+    pub fn get_owner_account_counts<'a>(
+        &self,
+        mmap: &'a Mmap,
+        footer: &TieredStorageFooter,
+    ) -> TieredStorageResult<Option<&'a [u32]>> {
+        match self {
+            Self::AddressesOnly => Ok(None),
+            Self::LocalIndexWithCounts => {
+                let count = footer.owner_count as usize;
+                let offset = (footer.owners_block_offset as usize)
+                    .saturating_add(std::mem::size_of::<Pubkey>().saturating_mul(count));
+                let end = offset.saturating_add(std::mem::size_of::<u32>().saturating_mul(count));
+                let owners_block_end = footer.owners_block_end(mmap.len() as u64) as usize;
+                if end > owners_block_end {
+                    return Err(TieredStorageError::OwnerReadOutOfRegion(
+                        end,
+                        owners_block_end,
+                    ));
+                }
+                let (counts, _) = get_pod_slice::<u32>(mmap, offset, count)?;
+
+                Ok(Some(counts))
+            }
+        }
+    }
 }
 
 /// The in-memory representation of owners block for write.
 /// It manages a set of unique addresses of account owners.
-#[derive(Debug, Default)]
-pub struct OwnersTable<'a> {
-    owners_set: IndexSet<&'a Pubkey>,
+///
+/// The table owns copies of the addresses instead of borrowing them from
+/// the accounts, so the owners may come from short-lived buffers.
+///
+/// The owner offsets are assigned in the order in which the owners first
+/// occur, and are never reordered, so the same accounts always result in
+/// the same owners block.
+#[derive(Clone, Debug, Default)]
+pub struct OwnersTable {
+    owners_set: IndexSet<Pubkey>,
+    /// The number of accounts of each owner, in the order of owners_set.
+    account_counts: Vec<u32>,
+}
+
+/// The positions of the owners of one file inside a list of candidate
+/// owners, which is resolved once so that matching an account of the file
+/// against the same candidates does not compare any address.
+///
+/// A cache only answers for the accounts of the file it is built from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwnerMatchCache {
+    /// The position inside the candidates of each owner of the file, in
+    /// the order of its owners block, or None if it is not a candidate.
+    positions: Box<[Option<usize>]>,
+}
+
+impl OwnerMatchCache {
+    /// Creates the cache from the owners of a file, in the order of its
+    /// owners block, and the candidate owners.
+    ///
+    /// Like account_matches_owners(), an owner that occurs more than once
+    /// in the candidates resolves to its first position.
+    pub fn new(file_owners: &[Pubkey], candidates: &[Pubkey]) -> Self {
+        Self {
+            positions: file_owners
+                .iter()
+                .map(|owner| candidates.iter().position(|candidate| candidate == owner))
+                .collect(),
+        }
+    }
+
+    /// Returns the position inside the candidates of the owner at the
+    /// specified offset, which is None if the owner is not a candidate, or
+    /// None for the offset if it is beyond the owners of the file.
+    pub fn position(&self, owner_offset: OwnerOffset) -> Option<Option<usize>> {
+        self.positions.get(owner_offset.0 as usize).copied()
+    }
 }
 
 /// OwnersBlock is persisted as a consecutive bytes of pubkeys without any
 /// meta-data.  For each account meta, it has a owner_offset field to
 /// access its owner's address in the OwnersBlock.
-impl<'a> OwnersTable<'a> {
+impl OwnersTable {
     /// Add the specified pubkey as the owner into the OwnersWriterTable
     /// if the specified pubkey has not existed in the OwnersWriterTable
     /// yet.  In any case, the function returns its OwnerOffset.
-    pub fn insert(&mut self, pubkey: &'a Pubkey) -> OwnerOffset {
-        let (offset, _existed) = self.owners_set.insert_full(pubkey);
+    pub fn insert(&mut self, pubkey: &Pubkey) -> OwnerOffset {
+        let offset = self.insert_full(pubkey);
+
+        // An offset that does not fit in u32 saturates instead of wrapping
+        // around, so that it is rejected by the limit of the account meta
+        // rather than aliasing another owner.
+        OwnerOffset(u32::try_from(offset).unwrap_or(u32::MAX))
+    }
+
+    /// Same as insert(), but also counts one more account owned by the
+    /// specified pubkey.  The counts are only persisted by
+    /// OwnersBlockFormat::LocalIndexWithCounts.
+    pub fn insert_account_owner(&mut self, pubkey: &Pubkey) -> OwnerOffset {
+        let offset = self.insert_full(pubkey);
+        self.account_counts[offset] = self.account_counts[offset].saturating_add(1);
+        OwnerOffset(u32::try_from(offset).unwrap_or(u32::MAX))
+    }
+
+    /// Inserts the specified pubkey if it has not existed yet, and returns
+    /// its index inside the table.
+    fn insert_full(&mut self, pubkey: &Pubkey) -> usize {
+        let (offset, inserted) = self.owners_set.insert_full(*pubkey);
+        if inserted {
+            self.account_counts.push(0);
+        }
+        offset
+    }
 
-        OwnerOffset(offset as u32)
+    /// Resets the number of accounts of every owner to zero, while keeping
+    /// the owners and their offsets.
+    pub fn clear_account_counts(&mut self) {
+        self.account_counts.fill(0);
     }
 
     /// Returns the number of unique owner addresses in the table.
@@ -111,13 +312,31 @@ impl<'a> OwnersTable<'a> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns TooManyOwners if the table has more than `max_owner_count`
+    /// unique owner addresses, i.e. some of its owner offsets cannot be
+    /// persisted.
+    pub fn check_owner_count(&self, max_owner_count: usize) -> TieredStorageResult<()> {
+        if self.len() > max_owner_count {
+            return Err(TieredStorageError::TooManyOwners(
+                self.len(),
+                max_owner_count,
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use {
-        super::*, crate::tiered_storage::file::TieredWritableFile, memmap2::MmapOptions,
-        std::fs::OpenOptions, tempfile::TempDir,
+        super::*,
+        crate::tiered_storage::{file::TieredWritableFile, footer::SanitizeFooterError},
+        assert_matches::assert_matches,
+        memmap2::MmapOptions,
+        solana_sdk::account::{AccountSharedData, ReadableAccount},
+        std::fs::OpenOptions,
+        tempfile::TempDir,
     };
 
     #[test]
@@ -135,6 +354,7 @@ mod tests {
             // Set owners_block_offset to 0 as we didn't write any account
             // meta/data nor index block.
             owners_block_offset: 0,
+            owner_count: NUM_OWNERS,
             ..TieredStorageFooter::default()
         };
 
@@ -167,6 +387,248 @@ mod tests {
                 address
             );
         }
+
+        // an owner offset beyond the owner count
+        assert_matches!(
+            footer
+                .owners_block_format
+                .get_owner_address(&mmap, &footer, OwnerOffset(NUM_OWNERS)),
+            Err(TieredStorageError::OwnerOffsetOutOfRange(
+                NUM_OWNERS, NUM_OWNERS
+            ))
+        );
+    }
+
+    #[test]
+    fn test_owner_offset_out_of_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_owner_offset_out_of_range");
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let unlisted_addresses: Vec<_> =
+            std::iter::repeat_with(Pubkey::new_unique).take(4).collect();
+
+        // The owner count understates the owners block, so an owner offset
+        // beyond the count would still be inside the file.
+        let footer = TieredStorageFooter {
+            owners_block_offset: 0,
+            owner_count: addresses.len() as u32,
+            ..TieredStorageFooter::default()
+        };
+
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+
+            let mut owners_table = OwnersTable::default();
+            for address in addresses.iter().chain(&unlisted_addresses) {
+                owners_table.insert(address);
+            }
+            footer
+                .owners_block_format
+                .write_owners_block(&mut file, &owners_table)
+                .unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+
+        let file = OpenOptions::new().read(true).open(path).unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+
+        assert_eq!(
+            footer
+                .owners_block_format
+                .get_owner_address(&mmap, &footer, OwnerOffset(1))
+                .unwrap(),
+            &addresses[1]
+        );
+        assert_matches!(
+            footer
+                .owners_block_format
+                .get_owner_address(&mmap, &footer, OwnerOffset(5)),
+            Err(TieredStorageError::OwnerOffsetOutOfRange(5, 2))
+        );
+    }
+
+    #[test]
+    fn test_get_owner_addresses() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_get_owner_addresses");
+        const NUM_OWNERS: u32 = 10;
+
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS as usize)
+            .collect();
+
+        let footer = TieredStorageFooter {
+            owners_block_offset: 0,
+            owner_count: NUM_OWNERS,
+            ..TieredStorageFooter::default()
+        };
+
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+
+            let mut owners_table = OwnersTable::default();
+            addresses.iter().for_each(|owner_address| {
+                owners_table.insert(owner_address);
+            });
+            footer
+                .owners_block_format
+                .write_owners_block(&mut file, &owners_table)
+                .unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+
+        let file = OpenOptions::new().read(true).open(path).unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+
+        assert_eq!(
+            footer
+                .owners_block_format
+                .get_owner_addresses(&mmap, &footer)
+                .unwrap(),
+            addresses
+        );
+
+        // an overstated owner count would read into the footer
+        let footer = TieredStorageFooter {
+            owner_count: NUM_OWNERS + 1,
+            ..footer
+        };
+        assert_matches!(
+            footer
+                .owners_block_format
+                .get_owner_addresses(&mmap, &footer),
+            Err(TieredStorageError::OwnerReadOutOfRegion(_, _))
+        );
+    }
+
+    #[test]
+    fn test_owners_block_overstated_owner_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test_owners_block_overstated_owner_count");
+        const NUM_OWNERS: u32 = 10;
+
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS as usize)
+            .collect();
+
+        // The footer claims one more owner than what the owners block has.
+        let footer = TieredStorageFooter {
+            owners_block_offset: 0,
+            owner_count: NUM_OWNERS + 1,
+            ..TieredStorageFooter::default()
+        };
+
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+
+            let mut owners_table = OwnersTable::default();
+            addresses.iter().for_each(|owner_address| {
+                owners_table.insert(owner_address);
+            });
+            footer
+                .owners_block_format
+                .write_owners_block(&mut file, &owners_table)
+                .unwrap();
+            footer.write_footer_block(&mut file).unwrap();
+        }
+
+        let file = OpenOptions::new().read(true).open(path).unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+
+        // The real last owner is still readable.
+        assert_eq!(
+            footer
+                .owners_block_format
+                .get_owner_address(&mmap, &footer, OwnerOffset(NUM_OWNERS - 1))
+                .unwrap(),
+            addresses.last().unwrap(),
+        );
+
+        // The overstated last owner would be read from the footer.
+        let owners_block_end = NUM_OWNERS as usize * std::mem::size_of::<Pubkey>();
+        assert_eq!(
+            footer.owners_block_end(mmap.len() as u64),
+            owners_block_end as u64
+        );
+        assert_matches!(
+            footer
+                .owners_block_format
+                .get_owner_address(&mmap, &footer, OwnerOffset(NUM_OWNERS)),
+            Err(TieredStorageError::OwnerReadOutOfRegion(offset, end))
+                if offset == owners_block_end && end == owners_block_end
+        );
+
+        // The mismatch is also caught by sanitizing the footer layout.
+        assert_matches!(
+            footer.sanitize_layout(mmap.len() as u64),
+            Err(SanitizeFooterError::OwnersBlockSizeMismatch(_, owner_count))
+                if owner_count == NUM_OWNERS + 1
+        );
+    }
+
+    #[test]
+    fn test_owners_block_with_counts() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_owners_block_with_counts");
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+        // the owner of each account, as indices into `addresses`
+        let owner_distribution = [0, 1, 0, 0, 2, 0, 1];
+        let expected_counts: [u32; 3] = [4, 2, 1];
+
+        let mut owners_table = OwnersTable::default();
+        for owner_index in owner_distribution {
+            owners_table.insert_account_owner(&addresses[owner_index]);
+        }
+        // inserting an owner without an account does not count
+        owners_table.insert(&addresses[2]);
+        assert_eq!(owners_table.account_counts, expected_counts);
+
+        // the odd number of counts is padded to keep the footer aligned
+        let format = OwnersBlockFormat::LocalIndexWithCounts;
+        assert_eq!(format.block_size(3), 3 * 36 + 4);
+        assert_eq!(format.block_size(4), 4 * 36);
+        assert_eq!(OwnersBlockFormat::AddressesOnly.block_size(3), 3 * 32);
+
+        let footer = TieredStorageFooter {
+            owners_block_format: format,
+            owners_block_offset: 0,
+            owner_count: addresses.len() as u32,
+            ..TieredStorageFooter::default()
+        };
+        {
+            let mut file = TieredWritableFile::new(&path).unwrap();
+            let bytes_written = format.write_owners_block(&mut file, &owners_table).unwrap();
+            assert_eq!(bytes_written as u64, format.block_size(3));
+            footer.write_footer_block(&mut file).unwrap();
+        }
+
+        let file = OpenOptions::new().read(true).open(path).unwrap();
+        let mmap = unsafe { MmapOptions::new().map(&file).unwrap() };
+        footer.sanitize_layout(mmap.len() as u64).unwrap();
+        assert_eq!(
+            format.get_owner_addresses(&mmap, &footer).unwrap(),
+            addresses
+        );
+        assert_eq!(
+            format
+                .get_owner_address(&mmap, &footer, OwnerOffset(2))
+                .unwrap(),
+            &addresses[2]
+        );
+        assert_eq!(
+            format.get_owner_account_counts(&mmap, &footer).unwrap(),
+            Some(&expected_counts[..])
+        );
+
+        // the same block read as addresses only has no counts
+        assert_eq!(
+            OwnersBlockFormat::AddressesOnly
+                .get_owner_account_counts(&mmap, &footer)
+                .unwrap(),
+            None
+        );
     }
 
     #[test]
@@ -195,4 +657,85 @@ mod tests {
         // as the input
         assert_eq!(owners_table.owners_set.len(), addresses.len());
     }
+
+    #[test]
+    fn test_owners_table_from_short_lived_buffers() {
+        let mut owners_table = OwnersTable::default();
+        const NUM_OWNERS: usize = 10;
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS)
+            .collect();
+
+        // Each owner is read from an account that is dropped right after
+        // its owner is inserted.
+        for (i, address) in addresses.iter().chain(addresses.iter()).enumerate() {
+            let account = AccountSharedData::new(1, 0, address);
+            assert_eq!(
+                owners_table.insert(account.owner()),
+                OwnerOffset((i % NUM_OWNERS) as u32)
+            );
+        }
+
+        assert_eq!(owners_table.len(), NUM_OWNERS);
+        assert!(owners_table.owners_set.iter().eq(addresses.iter()));
+    }
+
+    #[test]
+    fn test_owners_table_many_owners() {
+        const NUM_OWNERS: usize = 100_000;
+        let mut owners_table = OwnersTable::default();
+        let addresses: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS)
+            .collect();
+
+        // each insertion copies one address, whether it is new or not
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(
+                owners_table.insert_account_owner(address),
+                OwnerOffset(i as u32)
+            );
+        }
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(
+                owners_table.insert_account_owner(address),
+                OwnerOffset(i as u32)
+            );
+        }
+        assert_eq!(owners_table.len(), NUM_OWNERS);
+        assert!(owners_table.owners_set.iter().eq(addresses.iter()));
+        assert!(owners_table.account_counts.iter().all(|&count| count == 2));
+
+        // test: clearing the counts keeps the owners and their offsets
+        owners_table.clear_account_counts();
+        assert!(owners_table.owners_set.iter().eq(addresses.iter()));
+        assert!(owners_table.account_counts.iter().all(|&count| count == 0));
+        assert_eq!(
+            owners_table.insert(&addresses[NUM_OWNERS / 2]),
+            OwnerOffset((NUM_OWNERS / 2) as u32)
+        );
+    }
+
+    #[test]
+    fn test_check_owner_count() {
+        const MAX_OWNER_COUNT: usize = 10;
+        let mut owners_table = OwnersTable::default();
+        for _ in 0..MAX_OWNER_COUNT {
+            owners_table.insert(&Pubkey::new_unique());
+        }
+
+        // test: an owners table at the limit
+        assert_matches!(owners_table.check_owner_count(MAX_OWNER_COUNT), Ok(()));
+
+        // test: an existing owner does not count again
+        let existing_owner = *owners_table.owners_set.first().unwrap();
+        owners_table.insert(&existing_owner);
+        assert_matches!(owners_table.check_owner_count(MAX_OWNER_COUNT), Ok(()));
+
+        // test: one more owner exceeds the limit
+        owners_table.insert(&Pubkey::new_unique());
+        assert_matches!(
+            owners_table.check_owner_count(MAX_OWNER_COUNT),
+            Err(TieredStorageError::TooManyOwners(11, MAX_OWNER_COUNT))
+        );
+    }
 }
@@ -0,0 +1,239 @@
+#![cfg(test)]
+//! Property-based tests of the hot format, which write random batches of
+//! accounts through TieredStorage and check what the readers return.
+//!
+//! - The round-trip property reads every field of every account back.
+//! - The corruption property flips one byte of a written file and reopens
+//!   it, where the readers must either fail or return accounts that stay
+//!   inside the file, but never panic.
+use {
+    super::{generation::WriteOptions, readable::TieredStorageReader, TieredStorage, HOT_FORMAT},
+    crate::{
+        account_storage::meta::StorableAccountsWithHashesAndWriteVersions,
+        accounts_hash::AccountHash,
+        tiered_storage::{index::IndexOffset, owners::OWNER_NO_OWNER},
+    },
+    proptest::prelude::*,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
+        rent_collector::RENT_EXEMPT_RENT_EPOCH,
+        stake_history::Epoch,
+    },
+    std::{
+        fs,
+        panic::{self, AssertUnwindSafe},
+        path::Path,
+    },
+    tempfile::TempDir,
+};
+
+/// The number of distinct owners the accounts are drawn from, which is
+/// small so that owners are shared across accounts.
+const NUM_OWNERS: usize = 4;
+
+/// The maximum data length of a generated account.
+const MAX_DATA_LEN: usize = 10 * 1024;
+
+/// An account generated by proptest, before it is turned into an
+/// AccountSharedData.
+#[derive(Clone, Debug)]
+struct TestAccount {
+    address: Pubkey,
+    lamports: u64,
+    owner: usize,
+    data: Vec<u8>,
+    executable: bool,
+    rent_epoch: Option<Epoch>,
+    hash: Option<[u8; 32]>,
+}
+
+impl TestAccount {
+    fn to_account_shared_data(&self, owners: &[Pubkey]) -> AccountSharedData {
+        let mut account =
+            AccountSharedData::new(self.lamports, self.data.len(), &owners[self.owner]);
+        account.set_data_from_slice(&self.data);
+        account.set_executable(self.executable);
+        account.set_rent_epoch(self.rent_epoch.unwrap_or(RENT_EXEMPT_RENT_EPOCH));
+        account
+    }
+}
+
+fn test_account() -> impl Strategy<Value = TestAccount> {
+    (
+        any::<[u8; 32]>(),
+        // one out of four accounts is a zero-lamport account
+        prop_oneof![1 => Just(0u64), 3 => 1..=u64::MAX],
+        0..NUM_OWNERS,
+        (0..=MAX_DATA_LEN, any::<u8>()),
+        any::<bool>(),
+        proptest::option::of(0..RENT_EXEMPT_RENT_EPOCH),
+        proptest::option::of(any::<[u8; 32]>()),
+    )
+        .prop_map(
+            |(address, lamports, owner, (data_len, seed), executable, rent_epoch, hash)| {
+                TestAccount {
+                    address: Pubkey::new_from_array(address),
+                    lamports,
+                    owner,
+                    data: (0..data_len).map(|i| seed.wrapping_add(i as u8)).collect(),
+                    executable,
+                    rent_epoch,
+                    hash,
+                }
+            },
+        )
+}
+
+fn test_accounts() -> impl Strategy<Value = Vec<TestAccount>> {
+    proptest::collection::vec(test_account(), 0..32)
+}
+
+/// Writes the specified accounts to a hot TieredStorage at the specified
+/// path, and returns the TieredStorage, which is read-only even without any
+/// account.
+fn write_accounts(path: &Path, accounts: &[TestAccount], owners: &[Pubkey]) -> TieredStorage {
+    let shared_accounts: Vec<_> = accounts
+        .iter()
+        .map(|account| account.to_account_shared_data(owners))
+        .collect();
+    let account_refs: Vec<_> = accounts
+        .iter()
+        .zip(shared_accounts.iter())
+        .map(|(account, shared_account)| (&account.address, shared_account))
+        .collect();
+    // Slot information is not used here
+    let account_data = (Slot::MAX, &account_refs[..]);
+    let hashes: Vec<_> = accounts
+        .iter()
+        .map(|account| AccountHash(account.hash.map(Hash::new_from_array).unwrap_or_default()))
+        .collect();
+    let storable_accounts =
+        StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+            &account_data,
+            hashes,
+            vec![0; accounts.len()],
+        );
+
+    let tiered_storage = TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+    if accounts.is_empty() {
+        tiered_storage.finalize_empty(&HOT_FORMAT).unwrap();
+        return tiered_storage;
+    }
+    let stored_infos = tiered_storage
+        .write_accounts(&storable_accounts, 0, &HOT_FORMAT)
+        .unwrap();
+    // the hot format keeps the accounts in their input order
+    assert!(stored_infos
+        .iter()
+        .enumerate()
+        .all(|(i, stored_info)| stored_info.offset == i));
+    tiered_storage
+}
+
+/// Reads every account of the specified reader, and checks that each of
+/// them stays inside the file.  Returns the first error, if any.
+fn read_all_accounts(reader: &TieredStorageReader) -> Result<(), String> {
+    let file_len = reader.len();
+    for i in 0..reader.num_accounts() {
+        let (account, _) = reader
+            .get_account(IndexOffset(i as u32))
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("account {i} is missing"))?;
+        // the returned slices must be part of the file
+        assert!(account.data().len() < file_len);
+        let _ = (
+            account.pubkey(),
+            account.hash(),
+            account.owner(),
+            account.lamports(),
+            account.executable(),
+            account.rent_epoch(),
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_hot_storage_round_trip(accounts in test_accounts()) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_round_trip");
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS)
+            .collect();
+        let tiered_storage = write_accounts(&path, &accounts, &owners);
+
+        let reader = tiered_storage.reader().unwrap();
+        prop_assert_eq!(reader.num_accounts(), accounts.len());
+        prop_assert_eq!(reader.len() as u64, fs::metadata(&path).unwrap().len());
+        for (i, expected) in accounts.iter().enumerate() {
+            let (account, _) = reader.get_account(IndexOffset(i as u32)).unwrap().unwrap();
+            prop_assert_eq!(account.pubkey(), &expected.address);
+            // an account without a hash reads back the default hash
+            prop_assert_eq!(
+                account.hash(),
+                &AccountHash(expected.hash.map(Hash::new_from_array).unwrap_or_default())
+            );
+            if expected.lamports == 0 {
+                // a zero-lamport account is stored as the default account
+                prop_assert_eq!(account.lamports(), 0);
+                prop_assert_eq!(account.owner(), &*OWNER_NO_OWNER);
+                prop_assert_eq!(account.data(), &[] as &[u8]);
+                prop_assert!(!account.executable());
+                prop_assert_eq!(account.rent_epoch(), Epoch::default());
+            } else {
+                prop_assert_eq!(account.lamports(), expected.lamports);
+                prop_assert_eq!(account.owner(), &owners[expected.owner]);
+                prop_assert_eq!(account.data(), &expected.data[..]);
+                prop_assert_eq!(account.executable(), expected.executable);
+                prop_assert_eq!(
+                    account.rent_epoch(),
+                    expected.rent_epoch.unwrap_or(RENT_EXEMPT_RENT_EPOCH)
+                );
+            }
+        }
+        prop_assert!(reader
+            .get_account(IndexOffset(accounts.len() as u32))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_hot_storage_corrupted_byte(
+        accounts in proptest::collection::vec(test_account(), 1..8),
+        position in any::<prop::sample::Index>(),
+        xor in 1..=u8::MAX,
+    ) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_hot_storage_corrupted_byte");
+        let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(NUM_OWNERS)
+            .collect();
+        let mut tiered_storage = write_accounts(&path, &accounts, &owners);
+        tiered_storage.set_remove_on_drop(false);
+        drop(tiered_storage);
+
+        let mut bytes = fs::read(&path).unwrap();
+        let position = position.index(bytes.len());
+        bytes[position] ^= xor;
+        fs::write(&path, &bytes).unwrap();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            TieredStorageReader::new_from_path(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|reader| read_all_accounts(&reader))
+        }));
+        prop_assert!(
+            result.is_ok(),
+            "flipping byte {} of {} with {:#04x} panics the reader",
+            position,
+            bytes.len(),
+            xor
+        );
+    }
+}
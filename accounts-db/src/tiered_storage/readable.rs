@@ -3,10 +3,14 @@ use {
         account_storage::meta::StoredAccountMeta,
         accounts_file::MatchAccountOwnerError,
         tiered_storage::{
-            file::TieredReadableFile,
+            error::TieredStorageError,
+            file::{MmapAccessPattern, ReaderBackend, TieredReadableFile},
             footer::{AccountMetaFormat, TieredStorageFooter},
-            hot::HotStorageReader,
+            generation::FormatGeneration,
+            hot::{HotAccountForHash, HotStorageReader, StoredAccountIndexEntry},
             index::IndexOffset,
+            owners::OwnerMatchCache,
+            verification::{PrefixVerification, VerifyReport},
             TieredStorageResult,
         },
     },
@@ -20,13 +24,26 @@ pub enum TieredStorageReader {
     Hot(HotStorageReader),
 }
 
+static_assertions::assert_impl_all!(TieredStorageReader: Send, Sync);
+
 impl TieredStorageReader {
     /// Creates a reader for the specified tiered storage accounts file.
     pub fn new_from_path(path: impl AsRef<Path>) -> TieredStorageResult<Self> {
+        Self::new_from_path_with_backend(path, ReaderBackend::Mmap)
+    }
+
+    /// Creates a reader for the specified tiered storage accounts file that
+    /// accesses the file via the specified backend.
+    pub fn new_from_path_with_backend(
+        path: impl AsRef<Path>,
+        backend: ReaderBackend,
+    ) -> TieredStorageResult<Self> {
         let file = TieredReadableFile::new(&path)?;
         let footer = TieredStorageFooter::new_from_footer_block(&file)?;
         match footer.account_meta_format {
-            AccountMetaFormat::Hot => Ok(Self::Hot(HotStorageReader::new(file)?)),
+            AccountMetaFormat::Hot => Ok(Self::Hot(HotStorageReader::new_with_backend(
+                file, backend,
+            )?)),
         }
     }
 
@@ -57,6 +74,13 @@ impl TieredStorageReader {
         }
     }
 
+    /// Returns the oldest format generation able to read the file, which
+    /// lets snapshot producers check their outputs against the generation
+    /// configured for the cluster.
+    pub fn required_generation(&self) -> FormatGeneration {
+        FormatGeneration::required_by(self.footer())
+    }
+
     /// Returns the total number of accounts.
     pub fn num_accounts(&self) -> usize {
         match self {
@@ -64,6 +88,22 @@ impl TieredStorageReader {
         }
     }
 
+    /// Returns the number of bytes each account occupies in the file, in
+    /// index order.
+    pub fn account_sizes(&self) -> TieredStorageResult<Vec<usize>> {
+        match self {
+            Self::Hot(hot) => hot.account_sizes(),
+        }
+    }
+
+    /// Returns the total number of bytes occupied by the accounts in the
+    /// file.
+    pub fn total_account_bytes(&self) -> TieredStorageResult<usize> {
+        match self {
+            Self::Hot(hot) => hot.total_account_bytes(),
+        }
+    }
+
     /// Returns the account located at the specified index offset.
     pub fn get_account(
         &self,
@@ -74,6 +114,117 @@ impl TieredStorageReader {
         }
     }
 
+    /// Returns the lamports of the account located at the specified index
+    /// offset without reading the rest of the account, or None if the index
+    /// offset is out of range.
+    pub fn get_lamports(&self, index_offset: IndexOffset) -> TieredStorageResult<Option<u64>> {
+        match self {
+            Self::Hot(hot) => hot.get_lamports(index_offset),
+        }
+    }
+
+    /// Returns the accounts located at the specified index offsets, in the
+    /// order of the specified index offsets.  An index offset that is out of
+    /// range maps to None.
+    pub fn get_accounts(
+        &self,
+        index_offsets: &[IndexOffset],
+    ) -> TieredStorageResult<Vec<Option<StoredAccountMeta<'_>>>> {
+        match self {
+            Self::Hot(hot) => hot.get_accounts(index_offsets),
+        }
+    }
+
+    /// Returns the first account whose address matches the specified address,
+    /// along with its index offset.
+    ///
+    /// Returns None if no account in this file has the specified address.
+    pub fn find_account(
+        &self,
+        address: &Pubkey,
+    ) -> TieredStorageResult<Option<(StoredAccountMeta<'_>, IndexOffset)>> {
+        match self {
+            Self::Hot(hot) => hot.find_account(address),
+        }
+    }
+
+    /// Hints the kernel about how the content of the file will be accessed.
+    pub fn advise(&self, access: MmapAccessPattern) -> TieredStorageResult<()> {
+        match self {
+            Self::Hot(hot) => hot.advise(access),
+        }
+    }
+
+    /// Makes the pages of the specified accounts resident, so that loading
+    /// them afterward does not block on reading the file.  Index offsets
+    /// that are out of range are skipped.
+    pub fn prefetch(&self, index_offsets: &[IndexOffset]) {
+        match self {
+            Self::Hot(hot) => hot.prefetch(index_offsets),
+        }
+    }
+
+    /// Visits the address, the data and the lamports of every account in
+    /// index order without loading the accounts.  The scan stops at the
+    /// first account that cannot be read.
+    pub fn scan_account_data<'a>(
+        &'a self,
+        callback: impl FnMut(&'a Pubkey, &'a [u8], u64),
+    ) -> TieredStorageResult<()> {
+        match self {
+            Self::Hot(hot) => hot.scan_account_data(callback),
+        }
+    }
+
+    /// Visits the fields that the accounts hash calculation needs of every
+    /// account in index order, without loading the accounts.  The scan stops
+    /// at the first account that cannot be read.
+    pub fn scan_for_hash<'a>(
+        &'a self,
+        callback: impl FnMut(HotAccountForHash<'a>),
+    ) -> TieredStorageResult<()> {
+        match self {
+            Self::Hot(hot) => hot.scan_for_hash(callback),
+        }
+    }
+
+    /// Visits the address, the owner and the lamports of every account in
+    /// index order, along with its index offset, without reading the account
+    /// data.  The scan stops at the first account that cannot be read.
+    pub fn scan_metas<'a>(
+        &'a self,
+        callback: impl FnMut(&'a Pubkey, &'a Pubkey, u64, IndexOffset),
+    ) -> TieredStorageResult<()> {
+        match self {
+            Self::Hot(hot) => hot.scan_metas(callback),
+        }
+    }
+
+    /// Returns the address, the lamports and the data length of every
+    /// account in index order, along with its index offset, without reading
+    /// the account data.
+    pub fn index_entries(&self) -> TieredStorageResult<Vec<StoredAccountIndexEntry>> {
+        match self {
+            Self::Hot(hot) => hot.index_entries(),
+        }
+    }
+
+    /// Returns whether each of the specified pubkeys is the address of an
+    /// account in this file, in the order of the specified pubkeys.
+    pub fn contains_any(&self, pubkeys: &[Pubkey]) -> TieredStorageResult<Vec<bool>> {
+        match self {
+            Self::Hot(hot) => hot.contains_any(pubkeys),
+        }
+    }
+
+    /// Returns true if the specified pubkey is the address of an account in
+    /// this file, or false if the index block cannot be read.
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        match self {
+            Self::Hot(hot) => hot.contains(pubkey),
+        }
+    }
+
     /// Returns Ok(index_of_matching_owner) if the account owner at
     /// `account_offset` is one of the pubkeys in `owners`.
     ///
@@ -82,12 +233,17 @@ impl TieredStorageReader {
     ///
     /// Returns Err(MatchAccountOwnerError::UnableToLoad) if there is any internal
     /// error that causes the data unable to load, including `account_offset`
-    /// causes a data overrun.
+    /// causes a data overrun, or `index_offset` is out of range.
     pub fn account_matches_owners(
         &self,
         index_offset: IndexOffset,
         owners: &[Pubkey],
     ) -> Result<usize, MatchAccountOwnerError> {
+        // An out-of-range index would otherwise read past the index entries
+        // and alias the data of another block.
+        if index_offset.0 as usize >= self.num_accounts() {
+            return Err(MatchAccountOwnerError::UnableToLoad);
+        }
         match self {
             Self::Hot(hot) => {
                 let account_offset = hot
@@ -98,6 +254,127 @@ impl TieredStorageReader {
         }
     }
 
+    /// Returns the offset where the account blocks end.
+    pub fn account_blocks_end(&self) -> usize {
+        match self {
+            Self::Hot(hot) => hot.account_blocks_end(),
+        }
+    }
+
+    /// Resolves, once, the positions of the owners of this file inside the
+    /// specified candidate owners.  The returned cache can be held along
+    /// with this reader to match many of its accounts against the same
+    /// candidates via account_matches_owners_cached().
+    pub fn build_owner_match_cache(
+        &self,
+        owners: &[Pubkey],
+    ) -> TieredStorageResult<OwnerMatchCache> {
+        match self {
+            Self::Hot(hot) => hot.build_owner_match_cache(owners),
+        }
+    }
+
+    /// Same as account_matches_owners(), but answers from the specified
+    /// cache built by build_owner_match_cache() of this reader.
+    pub fn account_matches_owners_cached(
+        &self,
+        index_offset: IndexOffset,
+        cache: &OwnerMatchCache,
+    ) -> Result<usize, MatchAccountOwnerError> {
+        if index_offset.0 as usize >= self.num_accounts() {
+            return Err(MatchAccountOwnerError::UnableToLoad);
+        }
+        match self {
+            Self::Hot(hot) => {
+                let account_offset = hot
+                    .get_account_offset(index_offset)
+                    .map_err(|_| MatchAccountOwnerError::UnableToLoad)?;
+                hot.account_matches_owners_cached(account_offset, cache)
+            }
+        }
+    }
+
+    /// Returns the number of unique account owners.
+    pub fn owner_count(&self) -> usize {
+        match self {
+            Self::Hot(hot) => hot.owner_count(),
+        }
+    }
+
+    /// Returns the addresses of all the unique account owners.
+    pub fn owners(&self) -> TieredStorageResult<&[Pubkey]> {
+        match self {
+            Self::Hot(hot) => hot.owners(),
+        }
+    }
+
+    /// Returns the number of accounts owned by the specified owner, or None
+    /// if no account of this file is owned by it.
+    pub fn owner_account_count(&self, owner: &Pubkey) -> TieredStorageResult<Option<u32>> {
+        match self {
+            Self::Hot(hot) => hot.owner_account_count(owner),
+        }
+    }
+
+    /// Returns whether the specified pubkey falls inside the address range
+    /// of the stored accounts.
+    pub fn pubkey_in_range(&self, pubkey: &Pubkey) -> bool {
+        match self {
+            Self::Hot(hot) => hot.pubkey_in_range(pubkey),
+        }
+    }
+
+    /// Verifies the hash of the file content against the footer hash.
+    pub fn verify_file_hash(&self) -> TieredStorageResult<()> {
+        match self {
+            Self::Hot(hot) => hot.verify_file_hash(),
+        }
+    }
+
+    /// Checks the consistency of every account and of the file hash, and
+    /// returns all the inconsistencies found.
+    pub fn verify(&self) -> TieredStorageResult<VerifyReport> {
+        match self {
+            Self::Hot(hot) => hot.verify(),
+        }
+    }
+
+    /// Starts an incremental verification of the file hash by hashing up
+    /// to `budget_bytes` from the beginning of the file.
+    pub fn verify_prefix(&self, budget_bytes: u64) -> PrefixVerification {
+        match self {
+            Self::Hot(hot) => hot.verify_prefix(budget_bytes),
+        }
+    }
+
+    /// Continues the incremental verification of the file hash by hashing
+    /// up to `budget_bytes` right after the bytes covered by `state`.
+    pub fn resume_verification(
+        &self,
+        state: PrefixVerification,
+        budget_bytes: u64,
+    ) -> PrefixVerification {
+        match self {
+            Self::Hot(hot) => hot.resume_verification(state, budget_bytes),
+        }
+    }
+
+    /// Returns the addresses of all the accounts, ordered by their index.
+    pub fn account_addresses(&self) -> TieredStorageResult<&[Pubkey]> {
+        match self {
+            Self::Hot(hot) => hot.account_addresses(),
+        }
+    }
+
+    /// Returns an iterator over all the accounts in index order.
+    pub fn account_iter(&self) -> TieredStorageAccountIter<'_> {
+        TieredStorageAccountIter {
+            reader: self,
+            index_offset: IndexOffset(0),
+            error: None,
+        }
+    }
+
     /// Return a vector of account metadata for each account, starting from
     /// `index_offset`
     pub fn accounts(
@@ -109,3 +386,42 @@ impl TieredStorageReader {
         }
     }
 }
+
+/// The iterator over the accounts of a tiered storage instance in index
+/// order.
+///
+/// The iteration stops at the first account that cannot be loaded, and the
+/// error that terminated the iteration can be obtained via take_error().
+pub struct TieredStorageAccountIter<'a> {
+    reader: &'a TieredStorageReader,
+    index_offset: IndexOffset,
+    error: Option<TieredStorageError>,
+}
+
+impl<'a> TieredStorageAccountIter<'a> {
+    /// Returns the error that terminated the iteration early, if any.
+    pub fn take_error(&mut self) -> Option<TieredStorageError> {
+        self.error.take()
+    }
+}
+
+impl<'a> Iterator for TieredStorageAccountIter<'a> {
+    type Item = StoredAccountMeta<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.reader.get_account(self.index_offset) {
+            Ok(Some((account, next_index_offset))) => {
+                self.index_offset = next_index_offset;
+                Some(account)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
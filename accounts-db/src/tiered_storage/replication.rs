@@ -0,0 +1,241 @@
+//! Region signatures of tiered storage files for replication.
+//!
+//! A tool that ships re-packed files to downstream nodes only needs to send
+//! the parts of a file that the receiver does not have yet.  The account
+//! blocks are split into fixed-size chunks at fixed offsets, so that files
+//! sharing the same prefix of account blocks also share the signatures of
+//! the chunks covering it.  The index block, the owners block and the footer
+//! often change wholesale and are therefore signed as whole blocks.
+use {
+    crate::tiered_storage::{
+        file::{ReaderBackend, TieredReadableFile},
+        footer::TieredStorageFooter,
+        TieredStorageResult,
+    },
+    solana_sdk::hash::{hash, Hash},
+    std::{ops::Range, path::Path},
+};
+
+/// The size of the chunks the account blocks are split into.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The signature of a byte range of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionSignature {
+    /// The byte range of the region within its file.
+    pub range: Range<usize>,
+    /// The hash of the bytes of the region.
+    pub hash: Hash,
+}
+
+impl RegionSignature {
+    fn new(bytes: &[u8], range: Range<usize>) -> Self {
+        let hash = hash(&bytes[range.clone()]);
+        Self { range, hash }
+    }
+}
+
+/// The signatures of all the regions of a tiered storage file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionSignatures {
+    /// The signatures of the CHUNK_SIZE chunks covering the account blocks,
+    /// in file order.  The last chunk may be shorter than CHUNK_SIZE.
+    pub chunks: Vec<RegionSignature>,
+    /// The signature of the index block.
+    pub index_block: RegionSignature,
+    /// The signature of the owners block.
+    pub owners_block: RegionSignature,
+    /// The signature of the footer.
+    pub footer: RegionSignature,
+}
+
+/// The byte ranges of a file that differ from another version of it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegionDiff {
+    /// The differing byte ranges, in file order.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl RegionDiff {
+    /// Returns true if both versions have the same signatures.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Computes the region signatures of the specified tiered storage file.
+pub fn region_signatures(path: impl AsRef<Path>) -> TieredStorageResult<RegionSignatures> {
+    let file = TieredReadableFile::new(path)?;
+    let mmap = file.map(ReaderBackend::Mmap)?;
+    let footer = TieredStorageFooter::new_from_mmap(&mmap)?;
+
+    // The footer has been sanitized against the file size, so the block
+    // boundaries below are all within the file and in order.
+    let file_len = mmap.len() as u64;
+    let account_blocks_end = footer.account_blocks_end(file_len) as usize;
+    let index_block_offset = footer.index_block_offset as usize;
+    let owners_block_offset = footer.owners_block_offset as usize;
+    let owners_block_end = footer.owners_block_end(file_len) as usize;
+
+    let chunks = (0..account_blocks_end)
+        .step_by(CHUNK_SIZE)
+        .map(|start| {
+            let end = start.saturating_add(CHUNK_SIZE).min(account_blocks_end);
+            RegionSignature::new(&mmap, start..end)
+        })
+        .collect();
+
+    Ok(RegionSignatures {
+        chunks,
+        index_block: RegionSignature::new(&mmap, index_block_offset..owners_block_offset),
+        owners_block: RegionSignature::new(&mmap, owners_block_offset..owners_block_end),
+        footer: RegionSignature::new(&mmap, owners_block_end..mmap.len()),
+    })
+}
+
+/// Returns the byte ranges of `b` that differ from `a`, i.e. the ranges a
+/// receiver holding `a` must fetch to reconstruct `b`.
+///
+/// Chunks are compared by position.  Chunks of `a` beyond the account
+/// blocks of `b` are not reported, as they are replaced by the regions
+/// following the account blocks of `b`.
+pub fn diff_signatures(a: &RegionSignatures, b: &RegionSignatures) -> RegionDiff {
+    let chunks = b
+        .chunks
+        .iter()
+        .enumerate()
+        .filter(|(i, chunk)| a.chunks.get(*i) != Some(chunk));
+    let blocks = [
+        (&a.index_block, &b.index_block),
+        (&a.owners_block, &b.owners_block),
+        (&a.footer, &b.footer),
+    ]
+    .into_iter()
+    .filter(|(a_block, b_block)| a_block != b_block);
+
+    let ranges = chunks
+        .map(|(_, chunk)| chunk)
+        .chain(blocks.map(|(_, b_block)| b_block))
+        .map(|signature| signature.range.clone())
+        .filter(|range| !range.is_empty())
+        .collect();
+    RegionDiff { ranges }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            account_storage::meta::StoredMeta,
+            tiered_storage::test_utils::{create_test_account, write_hot_storage_with_accounts},
+        },
+        solana_sdk::account::{AccountSharedData, ReadableAccount, WritableAccount},
+        std::fs,
+        tempfile::TempDir,
+    };
+
+    const NUM_ACCOUNTS: u64 = 20;
+
+    /// Creates accounts of roughly 10KiB each, so that the account blocks
+    /// span several chunks.
+    fn new_accounts(range: Range<u64>) -> Vec<(StoredMeta, AccountSharedData)> {
+        range.map(|i| create_test_account(10_000 + i)).collect()
+    }
+
+    fn write_and_sign(
+        temp_dir: &TempDir,
+        name: &str,
+        accounts: &[(StoredMeta, AccountSharedData)],
+    ) -> (Vec<u8>, RegionSignatures) {
+        let path = temp_dir.path().join(name);
+        write_hot_storage_with_accounts(&path, accounts);
+        (fs::read(&path).unwrap(), region_signatures(&path).unwrap())
+    }
+
+    #[test]
+    fn test_identical_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let accounts = new_accounts(0..NUM_ACCOUNTS);
+        let (bytes, a) = write_and_sign(&temp_dir, "a", &accounts);
+        let (_, b) = write_and_sign(&temp_dir, "b", &accounts);
+
+        assert_eq!(a, b);
+        assert!(diff_signatures(&a, &b).is_empty());
+
+        // the regions cover the whole file without overlapping
+        assert!(a.chunks.len() > 2);
+        let mut ranges: Vec<_> = a.chunks.iter().map(|chunk| chunk.range.clone()).collect();
+        ranges.extend([
+            a.index_block.range.clone(),
+            a.owners_block.range.clone(),
+            a.footer.range.clone(),
+        ]);
+        assert_eq!(ranges[0].start, 0);
+        assert!(ranges.windows(2).all(|w| w[0].end == w[1].start));
+        assert_eq!(ranges.last().unwrap().end, bytes.len());
+    }
+
+    #[test]
+    fn test_append_accounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let accounts = new_accounts(0..NUM_ACCOUNTS + 10);
+        let (_, a) = write_and_sign(&temp_dir, "a", &accounts[..NUM_ACCOUNTS as usize]);
+        let (_, b) = write_and_sign(&temp_dir, "b", &accounts);
+
+        // the chunks fully covered by the shared accounts are unchanged
+        let num_full_chunks = a
+            .chunks
+            .iter()
+            .take_while(|chunk| chunk.range.len() == CHUNK_SIZE)
+            .count();
+        assert!(num_full_chunks > 0);
+        assert!(b.chunks.len() > a.chunks.len());
+
+        let diff = diff_signatures(&a, &b);
+        let expected_chunks = b.chunks[num_full_chunks..]
+            .iter()
+            .map(|chunk| chunk.range.clone());
+        let expected_blocks = [&b.index_block, &b.owners_block, &b.footer]
+            .into_iter()
+            .map(|block| block.range.clone());
+        assert_eq!(
+            diff.ranges,
+            expected_chunks.chain(expected_blocks).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_modify_account_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let accounts = new_accounts(0..NUM_ACCOUNTS);
+        let mut modified_accounts = accounts.clone();
+        let account = &mut modified_accounts[NUM_ACCOUNTS as usize / 2].1;
+        let data = vec![0xAB; account.data().len()];
+        account.set_data_from_slice(&data);
+
+        let (a_bytes, a) = write_and_sign(&temp_dir, "a", &accounts);
+        let (b_bytes, b) = write_and_sign(&temp_dir, "b", &modified_accounts);
+        assert_eq!(a_bytes.len(), b_bytes.len());
+
+        // the bytes that differ within the account blocks
+        let account_blocks_end = b.chunks.last().unwrap().range.end;
+        let differing: Vec<_> = (0..account_blocks_end)
+            .filter(|&i| a_bytes[i] != b_bytes[i])
+            .collect();
+        let first_chunk = differing.first().unwrap() / CHUNK_SIZE;
+        let last_chunk = differing.last().unwrap() / CHUNK_SIZE;
+        assert!(first_chunk > 0);
+        assert!(last_chunk < b.chunks.len() - 1);
+
+        // exactly the chunks covering the modified data, plus the footer
+        // which holds the file hash
+        let diff = diff_signatures(&a, &b);
+        let expected: Vec<_> = b.chunks[first_chunk..=last_chunk]
+            .iter()
+            .map(|chunk| chunk.range.clone())
+            .chain([b.footer.range.clone()])
+            .collect();
+        assert_eq!(diff.ranges, expected);
+    }
+}
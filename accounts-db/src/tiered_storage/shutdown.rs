@@ -0,0 +1,228 @@
+//! Bulk teardown of tiered storages, e.g. at validator exit.
+//!
+//! Dropping a large number of TieredStorage instances one by one unmaps and
+//! removes their files serially, and a removal failure is only logged.  The
+//! helper here spreads the drops over a thread pool and reports failures
+//! instead.
+use {
+    crate::tiered_storage::TieredStorage,
+    rayon::prelude::*,
+    std::{fs, io, path::PathBuf},
+};
+
+/// The maximum number of errors kept by a ShutdownReport.
+pub const MAX_REPORTED_ERRORS: usize = 8;
+
+/// The outcome of drop_storages().
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// The number of files that have been removed.
+    pub num_removed: usize,
+    /// The number of files that were already removed by someone else.
+    pub num_already_gone: usize,
+    /// The number of files that have been left on disk, either as requested
+    /// or because they belong to someone else.
+    pub num_kept: usize,
+    /// The number of files that failed to be removed.
+    pub num_failed: usize,
+    /// The details of the first MAX_REPORTED_ERRORS failures.
+    pub errors: Vec<(PathBuf, io::Error)>,
+}
+
+impl ShutdownReport {
+    fn merge(mut self, other: Self) -> Self {
+        self.num_removed += other.num_removed;
+        self.num_already_gone += other.num_already_gone;
+        self.num_kept += other.num_kept;
+        self.num_failed += other.num_failed;
+        let num_errors = MAX_REPORTED_ERRORS.saturating_sub(self.errors.len());
+        self.errors
+            .extend(other.errors.into_iter().take(num_errors));
+        self
+    }
+}
+
+/// Drops the specified storages using `threads` threads, and removes their
+/// files if `remove_files` is true.
+///
+/// As when dropping each TieredStorage, a file found at the path of a
+/// storage that failed to write it belongs to someone else, and is kept.
+/// What this adds is removing the files in parallel, and reporting the
+/// failures to the caller instead of only logging them.
+pub fn drop_storages(
+    storages: Vec<TieredStorage>,
+    threads: usize,
+    remove_files: bool,
+) -> ShutdownReport {
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.max(1))
+        .thread_name(|i| format!("solTsShutdown{i:02}"))
+        .build()
+        .unwrap();
+    thread_pool.install(|| {
+        storages
+            .into_par_iter()
+            .map(|mut storage| {
+                storage.set_remove_on_drop(false);
+                let path = storage.path().to_path_buf();
+                let owns_file = storage.owns_file();
+                // unmaps the file
+                drop(storage);
+
+                let mut report = ShutdownReport::default();
+                if !remove_files || !owns_file {
+                    report.num_kept = 1;
+                    return report;
+                }
+                match fs::remove_file(&path) {
+                    Ok(()) => report.num_removed = 1,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                        report.num_already_gone = 1
+                    }
+                    Err(err) => {
+                        report.num_failed = 1;
+                        report.errors.push((path, err));
+                    }
+                }
+                report
+            })
+            .reduce(ShutdownReport::default, ShutdownReport::merge)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accounts_hash::AccountHash,
+            tiered_storage::{
+                error::TieredStorageError,
+                hot::HOT_FORMAT,
+                test_utils::{create_test_account, with_storable_accounts, write_hot_storage},
+                TieredStorage,
+            },
+        },
+        assert_matches::assert_matches,
+        solana_sdk::hash::Hash,
+        tempfile::TempDir,
+        test_case::test_case,
+    };
+
+    const NUM_STORAGES: usize = 300;
+
+    fn new_storages(temp_dir: &TempDir) -> Vec<TieredStorage> {
+        (0..NUM_STORAGES)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("test_drop_storages_{i}"));
+                write_hot_storage(&path, &[i as u64 % 7]);
+                TieredStorage::new_readonly(path).unwrap()
+            })
+            .collect()
+    }
+
+    #[test_case(1)]
+    #[test_case(4)]
+    fn test_drop_storages_remove_files(threads: usize) {
+        let temp_dir = TempDir::new().unwrap();
+        let storages = new_storages(&temp_dir);
+        let paths: Vec<_> = storages.iter().map(|s| s.path().to_path_buf()).collect();
+
+        let report = drop_storages(storages, threads, true);
+        assert_eq!(report.num_removed, NUM_STORAGES);
+        assert_eq!(report.num_already_gone, 0);
+        assert_eq!(report.num_kept, 0);
+        assert_eq!(report.num_failed, 0);
+        assert!(report.errors.is_empty());
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+
+    #[test]
+    fn test_drop_storages_already_gone() {
+        let temp_dir = TempDir::new().unwrap();
+        let storages = new_storages(&temp_dir);
+        let num_gone = NUM_STORAGES / 3;
+        for storage in &storages[..num_gone] {
+            fs::remove_file(storage.path()).unwrap();
+        }
+
+        let report = drop_storages(storages, 4, true);
+        assert_eq!(report.num_removed, NUM_STORAGES - num_gone);
+        assert_eq!(report.num_already_gone, num_gone);
+        assert_eq!(report.num_failed, 0);
+    }
+
+    #[test]
+    fn test_drop_storages_keep_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let storages = new_storages(&temp_dir);
+        let paths: Vec<_> = storages.iter().map(|s| s.path().to_path_buf()).collect();
+
+        let report = drop_storages(storages, 4, false);
+        assert_eq!(report.num_kept, NUM_STORAGES);
+        assert_eq!(report.num_removed, 0);
+        assert_eq!(report.num_failed, 0);
+        assert!(paths.iter().all(|path| path.exists()));
+
+        // the files are intact
+        for path in paths {
+            let storage = TieredStorage::new_readonly(path).unwrap();
+            assert_eq!(storage.reader().unwrap().num_accounts(), 1);
+        }
+    }
+
+    #[test]
+    fn test_drop_storages_existing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storages = new_storages(&temp_dir);
+        let accounts = [create_test_account(7)];
+
+        // replace some storages with the storages whose write found the
+        // files of the replaced ones, which keep their files on drop
+        let num_existing = NUM_STORAGES / 3;
+        let existing_paths: Vec<_> = storages[..num_existing]
+            .iter()
+            .map(|storage| storage.path().to_path_buf())
+            .collect();
+        for storage in &mut storages[..num_existing] {
+            storage.set_remove_on_drop(false);
+            let other = TieredStorage::new_writable(storage.path());
+            assert_matches!(
+                with_storable_accounts(
+                    &accounts,
+                    vec![AccountHash(Hash::default())],
+                    vec![0],
+                    |storable_accounts| other.write_accounts(storable_accounts, 0, &HOT_FORMAT),
+                ),
+                Err(TieredStorageError::FileAlreadyExists(_))
+            );
+            *storage = other;
+        }
+        assert!(existing_paths.iter().all(|path| path.exists()));
+
+        // test: only the owned files are removed
+        let report = drop_storages(storages, 4, true);
+        assert_eq!(report.num_removed, NUM_STORAGES - num_existing);
+        assert_eq!(report.num_kept, num_existing);
+        assert_eq!(report.num_failed, 0);
+        assert!(existing_paths.iter().all(|path| path.exists()));
+    }
+
+    #[test]
+    fn test_drop_storages_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storages = new_storages(&temp_dir);
+        // a directory in place of a file cannot be removed by remove_file()
+        let num_failed = MAX_REPORTED_ERRORS + 2;
+        for storage in &storages[..num_failed] {
+            fs::remove_file(storage.path()).unwrap();
+            fs::create_dir(storage.path()).unwrap();
+        }
+        storages.truncate(NUM_STORAGES / 2);
+
+        let report = drop_storages(storages, 4, true);
+        assert_eq!(report.num_failed, num_failed);
+        assert_eq!(report.num_removed, NUM_STORAGES / 2 - num_failed);
+        assert_eq!(report.errors.len(), MAX_REPORTED_ERRORS);
+    }
+}
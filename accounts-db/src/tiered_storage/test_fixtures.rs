@@ -0,0 +1,529 @@
+#![cfg(test)]
+//! A corpus of valid and corrupted tiered-storage files used to validate the
+//! robustness of the readers.
+//!
+//! Every robustness fix should add a fixture to `generate_fixtures()` rather
+//! than hand-building its own corrupted file, so that `validate_all_fixtures`
+//! keeps exercising every known failure mode in one place.
+use {
+    super::{
+        error::TieredStorageError,
+        footer::{TieredStorageFooter, FOOTER_SIZE},
+        hot::tests::corrupt_account_data_padding,
+        index::IndexOffset,
+        test_utils::{
+            create_executable_account_without_data, create_test_account, write_hot_storage,
+            write_hot_storage_with_accounts,
+        },
+    },
+    memoffset::offset_of,
+    std::{
+        fs::{self, OpenOptions},
+        io::{Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+    },
+    tempfile::TempDir,
+};
+
+/// The expected outcome of opening (and reading) a fixture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ExpectedOutcome {
+    /// Opening succeeds and the standard read battery passes.
+    Readable { num_accounts: usize },
+    /// Opening succeeds, but the content is known to be inconsistent, so
+    /// the read battery is not exercised.
+    OpenOnly,
+    /// Opening fails with the specified error.
+    OpenError(ExpectedError),
+    /// Opening succeeds, but reading the accounts fails with the specified
+    /// error.
+    ReadError(ExpectedError),
+}
+
+/// The kind of TieredStorageError expected from a fixture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ExpectedError {
+    Io,
+    MagicNumberMismatch,
+    InvalidFooterVersion,
+    UnsupportedFormatVersion,
+    InvalidFooterSize,
+    SanitizeFooter,
+    InvalidAccountDataPadding,
+    AccountBlockSizeExceeded,
+    Truncated,
+}
+
+impl ExpectedError {
+    /// Returns true if the specified error is of this kind.
+    pub(super) fn matches(&self, error: &TieredStorageError) -> bool {
+        matches!(
+            (self, error),
+            (Self::Io, TieredStorageError::Io(_))
+                | (
+                    Self::MagicNumberMismatch,
+                    TieredStorageError::MagicNumberMismatch(_, _)
+                )
+                | (
+                    Self::InvalidFooterVersion,
+                    TieredStorageError::InvalidFooterVersion(_)
+                )
+                | (
+                    Self::UnsupportedFormatVersion,
+                    TieredStorageError::UnsupportedFormatVersion(_, _)
+                )
+                | (
+                    Self::InvalidFooterSize,
+                    TieredStorageError::InvalidFooterSize(_, _)
+                )
+                | (Self::SanitizeFooter, TieredStorageError::SanitizeFooter(_))
+                | (
+                    Self::InvalidAccountDataPadding,
+                    TieredStorageError::InvalidAccountDataPadding(_, _)
+                )
+                | (
+                    Self::AccountBlockSizeExceeded,
+                    TieredStorageError::AccountBlockSizeExceeded(_, _)
+                )
+                | (Self::Truncated, TieredStorageError::Truncated { .. })
+        )
+    }
+}
+
+/// A named file inside the fixture corpus.
+#[derive(Debug)]
+pub(super) struct Fixture {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub expected: ExpectedOutcome,
+}
+
+/// Overwrites the bytes at the specified offset relative to the beginning
+/// of the footer.
+pub(super) fn overwrite_footer_bytes(path: impl AsRef<Path>, footer_offset: usize, bytes: &[u8]) {
+    let mut file = OpenOptions::new().write(true).open(path).unwrap();
+    file.seek(SeekFrom::End(-(FOOTER_SIZE as i64) + footer_offset as i64))
+        .unwrap();
+    file.write_all(bytes).unwrap();
+}
+
+/// Truncates the file to its first `len` bytes.
+pub(super) fn truncate_file(path: impl AsRef<Path>, len: u64) {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_len(len)
+        .unwrap();
+}
+
+/// Removes the bytes of the file starting at the specified offset up to its
+/// footer, which models a file whose content is partially missing while its
+/// footer is intact.
+pub(crate) fn remove_bytes_before_footer(path: impl AsRef<Path>, offset: u64) {
+    let bytes = fs::read(&path).unwrap();
+    let footer_offset = bytes.len() - FOOTER_SIZE;
+    assert!(offset as usize <= footer_offset);
+    let mut truncated = bytes[..offset as usize].to_vec();
+    truncated.extend_from_slice(&bytes[footer_offset..]);
+    fs::write(path, truncated).unwrap();
+}
+
+const BASELINE_DATA_SIZES: &[u64] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1000, 2000, 3000, 0];
+
+/// Generates the corpus of fixtures inside the specified directory.
+pub(super) fn generate_fixtures(dir: &TempDir) -> Vec<Fixture> {
+    let mut fixtures = vec![];
+    let mut add_fixture = |name: &'static str, expected: ExpectedOutcome, build: &dyn Fn(&Path)| {
+        let path = dir.path().join(name);
+        build(&path);
+        fixtures.push(Fixture {
+            name,
+            path,
+            expected,
+        });
+    };
+
+    // A file with a footer field overwritten by the specified bytes.
+    let corrupt_footer = |footer_offset: usize, bytes: &'static [u8]| {
+        move |path: &Path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            overwrite_footer_bytes(path, footer_offset, bytes);
+        }
+    };
+
+    // valid files
+    add_fixture(
+        "valid_baseline",
+        ExpectedOutcome::Readable {
+            num_accounts: BASELINE_DATA_SIZES.len(),
+        },
+        &|path| write_hot_storage(path, BASELINE_DATA_SIZES),
+    );
+    add_fixture(
+        "valid_zero_accounts",
+        ExpectedOutcome::Readable { num_accounts: 0 },
+        &|path| write_hot_storage(path, &[]),
+    );
+    add_fixture(
+        "valid_single_account",
+        ExpectedOutcome::Readable { num_accounts: 1 },
+        &|path| write_hot_storage(path, &[42]),
+    );
+    add_fixture(
+        "valid_single_zero_lamport_account",
+        ExpectedOutcome::Readable { num_accounts: 1 },
+        &|path| write_hot_storage(path, &[0]),
+    );
+    add_fixture(
+        "valid_executable_without_data",
+        ExpectedOutcome::Readable { num_accounts: 3 },
+        &|path| {
+            write_hot_storage_with_accounts(
+                path,
+                &[
+                    create_test_account(1),
+                    create_executable_account_without_data(),
+                    create_test_account(2),
+                ],
+            )
+        },
+    );
+
+    // truncations
+    add_fixture(
+        "empty_file",
+        ExpectedOutcome::OpenError(ExpectedError::Io),
+        &|path| {
+            fs::File::create(path).unwrap();
+        },
+    );
+    add_fixture(
+        "shorter_than_magic_number",
+        ExpectedOutcome::OpenError(ExpectedError::Io),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            truncate_file(path, 4);
+        },
+    );
+    add_fixture(
+        "truncated_tail",
+        ExpectedOutcome::OpenError(ExpectedError::MagicNumberMismatch),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            let len = fs::metadata(path).unwrap().len();
+            truncate_file(path, len - 8);
+        },
+    );
+    add_fixture(
+        "missing_owners_block",
+        ExpectedOutcome::OpenError(ExpectedError::Truncated),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            let footer = TieredStorageFooter::new_from_path(path).unwrap();
+            remove_bytes_before_footer(path, footer.owners_block_offset + 8);
+        },
+    );
+    add_fixture(
+        "missing_index_block",
+        ExpectedOutcome::OpenError(ExpectedError::Truncated),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            let footer = TieredStorageFooter::new_from_path(path).unwrap();
+            remove_bytes_before_footer(path, footer.index_block_offset);
+        },
+    );
+    add_fixture(
+        "missing_account_blocks",
+        ExpectedOutcome::OpenError(ExpectedError::Truncated),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            remove_bytes_before_footer(path, 64);
+        },
+    );
+
+    // corrupted footer tail
+    add_fixture(
+        "bad_magic_number",
+        ExpectedOutcome::OpenError(ExpectedError::MagicNumberMismatch),
+        &corrupt_footer(FOOTER_SIZE - 8, b"BadMagic"),
+    );
+    add_fixture(
+        "bad_footer_version",
+        ExpectedOutcome::OpenError(ExpectedError::InvalidFooterVersion),
+        &corrupt_footer(offset_of!(TieredStorageFooter, format_version), &[0x00; 8]),
+    );
+    add_fixture(
+        "unsupported_footer_version",
+        ExpectedOutcome::OpenError(ExpectedError::UnsupportedFormatVersion),
+        &corrupt_footer(offset_of!(TieredStorageFooter, format_version), &[0xFF; 8]),
+    );
+    add_fixture(
+        "bad_footer_size",
+        ExpectedOutcome::OpenError(ExpectedError::InvalidFooterSize),
+        &corrupt_footer(offset_of!(TieredStorageFooter, footer_size), &[0x01; 8]),
+    );
+
+    // invalid formats
+    add_fixture(
+        "bad_account_meta_format",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, account_meta_format),
+            &[0xD0, 0xBA],
+        ),
+    );
+    add_fixture(
+        "bad_owners_block_format",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, owners_block_format),
+            &[0xD0, 0xBA],
+        ),
+    );
+    add_fixture(
+        "bad_index_block_format",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, index_block_format),
+            &[0xD0, 0xBA],
+        ),
+    );
+    add_fixture(
+        "bad_account_block_format",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, account_block_format),
+            &[0xD0, 0xBA],
+        ),
+    );
+
+    // inconsistent footer content
+    add_fixture(
+        "huge_account_entry_count",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, account_entry_count),
+            &[0xFF; 4],
+        ),
+    );
+    add_fixture(
+        "huge_owner_count",
+        ExpectedOutcome::OpenError(ExpectedError::Truncated),
+        &corrupt_footer(offset_of!(TieredStorageFooter, owner_count), &[0xFF; 4]),
+    );
+    // an overstated owner count is indistinguishable from a file whose
+    // owners block is partially missing
+    add_fixture(
+        "overstated_owner_count",
+        ExpectedOutcome::OpenError(ExpectedError::Truncated),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            let owner_count = TieredStorageFooter::new_from_path(path)
+                .unwrap()
+                .owner_count;
+            overwrite_footer_bytes(
+                path,
+                offset_of!(TieredStorageFooter, owner_count),
+                &(owner_count + 1).to_le_bytes(),
+            );
+        },
+    );
+    add_fixture(
+        "understated_owner_count",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &|path| {
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            let owner_count = TieredStorageFooter::new_from_path(path)
+                .unwrap()
+                .owner_count;
+            overwrite_footer_bytes(
+                path,
+                offset_of!(TieredStorageFooter, owner_count),
+                &(owner_count - 1).to_le_bytes(),
+            );
+        },
+    );
+    add_fixture(
+        "bad_account_meta_entry_size",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, account_meta_entry_size),
+            &[0x01, 0, 0, 0],
+        ),
+    );
+    add_fixture(
+        "index_block_after_footer",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, index_block_offset),
+            &[0xFF; 8],
+        ),
+    );
+    add_fixture(
+        "owners_block_before_index_block",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, owners_block_offset),
+            &[0; 8],
+        ),
+    );
+    add_fixture(
+        "inverted_account_address_range",
+        ExpectedOutcome::OpenError(ExpectedError::SanitizeFooter),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, min_account_address),
+            &[0xFF; 32],
+        ),
+    );
+    // every account is larger than the understated block size, which is
+    // only detected when the accounts are read
+    add_fixture(
+        "understated_account_block_size",
+        ExpectedOutcome::ReadError(ExpectedError::AccountBlockSizeExceeded),
+        &corrupt_footer(
+            offset_of!(TieredStorageFooter, account_block_size),
+            &[0x08, 0, 0, 0, 0, 0, 0, 0],
+        ),
+    );
+
+    // corrupted account entries
+    add_fixture(
+        "bad_account_data_padding",
+        ExpectedOutcome::ReadError(ExpectedError::InvalidAccountDataPadding),
+        &|path| {
+            // The last baseline account has neither data nor optional fields,
+            // so any padding exceeds its account block.
+            write_hot_storage(path, BASELINE_DATA_SIZES);
+            corrupt_account_data_padding(
+                path,
+                IndexOffset(BASELINE_DATA_SIZES.len() as u32 - 1),
+                7,
+            );
+        },
+    );
+
+    fixtures
+}
+
+mod tests {
+    use {
+        super::*,
+        crate::{accounts_file::MatchAccountOwnerError, tiered_storage::TieredStorage},
+        solana_sdk::{account::ReadableAccount, pubkey::Pubkey},
+    };
+
+    /// The standard read battery that every readable fixture must pass.
+    fn run_read_battery(tiered_storage: &TieredStorage, num_accounts: usize) {
+        let reader = tiered_storage.reader().unwrap();
+        assert_eq!(reader.num_accounts(), num_accounts);
+
+        let mut index_offset = IndexOffset(0);
+        let mut num_read = 0;
+        while let Some((stored_meta, next)) = reader.get_account(index_offset).unwrap() {
+            assert_eq!(
+                reader.account_matches_owners(index_offset, &[*stored_meta.owner()]),
+                if stored_meta.lamports() == 0 {
+                    Err(MatchAccountOwnerError::NoMatch)
+                } else {
+                    Ok(0)
+                }
+            );
+            assert_eq!(
+                reader.account_matches_owners(index_offset, &[Pubkey::new_unique()]),
+                Err(MatchAccountOwnerError::NoMatch)
+            );
+            num_read += 1;
+            index_offset = next;
+        }
+        assert_eq!(num_read, num_accounts);
+        assert_eq!(reader.accounts(IndexOffset(0)).unwrap().len(), num_accounts);
+    }
+
+    #[test]
+    fn verify_all_fixtures() {
+        let temp_dir = TempDir::new().unwrap();
+        for fixture in generate_fixtures(&temp_dir) {
+            // TieredStorage::verify() never removes the file, and reports
+            // the same errors as opening the file
+            let result = TieredStorage::verify(&fixture.path);
+            assert!(fixture.path.exists());
+            match (fixture.expected, result) {
+                (ExpectedOutcome::Readable { num_accounts }, Ok(report)) => {
+                    assert_eq!(report.num_accounts, num_accounts);
+                    assert!(
+                        report.is_consistent(),
+                        "fixture '{}': found {:?}",
+                        fixture.name,
+                        report.inconsistencies,
+                    );
+                }
+                (ExpectedOutcome::OpenOnly, Ok(_)) => {}
+                (ExpectedOutcome::ReadError(_), Ok(report)) => {
+                    assert!(!report.is_consistent(), "fixture '{}'", fixture.name);
+                }
+                (ExpectedOutcome::OpenError(expected_error), Err(err)) => {
+                    assert!(
+                        expected_error.matches(&err),
+                        "fixture '{}': expected {expected_error:?}, found {err:?}",
+                        fixture.name,
+                    );
+                }
+                (expected, result) => {
+                    panic!(
+                        "fixture '{}': expected {expected:?}, found {result:?}",
+                        fixture.name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn validate_all_fixtures() {
+        let temp_dir = TempDir::new().unwrap();
+        let fixtures = generate_fixtures(&temp_dir);
+        assert!(fixtures.len() >= 10);
+
+        for fixture in fixtures {
+            let result = TieredStorage::new_readonly(&fixture.path);
+            match (fixture.expected, result) {
+                (ExpectedOutcome::Readable { num_accounts }, Ok(tiered_storage)) => {
+                    run_read_battery(&tiered_storage, num_accounts);
+                }
+                (ExpectedOutcome::OpenOnly, Ok(_)) => {}
+                (ExpectedOutcome::ReadError(expected_error), Ok(tiered_storage)) => {
+                    let err = tiered_storage
+                        .reader()
+                        .unwrap()
+                        .accounts(IndexOffset(0))
+                        .unwrap_err();
+                    assert!(
+                        expected_error.matches(&err),
+                        "fixture '{}': expected {expected_error:?}, found {err:?}",
+                        fixture.name,
+                    );
+                }
+                (ExpectedOutcome::OpenError(expected_error), Err(err)) => {
+                    assert!(
+                        expected_error.matches(&err),
+                        "fixture '{}': expected {expected_error:?}, found {err:?}",
+                        fixture.name,
+                    );
+                }
+                (expected, Ok(_)) => {
+                    panic!(
+                        "fixture '{}': expected {expected:?}, found Ok",
+                        fixture.name
+                    );
+                }
+                (expected, Err(err)) => {
+                    panic!(
+                        "fixture '{}': expected {expected:?}, found {err:?}",
+                        fixture.name
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,27 +1,40 @@
 #![cfg(test)]
 //! Helper functions for TieredStorage tests
 use {
-    super::footer::TieredStorageFooter,
+    super::{
+        footer::{FooterHashKind, TieredStorageFooter},
+        generation::WriteOptions,
+        hot::{HotStorageWriter, HOT_FORMAT},
+    },
     crate::{
-        account_storage::meta::{StoredAccountMeta, StoredMeta},
+        account_storage::meta::{
+            StorableAccountsWithHashesAndWriteVersions, StoredAccountMeta, StoredMeta,
+            StoredMetaWriteVersion,
+        },
         accounts_hash::AccountHash,
         tiered_storage::owners::OWNER_NO_OWNER,
     },
     solana_sdk::{
         account::{Account, AccountSharedData, ReadableAccount},
+        clock::{Epoch, Slot},
         hash::Hash,
         pubkey::Pubkey,
         rent_collector::RENT_EXEMPT_RENT_EPOCH,
     },
+    std::path::Path,
 };
 
 /// Create a test account based on the specified seed.
 /// The created test account might have default rent_epoch
 /// and write_version.
 ///
+/// The rent epoch of every account whose seed is not a multiple of 3 is
+/// persisted, which includes a rent epoch of 0, while the others are
+/// rent-exempt.
+///
 /// When the seed is zero, then a zero-lamport test account will be
 /// created.
-pub(super) fn create_test_account(seed: u64) -> (StoredMeta, AccountSharedData) {
+pub(crate) fn create_test_account(seed: u64) -> (StoredMeta, AccountSharedData) {
     let data_byte = seed as u8;
     let owner_byte = u8::MAX - data_byte;
     let account = Account {
@@ -31,7 +44,7 @@ pub(super) fn create_test_account(seed: u64) -> (StoredMeta, AccountSharedData)
         owner: [owner_byte; 32].into(),
         executable: seed % 2 > 0,
         rent_epoch: if seed % 3 > 0 {
-            seed
+            seed - 1
         } else {
             RENT_EXEMPT_RENT_EPOCH
         },
@@ -45,10 +58,29 @@ pub(super) fn create_test_account(seed: u64) -> (StoredMeta, AccountSharedData)
     (stored_meta, AccountSharedData::from(account))
 }
 
+/// Creates an executable account without any data, which is anomalous but
+/// occurs in historical ledger data, e.g. after a failed deployment.
+pub(crate) fn create_executable_account_without_data() -> (StoredMeta, AccountSharedData) {
+    let account = Account {
+        lamports: 1_000,
+        data: vec![],
+        owner: Pubkey::new_unique(),
+        executable: true,
+        rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+    };
+    let stored_meta = StoredMeta {
+        write_version_obsolete: u64::MAX,
+        pubkey: Pubkey::new_unique(),
+        data_len: 0,
+    };
+    (stored_meta, AccountSharedData::from(account))
+}
+
 pub(super) fn verify_test_account(
     stored_meta: &StoredAccountMeta<'_>,
     account: Option<&impl ReadableAccount>,
     address: &Pubkey,
+    account_hash: &AccountHash,
 ) {
     let (lamports, owner, data, executable) = account
         .map(|acc| (acc.lamports(), acc.owner(), acc.data(), acc.executable()))
@@ -60,16 +92,111 @@ pub(super) fn verify_test_account(
     assert_eq!(stored_meta.executable(), executable);
     assert_eq!(stored_meta.owner(), owner);
     assert_eq!(stored_meta.pubkey(), address);
-    assert_eq!(*stored_meta.hash(), AccountHash(Hash::default()));
+    assert_eq!(stored_meta.hash(), account_hash);
+    // a zero-lamport account is read back as AccountSharedData::default()
+    let rent_epoch = account
+        .filter(|acc| acc.lamports() != 0)
+        .map_or(Epoch::default(), |acc| acc.rent_epoch());
+    assert_eq!(stored_meta.rent_epoch(), rent_epoch);
 }
 
 pub(super) fn verify_test_account_with_footer(
     stored_meta: &StoredAccountMeta<'_>,
     account: Option<&impl ReadableAccount>,
     address: &Pubkey,
+    account_hash: &AccountHash,
     footer: &TieredStorageFooter,
 ) {
-    verify_test_account(stored_meta, account, address);
-    assert!(footer.min_account_address <= *address);
-    assert!(footer.max_account_address >= *address);
+    verify_test_account(stored_meta, account, address, account_hash);
+    assert!(footer.account_address_range().contains(address));
+}
+
+/// Writes a hot storage file that contains accounts with the specified
+/// data sizes.
+pub(crate) fn write_hot_storage(path: impl AsRef<Path>, account_data_sizes: &[u64]) {
+    let accounts: Vec<_> = account_data_sizes
+        .iter()
+        .map(|size| create_test_account(*size))
+        .collect();
+    write_hot_storage_with_accounts(path, &accounts);
+}
+
+/// Writes a hot storage file that contains accounts with the specified
+/// data sizes, and whose file hash is computed by the specified algorithm.
+pub(crate) fn write_hot_storage_with_hash_kind(
+    path: impl AsRef<Path>,
+    account_data_sizes: &[u64],
+    hash_kind: FooterHashKind,
+) {
+    let accounts: Vec<_> = account_data_sizes
+        .iter()
+        .map(|size| create_test_account(*size))
+        .collect();
+    write_accounts_with_hash_kind(path, &accounts, hash_kind);
+}
+
+/// The storable accounts that the tests build from their test accounts.
+pub(crate) type TestStorableAccounts<'a> = StorableAccountsWithHashesAndWriteVersions<
+    'a,
+    'a,
+    AccountSharedData,
+    (Slot, &'a [(&'a Pubkey, &'a AccountSharedData)]),
+    AccountHash,
+>;
+
+/// Invokes `f` with the storable accounts of the specified accounts, along
+/// with the specified hashes and write versions, and returns its result.
+pub(crate) fn with_storable_accounts<R>(
+    accounts: &[(StoredMeta, AccountSharedData)],
+    hashes: Vec<AccountHash>,
+    write_versions: Vec<StoredMetaWriteVersion>,
+    f: impl FnOnce(&TestStorableAccounts<'_>) -> R,
+) -> R {
+    let account_refs: Vec<_> = accounts
+        .iter()
+        .map(|(stored_meta, account)| (&stored_meta.pubkey, account))
+        .collect();
+
+    // Slot information is not used here
+    let account_data = (Slot::MAX, &account_refs[..]);
+    let storable_accounts =
+        StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+            &account_data,
+            hashes,
+            write_versions,
+        );
+    f(&storable_accounts)
+}
+
+/// Writes a hot storage file that contains the specified accounts.
+pub(crate) fn write_hot_storage_with_accounts(
+    path: impl AsRef<Path>,
+    accounts: &[(StoredMeta, AccountSharedData)],
+) {
+    write_accounts_with_hash_kind(path, accounts, HOT_FORMAT.hash_kind);
+}
+
+fn write_accounts_with_hash_kind(
+    path: impl AsRef<Path>,
+    accounts: &[(StoredMeta, AccountSharedData)],
+    hash_kind: FooterHashKind,
+) {
+    let hashes: Vec<_> = std::iter::repeat_with(|| AccountHash(Hash::new_unique()))
+        .take(accounts.len())
+        .collect();
+    let write_versions: Vec<_> = accounts
+        .iter()
+        .map(|account| account.0.write_version_obsolete)
+        .collect();
+
+    let mut writer = HotStorageWriter::new_with_options(
+        path,
+        HOT_FORMAT.index_block_format,
+        hash_kind,
+        &WriteOptions::LATEST,
+    )
+    .unwrap();
+    with_storable_accounts(accounts, hashes, write_versions, |storable_accounts| {
+        writer.write_accounts(storable_accounts, 0).unwrap();
+    });
 }
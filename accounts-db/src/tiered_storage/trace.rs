@@ -0,0 +1,102 @@
+//! Tracing of the accounts persisted by the tiered storage writers.
+use {log::*, solana_sdk::pubkey::Pubkey, std::collections::VecDeque};
+
+/// Describes where the writer has persisted one account.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WriteTraceEvent {
+    /// The position of the account in the input accounts.
+    pub index: usize,
+    /// The address of the account.
+    pub address: Pubkey,
+    /// The file offset where the account block starts.
+    pub cursor_before: usize,
+    /// The file offset right after the account block.
+    pub cursor_after: usize,
+    /// The length of the account block.
+    pub block_len: usize,
+    /// The number of padding bytes after the account data.
+    pub padding: u8,
+}
+
+/// Keeps the most recent write events, up to a bounded capacity.
+#[derive(Debug)]
+pub struct WriteTrace {
+    events: VecDeque<WriteTraceEvent>,
+    capacity: usize,
+    num_dropped: usize,
+}
+
+impl WriteTrace {
+    /// Creates a trace that keeps at most `capacity` events.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            num_dropped: 0,
+        }
+    }
+
+    /// Records the specified event, dropping the oldest event when the
+    /// trace is full.
+    pub fn record(&mut self, event: WriteTraceEvent) {
+        trace!("tiered storage write: {event:?}");
+        if self.capacity == 0 {
+            self.num_dropped += 1;
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+            self.num_dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    /// Returns the recorded events, from the oldest to the most recent.
+    pub fn events(&self) -> &VecDeque<WriteTraceEvent> {
+        &self.events
+    }
+
+    /// Returns the number of events that have been dropped.
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_event(index: usize) -> WriteTraceEvent {
+        WriteTraceEvent {
+            index,
+            address: Pubkey::new_unique(),
+            cursor_before: index * 24,
+            cursor_after: (index + 1) * 24,
+            block_len: 24,
+            padding: 0,
+        }
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let mut trace = WriteTrace::with_capacity(3);
+        let events: Vec<_> = (0..5).map(new_event).collect();
+        for event in &events[..2] {
+            trace.record(*event);
+        }
+        assert!(trace.events().iter().eq(&events[..2]));
+        assert_eq!(trace.num_dropped(), 0);
+
+        // the oldest events are dropped once the trace is full
+        for event in &events[2..] {
+            trace.record(*event);
+        }
+        assert!(trace.events().iter().eq(&events[2..]));
+        assert_eq!(trace.num_dropped(), 2);
+
+        let mut trace = WriteTrace::with_capacity(0);
+        trace.record(events[0]);
+        assert!(trace.events().is_empty());
+        assert_eq!(trace.num_dropped(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+//! Incremental verification of the tiered storage file hash.
+//!
+//! Verifying the hash of every file at once can take too long at startup.
+//! Instead, the hash can be computed over a few bytes at a time, where each
+//! step continues from the state produced by the previous one.  Once all
+//! the bytes before the footer are covered, the resulting hash is compared
+//! against the one stored in the footer.
+//!
+//! This module also defines the report of a full consistency check of a
+//! file, see TieredStorage::verify().
+use {
+    crate::tiered_storage::{
+        footer::FooterHashKind,
+        hash_feed::{FileHasher, HashFeed},
+        index::IndexOffset,
+        owners::OwnerOffset,
+    },
+    solana_sdk::hash::Hash,
+    std::{collections::HashMap, fmt, path::PathBuf, sync::Mutex},
+};
+
+/// The outcome of an incremental file hash verification.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum VerificationStatus {
+    /// Some of the hashed bytes have not been covered yet.
+    #[default]
+    InProgress,
+    /// All the hashed bytes are covered and the hash matches the footer.
+    Verified,
+    /// All the hashed bytes are covered but the hash does not match the
+    /// footer.  `offset` is where the step that completed the hash started.
+    /// As only the hash of the whole file is available, the corruption may
+    /// be located anywhere in the file.
+    Mismatch {
+        offset: usize,
+        hash: Hash,
+        footer_hash: Hash,
+    },
+    /// The file does not persist a hash, so there is nothing to verify.
+    Skipped,
+}
+
+/// The state of an incremental file hash verification.
+///
+/// The state can be kept in memory and handed back to the reader to continue
+/// the verification later, possibly from a different thread.
+#[derive(Clone, Default)]
+pub struct PrefixVerification {
+    hasher: FileHasher,
+    verified_len: usize,
+    status: VerificationStatus,
+}
+
+impl fmt::Debug for PrefixVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefixVerification")
+            .field("hash_kind", &self.hasher.kind())
+            .field("verified_len", &self.verified_len)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl PrefixVerification {
+    /// Creates the state of a verification that hashes the file with the
+    /// specified algorithm.
+    pub fn new(hash_kind: FooterHashKind) -> Self {
+        Self {
+            hasher: FileHasher::new(hash_kind),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of bytes, from the beginning of the file, that
+    /// have been hashed so far.
+    pub fn verified_len(&self) -> usize {
+        self.verified_len
+    }
+
+    /// Returns the status of the verification.
+    pub fn status(&self) -> VerificationStatus {
+        self.status
+    }
+
+    /// Returns true if all the hashed bytes have been covered.
+    pub fn is_complete(&self) -> bool {
+        self.status != VerificationStatus::InProgress
+    }
+
+    /// Hashes up to `budget_bytes` of `hashed_bytes`, starting right after
+    /// the bytes that have been covered so far, and compares the result
+    /// against `footer_hash` once all of `hashed_bytes` are covered.
+    pub(crate) fn advance(
+        mut self,
+        hashed_bytes: &[u8],
+        footer_hash: Hash,
+        budget_bytes: u64,
+    ) -> Self {
+        if self.is_complete() {
+            return self;
+        }
+        if self.hasher.kind() == FooterHashKind::None {
+            self.status = VerificationStatus::Skipped;
+            return self;
+        }
+
+        let offset = self.verified_len.min(hashed_bytes.len());
+        let end = usize::try_from(budget_bytes)
+            .map_or(hashed_bytes.len(), |budget| offset.saturating_add(budget))
+            .min(hashed_bytes.len());
+        self.hasher.feed(&hashed_bytes[offset..end]);
+        self.verified_len = end;
+
+        if end == hashed_bytes.len() {
+            let hash = self.hasher.finish();
+            self.status = if hash == footer_hash {
+                VerificationStatus::Verified
+            } else {
+                VerificationStatus::Mismatch {
+                    offset,
+                    hash,
+                    footer_hash,
+                }
+            };
+        }
+        self
+    }
+}
+
+/// Tracks the incremental verification of a set of tiered storage files,
+/// so that the verification started at startup can be continued by a
+/// background task.
+#[derive(Debug, Default)]
+pub struct VerificationProgress {
+    states: Mutex<HashMap<PathBuf, PrefixVerification>>,
+}
+
+impl VerificationProgress {
+    /// Records the verification state of the specified file, replacing
+    /// its previous state if any.
+    pub fn record(&self, path: impl Into<PathBuf>, state: PrefixVerification) {
+        self.states.lock().unwrap().insert(path.into(), state);
+    }
+
+    /// Removes the specified file, for instance once it has been deleted.
+    pub fn remove(&self, path: impl Into<PathBuf>) -> Option<PrefixVerification> {
+        self.states.lock().unwrap().remove(&path.into())
+    }
+
+    /// Takes out the states of all the files whose verification is still in
+    /// progress.  The caller is expected to record the states back after
+    /// continuing their verification.
+    pub fn take_in_progress(&self) -> Vec<(PathBuf, PrefixVerification)> {
+        let mut states = self.states.lock().unwrap();
+        let paths: Vec<_> = states
+            .iter()
+            .filter(|(_, state)| !state.is_complete())
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths
+            .into_iter()
+            .map(|path| {
+                let state = states.remove(&path).unwrap();
+                (path, state)
+            })
+            .collect()
+    }
+
+    /// Returns the number of files whose verification is still in progress.
+    pub fn num_in_progress(&self) -> usize {
+        self.states
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|state| !state.is_complete())
+            .count()
+    }
+
+    /// Returns the files whose hash does not match their footer hash.
+    pub fn mismatched(&self) -> Vec<(PathBuf, VerificationStatus)> {
+        self.states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| matches!(state.status(), VerificationStatus::Mismatch { .. }))
+            .map(|(path, state)| (path.clone(), state.status()))
+            .collect()
+    }
+}
+
+/// An inconsistency found by a full consistency check of a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Inconsistency {
+    /// The account offset of the index entry does not point to an account
+    /// meta inside the account blocks.
+    AccountOffsetOutOfRange(IndexOffset),
+    /// The account block of the account cannot be located, e.g. it ends
+    /// before it starts or beyond the account blocks.
+    InvalidAccountBlock(IndexOffset),
+    /// The padding and the optional fields of the account exceed its
+    /// account block.
+    OptionalFieldsExceedAccountBlock(IndexOffset),
+    /// The checksum persisted at the end of the account block does not
+    /// match the content of the block.
+    BlockChecksumMismatch(IndexOffset),
+    /// The owner offset of the account is not less than the owner count.
+    OwnerOffsetOutOfRange(IndexOffset, OwnerOffset),
+    /// The hash of the file does not match the footer hash.
+    HashMismatch { hash: Hash, footer_hash: Hash },
+}
+
+/// The report of a full consistency check of a file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifyReport {
+    /// The number of accounts stored in the file.
+    pub num_accounts: usize,
+    /// The number of unique account owners stored in the file.
+    pub owner_count: usize,
+    /// The size of the file.
+    pub file_len: usize,
+    /// The number of bytes occupied by the accounts whose account block
+    /// can be located.  See HotStorageReader::account_sizes().
+    pub total_account_bytes: usize,
+    /// The number of accounts that persist their rent epoch.
+    pub num_accounts_with_rent_epoch: usize,
+    /// The number of accounts that persist their account hash.
+    pub num_accounts_with_account_hash: usize,
+    /// All the inconsistencies found, in the order they were found.
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl VerifyReport {
+    /// Returns true if no inconsistency was found.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, assert_matches::assert_matches, solana_sdk::hash::hash, test_case::test_case};
+
+    fn verify_in_steps(bytes: &[u8], footer_hash: Hash, budgets: &[u64]) -> PrefixVerification {
+        budgets
+            .iter()
+            .fold(PrefixVerification::default(), |state, budget| {
+                state.advance(bytes, footer_hash, *budget)
+            })
+    }
+
+    #[test_case(&[u64::MAX]; "one_shot")]
+    #[test_case(&[1; 1000]; "one_byte_at_a_time")]
+    #[test_case(&[7, 0, 300, 0, 1, u64::MAX]; "mixed")]
+    #[test_case(&[1000, 1000]; "exact")]
+    fn test_advance(budgets: &[u64]) {
+        let bytes: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let footer_hash = hash(&bytes);
+
+        let state = verify_in_steps(&bytes, footer_hash, budgets);
+        assert_eq!(state.verified_len(), bytes.len());
+        assert_eq!(state.status(), VerificationStatus::Verified);
+
+        // further steps do not change the outcome
+        let state = state.advance(&bytes, footer_hash, 10);
+        assert_eq!(state.verified_len(), bytes.len());
+        assert_eq!(state.status(), VerificationStatus::Verified);
+
+        let state = verify_in_steps(&bytes, Hash::new_unique(), budgets);
+        assert_matches!(
+            state.status(),
+            VerificationStatus::Mismatch { hash, .. } if hash == footer_hash
+        );
+    }
+
+    #[test]
+    fn test_advance_zero_budget() {
+        let bytes = [1u8; 100];
+        let footer_hash = hash(&bytes);
+
+        let state = PrefixVerification::default().advance(&bytes, footer_hash, 0);
+        assert_eq!(state.verified_len(), 0);
+        assert_eq!(state.status(), VerificationStatus::InProgress);
+
+        let state = state.advance(&bytes, footer_hash, 40);
+        for _ in 0..3 {
+            let state = state.clone().advance(&bytes, footer_hash, 0);
+            assert_eq!(state.verified_len(), 40);
+            assert_eq!(state.status(), VerificationStatus::InProgress);
+        }
+        let state = state
+            .advance(&bytes, footer_hash, 0)
+            .advance(&bytes, footer_hash, 60);
+        assert_eq!(state.status(), VerificationStatus::Verified);
+
+        // an empty region is verified by the first step, whatever its budget
+        let state = PrefixVerification::default().advance(&[], hash(&[]), 0);
+        assert_eq!(state.status(), VerificationStatus::Verified);
+    }
+
+    #[test]
+    fn test_verification_progress() {
+        let bytes = [1u8; 100];
+        let footer_hash = hash(&bytes);
+        let progress = VerificationProgress::default();
+
+        progress.record(
+            "verified",
+            PrefixVerification::default().advance(&bytes, footer_hash, u64::MAX),
+        );
+        progress.record(
+            "mismatched",
+            PrefixVerification::default().advance(&bytes, Hash::default(), u64::MAX),
+        );
+        progress.record(
+            "in_progress",
+            PrefixVerification::default().advance(&bytes, footer_hash, 10),
+        );
+        progress.record("removed", PrefixVerification::default());
+        assert!(progress.remove("removed").is_some());
+        assert_eq!(progress.num_in_progress(), 1);
+        assert_eq!(progress.mismatched().len(), 1);
+        assert_eq!(progress.mismatched()[0].0, PathBuf::from("mismatched"));
+
+        // a background task resumes the pending verification
+        while progress.num_in_progress() > 0 {
+            for (path, state) in progress.take_in_progress() {
+                assert_eq!(path, PathBuf::from("in_progress"));
+                progress.record(path, state.advance(&bytes, footer_hash, 30));
+            }
+        }
+        assert!(progress.take_in_progress().is_empty());
+        assert_eq!(progress.mismatched().len(), 1);
+    }
+}
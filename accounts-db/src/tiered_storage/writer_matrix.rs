@@ -0,0 +1,392 @@
+#![cfg(test)]
+//! A matrix test that writes a set of canonical inputs through every writer
+//! configuration and byte-compares the results against the baseline path,
+//! which is a plain HotStorageWriter without any skipped account.  The
+//! output of every configuration, including the ones whose bytes differ from
+//! the baseline by design, is also read back and compared with its input.
+//!
+//! Every new writer variant must register itself in `writer_configs()`
+//! so that it keeps being checked against all the canonical inputs.
+//!
+//! As the baseline writer does not restrict the format generation, neither
+//! do the TieredStorage configurations.
+use {
+    super::{
+        generation::WriteOptions,
+        hot::{HotStorageWriter, HOT_SIZED_INDEX_FORMAT, HOT_SORTED_FORMAT},
+        index::IndexOffset,
+        test_utils::TestStorableAccounts,
+        TieredStorage, ValidatedTieredStorageFormat, HOT_FORMAT,
+    },
+    crate::{
+        account_storage::meta::StorableAccountsWithHashesAndWriteVersions,
+        accounts_file::AccountsFile, accounts_hash::AccountHash,
+    },
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
+        rent_collector::RENT_EXEMPT_RENT_EPOCH,
+    },
+    std::{fs, path::Path},
+    tempfile::TempDir,
+};
+
+/// The number of accounts that are prepended to the input and then skipped
+/// by the writer configurations that exercise the `skip` parameter.
+const NUM_SKIPPED_ACCOUNTS: usize = 3;
+
+/// The accounts to be written, along with their addresses.
+type TestAccounts = [(Pubkey, AccountSharedData)];
+
+/// A named writer configuration inside the matrix.
+struct WriterConfig {
+    name: &'static str,
+    /// Whether the output of this configuration is expected to be
+    /// byte-identical to the output of the baseline configuration.
+    output_equivalent: bool,
+    /// Writes the accounts to the specified path and returns the bytes of
+    /// the resulting file.
+    write: fn(&Path, &TestAccounts) -> Vec<u8>,
+}
+
+/// Returns all the writer configurations.  The first one is the baseline.
+fn writer_configs() -> Vec<WriterConfig> {
+    vec![
+        WriterConfig {
+            name: "hot_storage_writer",
+            output_equivalent: true,
+            write: |path, accounts| {
+                write_with_skip(accounts, 0, |storable_accounts, skip| {
+                    let mut writer = HotStorageWriter::new_with_options(
+                        path,
+                        HOT_FORMAT.index_block_format,
+                        HOT_FORMAT.hash_kind,
+                        &WriteOptions::LATEST,
+                    )
+                    .unwrap();
+                    writer.write_accounts(storable_accounts, skip).unwrap();
+                });
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "hot_storage_writer_with_skip",
+            output_equivalent: true,
+            write: |path, accounts| {
+                write_with_skip(accounts, NUM_SKIPPED_ACCOUNTS, |storable_accounts, skip| {
+                    let mut writer = HotStorageWriter::new_with_options(
+                        path,
+                        HOT_FORMAT.index_block_format,
+                        HOT_FORMAT.hash_kind,
+                        &WriteOptions::LATEST,
+                    )
+                    .unwrap();
+                    writer.write_accounts(storable_accounts, skip).unwrap();
+                });
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "tiered_storage",
+            output_equivalent: true,
+            write: |path, accounts| {
+                // TieredStorage removes its file on drop, so the bytes must
+                // be read before it goes out of scope.
+                let tiered_storage =
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+                write_with_skip(accounts, 0, |storable_accounts, skip| {
+                    tiered_storage
+                        .write_accounts(storable_accounts, skip, &HOT_FORMAT)
+                        .unwrap();
+                });
+                finalize_if_empty(&tiered_storage, &HOT_FORMAT);
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "tiered_storage_with_skip",
+            output_equivalent: true,
+            write: |path, accounts| {
+                let tiered_storage =
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+                write_with_skip(accounts, NUM_SKIPPED_ACCOUNTS, |storable_accounts, skip| {
+                    tiered_storage
+                        .write_accounts(storable_accounts, skip, &HOT_FORMAT)
+                        .unwrap();
+                });
+                finalize_if_empty(&tiered_storage, &HOT_FORMAT);
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "tiered_storage_sorted_index",
+            // Accounts are stored in the order of their addresses.
+            output_equivalent: false,
+            write: |path, accounts| {
+                let tiered_storage =
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+                write_with_skip(accounts, 0, |storable_accounts, skip| {
+                    tiered_storage
+                        .write_accounts(storable_accounts, skip, &HOT_SORTED_FORMAT)
+                        .unwrap();
+                });
+                finalize_if_empty(&tiered_storage, &HOT_SORTED_FORMAT);
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "tiered_storage_sized_index",
+            // The index block also stores the size of each account block.
+            output_equivalent: false,
+            write: |path, accounts| {
+                let tiered_storage =
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST);
+                write_with_skip(accounts, 0, |storable_accounts, skip| {
+                    tiered_storage
+                        .write_accounts(storable_accounts, skip, &HOT_SIZED_INDEX_FORMAT)
+                        .unwrap();
+                });
+                finalize_if_empty(&tiered_storage, &HOT_SIZED_INDEX_FORMAT);
+                read_output(path)
+            },
+        },
+        WriterConfig {
+            name: "accounts_file",
+            output_equivalent: true,
+            write: |path, accounts| {
+                let accounts_file = AccountsFile::TieredStorage(
+                    TieredStorage::new_writable_with_options(path, WriteOptions::LATEST),
+                );
+                write_with_skip(accounts, 0, |storable_accounts, skip| {
+                    accounts_file
+                        .append_accounts(storable_accounts, skip)
+                        .unwrap()
+                        .unwrap();
+                });
+                if let AccountsFile::TieredStorage(tiered_storage) = &accounts_file {
+                    finalize_if_empty(tiered_storage, &HOT_FORMAT);
+                }
+                read_output(path)
+            },
+        },
+    ]
+}
+
+/// Persists a file without any account if nothing has been written, which
+/// is the case for the empty input, so that its output can be compared.
+fn finalize_if_empty(tiered_storage: &TieredStorage, format: &ValidatedTieredStorageFormat) {
+    if !tiered_storage.is_written() {
+        tiered_storage.finalize_empty(format).unwrap();
+    }
+}
+
+/// Reads the bytes of the specified file.
+fn read_output(path: &Path) -> Vec<u8> {
+    fs::read(path).unwrap()
+}
+
+/// Persists the specified output at the specified path and reads its
+/// accounts back, ordered by their addresses as the order of the accounts in
+/// the file depends on the configuration.
+fn read_back_accounts(path: &Path, output: &[u8]) -> Vec<(Pubkey, AccountSharedData)> {
+    fs::write(path, output).unwrap();
+    let tiered_storage = TieredStorage::new_readonly(path).unwrap();
+    let mut accounts: Vec<_> = tiered_storage
+        .reader()
+        .unwrap()
+        .accounts(IndexOffset(0))
+        .unwrap()
+        .iter()
+        .map(|account| (*account.pubkey(), account.to_account_shared_data()))
+        .collect();
+    accounts.sort_unstable_by_key(|(address, _)| *address);
+    accounts
+}
+
+/// Prepends `skip` filler accounts to the specified accounts, and invokes
+/// `write` with the resulting storable accounts and `skip`.
+fn write_with_skip(
+    accounts: &TestAccounts,
+    skip: usize,
+    write: impl FnOnce(&TestStorableAccounts<'_>, usize),
+) {
+    let fillers: Vec<_> = (0..skip)
+        .map(|i| {
+            (
+                Pubkey::new_unique(),
+                AccountSharedData::new(i as u64 + 1, i * 7, &Pubkey::new_unique()),
+            )
+        })
+        .collect();
+    let account_refs: Vec<_> = fillers
+        .iter()
+        .chain(accounts.iter())
+        .map(|(address, account)| (address, account))
+        .collect();
+
+    // Slot information is not used here
+    let account_data = (Slot::MAX, &account_refs[..]);
+    let hashes = vec![AccountHash(Hash::default()); account_refs.len()];
+    let write_versions = vec![0; account_refs.len()];
+    let storable_accounts =
+        StorableAccountsWithHashesAndWriteVersions::new_with_hashes_and_write_versions(
+            &account_data,
+            hashes,
+            write_versions,
+        );
+    write(&storable_accounts, skip);
+}
+
+/// Returns the canonical inputs of the matrix.
+fn canonical_inputs() -> Vec<(&'static str, Vec<(Pubkey, AccountSharedData)>)> {
+    let owners: Vec<_> = std::iter::repeat_with(Pubkey::new_unique).take(3).collect();
+    let new_account = |lamports: u64, data_len: usize| {
+        let mut account = AccountSharedData::new(lamports, data_len, &owners[data_len % 3]);
+        account.set_data_from_slice(&vec![data_len as u8; data_len]);
+        (Pubkey::new_unique(), account)
+    };
+
+    vec![
+        ("empty", vec![]),
+        ("single_account", vec![new_account(1, 10)]),
+        (
+            "small_accounts",
+            (0..20).map(|i| new_account(i as u64 + 1, i)).collect(),
+        ),
+        (
+            "large_accounts",
+            [1 << 20, 0, 10 << 20]
+                .into_iter()
+                .map(|data_len| new_account(1, data_len))
+                .collect(),
+        ),
+        (
+            "mixed_optional_fields",
+            (0..20)
+                .map(|i| {
+                    let (address, mut account) = new_account(i as u64 + 1, i);
+                    account.set_executable(i % 2 == 0);
+                    account.set_rent_epoch(if i % 3 == 0 {
+                        RENT_EXEMPT_RENT_EPOCH
+                    } else {
+                        i as u64
+                    });
+                    (address, account)
+                })
+                .collect(),
+        ),
+        (
+            "executable_without_data",
+            (0..4)
+                .map(|i| {
+                    let (address, mut account) = new_account(i as u64 + 1, i % 2);
+                    account.set_executable(true);
+                    (address, account)
+                })
+                .collect(),
+        ),
+        (
+            "zero_lamport_accounts",
+            (0..20)
+                .map(|i| new_account(if i % 2 == 0 { 0 } else { 1 }, i))
+                .collect(),
+        ),
+    ]
+}
+
+/// Panics with the first differing offset and the surrounding bytes if the
+/// two outputs are not identical.
+fn assert_same_bytes(config_name: &str, input_name: &str, expected: &[u8], actual: &[u8]) {
+    if expected == actual {
+        return;
+    }
+    const CONTEXT: usize = 16;
+    let offset = expected
+        .iter()
+        .zip(actual.iter())
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    let start = offset.saturating_sub(CONTEXT);
+    let context =
+        |bytes: &[u8]| bytes[start.min(bytes.len())..(offset + CONTEXT).min(bytes.len())].to_vec();
+    panic!(
+        "writer '{config_name}' drifts from the baseline on input '{input_name}': \
+         baseline has {} bytes, output has {} bytes, first difference at offset {offset}\n\
+         baseline[{start}..]: {:02x?}\n\
+         output[{start}..]:   {:02x?}",
+        expected.len(),
+        actual.len(),
+        context(expected),
+        context(actual),
+    );
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer_matrix() {
+        let temp_dir = TempDir::new().unwrap();
+        let configs = writer_configs();
+        let (baseline, variants) = configs.split_first().unwrap();
+
+        for (input_name, accounts) in canonical_inputs() {
+            let expected = (baseline.write)(
+                &temp_dir
+                    .path()
+                    .join(format!("{input_name}_{}", baseline.name)),
+                &accounts,
+            );
+            for config in variants.iter().filter(|config| config.output_equivalent) {
+                let actual = (config.write)(
+                    &temp_dir
+                        .path()
+                        .join(format!("{input_name}_{}", config.name)),
+                    &accounts,
+                );
+                assert_same_bytes(config.name, input_name, &expected, &actual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_writer_matrix_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for (input_name, accounts) in canonical_inputs() {
+            // a zero-lamport account is read back as the default account
+            let mut expected: Vec<_> = accounts
+                .iter()
+                .map(|(address, account)| {
+                    let account = if account.lamports() == 0 {
+                        AccountSharedData::default()
+                    } else {
+                        account.clone()
+                    };
+                    (*address, account)
+                })
+                .collect();
+            expected.sort_unstable_by_key(|(address, _)| *address);
+
+            for config in writer_configs() {
+                let name = format!("{input_name}_{}", config.name);
+                let output = (config.write)(&temp_dir.path().join(&name), &accounts);
+                let actual =
+                    read_back_accounts(&temp_dir.path().join(format!("{name}_read_back")), &output);
+                assert!(
+                    actual == expected,
+                    "writer '{}' does not round-trip input '{input_name}'",
+                    config.name,
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "first difference at offset 2")]
+    fn test_assert_same_bytes() {
+        assert_same_bytes("drifted", "input", &[0, 1, 2, 3], &[0, 1, 9, 3]);
+    }
+}